@@ -5,8 +5,6 @@
 use std::env;
 use std::fs;
 use std::process;
-use php_lexer;
-use php_parser::ast::Stmt;
 use php_runtime::Engine;
 
 fn main() {
@@ -48,11 +46,15 @@ fn main() {
     
     // Execute
     let mut engine = Engine::new();
-    if let Err(e) = engine.execute_stmt(&ast) {
+    let result = engine.execute_stmt(&ast);
+    
+    // Print output, including whatever ran before a fatal error
+    print!("{}", engine.get_output());
+    for error in engine.raised_errors() {
+        eprintln!("{}: {}", error.level.label(), error.message);
+    }
+    if let Err(e) = result {
         eprintln!("Runtime error: {}", e);
         process::exit(1);
     }
-    
-    // Print output
-    print!("{}", engine.get_output());
 }
@@ -1,9 +0,0 @@
-//! Integration tests for php-cli
-
-use php_cli::*;
-
-#[test]
-fn basic_test() {
-    // TODO: Add tests for php-cli
-    assert!(true);
-}
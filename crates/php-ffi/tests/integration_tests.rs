@@ -1,9 +0,0 @@
-//! Integration tests for php-ffi
-
-use php_ffi::*;
-
-#[test]
-fn basic_test() {
-    // TODO: Add tests for php-ffi
-    assert!(true);
-}
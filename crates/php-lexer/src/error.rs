@@ -16,6 +16,10 @@ pub enum LexError {
     /// Invalid number format
     #[error("Invalid number format '{number}' at line {line}, column {column}")]
     InvalidNumber { number: String, line: usize, column: usize },
+
+    /// Digit not allowed in a hexadecimal, octal or binary literal
+    #[error("Invalid digit '{digit}' in {base} literal '{number}' at line {line}, column {column}")]
+    InvalidDigit { digit: char, base: &'static str, number: String, line: usize, column: usize },
     
     /// Unexpected end of file
     #[error("Unexpected end of file")]
@@ -51,6 +51,14 @@ impl KeywordHandler {
     keywords.insert("declare", Token::Declare);
         keywords.insert("try", Token::Try);
         keywords.insert("catch", Token::Catch);
+        keywords.insert("finally", Token::Finally);
+        keywords.insert("throw", Token::Throw);
+        keywords.insert("match", Token::Match);
+        keywords.insert("fn", Token::Fn);
+        keywords.insert("yield", Token::Yield);
+        keywords.insert("and", Token::KeywordAnd);
+        keywords.insert("or", Token::KeywordOr);
+        keywords.insert("xor", Token::KeywordXor);
         
         // Built-in functions
         keywords.insert("print_r", Token::PrintR);
@@ -14,8 +14,15 @@ pub struct LiteralHandler;
 
 impl LiteralHandler {
     /// Tokenize a variable ($variable)
+    ///
+    /// A '$' directly followed by another '$' or '{' starts a variable-variable
+    /// ($$name / ${expr}) and is emitted as a bare `Token::Dollar`.
     pub fn tokenize_variable(stream: &mut CharStream) -> LexResult<Token> {
         stream.next(); // consume '$'
+
+        if let Some(&'$') | Some(&'{') = stream.peek() {
+            return Ok(Token::Dollar);
+        }
         
         let mut name = String::new();
         while let Some(&ch) = stream.peek() {
@@ -42,13 +49,75 @@ impl LiteralHandler {
     pub fn tokenize_string(stream: &mut CharStream) -> LexResult<Token> {
         let quote_char = stream.peek().copied().unwrap();
         let content = stream.read_string(quote_char)?;
-        Ok(Token::String(content))
+        if quote_char == '"' {
+            Ok(Token::InterpolatedString(content))
+        } else {
+            Ok(Token::String(content))
+        }
     }
 
-    /// Tokenize a number literal
+    /// Tokenize a number literal: `Token::Int` unless it has a fraction or exponent.
+    /// Integers may be written in hexadecimal (`0x1F`), octal (`0o17`, `017`) or binary (`0b101`).
     pub fn tokenize_number(stream: &mut CharStream) -> LexResult<Token> {
-        let number = stream.read_number()?;
-        Ok(Token::Number(number))
+        let start_pos = stream.position();
+        let prefix = stream.peek_ahead(2).to_ascii_lowercase();
+        let radix = match prefix.as_str() {
+            "0x" => Some((16, "hexadecimal")),
+            "0o" => Some((8, "octal")),
+            "0b" => Some((2, "binary")),
+            _ => None,
+        };
+        if let Some((radix, base)) = radix {
+            stream.next();
+            stream.next();
+            let digits = stream.read_identifier();
+            let digits = Self::strip_separators(&digits, char::is_ascii_alphanumeric)
+                .ok_or_else(|| LexError::InvalidNumber { number: format!("{}{}", prefix, digits), line: start_pos.line, column: start_pos.column })?;
+            return Self::radix_integer(&prefix, &digits, radix, base, start_pos.line, start_pos.column);
+        }
+        let raw = stream.read_number();
+        let number = Self::strip_separators(&raw, char::is_ascii_digit)
+            .ok_or_else(|| LexError::InvalidNumber { number: raw.clone(), line: start_pos.line, column: start_pos.column })?;
+        if !number.contains(['.', 'e', 'E']) {
+            if number.len() > 1 && number.starts_with('0') {
+                return Self::radix_integer("0", &number[1..], 8, "octal", start_pos.line, start_pos.column);
+            }
+            // Like PHP, decimal integers too large for an int become floats
+            if let Ok(int) = number.parse::<i64>() {
+                return Ok(Token::Int(int));
+            }
+        }
+        number.parse::<f64>().map(Token::Number).map_err(|_| LexError::InvalidNumber {
+            number,
+            line: start_pos.line,
+            column: start_pos.column,
+        })
+    }
+
+    /// Remove `_` digit separators (`1_000`), or None when one is not between two digits
+    fn strip_separators(raw: &str, is_digit: fn(&char) -> bool) -> Option<String> {
+        let chars: Vec<char> = raw.chars().collect();
+        for (i, c) in chars.iter().enumerate() {
+            if *c == '_' && !(i > 0 && is_digit(&chars[i - 1]) && chars.get(i + 1).is_some_and(is_digit)) {
+                return None;
+            }
+        }
+        Some(raw.replace('_', ""))
+    }
+
+    /// Parse the digits of a prefixed integer literal; values past PHP_INT_MAX become floats
+    fn radix_integer(prefix: &str, digits: &str, radix: u32, base: &'static str, line: usize, column: usize) -> LexResult<Token> {
+        let number = format!("{}{}", prefix, digits);
+        if let Some(digit) = digits.chars().find(|c| !c.is_digit(radix)) {
+            return Err(LexError::InvalidDigit { digit, base, number, line, column });
+        }
+        if digits.is_empty() {
+            return Err(LexError::InvalidNumber { number, line, column });
+        }
+        Ok(match i64::from_str_radix(digits, radix) {
+            Ok(int) => Token::Int(int),
+            Err(_) => Token::Number(digits.chars().fold(0.0, |acc, c| acc * radix as f64 + c.to_digit(radix).unwrap_or(0) as f64)),
+        })
     }
 
     /// Tokenize an identifier
@@ -58,8 +127,8 @@ impl LiteralHandler {
 
     /// Tokenize a heredoc or nowdoc string literal beginning with <<<
     /// Basic implementation: captures content until a line that exactly matches the identifier
-    /// (optionally followed by a semicolon). Nowdoc (with single quotes) is treated the same as
-    /// heredoc for now (no special interpolation differences at lexing stage in this simplified engine).
+    /// (optionally followed by a semicolon). A nowdoc (with single quotes) becomes a literal
+    /// `Token::String`, a heredoc a `Token::InterpolatedString`.
     pub fn tokenize_heredoc(stream: &mut CharStream) -> LexResult<Token> {
         // We are positioned at first '<' of the sequence '<<<'
         // Consume the three '<'
@@ -147,8 +216,6 @@ impl LiteralHandler {
             }
         }
 
-        // For nowdoc we don't perform interpolation at lexer stage; runtime will treat raw string
-        let _ = nowdoc; // suppress unused warning for now
         // If a semicolon followed the terminator, emit a semicolon token next by pushing it back conceptually.
         // Since we don't have a pushback mechanism, we'll store the fact in a thread-local? Simpler: append ';' to content? Not correct.
         // Instead: we will include a trailing semicolon token by hacking: place a sentinel in stream that the main lexer will detect.
@@ -156,6 +223,10 @@ impl LiteralHandler {
         // Simpler: return String token; main lexer after calling this will check last consumed line? For now, ignore emitting semicolon and rely on parser not requiring it.
         // However parser currently expects semicolon after assignment. We'll fake by appending an actual semicolon token via a global flag (out of scope). So fallback: ensure upstream code doesn't require semicolon by adding one at end of content? This changes string value; unacceptable.
         // Revised simpler solution: Do nothing extra; adjust parser to accept missing semicolon after heredoc assignment.
-        Ok(Token::String(content))
+        if nowdoc {
+            Ok(Token::String(content))
+        } else {
+            Ok(Token::InterpolatedString(content))
+        }
     }
 }
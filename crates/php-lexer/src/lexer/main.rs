@@ -19,6 +19,8 @@ use super::operators::OperatorHandler;
 pub struct Lexer<'a> {
     stream: CharStream<'a>,
     keyword_handler: KeywordHandler,
+    /// Set after `?>`: the next token is the inline HTML up to `<?php`
+    after_close_tag: bool,
 }
 
 impl<'a> Lexer<'a> {
@@ -27,6 +29,7 @@ impl<'a> Lexer<'a> {
         Self {
             stream: CharStream::new(input),
             keyword_handler: KeywordHandler::new(),
+            after_close_tag: false,
         }
     }
     
@@ -47,6 +50,14 @@ impl<'a> Lexer<'a> {
     
     /// Get next token from input
     pub fn next_token(&mut self) -> LexResult<Option<Token>> {
+        // Text after `?>` is echoed verbatim until PHP mode is re-entered
+        if self.after_close_tag {
+            self.after_close_tag = false;
+            if let Some(html) = self.read_inline_html() {
+                return Ok(Some(Token::InlineHtml(html)));
+            }
+        }
+
         // Skip whitespace
         self.skip_whitespace();
         
@@ -64,7 +75,9 @@ impl<'a> Lexer<'a> {
             
             // Get next character and tokenize it
             if let Some(ch) = self.stream.peek().copied() {
-                return Ok(Some(self.tokenize_char(ch)?));
+                let token = self.tokenize_char(ch)?;
+                self.after_close_tag = token == Token::PhpClose;
+                return Ok(Some(token));
             } else {
                 return Ok(Some(Token::EOF));
             }
@@ -72,11 +85,33 @@ impl<'a> Lexer<'a> {
     }
     
     /// Convert to iterator
+    #[allow(dead_code)]
     pub fn into_iter(mut self) -> LexResult<TokenIterator> {
         let tokens = self.tokenize()?;
         Ok(TokenIterator::new(tokens))
     }
     
+    /// Read the literal text following a `?>` up to the next `<?php` or the
+    /// end of input. Like PHP, a single newline directly after the close tag
+    /// is swallowed. Returns `None` when there is no text to emit.
+    fn read_inline_html(&mut self) -> Option<String> {
+        if self.stream.peek_ahead(2) == "\r\n" {
+            self.stream.next();
+            self.stream.next();
+        } else if let Some(&'\n') = self.stream.peek() {
+            self.stream.next();
+        }
+
+        let mut html = String::new();
+        while !self.stream.is_at_end() && self.stream.peek_ahead(5) != "<?php" {
+            if let Some(ch) = self.stream.next() {
+                html.push(ch);
+            }
+        }
+
+        if html.is_empty() { None } else { Some(html) }
+    }
+
     /// Skip whitespace characters
     fn skip_whitespace(&mut self) {
         while let Some(&ch) = self.stream.peek() {
@@ -155,7 +190,17 @@ impl<'a> Lexer<'a> {
                     Ok(Token::Minus)
                 }
             }
-            '*' => { self.stream.next(); Ok(Token::Multiply) }
+            '*' => {
+                self.stream.next(); // consume '*'
+                if let Some(&'*') = self.stream.peek() {
+                    self.stream.next(); // consume second '*'
+                    Ok(Token::Power)
+                } else {
+                    Ok(Token::Multiply)
+                }
+            }
+            '%' => { self.stream.next(); Ok(Token::Percent) }
+            '^' => { self.stream.next(); Ok(Token::Caret) }
             '|' => {
                 if self.stream.peek_ahead(2).starts_with("||") {
                     self.stream.next(); // |
@@ -231,10 +276,12 @@ impl<'a> Lexer<'a> {
 }
 
 /// Iterator wrapper for tokens
+#[allow(dead_code)]
 pub struct TokenIterator {
     tokens: Peekable<IntoIter<Token>>,
 }
 
+#[allow(dead_code)]
 impl TokenIterator {
     /// Create new token iterator
     pub fn new(tokens: Vec<Token>) -> Self {
@@ -3,6 +3,7 @@
 //! This module handles recognition of PHP operators:
 //! - Arithmetic operators (+, -, *, /, %)
 //! - Comparison operators (==, !=, <, >, <=, >=)
+//! - Shift operators (<<, >>)
 //! - Assignment operators (=)
 //! - Logical operators (&&, ||, !)
 
@@ -14,12 +15,16 @@ use crate::token::Token;
 pub struct OperatorHandler;
 
 impl OperatorHandler {
-    /// Tokenize equals, double equals, or arrow operator
+    /// Tokenize equals, double equals, identical, or arrow operator
     pub fn tokenize_equals(stream: &mut CharStream) -> LexResult<Token> {
         stream.next(); // consume '='
         
         if let Some(&'=') = stream.peek() {
             stream.next(); // consume second '='
+            if let Some(&'=') = stream.peek() {
+                stream.next(); // consume third '='
+                return Ok(Token::Identical);
+            }
             Ok(Token::DoubleEquals)
         } else if let Some(&'>') = stream.peek() {
             stream.next(); // consume '>'
@@ -29,9 +34,13 @@ impl OperatorHandler {
         }
     }
 
-    /// Tokenize less than or less or equal
+    /// Tokenize less than, less or equal, spaceship or shift left (heredoc `<<<` is handled before)
     pub fn tokenize_less_than(stream: &mut CharStream) -> LexResult<Token> {
         stream.next(); // consume '<'
+        if let Some(&'<') = stream.peek() {
+            stream.next(); // consume second '<'
+            return Ok(Token::ShiftLeft);
+        }
         if let Some(&'=') = stream.peek() {
             stream.next(); // consume '='
             if let Some(&'>') = stream.peek() {
@@ -43,11 +52,14 @@ impl OperatorHandler {
         Ok(Token::LessThan)
     }
 
-    /// Tokenize greater than or greater or equal
+    /// Tokenize greater than, greater or equal or shift right
     pub fn tokenize_greater_than(stream: &mut CharStream) -> LexResult<Token> {
         stream.next(); // consume '>'
         
-        if let Some(&'=') = stream.peek() {
+        if let Some(&'>') = stream.peek() {
+            stream.next(); // consume second '>'
+            Ok(Token::ShiftRight)
+        } else if let Some(&'=') = stream.peek() {
             stream.next(); // consume '='
             Ok(Token::GreaterOrEqual)
         } else {
@@ -55,16 +67,19 @@ impl OperatorHandler {
         }
     }
 
-    /// Tokenize not equals
+    /// Tokenize not equals, not identical or logical not
     pub fn tokenize_not_equals(stream: &mut CharStream) -> LexResult<Token> {
         stream.next(); // consume '!'
         
         if let Some(&'=') = stream.peek() {
             stream.next(); // consume '='
+            if let Some(&'=') = stream.peek() {
+                stream.next(); // consume second '='
+                return Ok(Token::NotIdentical);
+            }
             Ok(Token::NotEquals)
         } else {
-            // Just '!' - could be logical NOT in the future
-            Ok(Token::NotEquals) // For now, treat as incomplete !=
+            Ok(Token::Not)
         }
     }
 
@@ -93,6 +108,12 @@ impl OperatorHandler {
             stream.next(); // second '?'
             return Ok(Token::NullCoalescing);
         }
+        if stream.peek_ahead(3) == "?->" {
+            stream.next(); // '?'
+            stream.next(); // '-'
+            stream.next(); // '>'
+            return Ok(Token::NullsafeObjectOperator);
+        }
         if two == "?>" {
             stream.next(); // '?'
             stream.next(); // '>'
@@ -103,9 +124,13 @@ impl OperatorHandler {
         Ok(Token::QuestionMark)
     }
 
-    /// Tokenize colon
+    /// Tokenize colon or scope resolution operator '::'
     pub fn tokenize_colon(stream: &mut CharStream) -> LexResult<Token> {
         stream.next(); // consume ':'
+        if stream.peek() == Some(&':') {
+            stream.next(); // consume second ':'
+            return Ok(Token::DoubleColon);
+        }
         Ok(Token::Colon)
     }
 }
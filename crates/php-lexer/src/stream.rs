@@ -90,6 +90,7 @@ impl<'a> CharStream<'a> {
     }
     
     /// Take a specific number of characters
+    #[allow(dead_code)]
     pub fn take(&mut self, count: usize) -> String {
         let mut result = String::new();
         for _ in 0..count {
@@ -103,6 +104,7 @@ impl<'a> CharStream<'a> {
     }
     
     /// Skip whitespace characters
+    #[allow(dead_code)]
     pub fn skip_whitespace(&mut self) {
         while let Some(&ch) = self.peek() {
             if ch.is_whitespace() {
@@ -127,31 +129,64 @@ impl<'a> CharStream<'a> {
         identifier
     }
     
-    /// Read a number (integer or float)
-    pub fn read_number(&mut self) -> LexResult<f64> {
-        let start_pos = self.position();
-        let mut number_str = String::new();
-        
+    /// Read the source text of a number literal: digits, an optional
+    /// fraction and an optional exponent (`12`, `1.5`, `2e-3`). Digit runs
+    /// keep any `_` separators for the caller to validate.
+    pub fn read_number(&mut self) -> String {
+        let mut number_str = self.read_digits();
+        let fraction = self.peek_ahead(2);
+        if fraction.len() == 2 && fraction.starts_with('.') && fraction[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            number_str.push(self.next().unwrap_or('.'));
+            number_str.push_str(&self.read_digits());
+        }
+        let exponent = self.peek_ahead(3);
+        let mut exponent_chars = exponent.chars();
+        if let (Some('e' | 'E'), Some(sign_or_digit)) = (exponent_chars.next(), exponent_chars.next()) {
+            let signed = matches!(sign_or_digit, '+' | '-');
+            let digit_follows = if signed { exponent_chars.next().is_some_and(|c| c.is_ascii_digit()) } else { sign_or_digit.is_ascii_digit() };
+            if digit_follows {
+                number_str.push(self.next().unwrap_or('e'));
+                if signed {
+                    number_str.push(self.next().unwrap_or('+'));
+                }
+                number_str.push_str(&self.read_digits());
+            }
+        }
+        number_str
+    }
+
+    /// Read a run of ASCII digits and `_` separators
+    fn read_digits(&mut self) -> String {
+        let mut digits = String::new();
         while let Some(&ch) = self.peek() {
-            if ch.is_numeric() || ch == '.' {
-                number_str.push(ch);
-                self.next();
-            } else {
+            if !ch.is_ascii_digit() && ch != '_' {
                 break;
             }
+            digits.push(ch);
+            self.next();
         }
-        
-        number_str.parse::<f64>().map_err(|_| LexError::InvalidNumber {
-            number: number_str,
-            line: start_pos.line,
-            column: start_pos.column,
-        })
+        digits
     }
     
-    /// Read a string literal (with quote character)
+    /// Read a string literal (with quote character). Double quotes process the
+    /// usual escape sequences; single quotes only unescape `\\` and `\'`.
+    ///
+    /// `\xHH` and octal escapes produce raw bytes, so `"\xE2\x82\xAC"` is `€`.
+    /// Strings are UTF-8, so bytes that do not form valid UTF-8 become U+FFFD.
+    /// A `$` from `\$` is written as `\$` in the token (see `encode_escaped_dollars`)
+    /// so interpolation leaves it literal.
     pub fn read_string(&mut self, quote: char) -> LexResult<String> {
         let start_pos = self.position();
-        let mut string_content = String::new();
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut escaped_dollars = Vec::new();
+        let push = |bytes: &mut Vec<u8>, ch: char| bytes.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+        // A `$` spelled as a byte escape is as literal as `\$`
+        let push_byte = |bytes: &mut Vec<u8>, escaped_dollars: &mut Vec<usize>, byte: u8| {
+            if byte == b'$' {
+                escaped_dollars.push(bytes.len());
+            }
+            bytes.push(byte);
+        };
         
         // Skip opening quote
         self.next();
@@ -159,29 +194,66 @@ impl<'a> CharStream<'a> {
         while let Some(&ch) = self.peek() {
             if ch == quote {
                 self.next(); // Skip closing quote
-                return Ok(string_content);
+                if quote == '"' {
+                    bytes = encode_escaped_dollars(&bytes, &escaped_dollars);
+                }
+                return Ok(String::from_utf8(bytes).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned()));
             }
             
-            // Handle escape sequences (basic)
-            if ch == '\\' {
+            if ch == '\\' && quote == '\'' {
+                self.next(); // Skip backslash
+                match self.peek() {
+                    Some(&escaped @ ('\\' | '\'')) => {
+                        push(&mut bytes, escaped);
+                        self.next();
+                    }
+                    _ => push(&mut bytes, '\\'),
+                }
+            } else if ch == '\\' {
+                // Handle escape sequences (basic)
                 self.next(); // Skip backslash
                 if let Some(&escaped) = self.peek() {
                     match escaped {
-                        'n' => string_content.push('\n'),
-                        't' => string_content.push('\t'),
-                        'r' => string_content.push('\r'),
-                        '\\' => string_content.push('\\'),
-                        '\'' => string_content.push('\''),
-                        '"' => string_content.push('"'),
+                        'x' => {
+                            self.next();
+                            match self.read_escape_digits(16, 2) {
+                                Some(byte) => push_byte(&mut bytes, &mut escaped_dollars, byte as u8),
+                                None => bytes.extend_from_slice(b"\\x"),
+                            }
+                            continue;
+                        }
+                        '0'..='7' => {
+                            let byte = self.read_escape_digits(8, 3).unwrap_or(0);
+                            push_byte(&mut bytes, &mut escaped_dollars, (byte & 0xFF) as u8);
+                            continue;
+                        }
+                        'u' => {
+                            self.next();
+                            match self.read_unicode_escape() {
+                                Some(ch) => push(&mut bytes, ch),
+                                None => bytes.extend_from_slice(b"\\u"),
+                            }
+                            continue;
+                        }
+                        'n' => push(&mut bytes, '\n'),
+                        't' => push(&mut bytes, '\t'),
+                        'r' => push(&mut bytes, '\r'),
+                        '\\' => push(&mut bytes, '\\'),
+                        '\'' => push(&mut bytes, '\''),
+                        '"' => push(&mut bytes, '"'),
+                        '$' => {
+                            escaped_dollars.push(bytes.len());
+                            push(&mut bytes, '$');
+                        }
                         _ => {
-                            string_content.push('\\');
-                            string_content.push(escaped);
+                            push(&mut bytes, '\\');
+                            push(&mut bytes, escaped);
                         }
                     }
                     self.next();
                 }
             } else {
-                string_content.push(ch);
+                push(&mut bytes, ch);
                 self.next();
             }
         }
@@ -192,8 +264,66 @@ impl<'a> CharStream<'a> {
         })
     }
 
+    /// Consume up to `max` digits in `radix` for a numeric escape, returning
+    /// their value, or `None` (consuming nothing) when there is no digit
+    fn read_escape_digits(&mut self, radix: u32, max: usize) -> Option<u32> {
+        let mut value = None;
+        for _ in 0..max {
+            let Some(digit) = self.peek().and_then(|c| c.to_digit(radix)) else { break };
+            value = Some(value.unwrap_or(0) * radix + digit);
+            self.next();
+        }
+        value
+    }
+
+    /// Consume the `{codepoint}` of a `\u{...}` escape. Anything malformed is
+    /// left unconsumed so the caller keeps it literally.
+    fn read_unicode_escape(&mut self) -> Option<char> {
+        let ahead = self.peek_ahead(9);
+        let body = ahead.strip_prefix('{')?;
+        let close = body.find('}')?;
+        let digits = &body[..close];
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let ch = u32::from_str_radix(digits, 16).ok().and_then(char::from_u32)?;
+        for _ in 0..close + 2 {
+            self.next();
+        }
+        Some(ch)
+    }
+
     /// Check if we're at the end of the input
     pub fn is_at_end(&mut self) -> bool {
         self.chars.peek().is_none()
     }
 }
+
+/// Mark the `$` bytes at `escaped` as literal for runtime interpolation. Before
+/// every `$`, backslashes are doubled and an escaped `$` gets one more, so an odd
+/// run of backslashes before a `$` means the `$` is literal. Backslashes
+/// elsewhere are left alone.
+fn encode_escaped_dollars(bytes: &[u8], escaped: &[usize]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(bytes.len() + escaped.len());
+    let mut escaped = escaped.iter().copied().peekable();
+    let mut backslashes = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'\\' => {
+                backslashes += 1;
+                continue;
+            }
+            b'$' => {
+                encoded.resize(encoded.len() + backslashes * 2, b'\\');
+                if escaped.next_if_eq(&i).is_some() {
+                    encoded.push(b'\\');
+                }
+            }
+            _ => encoded.resize(encoded.len() + backslashes, b'\\'),
+        }
+        backslashes = 0;
+        encoded.push(byte);
+    }
+    encoded.resize(encoded.len() + backslashes, b'\\');
+    encoded
+}
@@ -8,6 +8,8 @@ pub enum Token {
     // PHP Tags
     PhpOpen,
     PhpClose,
+    /// Literal text outside of `<?php ... ?>`, echoed as-is
+    InlineHtml(String),
     
     // Language constructs
     Echo,
@@ -43,8 +45,13 @@ pub enum Token {
     Continue,
     Do,
     Declare,
+    Match,
+    Fn,
+    Yield,
     Try,
     Catch,
+    Finally,
+    Throw,
     
     // Built-in functions (will move to stdlib later)
     PrintR,
@@ -61,14 +68,23 @@ pub enum Token {
     
     // Literals and identifiers
     Variable(String),
+    Int(i64),
     Number(f64),
+    /// Single-quoted or nowdoc string, taken literally
     String(String),
+    /// Double-quoted or heredoc string, whose variables are interpolated
+    InterpolatedString(String),
     Identifier(String),
     
     // Operators
     Equals,
     DoubleEquals,
     NotEquals,
+    /// Identity operator '==='
+    Identical,
+    /// Non-identity operator '!=='
+    NotIdentical,
+    Not, // !
     Arrow, // =>
     LessThan,
     GreaterThan,
@@ -76,10 +92,18 @@ pub enum Token {
     GreaterOrEqual,
     /// Spaceship operator <=>
     Spaceship,
+    /// Shift left operator <<
+    ShiftLeft,
+    /// Shift right operator >>
+    ShiftRight,
     Plus,
     Minus,
     Multiply,
+    /// Exponentiation operator '**'
+    Power,
     Divide,
+    /// Modulo operator '%'
+    Percent,
     Dot,
     Colon,
     QuestionMark,
@@ -92,14 +116,28 @@ pub enum Token {
     Ampersand,
     /// Object operator '->'
     ObjectOperator,
+    /// Nullsafe object operator '?->'
+    NullsafeObjectOperator,
+    /// Scope resolution operator '::'
+    DoubleColon,
     /// Pipe '|' for union types (currently skipped by parser)
     Pipe,
+    /// Caret '^' (bitwise XOR)
+    Caret,
     /// Logical AND '&&'
     LogicalAnd,
     /// Logical OR '||'
     LogicalOr,
+    /// Low-precedence logical AND keyword 'and'
+    KeywordAnd,
+    /// Low-precedence logical OR keyword 'or'
+    KeywordOr,
+    /// Low-precedence logical XOR keyword 'xor'
+    KeywordXor,
     /// Ellipsis '...' for variadics/spread (currently skipped by parser)
     Ellipsis,
+    /// Bare '$' introducing a variable-variable ($$name or ${expr})
+    Dollar,
     
     // Punctuation
     Semicolon,
@@ -126,24 +164,89 @@ impl Token {
             Token::Var | Token::Const | Token::True | Token::False | Token::Null |
             Token::Isset | Token::Empty | Token::Switch | Token::Case |
             Token::Default | Token::Break | Token::Continue | Token::Do |
-            Token::Try | Token::Catch
+            Token::Try | Token::Catch | Token::Finally | Token::Throw | Token::Match |
+            Token::Fn | Token::Yield
         )
     }
     
+    /// Source spelling of a reserved word token (keywords, `and`/`or`/`xor` and the
+    /// built-in function names the lexer recognises), used where PHP accepts them as
+    /// identifiers, such as method and property names
+    pub fn keyword_text(&self) -> Option<&'static str> {
+        Some(match self {
+            Token::Echo => "echo",
+            Token::Print => "print",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::ElseIf => "elseif",
+            Token::While => "while",
+            Token::For => "for",
+            Token::Foreach => "foreach",
+            Token::Function => "function",
+            Token::Return => "return",
+            Token::Class => "class",
+            Token::Extends => "extends",
+            Token::Implements => "implements",
+            Token::New => "new",
+            Token::Public => "public",
+            Token::Private => "private",
+            Token::Protected => "protected",
+            Token::Static => "static",
+            Token::Var => "var",
+            Token::Const => "const",
+            Token::As => "as",
+            Token::True => "true",
+            Token::False => "false",
+            Token::Null => "null",
+            Token::Isset => "isset",
+            Token::Empty => "empty",
+            Token::Switch => "switch",
+            Token::Case => "case",
+            Token::Default => "default",
+            Token::Break => "break",
+            Token::Continue => "continue",
+            Token::Do => "do",
+            Token::Declare => "declare",
+            Token::Match => "match",
+            Token::Fn => "fn",
+            Token::Yield => "yield",
+            Token::Try => "try",
+            Token::Catch => "catch",
+            Token::Finally => "finally",
+            Token::Throw => "throw",
+            Token::KeywordAnd => "and",
+            Token::KeywordOr => "or",
+            Token::KeywordXor => "xor",
+            Token::PrintR => "print_r",
+            Token::Strlen => "strlen",
+            Token::Strpos => "strpos",
+            Token::Substr => "substr",
+            Token::ArrayPush => "array_push",
+            Token::ArrayPop => "array_pop",
+            Token::ArrayMerge => "array_merge",
+            Token::InArray => "in_array",
+            Token::Explode => "explode",
+            Token::Implode => "implode",
+            Token::Count => "count",
+            _ => return None,
+        })
+    }
+
     /// Returns true if this token represents an operator
     pub fn is_operator(&self) -> bool {
         matches!(self,
-            Token::Equals | Token::Plus | Token::Minus | Token::Multiply |
-            Token::Divide | Token::Dot | Token::Colon | Token::QuestionMark |
+            Token::Equals | Token::Plus | Token::Minus | Token::Multiply | Token::Power |
+            Token::Divide | Token::Percent | Token::Dot | Token::Colon | Token::QuestionMark |
             Token::NullCoalescing | Token::Arrow | Token::Increment | Token::Decrement |
-            Token::LogicalAnd | Token::LogicalOr | Token::Ampersand | Token::Pipe
+            Token::LogicalAnd | Token::LogicalOr | Token::Ampersand | Token::Pipe | Token::Caret | Token::ShiftLeft | Token::ShiftRight |
+            Token::KeywordAnd | Token::KeywordOr | Token::KeywordXor
         )
     }
     
     /// Returns true if this token represents a literal value
     pub fn is_literal(&self) -> bool {
         matches!(self,
-            Token::Number(_) | Token::String(_) | Token::True | Token::False | Token::Null
+            Token::Int(_) | Token::Number(_) | Token::String(_) | Token::InterpolatedString(_) | Token::True | Token::False | Token::Null
         )
     }
 }
@@ -153,30 +256,48 @@ impl std::fmt::Display for Token {
         match self {
             Token::PhpOpen => write!(f, "<?php"),
             Token::PhpClose => write!(f, "?>"),
+            Token::InlineHtml(html) => write!(f, "{}", html),
             Token::Echo => write!(f, "echo"),
             Token::Print => write!(f, "print"),
             Token::Variable(name) => write!(f, "${}", name),
+            Token::Int(n) => write!(f, "{}", n),
             Token::Number(n) => write!(f, "{}", n),
-            Token::String(s) => write!(f, "\"{}\"", s),
+            Token::String(s) => write!(f, "'{}'", s),
+            Token::InterpolatedString(s) => write!(f, "\"{}\"", s),
             Token::Equals => write!(f, "="),
+            Token::Not => write!(f, "!"),
             Token::Arrow => write!(f, "=>"),
             Token::Plus => write!(f, "+"),
             Token::Minus => write!(f, "-"),
             Token::Multiply => write!(f, "*"),
+            Token::Power => write!(f, "**"),
             Token::Divide => write!(f, "/"),
+            Token::Percent => write!(f, "%"),
             Token::Dot => write!(f, "."),
             Token::Colon => write!(f, ":"),
+            Token::DoubleColon => write!(f, "::"),
             Token::QuestionMark => write!(f, "?"),
             Token::NullCoalescing => write!(f, "??"),
+            Token::NullsafeObjectOperator => write!(f, "?->"),
             Token::Increment => write!(f, "++"),
             Token::Decrement => write!(f, "--"),
             Token::At => write!(f, "@"),
             Token::Ampersand => write!(f, "&"),
             Token::Pipe => write!(f, "|"),
+            Token::Caret => write!(f, "^"),
+            Token::ShiftLeft => write!(f, "<<"),
+            Token::ShiftRight => write!(f, ">>"),
             Token::LogicalAnd => write!(f, "&&"),
             Token::LogicalOr => write!(f, "||"),
+            Token::KeywordAnd => write!(f, "and"),
+            Token::KeywordOr => write!(f, "or"),
+            Token::KeywordXor => write!(f, "xor"),
             Token::Ellipsis => write!(f, "..."),
+            Token::Dollar => write!(f, "$"),
             Token::Declare => write!(f, "declare"),
+            Token::Match => write!(f, "match"),
+            Token::Fn => write!(f, "fn"),
+            Token::Yield => write!(f, "yield"),
             Token::Semicolon => write!(f, ";"),
             Token::OpenParen => write!(f, "("),
             Token::CloseParen => write!(f, ")"),
@@ -34,16 +34,27 @@ fn test_numbers() {
     
     // Find number tokens
     let number_tokens: Vec<&Token> = tokens.iter()
-        .filter(|t| matches!(t, Token::Number(_)))
+        .filter(|t| matches!(t, Token::Int(_) | Token::Number(_)))
         .collect();
     
-    assert_eq!(number_tokens.len(), 2);
-    if let Token::Number(n) = number_tokens[0] {
-        assert_eq!(n, &25.0);
-    }
-    if let Token::Number(n) = number_tokens[1] {
-        assert_eq!(n, &99.99);
-    }
+    assert_eq!(number_tokens, vec![&Token::Int(25), &Token::Number(99.99)]);
+}
+
+#[test]
+fn test_integer_and_float_literals() {
+    let tokens = lex("<?php 10; 10.5; 1e3; 0.0; 2E-2; 9223372036854775808;").expect("Failed to lex input");
+    let numbers: Vec<Token> = tokens.into_iter()
+        .filter(|t| matches!(t, Token::Int(_) | Token::Number(_)))
+        .collect();
+
+    assert_eq!(numbers, vec![
+        Token::Int(10),
+        Token::Number(10.5),
+        Token::Number(1000.0),
+        Token::Number(0.0),
+        Token::Number(0.02),
+        Token::Number(9223372036854775808.0),
+    ]);
 }
 
 #[test]
@@ -88,3 +99,137 @@ fn test_keywords() {
     // Should have: if, return, true, else, return, false = 6 keywords
     assert_eq!(keyword_tokens.len(), 6);
 }
+
+#[test]
+fn test_variable_variable_tokens() {
+    let tokens = lex("<?php $$name; ${'x'};").expect("Failed to lex input");
+
+    assert_eq!(tokens[1], Token::Dollar);
+    assert_eq!(tokens[2], Token::Variable("name".to_string()));
+    assert_eq!(tokens[4], Token::Dollar);
+    assert_eq!(tokens[5], Token::OpenBrace);
+}
+
+#[test]
+fn test_keyword_logical_operator_tokens() {
+    let tokens = lex("<?php $a and $b or $c xor $d; A::b();").expect("Failed to lex input");
+
+    assert_eq!(tokens[2], Token::KeywordAnd);
+    assert_eq!(tokens[4], Token::KeywordOr);
+    assert_eq!(tokens[6], Token::KeywordXor);
+    assert_eq!(tokens[10], Token::DoubleColon);
+}
+
+#[test]
+fn test_modulo_token() {
+    let tokens = lex("<?php $a % 3;").expect("Failed to lex");
+    assert!(tokens.contains(&Token::Percent));
+}
+
+#[test]
+fn test_prefixed_integer_literals() {
+    let tokens = lex("<?php 0xff; 0XFF; 0b1111; 0o755; 017; 0; 0x7FFFFFFFFFFFFFFF;").expect("Failed to lex input");
+    let numbers: Vec<Token> = tokens.into_iter()
+        .filter(|t| matches!(t, Token::Int(_) | Token::Number(_)))
+        .collect();
+
+    assert_eq!(numbers, vec![
+        Token::Int(255),
+        Token::Int(255),
+        Token::Int(15),
+        Token::Int(493),
+        Token::Int(15),
+        Token::Int(0),
+        Token::Int(i64::MAX),
+    ]);
+
+    let error = lex("<?php 0xG1;").unwrap_err();
+    assert!(matches!(error, LexError::InvalidDigit { digit: 'G', base: "hexadecimal", .. }), "{:?}", error);
+    assert!(matches!(lex("<?php 019;").unwrap_err(), LexError::InvalidDigit { digit: '9', base: "octal", .. }));
+}
+
+#[test]
+fn test_numeric_separators() {
+    let tokens = lex("<?php 1_234_567; 1.234_56; 0xDEAD_BEEF; 0b1010_1010; 1_0e1_0;").expect("Failed to lex input");
+    let numbers: Vec<Token> = tokens.into_iter()
+        .filter(|t| matches!(t, Token::Int(_) | Token::Number(_)))
+        .collect();
+
+    assert_eq!(numbers, vec![
+        Token::Int(1_234_567),
+        Token::Number(1.234_56),
+        Token::Int(0xDEAD_BEEF),
+        Token::Int(0b1010_1010),
+        Token::Number(10e10),
+    ]);
+
+    for invalid in ["<?php 100_;", "<?php 1__0;", "<?php 1_.5;", "<?php 0x_FF;"] {
+        assert!(matches!(lex(invalid), Err(LexError::InvalidNumber { .. })), "{} should not lex", invalid);
+    }
+    // A leading underscore makes an identifier, as in PHP
+    assert_eq!(lex("<?php _100;").expect("Failed to lex input")[1], Token::Identifier("_100".to_string()));
+}
+
+#[test]
+fn test_shift_operators() {
+    let tokens = lex("<?php 1 << 4; 256 >> 2; $x <<= 1; echo <<<EOT\nhi\nEOT;\n").expect("Failed to lex input");
+    assert_eq!(tokens.iter().filter(|t| **t == Token::ShiftLeft).count(), 2);
+    assert_eq!(tokens.iter().filter(|t| **t == Token::ShiftRight).count(), 1);
+    assert!(tokens.iter().any(|t| matches!(t, Token::InterpolatedString(s) if s.starts_with("hi"))));
+}
+
+#[test]
+fn test_inline_html_after_close_tag() {
+    let tokens = lex("<?php echo 1; ?>\n middle <?php echo 2; ?>").expect("Failed to lex input");
+    assert_eq!(tokens[4], Token::PhpClose);
+    // The newline directly after ?> is swallowed, as in PHP
+    assert_eq!(tokens[5], Token::InlineHtml(" middle ".to_string()));
+    assert_eq!(tokens[6], Token::PhpOpen);
+    assert_eq!(tokens[tokens.len() - 2], Token::PhpClose);
+}
+
+#[test]
+fn test_single_quoted_strings_keep_backslashes() {
+    let tokens = lex(r#"<?php '\n\t\\\'$x'; "\n$x"; <<<'EOT'
+raw $x
+EOT;
+"#).expect("Failed to lex input");
+    assert_eq!(tokens[1], Token::String(r"\n\t\'$x".to_string()));
+    assert_eq!(tokens[3], Token::InterpolatedString("\n$x".to_string()));
+    assert_eq!(tokens[5], Token::String("raw $x\n".to_string()));
+}
+
+#[test]
+fn test_hex_octal_and_unicode_escapes() {
+    let decoded = |src: &str| match lex(src).expect("Failed to lex input").remove(1) {
+        Token::InterpolatedString(s) => s,
+        other => panic!("Expected a string, got {:?}", other),
+    };
+    assert_eq!(decoded(r#"<?php "\x41";"#), "A");
+    assert_eq!(decoded(r#"<?php "\u{48}\u{49}";"#), "HI");
+    assert_eq!(decoded(r#"<?php "\101\0";"#), "A\0");
+    assert_eq!(decoded(r#"<?php "\u{1F600}";"#), "\u{1F600}");
+    // Invalid sequences stay literal
+    assert_eq!(decoded(r#"<?php "\xZZ \u41 \u{}";"#), r"\xZZ \u41 \u{}");
+}
+
+#[test]
+fn test_byte_escapes_and_escaped_dollars() {
+    let decoded = |src: &str| match lex(src).expect("Failed to lex input").remove(1) {
+        Token::InterpolatedString(s) => s,
+        other => panic!("Expected a string, got {:?}", other),
+    };
+    // Byte escapes are assembled and read as UTF-8
+    assert_eq!(decoded(r#"<?php "\xE2\x82\xAC \342\202\254";"#), "€ €");
+    // Strings are UTF-8, so a lone byte that is not valid UTF-8 becomes U+FFFD
+    assert_eq!(decoded(r#"<?php "\xFF";"#), "\u{FFFD}");
+    // An escaped `$` gets an odd run of backslashes; backslashes before a `$` are doubled
+    assert_eq!(decoded(r#"<?php "\$a \x24b \\$c \\";"#), r"\$a \$b \\$c \");
+}
+
+#[test]
+fn test_equality_and_identity_operators() {
+    let tokens = lex("<?php $a === $b !== $c == $d != $e = $f;").expect("Failed to lex input");
+    let operators: Vec<&Token> = tokens.iter().filter(|t| !matches!(t, Token::PhpOpen | Token::Variable(_) | Token::Semicolon | Token::EOF)).collect();
+    assert_eq!(operators, vec![&Token::Identical, &Token::NotIdentical, &Token::DoubleEquals, &Token::NotEquals, &Token::Equals]);
+}
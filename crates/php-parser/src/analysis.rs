@@ -0,0 +1,523 @@
+//! Static analysis of a parsed program
+//!
+//! A best-effort lint pass for the playground: it reports variables read before
+//! any assignment in their scope, calls to functions that are neither defined by
+//! the script nor supplied by the caller, and statements that can never run.
+//! The pass is deliberately lenient: an assignment anywhere earlier in a scope
+//! (or anywhere inside an enclosing loop) counts as defining the variable.
+
+use crate::ast::{ArrayElement, ClassMember, ClassName, DestructTarget, Expr, MemberName, Param, Stmt, UnaryOp};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Variables PHP defines in every scope
+const SUPERGLOBALS: &[&str] = &["GLOBALS", "_SERVER", "_GET", "_POST", "_FILES", "_COOKIE", "_SESSION", "_REQUEST", "_ENV"];
+
+/// Variables additionally defined in the global scope of a CLI script
+const SCRIPT_GLOBALS: &[&str] = &["argv", "argc"];
+
+/// Native functions writing to a by-reference argument: (function, argument index)
+const BY_REF_ARGUMENTS: &[(&str, usize)] = &[
+    ("preg_match", 2), ("preg_match_all", 2), ("parse_str", 1), ("str_replace", 3), ("str_ireplace", 3),
+];
+
+/// Functions that make the set of variables in a scope impossible to track
+const DYNAMIC_SCOPE_FUNCTIONS: &[&str] = &["extract", "compact", "get_defined_vars", "eval"];
+
+/// Problem found by [`analyze`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    /// Variable read before any assignment in its scope (name without '$')
+    UndefinedVariable(String),
+    /// Call to a function that is not defined or known
+    UnknownFunction(String),
+    /// Statement following one that always leaves the block (`return`, `throw`, ...)
+    UnreachableCode {
+        /// Keyword of the statement that leaves the block
+        after: &'static str,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::UndefinedVariable(name) => write!(f, "Undefined variable ${}", name),
+            Diagnostic::UnknownFunction(name) => write!(f, "Call to undefined function {}()", name),
+            Diagnostic::UnreachableCode { after } => write!(f, "Unreachable code after {} statement", after),
+        }
+    }
+}
+
+/// Analyze a program, treating only functions it defines itself as known
+///
+/// Use [`analyze_with_functions`] to also accept the runtime's native functions.
+pub fn analyze(program: &Stmt) -> Vec<Diagnostic> {
+    analyze_with_functions(program, &[])
+}
+
+/// Analyze a program, additionally accepting calls to the given native functions
+pub fn analyze_with_functions(program: &Stmt, native_functions: &[&str]) -> Vec<Diagnostic> {
+    let mut functions: HashSet<String> = native_functions.iter().map(|name| name.to_ascii_lowercase()).collect();
+    collect_functions(program, &mut functions);
+    let mut analyzer = Analyzer { functions, diagnostics: Vec::new(), scopes: Vec::new() };
+    let globals = SUPERGLOBALS.iter().chain(SCRIPT_GLOBALS).map(|name| name.to_string()).collect();
+    analyzer.scopes.push(Scope { defined: globals, dynamic: false });
+    analyzer.stmt(program);
+    analyzer.diagnostics
+}
+
+/// Record every function declared anywhere in the program; declarations are hoisted
+fn collect_functions(stmt: &Stmt, functions: &mut HashSet<String>) {
+    match stmt {
+        Stmt::FunctionDefinition { name, body, .. } => {
+            functions.insert(name.to_ascii_lowercase());
+            collect_functions(body, functions);
+        }
+        Stmt::Block(stmts) => stmts.iter().for_each(|stmt| collect_functions(stmt, functions)),
+        Stmt::If { then_stmt, else_stmt, .. } => {
+            collect_functions(then_stmt, functions);
+            if let Some(else_stmt) = else_stmt {
+                collect_functions(else_stmt, functions);
+            }
+        }
+        Stmt::While { body, .. } | Stmt::DoWhile { body, .. } | Stmt::For { body, .. } | Stmt::Foreach { body, .. } => collect_functions(body, functions),
+        Stmt::Try { body, catches, finally } => {
+            collect_functions(body, functions);
+            catches.iter().for_each(|clause| collect_functions(&clause.body, functions));
+            if let Some(finally) = finally {
+                collect_functions(finally, functions);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Record every variable a statement may assign, without reporting anything
+fn collect_assigned(stmt: &Stmt, names: &mut HashSet<String>) {
+    match stmt {
+        Stmt::Assignment { variable, .. } | Stmt::NullCoalesceAssign { variable, .. } => { names.insert(variable.clone()); }
+        Stmt::StaticVar { name, .. } => { names.insert(name.clone()); }
+        Stmt::Expression(expr) => collect_assigned_expr(expr, names),
+        Stmt::Block(stmts) => stmts.iter().for_each(|stmt| collect_assigned(stmt, names)),
+        Stmt::If { then_stmt, else_stmt, .. } => {
+            collect_assigned(then_stmt, names);
+            if let Some(else_stmt) = else_stmt {
+                collect_assigned(else_stmt, names);
+            }
+        }
+        Stmt::While { body, .. } | Stmt::DoWhile { body, .. } => collect_assigned(body, names),
+        Stmt::For { init, body, .. } => {
+            if let Some(init) = init {
+                collect_assigned(init, names);
+            }
+            collect_assigned(body, names);
+        }
+        Stmt::Foreach { value_var, key_var, body, .. } => {
+            names.insert(value_var.clone());
+            names.extend(key_var.iter().cloned());
+            collect_assigned(body, names);
+        }
+        Stmt::DestructuringAssignment { targets, .. } => names.extend(targets.iter().map(destruct_name)),
+        Stmt::Try { body, catches, finally } => {
+            collect_assigned(body, names);
+            for clause in catches {
+                names.extend(clause.variable.clone());
+                collect_assigned(&clause.body, names);
+            }
+            if let Some(finally) = finally {
+                collect_assigned(finally, names);
+            }
+        }
+        Stmt::Switch { cases, default, .. } => {
+            cases.iter().flat_map(|case| &case.statements).for_each(|stmt| collect_assigned(stmt, names));
+            default.iter().flatten().for_each(|stmt| collect_assigned(stmt, names));
+        }
+        _ => {}
+    }
+}
+
+fn collect_assigned_expr(expr: &Expr, names: &mut HashSet<String>) {
+    if let Expr::Assign { target, value } = expr {
+        if let Some(name) = base_variable(target) {
+            names.insert(name.to_string());
+        }
+        collect_assigned_expr(value, names);
+    }
+}
+
+fn destruct_name(target: &DestructTarget) -> String {
+    match target {
+        DestructTarget::Var(name) | DestructTarget::KeyVar(_, name) => name.clone(),
+    }
+}
+
+/// Variable at the root of an assignable chain: `$a`, `$a[1]['x']`, `$a->b`
+fn base_variable(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::Variable(name) => Some(name),
+        Expr::ArrayAccess { array, .. } => base_variable(array),
+        Expr::PropertyAccess { target, .. } => base_variable(target),
+        _ => None,
+    }
+}
+
+/// Leaving keyword of a statement that never completes normally
+fn terminator(stmt: &Stmt) -> Option<&'static str> {
+    match stmt {
+        Stmt::Return(_) => Some("return"),
+        Stmt::Break => Some("break"),
+        Stmt::Continue => Some("continue"),
+        Stmt::Expression(Expr::Throw(_)) => Some("throw"),
+        _ => None,
+    }
+}
+
+/// Variables known to be defined in one function body or the global scope
+struct Scope {
+    defined: HashSet<String>,
+    /// Variables can appear by name at runtime (`$$name`, `extract()`), so none are reported
+    dynamic: bool,
+}
+
+struct Analyzer {
+    /// Known function names, lowercased
+    functions: HashSet<String>,
+    diagnostics: Vec<Diagnostic>,
+    scopes: Vec<Scope>,
+}
+
+impl Analyzer {
+    fn scope(&mut self) -> &mut Scope {
+        self.scopes.last_mut().expect("analyzer always has a scope")
+    }
+
+    fn define(&mut self, name: &str) {
+        self.scope().defined.insert(name.to_string());
+    }
+
+    fn read(&mut self, name: &str) {
+        let scope = self.scope();
+        if !scope.dynamic && !scope.defined.contains(name) {
+            scope.defined.insert(name.to_string()); // report each variable once
+            self.diagnostics.push(Diagnostic::UndefinedVariable(name.to_string()));
+        }
+    }
+
+    /// Analyze a function body in a fresh scope seeded with the given variables
+    fn function_body(&mut self, params: &[Param], inherited: impl IntoIterator<Item = String>, body: &Stmt) {
+        let mut defined: HashSet<String> = SUPERGLOBALS.iter().map(|name| name.to_string()).collect();
+        defined.extend(inherited);
+        for param in params {
+            if let Some(default) = &param.default {
+                self.expr(default);
+            }
+            defined.insert(param.name.clone());
+        }
+        self.scopes.push(Scope { defined, dynamic: false });
+        self.stmt(body);
+        self.scopes.pop();
+    }
+
+    /// Analyze property defaults, constants and method bodies of a class
+    fn class_members(&mut self, members: &[ClassMember]) {
+        for member in members {
+            match member {
+                ClassMember::Property { default: Some(value), .. } | ClassMember::Constant { value, .. } => self.expr(value),
+                ClassMember::Property { default: None, .. } => {}
+                ClassMember::Method { parameters, body, is_static, .. } => {
+                    let this = (!is_static).then(|| "this".to_string());
+                    self.function_body(parameters, this, body);
+                }
+            }
+        }
+    }
+
+    fn block(&mut self, stmts: &[Stmt]) {
+        let mut reported = false;
+        let mut left_by = None;
+        for stmt in stmts {
+            if let (Some(after), false) = (left_by, reported) {
+                if !matches!(stmt, Stmt::FunctionDefinition { .. } | Stmt::ClassDefinition { .. } | Stmt::Nop) {
+                    self.diagnostics.push(Diagnostic::UnreachableCode { after });
+                    reported = true;
+                }
+            }
+            self.stmt(stmt);
+            left_by = left_by.or_else(|| terminator(stmt));
+        }
+    }
+
+    /// Treat anything a loop body assigns as defined from the first iteration on
+    fn predefine_loop(&mut self, body: &Stmt) {
+        let mut assigned = HashSet::new();
+        collect_assigned(body, &mut assigned);
+        self.scope().defined.extend(assigned);
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression(expr) | Stmt::Echo(expr) | Stmt::Print(expr) => self.expr(expr),
+            Stmt::Assignment { variable, value } => {
+                self.expr(value);
+                self.define(variable);
+            }
+            Stmt::NullCoalesceAssign { variable, value } => {
+                self.expr(value);
+                self.define(variable);
+            }
+            Stmt::ConstantDefinition { value, .. } => self.expr(value),
+            Stmt::Block(stmts) => self.block(stmts),
+            Stmt::If { condition, then_stmt, else_stmt } => {
+                self.expr(condition);
+                self.stmt(then_stmt);
+                if let Some(else_stmt) = else_stmt {
+                    self.stmt(else_stmt);
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.predefine_loop(body);
+                self.expr(condition);
+                self.stmt(body);
+            }
+            Stmt::DoWhile { body, condition } => {
+                self.predefine_loop(body);
+                self.stmt(body);
+                self.expr(condition);
+            }
+            Stmt::For { init, condition, increment, body } => {
+                if let Some(init) = init {
+                    self.stmt(init);
+                }
+                self.predefine_loop(body);
+                if let Some(condition) = condition {
+                    self.expr(condition);
+                }
+                self.stmt(body);
+                if let Some(increment) = increment {
+                    self.expr(increment);
+                }
+            }
+            Stmt::Foreach { array, value_var, key_var, body } => {
+                self.expr(array);
+                self.define(value_var);
+                if let Some(key_var) = key_var {
+                    self.define(key_var);
+                }
+                self.predefine_loop(body);
+                self.stmt(body);
+            }
+            Stmt::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Stmt::Break | Stmt::Continue | Stmt::Nop | Stmt::InlineHtml(_) => {}
+            Stmt::FunctionDefinition { parameters, body, .. } => self.function_body(parameters, None, body),
+            Stmt::Switch { expression, cases, default } => {
+                self.expr(expression);
+                for case in cases {
+                    self.expr(&case.value);
+                    self.block(&case.statements);
+                }
+                if let Some(default) = default {
+                    self.block(default);
+                }
+            }
+            Stmt::DestructuringAssignment { targets, value } => {
+                self.expr(value);
+                for target in targets {
+                    if let DestructTarget::KeyVar(key, _) = target {
+                        self.expr(key);
+                    }
+                    self.define(&destruct_name(target));
+                }
+            }
+            Stmt::StaticVar { name, initial } => {
+                if let Some(initial) = initial {
+                    self.expr(initial);
+                }
+                self.define(name);
+            }
+            Stmt::Try { body, catches, finally } => {
+                self.stmt(body);
+                for clause in catches {
+                    if let Some(variable) = &clause.variable {
+                        self.define(variable);
+                    }
+                    self.stmt(&clause.body);
+                }
+                if let Some(finally) = finally {
+                    self.stmt(finally);
+                }
+            }
+            Stmt::ClassDefinition { members, .. } => self.class_members(members),
+        }
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) {
+        exprs.iter().for_each(|expr| self.expr(expr));
+    }
+
+    /// Analyze an expression that is written to rather than read
+    fn target(&mut self, target: &Expr) {
+        match target {
+            Expr::Variable(name) => self.define(name),
+            Expr::ArrayAccess { array, index } => {
+                self.expr(index);
+                self.target(array);
+            }
+            Expr::PropertyAccess { target, property } => {
+                self.member(property);
+                self.target(target);
+            }
+            Expr::Array(elements) => {
+                for ArrayElement { key, value } in elements {
+                    if let Some(key) = key {
+                        self.expr(key);
+                    }
+                    self.target(value);
+                }
+            }
+            other => self.expr(other),
+        }
+    }
+
+    fn class_name(&mut self, class: &ClassName) {
+        match class {
+            ClassName::Dynamic(class) => self.expr(class),
+            ClassName::Anonymous { members, .. } => self.class_members(members),
+            ClassName::Identifier(_) => {}
+        }
+    }
+
+    fn member(&mut self, member: &MemberName) {
+        if let MemberName::Dynamic(expr) = member {
+            self.expr(expr);
+        }
+    }
+
+    fn call_args(&mut self, function: Option<&str>, args: &[Expr]) {
+        for (index, arg) in args.iter().enumerate() {
+            let by_ref = function.is_some_and(|name| {
+                BY_REF_ARGUMENTS.iter().any(|(by_ref_fn, by_ref_index)| name.eq_ignore_ascii_case(by_ref_fn) && *by_ref_index == index)
+            });
+            if by_ref {
+                self.target(arg);
+            } else {
+                self.expr(arg);
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Variable(name) => self.read(name),
+            Expr::VariableVariable(inner) => {
+                self.scope().dynamic = true;
+                self.expr(inner);
+            }
+            Expr::Assign { target, value } => {
+                self.expr(value);
+                self.target(target);
+            }
+            Expr::Constant(_) | Expr::Number(_) | Expr::Int(_) | Expr::String(_) | Expr::InterpolatedString(_) | Expr::Bool(_) | Expr::Null => {}
+            Expr::ClassConstant { class, .. } | Expr::StaticProperty { class, .. } => self.class_name(class),
+            Expr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Yield { key: Some(key), value } => {
+                self.expr(key);
+                self.expr(value);
+            }
+            Expr::Yield { key: None, value } | Expr::YieldFrom(value) | Expr::Throw(value) | Expr::Spread(value) | Expr::NamedArgument { value, .. } => self.expr(value),
+            Expr::MethodCall { target, method, args } | Expr::NullsafeMethodCall { target, method, args } => {
+                self.expr(target);
+                self.member(method);
+                self.exprs(args);
+            }
+            Expr::PropertyAccess { target, property } | Expr::NullsafePropertyAccess { target, property } => {
+                self.expr(target);
+                self.member(property);
+            }
+            Expr::StaticCall { class, args, .. } | Expr::New { class, args } => {
+                self.class_name(class);
+                self.exprs(args);
+            }
+            Expr::Unary { op, operand } => match op {
+                // Incrementing an undefined variable is a common (if sloppy) way to start a counter
+                UnaryOp::PreIncrement | UnaryOp::PostIncrement | UnaryOp::PreDecrement | UnaryOp::PostDecrement => self.target(operand),
+                UnaryOp::Minus | UnaryOp::Plus | UnaryOp::Not => self.expr(operand),
+            },
+            Expr::FunctionCall { name, args } => {
+                if name.eq_ignore_ascii_case("isset") {
+                    return; // isset() exists to probe possibly undefined variables
+                }
+                if DYNAMIC_SCOPE_FUNCTIONS.iter().any(|dynamic| name.eq_ignore_ascii_case(dynamic)) {
+                    self.scope().dynamic = true;
+                }
+                if !self.functions.contains(&name.to_ascii_lowercase()) {
+                    self.diagnostics.push(Diagnostic::UnknownFunction(name.clone()));
+                }
+                self.call_args(Some(name), args);
+            }
+            Expr::Array(elements) => {
+                for ArrayElement { key, value } in elements {
+                    if let Some(key) = key {
+                        self.expr(key);
+                    }
+                    self.expr(value);
+                }
+            }
+            Expr::ArrayAccess { array, index } => {
+                self.expr(array);
+                self.expr(index);
+            }
+            Expr::NullCoalesce { left, right } => {
+                // The left operand may legitimately be undefined
+                if base_variable(left).is_none() {
+                    self.expr(left);
+                }
+                self.expr(right);
+            }
+            Expr::ArrowFunction { params, body } => {
+                let inherited: Vec<String> = self.scope().defined.iter().cloned().collect();
+                self.function_body(params, inherited, &Stmt::Return(Some((**body).clone())));
+            }
+            Expr::Closure { params, uses, body } => {
+                let mut inherited = Vec::new();
+                for captured in uses {
+                    if !captured.by_ref {
+                        self.read(&captured.name);
+                    }
+                    inherited.push(captured.name.clone());
+                }
+                if self.scope().defined.contains("this") {
+                    inherited.push("this".to_string());
+                }
+                self.function_body(params, inherited, body);
+            }
+            Expr::DynamicCall { target, args } => {
+                self.expr(target);
+                self.call_args(None, args);
+            }
+            Expr::Ternary { condition, then_expr, else_expr } => {
+                self.expr(condition);
+                if let Some(then_expr) = then_expr {
+                    self.expr(then_expr);
+                }
+                self.expr(else_expr);
+            }
+            Expr::Match { subject, arms, default_arm } => {
+                self.expr(subject);
+                for (conditions, result) in arms {
+                    self.exprs(conditions);
+                    self.expr(result);
+                }
+                if let Some(default_arm) = default_arm {
+                    self.expr(default_arm);
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 //! Expression AST nodes
 
+use super::{Param, Stmt};
 use std::fmt;
 
 /// Represents PHP expressions
@@ -7,12 +8,25 @@ use std::fmt;
 pub enum Expr {
     /// Variable reference: $var
     Variable(String),
+    /// Variable-variable: $$name or ${expr}; the inner expression yields the variable name
+    VariableVariable(Box<Expr>),
+    /// Assignment expression: target = value (targets beyond plain variables)
+    Assign {
+        /// Assignment target (variable, variable-variable, ...)
+        target: Box<Expr>,
+        /// Assigned value
+        value: Box<Expr>,
+    },
     /// Constant reference: CONSTANT_NAME
     Constant(String),
-    /// Numeric literal: 42, 3.14
+    /// Float literal: 3.14, 1e3
     Number(f64),
-    /// String literal: "hello"
+    /// Integer literal: 42
+    Int(i64),
+    /// String literal: 'hello', or a double-quoted one without variables
     String(String),
+    /// Double-quoted string interpolated at runtime: "hello $name"
+    InterpolatedString(String),
     /// Boolean literal: true, false
     Bool(bool),
     /// Null literal
@@ -26,20 +40,77 @@ pub enum Expr {
         /// Right operand
         right: Box<Expr>,
     },
-    /// Yield expression (simplified, no generator state): yield expr; or yield from expr;
+    /// Yield expression: yield expr; or yield key => expr; (a bare `yield;` yields null)
     Yield {
-        /// Inner expression value (ignored for now)
+        /// Explicit key; without one the generator numbers its values
+        key: Option<Box<Expr>>,
+        /// Yielded value
         value: Box<Expr>,
     },
+    /// Delegating yield: yield from expr;
+    YieldFrom(Box<Expr>),
     /// Method call: target->method(args)
     MethodCall {
         /// Target expression
         target: Box<Expr>,
         /// Method name
+        method: MemberName,
+        /// Arguments
+        args: Vec<Expr>,
+    },
+    /// Property fetch: target->property
+    PropertyAccess {
+        /// Target expression
+        target: Box<Expr>,
+        /// Property name
+        property: MemberName,
+    },
+    /// Nullsafe method call: target?->method(args)
+    NullsafeMethodCall {
+        /// Target expression
+        target: Box<Expr>,
+        /// Method name
+        method: MemberName,
+        /// Arguments
+        args: Vec<Expr>,
+    },
+    /// Nullsafe property fetch: target?->property
+    NullsafePropertyAccess {
+        /// Target expression
+        target: Box<Expr>,
+        /// Property name
+        property: MemberName,
+    },
+    /// Static method call: ClassName::method(args), self::method(args), $class::method(args)
+    StaticCall {
+        /// Class name as written (may be self, parent or static) or computed at runtime
+        class: ClassName,
+        /// Method name
         method: String,
         /// Arguments
         args: Vec<Expr>,
     },
+    /// Class constant fetch: ClassName::CONSTANT, self::CONSTANT, ClassName::class, $class::CONSTANT
+    ClassConstant {
+        /// Class name as written (may be self, parent or static) or computed at runtime
+        class: ClassName,
+        /// Constant name
+        name: String,
+    },
+    /// Static property fetch: ClassName::$property, self::$property, $class::$property
+    StaticProperty {
+        /// Class name as written (may be self, parent or static) or computed at runtime
+        class: ClassName,
+        /// Property name without '$'
+        name: String,
+    },
+    /// Object instantiation: new ClassName(args) or new $class(args)
+    New {
+        /// Class name
+        class: ClassName,
+        /// Constructor arguments
+        args: Vec<Expr>,
+    },
     /// Unary operation: -$a, !$b
     Unary {
         /// Operator
@@ -54,6 +125,17 @@ pub enum Expr {
         /// Arguments
         args: Vec<Expr>,
     },
+    /// Throw expression: throw $exception
+    Throw(Box<Expr>),
+    /// Unpacking inside an array literal or argument list: [...$items], f(...$args)
+    Spread(Box<Expr>),
+    /// Named call argument: f(name: $value)
+    NamedArgument {
+        /// Parameter name without '$'
+        name: String,
+        /// Argument value
+        value: Box<Expr>,
+    },
     /// Array literal: [1, 2, 3] or array(1, 2, 3)
     Array(Vec<ArrayElement>),
     /// Array access: $arr[0] or $arr['key']
@@ -72,11 +154,20 @@ pub enum Expr {
     },
     /// Arrow function: fn(params) => expr
     ArrowFunction {
-        /// Parameter variable names
-        params: Vec<String>,
+        /// Parameters
+        params: Vec<Param>,
         /// Body expression
         body: Box<Expr>,
     },
+    /// Anonymous function: function(params) use (captures) { body }
+    Closure {
+        /// Parameters
+        params: Vec<Param>,
+        /// Variables captured from the defining scope by `use`
+        uses: Vec<Param>,
+        /// Function body
+        body: Box<Stmt>,
+    },
     /// Dynamic function/closure call: $var(...)
     DynamicCall {
         /// Variable holding closure
@@ -104,6 +195,61 @@ pub enum Expr {
     },
 }
 
+/// Class name after 'new': a literal identifier or a runtime expression (new $class, new ($expr))
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClassName {
+    /// Literal class name: new Point
+    Identifier(String),
+    /// Class name computed at runtime: new $class or new ($expr)
+    Dynamic(Box<Expr>),
+    /// Anonymous class declared in place: new class(args) extends Base { ... }
+    Anonymous {
+        /// Name generated by the parser, unique within the parsed source
+        name: String,
+        /// Optional parent class name
+        parent: Option<String>,
+        /// Interfaces listed after `implements`
+        interfaces: Vec<String>,
+        /// Properties, methods and constants
+        members: Vec<super::ClassMember>,
+    },
+}
+
+impl fmt::Display for ClassName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClassName::Identifier(name) => write!(f, "{}", name),
+            ClassName::Dynamic(expr) => match expr.as_ref() {
+                Expr::Variable(_) | Expr::PropertyAccess { .. } | Expr::ArrayAccess { .. } => write!(f, "{}", expr),
+                _ => write!(f, "({})", expr),
+            },
+            ClassName::Anonymous { parent: Some(parent), .. } => write!(f, "class extends {}", parent),
+            ClassName::Anonymous { parent: None, .. } => write!(f, "class"),
+        }
+    }
+}
+
+/// Member name after '->': a literal identifier or a runtime expression ($obj->$name, $obj->{expr})
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemberName {
+    /// Literal member name: $obj->name
+    Identifier(String),
+    /// Name computed at runtime: $obj->$name or $obj->{expr}
+    Dynamic(Box<Expr>),
+}
+
+impl fmt::Display for MemberName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemberName::Identifier(name) => write!(f, "{}", name),
+            MemberName::Dynamic(expr) => match expr.as_ref() {
+                Expr::Variable(_) => write!(f, "{}", expr),
+                _ => write!(f, "{{{}}}", expr),
+            },
+        }
+    }
+}
+
 /// Array element in array literal
 #[derive(Debug, Clone, PartialEq)]
 pub struct ArrayElement {
@@ -117,9 +263,15 @@ impl fmt::Display for Expr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Expr::Variable(name) => write!(f, "${}", name),
+            Expr::VariableVariable(inner) => match inner.as_ref() {
+                Expr::Variable(_) | Expr::VariableVariable(_) => write!(f, "${}", inner),
+                _ => write!(f, "${{{}}}", inner),
+            },
+            Expr::Assign { target, value } => write!(f, "{} = {}", target, value),
             Expr::Constant(name) => write!(f, "{}", name),
             Expr::Number(n) => write!(f, "{}", n),
-            Expr::String(s) => write!(f, "\"{}\"", s),
+            Expr::Int(n) => write!(f, "{}", n),
+            Expr::String(s) | Expr::InterpolatedString(s) => write!(f, "\"{}\"", s),
             Expr::Bool(b) => write!(f, "{}", if *b { "true" } else { "false" }),
             Expr::Null => write!(f, "null"),
             Expr::Binary { left, op, right } => write!(f, "({} {} {})", left, op, right),
@@ -148,9 +300,20 @@ impl fmt::Display for Expr {
             Expr::NullCoalesce { left, right } => write!(f, "({} ?? {})", left, right),
             Expr::ArrowFunction { params, body } => {
                 write!(f, "fn(")?;
-                for (i,p) in params.iter().enumerate() { if i>0 { write!(f, ", ")?; } write!(f, "${}", p)?; }
+                for (i,p) in params.iter().enumerate() { if i>0 { write!(f, ", ")?; } write!(f, "{}", p)?; }
                 write!(f, ") => {}", body)
             }
+            Expr::Closure { params, uses, body } => {
+                write!(f, "function(")?;
+                for (i,p) in params.iter().enumerate() { if i>0 { write!(f, ", ")?; } write!(f, "{}", p)?; }
+                write!(f, ")")?;
+                if !uses.is_empty() {
+                    write!(f, " use (")?;
+                    for (i,u) in uses.iter().enumerate() { if i>0 { write!(f, ", ")?; } write!(f, "{}", u)?; }
+                    write!(f, ")")?;
+                }
+                write!(f, " {}", body)
+            }
             Expr::DynamicCall { target, args } => {
                 write!(f, "{}(", target)?;
                 for (i,a) in args.iter().enumerate() { if i>0 { write!(f, ", ")?; } write!(f, "{}", a)?; }
@@ -169,12 +332,36 @@ impl fmt::Display for Expr {
                 if let Some(def) = default_arm { if !arms.is_empty() { write!(f, ", ")?; } write!(f, "default => {}", def)?; }
                 write!(f, " }}")
             }
-            Expr::Yield { value } => write!(f, "yield {}", value),
+            Expr::Yield { key: Some(key), value } => write!(f, "yield {} => {}", key, value),
+            Expr::Yield { key: None, value } => write!(f, "yield {}", value),
+            Expr::YieldFrom(source) => write!(f, "yield from {}", source),
             Expr::MethodCall { target, method, args } => {
                 write!(f, "{}->{}(", target, method)?;
                 for (i,a) in args.iter().enumerate() { if i>0 { write!(f, ", ")?; } write!(f, "{}", a)?; }
                 write!(f, ")")
             }
+            Expr::PropertyAccess { target, property } => write!(f, "{}->{}", target, property),
+            Expr::NullsafeMethodCall { target, method, args } => {
+                write!(f, "{}?->{}(", target, method)?;
+                for (i,a) in args.iter().enumerate() { if i>0 { write!(f, ", ")?; } write!(f, "{}", a)?; }
+                write!(f, ")")
+            }
+            Expr::NullsafePropertyAccess { target, property } => write!(f, "{}?->{}", target, property),
+            Expr::Spread(inner) => write!(f, "...{}", inner),
+            Expr::NamedArgument { name, value } => write!(f, "{}: {}", name, value),
+            Expr::Throw(inner) => write!(f, "throw {}", inner),
+            Expr::ClassConstant { class, name } => write!(f, "{}::{}", class, name),
+            Expr::StaticProperty { class, name } => write!(f, "{}::${}", class, name),
+            Expr::StaticCall { class, method, args } => {
+                write!(f, "{}::{}(", class, method)?;
+                for (i,a) in args.iter().enumerate() { if i>0 { write!(f, ", ")?; } write!(f, "{}", a)?; }
+                write!(f, ")")
+            }
+            Expr::New { class, args } => {
+                write!(f, "new {}(", class)?;
+                for (i,a) in args.iter().enumerate() { if i>0 { write!(f, ", ")?; } write!(f, "{}", a)?; }
+                write!(f, ")")
+            }
         }
     }
 }
@@ -15,12 +15,18 @@ pub enum BinaryOp {
     Divide,
     /// Modulo: %
     Modulo,
+    /// Exponentiation: ** (right associative)
+    Power,
     /// String concatenation: .
     Concatenate,
     /// Equality: ==
     Equal,
     /// Inequality: !=
     NotEqual,
+    /// Identity: === (same type and value)
+    Identical,
+    /// Non-identity: !==
+    NotIdentical,
     /// Less than: <
     LessThan,
     /// Less than or equal: <=
@@ -33,12 +39,20 @@ pub enum BinaryOp {
     LogicalAnd,
     /// Logical OR: ||
     LogicalOr,
+    /// Logical XOR: xor
+    LogicalXor,
     /// Spaceship: <=>
     Spaceship,
     /// Bitwise AND: &
     BitwiseAnd,
     /// Bitwise OR: |
     BitwiseOr,
+    /// Bitwise XOR: ^
+    BitwiseXor,
+    /// Shift left: <<
+    ShiftLeft,
+    /// Shift right: >>
+    ShiftRight,
 }
 
 /// Unary operators
@@ -46,6 +60,8 @@ pub enum BinaryOp {
 pub enum UnaryOp {
     /// Arithmetic negation: -
     Minus,
+    /// Arithmetic identity: + (converts the operand to int or float)
+    Plus,
     /// Logical negation: !
     Not,
     /// Pre-increment: ++
@@ -62,22 +78,26 @@ impl BinaryOp {
     /// Get operator precedence (higher number = higher precedence)
     pub fn precedence(&self) -> u8 {
         match self {
+            BinaryOp::LogicalXor => 0,
             BinaryOp::LogicalOr => 1,
             BinaryOp::LogicalAnd => 2,
-            BinaryOp::Equal | BinaryOp::NotEqual => 3,
-            BinaryOp::LessThan | BinaryOp::LessThanOrEqual | 
-            BinaryOp::GreaterThan | BinaryOp::GreaterThanOrEqual | BinaryOp::Spaceship => 4,
+            BinaryOp::BitwiseOr => 3,
+            BinaryOp::BitwiseXor => 4,
             BinaryOp::BitwiseAnd => 5,
-            BinaryOp::BitwiseOr => 5, // treat same precedence for simplified implementation
-            BinaryOp::Concatenate => 6,
-            BinaryOp::Add | BinaryOp::Subtract => 7,
-            BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Modulo => 8,
+            BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::Identical | BinaryOp::NotIdentical => 6,
+            BinaryOp::LessThan | BinaryOp::LessThanOrEqual | 
+            BinaryOp::GreaterThan | BinaryOp::GreaterThanOrEqual | BinaryOp::Spaceship => 7,
+            BinaryOp::Concatenate => 8,
+            BinaryOp::ShiftLeft | BinaryOp::ShiftRight => 9,
+            BinaryOp::Add | BinaryOp::Subtract => 10,
+            BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Modulo => 11,
+            BinaryOp::Power => 12,
         }
     }
     
     /// Check if operator is left-associative
     pub fn is_left_associative(&self) -> bool {
-        true // All PHP binary operators are left-associative
+        !matches!(self, BinaryOp::Power)
     }
 }
 
@@ -89,18 +109,25 @@ impl fmt::Display for BinaryOp {
             BinaryOp::Multiply => "*",
             BinaryOp::Divide => "/",
             BinaryOp::Modulo => "%",
+            BinaryOp::Power => "**",
             BinaryOp::Concatenate => ".",
             BinaryOp::Equal => "==",
             BinaryOp::NotEqual => "!=",
+            BinaryOp::Identical => "===",
+            BinaryOp::NotIdentical => "!==",
             BinaryOp::LessThan => "<",
             BinaryOp::LessThanOrEqual => "<=",
             BinaryOp::GreaterThan => ">",
             BinaryOp::GreaterThanOrEqual => ">=",
             BinaryOp::LogicalAnd => "&&",
             BinaryOp::LogicalOr => "||",
+            BinaryOp::LogicalXor => "xor",
             BinaryOp::Spaceship => "<=>",
             BinaryOp::BitwiseAnd => "&",
             BinaryOp::BitwiseOr => "|",
+            BinaryOp::BitwiseXor => "^",
+            BinaryOp::ShiftLeft => "<<",
+            BinaryOp::ShiftRight => ">>",
         };
         write!(f, "{}", op)
     }
@@ -110,6 +137,7 @@ impl fmt::Display for UnaryOp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let op = match self {
             UnaryOp::Minus => "-",
+            UnaryOp::Plus => "+",
             UnaryOp::Not => "!",
             UnaryOp::PreIncrement | UnaryOp::PostIncrement => "++",
             UnaryOp::PreDecrement | UnaryOp::PostDecrement => "--",
@@ -12,6 +12,8 @@ pub enum Stmt {
     Echo(Expr),
     /// Print statement: print $var;
     Print(Expr),
+    /// Literal text between `?>` and `<?php`, output without interpolation
+    InlineHtml(String),
     /// Variable assignment: $var = value;
     Assignment {
         /// Variable name
@@ -52,6 +54,13 @@ pub enum Stmt {
         /// Loop body
         body: Box<Stmt>,
     },
+    /// Do-while loop: do { ... } while (condition); the body runs at least once
+    DoWhile {
+        /// Loop body
+        body: Box<Stmt>,
+        /// Condition checked after each iteration
+        condition: Expr,
+    },
     /// For loop: for (init; condition; increment) { ... }
     For {
         /// Initialization statement
@@ -85,7 +94,7 @@ pub enum Stmt {
         /// Function name
         name: String,
         /// Parameters
-        parameters: Vec<String>,
+        parameters: Vec<Param>,
         /// Function body
         body: Box<Stmt>,
     },
@@ -112,6 +121,115 @@ pub enum Stmt {
         /// Optional initialization expression
         initial: Option<Expr>,
     },
+    /// Empty statement: a lone `;` (e.g. `while ($cond);` or `;;`)
+    Nop,
+    /// Exception handling: try { ... } catch (Type $e) { ... } finally { ... }
+    Try {
+        /// Protected block
+        body: Box<Stmt>,
+        /// Catch clauses, tried in order
+        catches: Vec<CatchClause>,
+        /// Optional block that always runs last
+        finally: Option<Box<Stmt>>,
+    },
+    /// Class declaration: class Name extends Parent { members }
+    ClassDefinition {
+        /// Class name
+        name: String,
+        /// Optional parent class name
+        parent: Option<String>,
+        /// Interfaces listed after `implements`
+        interfaces: Vec<String>,
+        /// Properties, methods and constants
+        members: Vec<ClassMember>,
+    },
+}
+
+/// Member declared inside a class body
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClassMember {
+    /// Property: public $name = default;
+    Property {
+        /// Property name (without '$')
+        name: String,
+        /// Optional default value expression
+        default: Option<Expr>,
+        /// Declared static
+        is_static: bool,
+        /// Declared readonly: may be initialized once, then never written again
+        is_readonly: bool,
+    },
+    /// Method: public function name($params) { ... }
+    Method {
+        /// Method name
+        name: String,
+        /// Parameters
+        parameters: Vec<Param>,
+        /// Method body
+        body: Box<Stmt>,
+        /// Declared static
+        is_static: bool,
+    },
+    /// Class constant: const NAME = value;
+    Constant {
+        /// Constant name
+        name: String,
+        /// Value expression
+        value: Expr,
+    },
+}
+
+/// Function, method or closure parameter
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    /// Parameter name (without '$')
+    pub name: String,
+    /// Declared by reference (`&$name`)
+    pub by_ref: bool,
+    /// Collects the remaining arguments (`...$name`)
+    pub variadic: bool,
+    /// Default value used when the argument is omitted
+    pub default: Option<Expr>,
+    /// Constructor parameter promoted to a property by a visibility or
+    /// `readonly` modifier: `public int $x`
+    pub promoted: bool,
+    /// Promoted property is readonly
+    pub readonly: bool,
+}
+
+impl Param {
+    /// Create a required by-value parameter
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self { name: name.into(), by_ref: false, variadic: false, default: None, promoted: false, readonly: false }
+    }
+
+    /// Whether a caller must supply this parameter
+    pub fn is_required(&self) -> bool {
+        !self.variadic && self.default.is_none()
+    }
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.promoted { write!(f, "{}", if self.readonly { "public readonly " } else { "public " })?; }
+        if self.by_ref { write!(f, "&")?; }
+        if self.variadic { write!(f, "...")?; }
+        write!(f, "${}", self.name)?;
+        if let Some(default) = &self.default { write!(f, " = {}", default)?; }
+        Ok(())
+    }
+}
+
+/// Catch clause of a try statement: catch (TypeA | TypeB $e) { ... }
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatchClause {
+    /// Exception class names caught by this clause
+    pub types: Vec<String>,
+    /// Variable receiving the exception (without '$'); `None` for a
+    /// non-capturing `catch (Type)`
+    pub variable: Option<String>,
+    /// Handler body
+    pub body: Stmt,
 }
 
 /// Single switch case
@@ -129,6 +247,7 @@ impl fmt::Display for Stmt {
             Stmt::Expression(expr) => write!(f, "{};", expr),
             Stmt::Echo(expr) => write!(f, "echo {};", expr),
             Stmt::Print(expr) => write!(f, "print {};", expr),
+            Stmt::InlineHtml(html) => write!(f, "?>{}<?php", html),
             Stmt::Assignment { variable, value } => write!(f, "${} = {};", variable, value),
             Stmt::NullCoalesceAssign { variable, value } => write!(f, "${} ??= {};", variable, value),
             Stmt::ConstantDefinition { name, value } => write!(f, "const {} = {};", name, value),
@@ -147,6 +266,7 @@ impl fmt::Display for Stmt {
                 Ok(())
             }
             Stmt::While { condition, body } => write!(f, "while ({}) {}", condition, body),
+            Stmt::DoWhile { body, condition } => write!(f, "do {} while ({});", body, condition),
             Stmt::For { init, condition, increment, body } => {
                 write!(f, "for (")?;
                 if let Some(init) = init { write!(f, "{}", init)?; } else { write!(f, ";")?; }
@@ -176,7 +296,7 @@ impl fmt::Display for Stmt {
                 write!(f, "function {}(", name)?;
                 for (i, param) in parameters.iter().enumerate() {
                     if i > 0 { write!(f, ", ")?; }
-                    write!(f, "${}", param)?;
+                    write!(f, "{}", param)?;
                 }
                 write!(f, ") {}", body)
             }
@@ -202,7 +322,7 @@ impl fmt::Display for Stmt {
                     if i > 0 { write!(f, ", ")?; }
                     match t {
                         DestructTarget::Var(v) => write!(f, "${}", v)?,
-                        DestructTarget::KeyVar(k, v) => write!(f, "'{}' => ${}", k, v)?,
+                        DestructTarget::KeyVar(k, v) => write!(f, "{} => ${}", k, v)?,
                     }
                 }
                 write!(f, "] = {};", value)
@@ -210,6 +330,42 @@ impl fmt::Display for Stmt {
             Stmt::StaticVar { name, initial } => {
                 if let Some(init) = initial { write!(f, "static ${} = {};", name, init) } else { write!(f, "static ${};", name) }
             }
+            Stmt::Nop => write!(f, ";"),
+            Stmt::Try { body, catches, finally } => {
+                write!(f, "try {}", body)?;
+                for catch in catches {
+                    write!(f, " catch ({}", catch.types.join(" | "))?;
+                    if let Some(variable) = &catch.variable { write!(f, " ${}", variable)?; }
+                    write!(f, ") {}", catch.body)?;
+                }
+                if let Some(finally) = finally { write!(f, " finally {}", finally)?; }
+                Ok(())
+            }
+            Stmt::ClassDefinition { name, parent, interfaces, members } => {
+                write!(f, "class {}", name)?;
+                if let Some(parent) = parent { write!(f, " extends {}", parent)?; }
+                if !interfaces.is_empty() { write!(f, " implements {}", interfaces.join(", "))?; }
+                writeln!(f, " {{")?;
+                for member in members {
+                    match member {
+                        ClassMember::Property { name, default, is_static, is_readonly } => {
+                            write!(f, "  {}{}${}", if *is_static { "static " } else { "" }, if *is_readonly { "readonly " } else { "" }, name)?;
+                            if let Some(default) = default { write!(f, " = {}", default)?; }
+                            writeln!(f, ";")?;
+                        }
+                        ClassMember::Method { name, parameters, body, is_static } => {
+                            write!(f, "  {}function {}(", if *is_static { "static " } else { "" }, name)?;
+                            for (i, param) in parameters.iter().enumerate() {
+                                if i > 0 { write!(f, ", ")?; }
+                                write!(f, "{}", param)?;
+                            }
+                            writeln!(f, ") {}", body)?;
+                        }
+                        ClassMember::Constant { name, value } => writeln!(f, "  const {} = {};", name, value)?,
+                    }
+                }
+                write!(f, "}}")
+            }
         }
     }
 }
@@ -219,6 +375,6 @@ impl fmt::Display for Stmt {
 pub enum DestructTarget {
     /// Plain variable target
     Var(String),
-    /// Keyed target 'key' => $var
-    KeyVar(String, String),
+    /// Keyed target key => $var, where the key is any expression
+    KeyVar(Expr, String),
 }
@@ -7,11 +7,23 @@ use thiserror::Error;
 pub enum ParseError {
     /// Unexpected token
     #[error("Unexpected token: {token:?} at position {position}")]
-    UnexpectedToken { token: String, position: usize },
+    UnexpectedToken {
+        /// Debug rendering of the offending token
+        token: String,
+        /// Token position in the stream
+        position: usize,
+    },
     
     /// Expected specific token
     #[error("Expected {expected}, found {found} at position {position}")]
-    ExpectedToken { expected: String, found: String, position: usize },
+    ExpectedToken {
+        /// Description of the expected token
+        expected: String,
+        /// Debug rendering of the token actually found
+        found: String,
+        /// Token position in the stream
+        position: usize,
+    },
     
     /// Unexpected end of input
     #[error("Unexpected end of input")]
@@ -19,11 +31,17 @@ pub enum ParseError {
     
     /// Invalid expression
     #[error("Invalid expression: {message}")]
-    InvalidExpression { message: String },
+    InvalidExpression {
+        /// Error description
+        message: String,
+    },
     
     /// Invalid statement
     #[error("Invalid statement: {message}")]
-    InvalidStatement { message: String },
+    InvalidStatement {
+        /// Error description
+        message: String,
+    },
 }
 
 /// Result type for parser operations
@@ -9,7 +9,9 @@
 pub mod ast;
 pub mod parser;
 pub mod error;
+pub mod analysis;
 
 pub use ast::*;
 pub use parser::*;
 pub use error::*;
+pub use analysis::{analyze, analyze_with_functions, Diagnostic};
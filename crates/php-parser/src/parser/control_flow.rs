@@ -7,8 +7,8 @@
 //! - Break and continue statements
 //! - Return statements
 
-use crate::ast::{Stmt};
-use crate::ast::SwitchCase;
+use crate::ast::{ClassMember, ClassName, Expr, MemberName, Stmt};
+use crate::ast::{CatchClause, SwitchCase};
 use crate::error::{ParseError, ParseResult};
 use php_lexer::Token;
 use std::iter::Peekable;
@@ -84,6 +84,22 @@ impl ControlFlowParser {
         Ok(Stmt::While { condition, body })
     }
 
+    /// Parse do-while loop: do body while (condition);
+    pub fn parse_do_while(
+        tokens: &mut Peekable<IntoIter<Token>>,
+        position: &mut usize,
+    ) -> ParseResult<Stmt> {
+        Self::consume_token(tokens, position, Token::Do)?;
+        let body = Box::new(super::main::Parser::parse_statement_with_tokens(tokens, position)?);
+        Self::consume_token(tokens, position, Token::While)?;
+        Self::consume_token(tokens, position, Token::OpenParen)?;
+        let condition = super::expressions::ExpressionParser::parse_expression(tokens, position)?;
+        Self::consume_token(tokens, position, Token::CloseParen)?;
+        Self::consume_semicolon(tokens, position)?;
+
+        Ok(Stmt::DoWhile { body, condition })
+    }
+
     /// Parse for loop
     pub fn parse_for(
         tokens: &mut Peekable<IntoIter<Token>>,
@@ -135,7 +151,7 @@ impl ControlFlowParser {
     ) -> ParseResult<Stmt> {
         Self::consume_token(tokens, position, Token::Return)?;
 
-        let value = if let Some(&Token::Semicolon) = tokens.peek() {
+        let value = if super::utils::ParserUtils::at_statement_end(tokens) {
             None
         } else {
             Some(super::expressions::ExpressionParser::parse_expression(tokens, position)?)
@@ -156,6 +172,127 @@ impl ControlFlowParser {
         Ok(Stmt::Break)
     }
 
+    /// Reject `break`/`continue` outside any loop or switch, which PHP reports at compile time.
+    /// Function, method and closure bodies start a fresh context: a loop around a declaration does not count.
+    pub fn check_loop_context(stmt: &Stmt, in_loop: bool) -> ParseResult<()> {
+        let check_expr = Self::check_nested_functions;
+        match stmt {
+            Stmt::Break | Stmt::Continue if !in_loop => {
+                let keyword = if matches!(stmt, Stmt::Break) { "break" } else { "continue" };
+                Err(ParseError::InvalidStatement { message: format!("'{}' not in the 'loop' or 'switch' context", keyword) })
+            }
+            Stmt::Block(statements) => statements.iter().try_for_each(|s| Self::check_loop_context(s, in_loop)),
+            Stmt::Expression(expr) | Stmt::Echo(expr) | Stmt::Print(expr) | Stmt::Return(Some(expr)) => check_expr(expr),
+            Stmt::Assignment { value, .. } | Stmt::NullCoalesceAssign { value, .. } | Stmt::ConstantDefinition { value, .. }
+            | Stmt::DestructuringAssignment { value, .. } | Stmt::StaticVar { initial: Some(value), .. } => check_expr(value),
+            Stmt::If { condition, then_stmt, else_stmt } => {
+                check_expr(condition)?;
+                Self::check_loop_context(then_stmt, in_loop)?;
+                else_stmt.as_deref().map_or(Ok(()), |s| Self::check_loop_context(s, in_loop))
+            }
+            Stmt::While { condition, body } | Stmt::DoWhile { body, condition } => {
+                check_expr(condition)?;
+                Self::check_loop_context(body, true)
+            }
+            Stmt::For { init, condition, increment, body } => {
+                init.as_deref().map_or(Ok(()), |s| Self::check_loop_context(s, in_loop))?;
+                condition.iter().chain(increment).try_for_each(check_expr)?;
+                Self::check_loop_context(body, true)
+            }
+            Stmt::Foreach { array, body, .. } => {
+                check_expr(array)?;
+                Self::check_loop_context(body, true)
+            }
+            Stmt::Switch { expression, cases, default } => {
+                check_expr(expression)?;
+                cases.iter().try_for_each(|case| check_expr(&case.value))?;
+                cases.iter()
+                    .flat_map(|case| case.statements.iter())
+                    .chain(default.iter().flatten())
+                    .try_for_each(|s| Self::check_loop_context(s, true))
+            }
+            Stmt::Try { body, catches, finally } => {
+                Self::check_loop_context(body, in_loop)?;
+                catches.iter().try_for_each(|clause| Self::check_loop_context(&clause.body, in_loop))?;
+                finally.as_deref().map_or(Ok(()), |s| Self::check_loop_context(s, in_loop))
+            }
+            Stmt::FunctionDefinition { body, .. } => Self::check_loop_context(body, false),
+            Stmt::ClassDefinition { members, .. } => Self::check_methods(members),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check every method body of a class, each in a fresh context
+    fn check_methods(members: &[ClassMember]) -> ParseResult<()> {
+        members.iter().try_for_each(|member| match member {
+            ClassMember::Method { body, .. } => Self::check_loop_context(body, false),
+            _ => Ok(()),
+        })
+    }
+
+    /// Check the closures and anonymous-class methods nested anywhere in an expression
+    fn check_nested_functions(expr: &Expr) -> ParseResult<()> {
+        let check = Self::check_nested_functions;
+        let member = |name: &MemberName| match name {
+            MemberName::Dynamic(expr) => check(expr),
+            MemberName::Identifier(_) => Ok(()),
+        };
+        match expr {
+            Expr::Closure { body, .. } => Self::check_loop_context(body, false),
+            Expr::New { class, args } | Expr::StaticCall { class, args, .. } => {
+                match class {
+                    ClassName::Anonymous { members, .. } => Self::check_methods(members)?,
+                    ClassName::Dynamic(class) => check(class)?,
+                    ClassName::Identifier(_) => {}
+                }
+                args.iter().try_for_each(check)
+            }
+            Expr::VariableVariable(inner) | Expr::YieldFrom(inner) | Expr::Throw(inner) | Expr::Spread(inner)
+            | Expr::NamedArgument { value: inner, .. } | Expr::Unary { operand: inner, .. } | Expr::ArrowFunction { body: inner, .. } => check(inner),
+            Expr::Assign { target: left, value: right } | Expr::Binary { left, right, .. }
+            | Expr::ArrayAccess { array: left, index: right } | Expr::NullCoalesce { left, right } => {
+                check(left)?;
+                check(right)
+            }
+            Expr::Yield { key, value } => {
+                key.as_deref().map_or(Ok(()), check)?;
+                check(value)
+            }
+            Expr::MethodCall { target, method, args } | Expr::NullsafeMethodCall { target, method, args } => {
+                check(target)?;
+                member(method)?;
+                args.iter().try_for_each(check)
+            }
+            Expr::PropertyAccess { target, property } | Expr::NullsafePropertyAccess { target, property } => {
+                check(target)?;
+                member(property)
+            }
+            Expr::FunctionCall { args, .. } => args.iter().try_for_each(check),
+            Expr::DynamicCall { target, args } => {
+                check(target)?;
+                args.iter().try_for_each(check)
+            }
+            Expr::Array(elements) => elements.iter().try_for_each(|element| {
+                element.key.as_ref().map_or(Ok(()), check)?;
+                check(&element.value)
+            }),
+            Expr::Ternary { condition, then_expr, else_expr } => {
+                check(condition)?;
+                then_expr.as_deref().map_or(Ok(()), check)?;
+                check(else_expr)
+            }
+            Expr::Match { subject, arms, default_arm } => {
+                check(subject)?;
+                for (conditions, result) in arms {
+                    conditions.iter().try_for_each(check)?;
+                    check(result)?;
+                }
+                default_arm.as_deref().map_or(Ok(()), check)
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Parse continue statement
     pub fn parse_continue(
         tokens: &mut Peekable<IntoIter<Token>>,
@@ -185,11 +322,15 @@ impl ControlFlowParser {
         }
     }
 
-    /// Consume semicolon
+    /// Consume the semicolon ending a statement
     fn consume_semicolon(
         tokens: &mut Peekable<IntoIter<Token>>,
         position: &mut usize,
     ) -> ParseResult<()> {
+        // `?>` terminates the statement too; leave it for the block parser
+        if let Some(Token::PhpClose) = tokens.peek() {
+            return Ok(());
+        }
         Self::consume_token(tokens, position, Token::Semicolon)
     }
 
@@ -290,7 +431,7 @@ impl ControlFlowParser {
                             Some(Token::Break) => {
                                 // consume break and its semicolon
                                 Self::consume_token(tokens, position, Token::Break)?;
-                                if let Err(e) = Self::consume_semicolon(tokens, position) { return Err(e); }
+                                Self::consume_semicolon(tokens, position)?;
                                 break;
                             }
                             Some(Token::Case) | Some(Token::Default) | Some(Token::CloseBrace) => break,
@@ -311,7 +452,7 @@ impl ControlFlowParser {
                         match tokens.peek() {
                             Some(Token::Break) => {
                                 Self::consume_token(tokens, position, Token::Break)?;
-                                if let Err(e) = Self::consume_semicolon(tokens, position) { return Err(e); }
+                                Self::consume_semicolon(tokens, position)?;
                                 break;
                             }
                             Some(Token::CloseBrace) => break,
@@ -336,36 +477,51 @@ impl ControlFlowParser {
         Ok(Stmt::Switch { expression: expr, cases, default: default_block })
     }
 
-    /// Parse try-catch block (simplified: returns try block only, catches ignored)
+    /// Parse try / catch / finally
     pub fn parse_try(
         tokens: &mut Peekable<IntoIter<Token>>,
         position: &mut usize,
     ) -> ParseResult<Stmt> {
         Self::consume_token(tokens, position, Token::Try)?;
-        // Parse try block (expect '{')
-        let try_block = super::main::Parser::parse_block_statement(tokens, position)?;
-        // Zero or more catch blocks; consume and ignore
-        loop {
-            match tokens.peek() {
-                Some(Token::Catch) => {
-                    super::utils::ParserUtils::next_token(tokens, position); // consume 'catch'
-                    Self::consume_token(tokens, position, Token::OpenParen)?;
-                    // Consume tokens inside parentheses until CloseParen
-                    while let Some(tok) = tokens.peek() {
-                        if matches!(tok, Token::CloseParen) { break; }
-                        super::utils::ParserUtils::next_token(tokens, position);
-                    }
-                    Self::consume_token(tokens, position, Token::CloseParen)?;
-                    // Catch block
-                    let _catch_block = super::main::Parser::parse_block_statement(tokens, position)?; // ignored
+        let body = super::main::Parser::parse_block_statement(tokens, position)?;
+        let mut catches = Vec::new();
+        while let Some(Token::Catch) = tokens.peek() {
+            super::utils::ParserUtils::next_token(tokens, position); // consume 'catch'
+            Self::consume_token(tokens, position, Token::OpenParen)?;
+            // Type list: A | B | \Fully\Qualified
+            let mut types = Vec::new();
+            loop {
+                match super::utils::ParserUtils::next_token(tokens, position) {
+                    Some(Token::Identifier(name)) => types.push(name),
+                    other => return Err(ParseError::ExpectedToken { expected: "exception class name".into(), found: format!("{:?}", other), position: *position }),
                 }
-                Some(Token::Identifier(id)) if id == "finally" => {
-                    super::utils::ParserUtils::next_token(tokens, position); // 'finally'
-                    let _finally_block = super::main::Parser::parse_block_statement(tokens, position)?; // ignored
+                if let Some(Token::Pipe) = tokens.peek() {
+                    super::utils::ParserUtils::next_token(tokens, position); // '|'
+                } else {
+                    break;
                 }
-                _ => break,
             }
+            // PHP 8 allows omitting the variable: catch (Exception) { ... }
+            let variable = match tokens.peek() {
+                Some(Token::Variable(_)) => match super::utils::ParserUtils::next_token(tokens, position) {
+                    Some(Token::Variable(name)) => Some(name),
+                    _ => unreachable!("peeked a variable"),
+                },
+                _ => None,
+            };
+            Self::consume_token(tokens, position, Token::CloseParen)?;
+            let catch_body = super::main::Parser::parse_block_statement(tokens, position)?;
+            catches.push(CatchClause { types, variable, body: catch_body });
+        }
+        let finally = if let Some(Token::Finally) = tokens.peek() {
+            super::utils::ParserUtils::next_token(tokens, position); // consume 'finally'
+            Some(Box::new(super::main::Parser::parse_block_statement(tokens, position)?))
+        } else {
+            None
+        };
+        if catches.is_empty() && finally.is_none() {
+            return Err(ParseError::ExpectedToken { expected: "catch or finally".into(), found: format!("{:?}", tokens.peek()), position: *position });
         }
-        Ok(try_block)
+        Ok(Stmt::Try { body: Box::new(body), catches, finally })
     }
 }
@@ -6,7 +6,7 @@
 //! - Function calls
 //! - Parenthesized expressions
 
-use crate::ast::{ArrayElement, BinaryOp, Expr};
+use crate::ast::{ArrayElement, BinaryOp, Expr, MemberName, ClassName, Param};
 use crate::error::{ParseError, ParseResult};
 use php_lexer::Token;
 use std::iter::Peekable;
@@ -20,10 +20,46 @@ impl ExpressionParser {
     pub fn parse_expression(
         tokens: &mut Peekable<IntoIter<Token>>,
         position: &mut usize,
+    ) -> ParseResult<Expr> {
+        let left = Self::parse_assignment_expression(tokens, position)?;
+        Self::parse_keyword_logical_rest(tokens, position, left, 0)
+    }
+
+    /// Parse an expression that stops before the `and`, `xor` and `or` keywords.
+    /// They bind looser than assignment: in `$a = false or true` the value is just `false`.
+    pub fn parse_assignment_expression(
+        tokens: &mut Peekable<IntoIter<Token>>,
+        position: &mut usize,
     ) -> ParseResult<Expr> {
         Self::parse_expression_precedence(tokens, position, 0)
     }
 
+    /// Continue an expression with keyword logical operators (`and` > `xor` > `or`)
+    /// following an already parsed left operand
+    pub fn parse_keyword_logical_rest(
+        tokens: &mut Peekable<IntoIter<Token>>,
+        position: &mut usize,
+        mut left: Expr,
+        min_level: u8,
+    ) -> ParseResult<Expr> {
+        loop {
+            let (op, level) = match tokens.peek() {
+                Some(Token::KeywordOr) => (BinaryOp::LogicalOr, 0),
+                Some(Token::KeywordXor) => (BinaryOp::LogicalXor, 1),
+                Some(Token::KeywordAnd) => (BinaryOp::LogicalAnd, 2),
+                _ => break,
+            };
+            if level < min_level {
+                break;
+            }
+            super::utils::ParserUtils::next_token(tokens, position); // keyword operator
+            let operand = Self::parse_assignment_expression(tokens, position)?;
+            let right = Self::parse_keyword_logical_rest(tokens, position, operand, level + 1)?;
+            left = Expr::Binary { left: Box::new(left), op, right: Box::new(right) };
+        }
+        Ok(left)
+    }
+
     /// Parse expression with precedence climbing
     fn parse_expression_precedence(
         tokens: &mut Peekable<IntoIter<Token>>,
@@ -38,15 +74,40 @@ impl ExpressionParser {
         // Handle postfix operators (like $i++, $i--)
         left = Self::parse_postfix(tokens, position, left)?;
 
+        // Assignment used as an expression (e.g. $$name = expr)
+        if let Some(Token::Equals) = tokens.peek() {
+            if Self::is_assignable(&left) {
+                super::utils::ParserUtils::next_token(tokens, position); // '='
+                let value = Self::parse_assignment_expression(tokens, position)?;
+                return Ok(Expr::Assign { target: Box::new(left), value: Box::new(value) });
+            }
+        }
+        // Compound assignment `target <op>= rhs` becomes `target = target <op> rhs`
+        if let Some(op) = tokens.peek().and_then(Self::compound_assignment_op) {
+            let mut lookahead = tokens.clone();
+            lookahead.next(); // operator
+            if Self::is_assignable(&left) && matches!(lookahead.peek(), Some(Token::Equals)) {
+                super::utils::ParserUtils::next_token(tokens, position); // operator
+                super::utils::ParserUtils::next_token(tokens, position); // '='
+                let rhs = Self::parse_assignment_expression(tokens, position)?;
+                let value = Expr::Binary { left: Box::new(left.clone()), op, right: Box::new(rhs) };
+                return Ok(Expr::Assign { target: Box::new(left), value: Box::new(value) });
+            }
+        }
+
         loop {
             let op = match tokens.peek() {
                 Some(Token::Plus) => BinaryOp::Add,
                 Some(Token::Minus) => BinaryOp::Subtract,
                 Some(Token::Multiply) => BinaryOp::Multiply,
+                Some(Token::Power) => BinaryOp::Power,
                 Some(Token::Divide) => BinaryOp::Divide,
+                Some(Token::Percent) => BinaryOp::Modulo,
                 Some(Token::Dot) => BinaryOp::Concatenate,
                 Some(Token::DoubleEquals) => BinaryOp::Equal,
                 Some(Token::NotEquals) => BinaryOp::NotEqual,
+                Some(Token::Identical) => BinaryOp::Identical,
+                Some(Token::NotIdentical) => BinaryOp::NotIdentical,
                 Some(Token::LessThan) => BinaryOp::LessThan,
                 Some(Token::GreaterThan) => BinaryOp::GreaterThan,
                 Some(Token::LessOrEqual) => BinaryOp::LessThanOrEqual,
@@ -54,9 +115,11 @@ impl ExpressionParser {
                 Some(Token::Spaceship) => BinaryOp::Spaceship,
                 Some(Token::Ampersand) => BinaryOp::BitwiseAnd,
                 Some(Token::Pipe) => BinaryOp::BitwiseOr,
+                Some(Token::Caret) => BinaryOp::BitwiseXor,
+                Some(Token::ShiftLeft) => BinaryOp::ShiftLeft,
+                Some(Token::ShiftRight) => BinaryOp::ShiftRight,
                 Some(Token::LogicalAnd) => BinaryOp::LogicalAnd,
                 Some(Token::LogicalOr) => BinaryOp::LogicalOr,
-                Some(Token::NullCoalescing) => BinaryOp::Concatenate, // placeholder not real; null coalescing handled separately
                 _ => break,
             };
 
@@ -67,7 +130,9 @@ impl ExpressionParser {
 
             super::utils::ParserUtils::next_token(tokens, position);
 
-            let right = Self::parse_expression_precedence(tokens, position, precedence + 1)?;
+            // `**` is right associative: 2 ** 3 ** 2 is 2 ** (3 ** 2)
+            let next_min = if op == BinaryOp::Power { precedence } else { precedence + 1 };
+            let right = Self::parse_expression_precedence(tokens, position, next_min)?;
 
             left = Expr::Binary {
                 left: Box::new(left),
@@ -76,6 +141,12 @@ impl ExpressionParser {
             };
         }
 
+        // ?? and ?: bind looser than every binary operator, so operands parsed at a
+        // higher precedence level must leave them to the outermost call
+        if min_precedence > 0 {
+            return Ok(left);
+        }
+
         // Null coalescing (??) is right associative, low precedence. Handle here after other binary ops.
         while let Some(Token::NullCoalescing) = tokens.peek() {
             super::utils::ParserUtils::next_token(tokens, position); // consume '??'
@@ -108,66 +179,6 @@ impl ExpressionParser {
             };
         }
 
-        // match expression: match (expr) { condList => result, default => result }
-        if let Some(Token::Identifier(id)) = tokens.peek().cloned() {
-            if id == "match" {
-                super::utils::ParserUtils::next_token(tokens, position); // 'match'
-                Self::consume_token(tokens, position, Token::OpenParen)?;
-                let subject = Self::parse_expression(tokens, position)?;
-                Self::consume_token(tokens, position, Token::CloseParen)?;
-                Self::consume_token(tokens, position, Token::OpenBrace)?;
-                let mut arms: Vec<(Vec<Expr>, Box<Expr>)> = Vec::new();
-                let mut default_arm: Option<Box<Expr>> = None;
-                while let Some(tok) = tokens.peek() {
-                    if matches!(tok, Token::CloseBrace) { break; }
-                    // default arm (Token::Default or identifier "default")
-                    match tokens.peek().cloned() {
-                        Some(Token::Default) => {
-                            super::utils::ParserUtils::next_token(tokens, position); // default
-                            Self::consume_token(tokens, position, Token::Arrow)?;
-                            let result_expr = Self::parse_expression(tokens, position)?;
-                            default_arm = Some(Box::new(result_expr));
-                            if let Some(Token::Comma) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); }
-                            continue;
-                        }
-                        Some(Token::Identifier(d)) if d == "default" => {
-                            super::utils::ParserUtils::next_token(tokens, position);
-                            Self::consume_token(tokens, position, Token::Arrow)?;
-                            let result_expr = Self::parse_expression(tokens, position)?;
-                            default_arm = Some(Box::new(result_expr));
-                            if let Some(Token::Comma) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); }
-                            continue;
-                        }
-                        _ => {}
-                    }
-                    // parse one or more conditions separated by commas until '=>'
-                    let mut conds = Vec::new();
-                    loop {
-                        let cond_expr = Self::parse_expression(tokens, position)?;
-                        conds.push(cond_expr);
-                        if let Some(Token::Comma) = tokens.peek() { // could be separator between conditions or end of arm
-                            // lookahead to see if Arrow follows next
-                            let mut la = tokens.clone();
-                            la.next(); // consume comma in lookahead
-                            if let Some(Token::Arrow) = la.peek() {
-                                super::utils::ParserUtils::next_token(tokens, position); // consume comma and break
-                                break;
-                            } else {
-                                super::utils::ParserUtils::next_token(tokens, position); // consume comma continue
-                                continue;
-                            }
-                        }
-                        break;
-                    }
-                    Self::consume_token(tokens, position, Token::Arrow)?;
-                    let result_expr = Self::parse_expression(tokens, position)?;
-                    arms.push((conds, Box::new(result_expr)));
-                    if let Some(Token::Comma) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); }
-                }
-                Self::consume_token(tokens, position, Token::CloseBrace)?;
-                return Ok(Expr::Match { subject: Box::new(subject), arms, default_arm });
-            }
-        }
         Ok(left)
     }
 
@@ -182,6 +193,18 @@ impl ExpressionParser {
             // Parse the next primary and return directly (no reference semantics implemented)
             return Self::parse_primary(tokens, position);
         }
+        // Unary minus/plus/not bind tighter than every binary operator except `**`: -2 ** 2 is -(2 ** 2)
+        let prefix = match tokens.peek() {
+            Some(Token::Minus) => Some(crate::ast::UnaryOp::Minus),
+            Some(Token::Plus) => Some(crate::ast::UnaryOp::Plus),
+            Some(Token::Not) => Some(crate::ast::UnaryOp::Not),
+            _ => None,
+        };
+        if let Some(op) = prefix {
+            super::utils::ParserUtils::next_token(tokens, position); // '-', '+' or '!'
+            let operand = Self::parse_expression_precedence(tokens, position, Self::get_precedence(&BinaryOp::Power))?;
+            return Ok(Expr::Unary { op, operand: Box::new(operand) });
+        }
         // Prefix increment/decrement
         if let Some(Token::Increment) = tokens.peek() {
             super::utils::ParserUtils::next_token(tokens, position); // '++'
@@ -208,137 +231,134 @@ impl ExpressionParser {
             } else {
                 loop {
                     // Skip type hints (identifiers + pipes)
-                    loop { match tokens.peek() { Some(Token::Identifier(_)) => { super::utils::ParserUtils::next_token(tokens, position); }, _ => break } if let Some(Token::Pipe) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); continue; } else { break; } }
-                    if let Some(Token::Ellipsis) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); }
-                    // Optional by-reference '&'
-                    if let Some(Token::Ampersand) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); }
+                    while let Some(Token::Identifier(_)) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); if let Some(Token::Pipe) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); } else { break; } }
+                    // Optional by-reference '&' and variadic '...'
+                    let by_ref = matches!(tokens.peek(), Some(Token::Ampersand));
+                    if by_ref { super::utils::ParserUtils::next_token(tokens, position); }
+                    let variadic = matches!(tokens.peek(), Some(Token::Ellipsis));
+                    if variadic { super::utils::ParserUtils::next_token(tokens, position); }
                     let var_name = match super::utils::ParserUtils::next_token(tokens, position) { Some(Token::Variable(v)) => v, other => return Err(ParseError::ExpectedToken { expected: "parameter variable".into(), found: format!("{:?}", other), position: *position }) };
-                    if let Some(Token::Equals) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); let _ = Self::parse_expression(tokens, position)?; }
-                    params.push(var_name);
+                    let default = if let Some(Token::Equals) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); Some(Self::parse_expression(tokens, position)?) } else { None };
+                    params.push(Param { by_ref, variadic, default, ..Param::new(var_name) });
                     match tokens.peek() { Some(Token::Comma) => { super::utils::ParserUtils::next_token(tokens, position); }, Some(Token::CloseParen) => { super::utils::ParserUtils::next_token(tokens, position); break; }, other => return Err(ParseError::ExpectedToken { expected: ", or )".into(), found: format!("{:?}", other), position: *position }) }
                 }
             }
+            // Captured variables: use ($a, &$b)
+            let mut uses = Vec::new();
+            if matches!(tokens.peek(), Some(Token::Identifier(name)) if name.eq_ignore_ascii_case("use")) {
+                super::utils::ParserUtils::next_token(tokens, position); // 'use'
+                Self::consume_token(tokens, position, Token::OpenParen)?;
+                while !matches!(tokens.peek(), Some(Token::CloseParen)) {
+                    let by_ref = matches!(tokens.peek(), Some(Token::Ampersand));
+                    if by_ref { super::utils::ParserUtils::next_token(tokens, position); }
+                    match super::utils::ParserUtils::next_token(tokens, position) {
+                        Some(Token::Variable(name)) => uses.push(Param { by_ref, ..Param::new(name) }),
+                        other => return Err(ParseError::ExpectedToken { expected: "captured variable".into(), found: format!("{:?}", other), position: *position }),
+                    }
+                    if let Some(Token::Comma) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); } else { break; }
+                }
+                Self::consume_token(tokens, position, Token::CloseParen)?;
+            }
             // Optional return type: ':' identifiers and pipes
             if let Some(Token::Colon) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); while let Some(Token::Identifier(_)) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); if let Some(Token::Pipe) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); continue; } break; } }
             // Body block
             Self::consume_token(tokens, position, Token::OpenBrace)?;
-            // Collect statements until closing brace, but we simplify: parse as block, then wrap in implicit return of last expression if it is an expression statement
             let mut body_stmts = Vec::new();
             while let Some(tk) = tokens.peek() { if matches!(tk, Token::CloseBrace) { break; } body_stmts.push(super::main::Parser::parse_statement_with_tokens(tokens, position)?); }
             Self::consume_token(tokens, position, Token::CloseBrace)?;
-            // Heuristic: if last statement is Expression(e) produce closure body expression = e; else null
-            let body_expr = if let Some(last) = body_stmts.last() { if let crate::ast::Stmt::Expression(e) = last { e.clone() } else { Expr::Null } } else { Expr::Null };
-            return Ok(Expr::ArrowFunction { params, body: Box::new(body_expr) });
-        }
-        // Arrow function start: identifier 'fn'
-        if let Some(Token::Identifier(name)) = tokens.peek().cloned() {
-            if name == "fn" {
-                super::utils::ParserUtils::next_token(tokens, position); // consume 'fn'
-                // Expect '('
-                Self::consume_token(tokens, position, Token::OpenParen)?;
-                let mut params = Vec::new();
-                // Parse param list (possibly empty) skipping type hints (identifiers and pipes) until variable appears
-                if let Some(token) = tokens.peek() {
-                    if let Token::CloseParen = token { super::utils::ParserUtils::next_token(tokens, position); } else {
-                        loop {
-                            // Skip simple type hints (Identifier ('|' Identifier)*)
-                            loop {
-                                match tokens.peek() {
-                                    Some(Token::Identifier(_)) => { super::utils::ParserUtils::next_token(tokens, position); }
-                                    _ => break,
-                                }
-                                if let Some(Token::Pipe) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); continue; } else { break; }
-                            }
-                            // Variadic/spread ellipsis (ignored semantics)
-                            if let Some(Token::Ellipsis) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); }
-                            // Optional by-reference '&'
-                            if let Some(Token::Ampersand) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); }
-                            // Expect variable name
-                            let var_name = match super::utils::ParserUtils::next_token(tokens, position) {
-                                Some(Token::Variable(v)) => v,
-                                other => return Err(ParseError::ExpectedToken { expected: "parameter variable".into(), found: format!("{:?}", other), position: *position }),
-                            };
-                            // Optional default value assign skip: '=' expr
-                            if let Some(Token::Equals) = tokens.peek() {
-                                super::utils::ParserUtils::next_token(tokens, position);
-                                let _ = Self::parse_expression(tokens, position)?; // discard
-                            }
-                            params.push(var_name);
-                            match tokens.peek() {
-                                Some(Token::Comma) => { super::utils::ParserUtils::next_token(tokens, position); continue; }
-                                Some(Token::CloseParen) => { super::utils::ParserUtils::next_token(tokens, position); break; }
-                                other => return Err(ParseError::ExpectedToken { expected: ", or )".into(), found: format!("{:?}", other), position: *position }),
-                            }
-                        }
-                    }
-                }
-                // Expect => (represented as Arrow token? we currently have Token::Arrow for '=>')
-                Self::consume_token(tokens, position, Token::Arrow)?;
-                let body = Self::parse_expression(tokens, position)?;
-                return Ok(Expr::ArrowFunction { params, body: Box::new(body) });
-            }
+            return Ok(Expr::Closure { params, uses, body: Box::new(crate::ast::Stmt::Block(body_stmts)) });
         }
-        // Match expression starting directly (e.g., = match (...){...};)
-        if let Some(Token::Identifier(name)) = tokens.peek().cloned() {
-            if name == "match" {
-                super::utils::ParserUtils::next_token(tokens, position); // 'match'
-                Self::consume_token(tokens, position, Token::OpenParen)?;
-                let subject = Self::parse_expression(tokens, position)?;
-                Self::consume_token(tokens, position, Token::CloseParen)?;
-                Self::consume_token(tokens, position, Token::OpenBrace)?;
-                let mut arms: Vec<(Vec<Expr>, Box<Expr>)> = Vec::new();
-                let mut default_arm: Option<Box<Expr>> = None;
-                while let Some(tok) = tokens.peek() {
-                    if matches!(tok, Token::CloseBrace) { break; }
-                    match tokens.peek().cloned() {
-                        Some(Token::Default) => {
+        // Arrow function: fn (params) => expr
+        if let Some(Token::Fn) = tokens.peek() {
+            super::utils::ParserUtils::next_token(tokens, position); // consume 'fn'
+            // Expect '('
+            Self::consume_token(tokens, position, Token::OpenParen)?;
+            let mut params = Vec::new();
+            // Parse param list (possibly empty) skipping type hints (identifiers and pipes) until variable appears
+            if let Some(token) = tokens.peek() {
+                if let Token::CloseParen = token { super::utils::ParserUtils::next_token(tokens, position); } else {
+                    loop {
+                        // Skip simple type hints (Identifier ('|' Identifier)*)
+                        while let Some(Token::Identifier(_)) = tokens.peek() {
                             super::utils::ParserUtils::next_token(tokens, position);
-                            Self::consume_token(tokens, position, Token::Arrow)?;
-                            let res = Self::parse_expression(tokens, position)?;
-                            default_arm = Some(Box::new(res));
-                            if let Some(Token::Comma) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); }
-                            continue;
+                            if let Some(Token::Pipe) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); } else { break; }
                         }
-                        Some(Token::Identifier(d)) if d == "default" => {
+                        // Optional by-reference '&'
+                        let by_ref = matches!(tokens.peek(), Some(Token::Ampersand));
+                        if by_ref { super::utils::ParserUtils::next_token(tokens, position); }
+                        // Variadic ellipsis '...'
+                        let variadic = matches!(tokens.peek(), Some(Token::Ellipsis));
+                        if variadic { super::utils::ParserUtils::next_token(tokens, position); }
+                        // Expect variable name
+                        let var_name = match super::utils::ParserUtils::next_token(tokens, position) {
+                            Some(Token::Variable(v)) => v,
+                            other => return Err(ParseError::ExpectedToken { expected: "parameter variable".into(), found: format!("{:?}", other), position: *position }),
+                        };
+                        // Optional default value: '=' expr
+                        let default = if let Some(Token::Equals) = tokens.peek() {
                             super::utils::ParserUtils::next_token(tokens, position);
-                            Self::consume_token(tokens, position, Token::Arrow)?;
-                            let res = Self::parse_expression(tokens, position)?;
-                            default_arm = Some(Box::new(res));
-                            if let Some(Token::Comma) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); }
-                            continue;
+                            Some(Self::parse_expression(tokens, position)?)
+                        } else {
+                            None
+                        };
+                        params.push(Param { by_ref, variadic, default, ..Param::new(var_name) });
+                        match tokens.peek() {
+                            Some(Token::Comma) => { super::utils::ParserUtils::next_token(tokens, position); continue; }
+                            Some(Token::CloseParen) => { super::utils::ParserUtils::next_token(tokens, position); break; }
+                            other => return Err(ParseError::ExpectedToken { expected: ", or )".into(), found: format!("{:?}", other), position: *position }),
                         }
-                        _ => {}
-                    }
-                    let mut conds = Vec::new();
-                    loop {
-                        let cond_expr = Self::parse_expression(tokens, position)?;
-                        conds.push(cond_expr);
-                        if let Some(Token::Comma) = tokens.peek() { // lookahead for arrow after comma
-                            let mut la = tokens.clone(); la.next(); if let Some(Token::Arrow) = la.peek() { super::utils::ParserUtils::next_token(tokens, position); break; } else { super::utils::ParserUtils::next_token(tokens, position); continue; } }
-                        break;
                     }
-                    Self::consume_token(tokens, position, Token::Arrow)?;
-                    let result_expr = Self::parse_expression(tokens, position)?;
-                    arms.push((conds, Box::new(result_expr)));
-                    if let Some(Token::Comma) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); }
                 }
-                Self::consume_token(tokens, position, Token::CloseBrace)?;
-                return Ok(Expr::Match { subject: Box::new(subject), arms, default_arm });
             }
+            // Expect => (represented as Arrow token? we currently have Token::Arrow for '=>')
+            Self::consume_token(tokens, position, Token::Arrow)?;
+            let body = Self::parse_expression(tokens, position)?;
+            return Ok(Expr::ArrowFunction { params, body: Box::new(body) });
         }
-        // Yield expression (identifier 'yield' not a keyword yet)
-        if let Some(Token::Identifier(name)) = tokens.peek().cloned() {
-            if name == "yield" {
-                super::utils::ParserUtils::next_token(tokens, position); // 'yield'
-                // Optional 'from'
-                if let Some(Token::Identifier(n2)) = tokens.peek().cloned() { if n2 == "from" { super::utils::ParserUtils::next_token(tokens, position); } }
-                let inner = Self::parse_expression(tokens, position)?; // value expression
-                return Ok(Expr::Yield { value: Box::new(inner) });
+        if let Some(Token::Match) = tokens.peek() {
+            return Self::parse_match(tokens, position);
+        }
+        // Variable-variable: $$name or ${expr}
+        if let Some(Token::Dollar) = tokens.peek() {
+            super::utils::ParserUtils::next_token(tokens, position); // '$'
+            let inner = match tokens.peek() {
+                Some(Token::OpenBrace) => {
+                    super::utils::ParserUtils::next_token(tokens, position); // '{'
+                    let expr = Self::parse_expression(tokens, position)?;
+                    Self::consume_token(tokens, position, Token::CloseBrace)?;
+                    expr
+                }
+                Some(Token::Variable(_)) | Some(Token::Dollar) => Self::parse_primary(tokens, position)?,
+                other => return Err(ParseError::ExpectedToken { expected: "variable or {".into(), found: format!("{:?}", other), position: *position }),
+            };
+            return Ok(Expr::VariableVariable(Box::new(inner)));
+        }
+        // yield [expr] / yield from expr
+        if let Some(Token::Yield) = tokens.peek() {
+            super::utils::ParserUtils::next_token(tokens, position); // 'yield'
+            if matches!(tokens.peek(), Some(Token::Identifier(from)) if from.eq_ignore_ascii_case("from")) {
+                super::utils::ParserUtils::next_token(tokens, position); // 'from'
+                let source = Self::parse_expression(tokens, position)?;
+                return Ok(Expr::YieldFrom(Box::new(source)));
             }
+            let value = match tokens.peek() {
+                Some(Token::Semicolon) | Some(Token::CloseParen) | Some(Token::Comma) | Some(Token::CloseBracket) => Expr::Null,
+                _ => Self::parse_expression(tokens, position)?,
+            };
+            if let Some(Token::Arrow) = tokens.peek() {
+                super::utils::ParserUtils::next_token(tokens, position); // '=>'
+                let yielded = Self::parse_expression(tokens, position)?;
+                return Ok(Expr::Yield { key: Some(Box::new(value)), value: Box::new(yielded) });
+            }
+            return Ok(Expr::Yield { key: None, value: Box::new(value) });
         }
         match super::utils::ParserUtils::next_token(tokens, position) {
             Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Int(n)) => Ok(Expr::Int(n)),
             Some(Token::String(s)) => Ok(Expr::String(s)),
+            // Only strings that can reference a variable need runtime interpolation
+            Some(Token::InterpolatedString(s)) if s.contains('$') => Ok(Expr::InterpolatedString(s)),
+            Some(Token::InterpolatedString(s)) => Ok(Expr::String(s)),
             Some(Token::Variable(name)) => {
                 // Pattern: $var(...)
                 if let Some(Token::OpenParen) = tokens.peek() {
@@ -363,12 +383,18 @@ impl ExpressionParser {
                         return Ok(call_expr);
                     }
                 }
+                // Static member on a runtime class: $class::CONSTANT, $class::method(), $class::$property
+                if let Some(Token::DoubleColon) = tokens.peek() {
+                    super::utils::ParserUtils::next_token(tokens, position); // '::'
+                    return Self::parse_static_member(ClassName::Dynamic(Box::new(Expr::Variable(name))), tokens, position);
+                }
                 Ok(Expr::Variable(name))
             }
             // Built-in function tokens: convert to identifier name for uniform handling
             Some(Token::ArrayMerge) => Self::parse_builtin_as_call("array_merge".to_string(), tokens, position),
             Some(Token::ArrayPush) => Self::parse_builtin_as_call("array_push".to_string(), tokens, position),
             Some(Token::ArrayPop) => Self::parse_builtin_as_call("array_pop".to_string(), tokens, position),
+            Some(Token::InArray) => Self::parse_builtin_as_call("in_array".to_string(), tokens, position),
             Some(Token::Count) => Self::parse_builtin_as_call("count".to_string(), tokens, position),
             Some(Token::Explode) => Self::parse_builtin_as_call("explode".to_string(), tokens, position),
             Some(Token::Implode) => Self::parse_builtin_as_call("implode".to_string(), tokens, position),
@@ -407,6 +433,11 @@ impl ExpressionParser {
                         return Ok(Expr::Array(elements));
                     }
                 }
+                // Static method call: Name::method(args)
+                if let Some(Token::DoubleColon) = tokens.peek() {
+                    super::utils::ParserUtils::next_token(tokens, position); // '::'
+                    return Self::parse_static_member(ClassName::Identifier(name), tokens, position);
+                }
                 // Check if this is a function call (identifier followed by opening parenthesis)
                 if let Some(&Token::OpenParen) = tokens.peek() {
                     super::utils::ParserUtils::next_token(tokens, position); // consume opening parenthesis
@@ -421,6 +452,72 @@ impl ExpressionParser {
                     Ok(Expr::Constant(name))
                 }
             }
+            Some(Token::Throw) => {
+                let exception = Self::parse_assignment_expression(tokens, position)?;
+                Ok(Expr::Throw(Box::new(exception)))
+            }
+            Some(Token::Static) => {
+                Self::consume_token(tokens, position, Token::DoubleColon)?;
+                Self::parse_static_member(ClassName::Identifier("static".to_string()), tokens, position)
+            }
+            Some(Token::New) => {
+                if let Some(Token::Class) = tokens.peek() {
+                    // new class(args) extends Base { ... }: constructor arguments precede the body
+                    super::utils::ParserUtils::next_token(tokens, position); // 'class'
+                    let name = format!("class@anonymous#{}", position);
+                    let mut args = Vec::new();
+                    if let Some(Token::OpenParen) = tokens.peek() {
+                        super::utils::ParserUtils::next_token(tokens, position); // '('
+                        args = Self::parse_function_args(tokens, position)?;
+                        Self::consume_token(tokens, position, Token::CloseParen)?;
+                    }
+                    let (parent, interfaces, members) = super::statements::StatementParser::parse_class_tail(tokens, position)?;
+                    return Ok(Expr::New { class: ClassName::Anonymous { name, parent, interfaces, members }, args });
+                }
+                let class = match super::utils::ParserUtils::next_token(tokens, position) {
+                    Some(Token::Identifier(name)) => ClassName::Identifier(name),
+                    Some(Token::Variable(var)) => {
+                        // new $class / new $obj->prop / new $map['key']: no calls allowed in the class part
+                        let mut class_expr = Expr::Variable(var);
+                        loop {
+                            match tokens.peek() {
+                                Some(Token::OpenBracket) => {
+                                    super::utils::ParserUtils::next_token(tokens, position); // '['
+                                    let index = Self::parse_expression(tokens, position)?;
+                                    Self::consume_token(tokens, position, Token::CloseBracket)?;
+                                    class_expr = Expr::ArrayAccess { array: Box::new(class_expr), index: Box::new(index) };
+                                }
+                                Some(Token::ObjectOperator) => {
+                                    super::utils::ParserUtils::next_token(tokens, position); // '->'
+                                    let property = match super::utils::ParserUtils::next_token(tokens, position) {
+                                        Some(Token::Variable(var)) => MemberName::Dynamic(Box::new(Expr::Variable(var))),
+                                        other => match other.as_ref().and_then(super::utils::ParserUtils::member_identifier) {
+                                            Some(id) => MemberName::Identifier(id),
+                                            None => return Err(ParseError::ExpectedToken { expected: "property name".into(), found: format!("{:?}", other), position: *position }),
+                                        },
+                                    };
+                                    class_expr = Expr::PropertyAccess { target: Box::new(class_expr), property };
+                                }
+                                _ => break,
+                            }
+                        }
+                        ClassName::Dynamic(Box::new(class_expr))
+                    }
+                    Some(Token::OpenParen) => {
+                        let class_expr = Self::parse_expression(tokens, position)?;
+                        Self::consume_token(tokens, position, Token::CloseParen)?;
+                        ClassName::Dynamic(Box::new(class_expr))
+                    }
+                    other => return Err(ParseError::ExpectedToken { expected: "class name".into(), found: format!("{:?}", other), position: *position }),
+                };
+                let mut args = Vec::new();
+                if let Some(Token::OpenParen) = tokens.peek() {
+                    super::utils::ParserUtils::next_token(tokens, position); // '('
+                    args = Self::parse_function_args(tokens, position)?;
+                    Self::consume_token(tokens, position, Token::CloseParen)?;
+                }
+                Ok(Expr::New { class, args })
+            }
             Some(Token::True) => Ok(Expr::Bool(true)),
             Some(Token::False) => Ok(Expr::Bool(false)),
             Some(Token::Null) => Ok(Expr::Null),
@@ -502,9 +599,10 @@ impl ExpressionParser {
         tokens: &mut Peekable<IntoIter<Token>>,
         position: &mut usize,
     ) -> ParseResult<Expr> {
-        // Skip spread ellipsis (ignored semantics)
+        // Argument unpacking: ...$args
         if let Some(Token::Ellipsis) = tokens.peek() {
             super::utils::ParserUtils::next_token(tokens, position); // '...'
+            return Ok(Expr::Spread(Box::new(Self::parse_expression(tokens, position)?)));
         }
         if let Some(Token::Identifier(_)) = tokens.peek() {
             // Clone iterator to inspect following token
@@ -512,10 +610,12 @@ impl ExpressionParser {
             let first = clone_iter.next();
             let second = clone_iter.peek();
             // Named arg pattern: name ':' expr
-            if matches!(first, Some(Token::Identifier(_))) && matches!(second, Some(Token::Colon)) {
+            if let (Some(Token::Identifier(name)), Some(Token::Colon)) = (&first, second) {
+                let name = name.clone();
                 super::utils::ParserUtils::next_token(tokens, position); // identifier
                 super::utils::ParserUtils::next_token(tokens, position); // colon
-                return Self::parse_expression(tokens, position);
+                let value = Self::parse_expression(tokens, position)?;
+                return Ok(Expr::NamedArgument { name, value: Box::new(value) });
             }
             // declare-style pattern: name '=' expr (treat as expression after '=' for now)
             if matches!(first, Some(Token::Identifier(_))) && matches!(second, Some(Token::Equals)) {
@@ -528,22 +628,49 @@ impl ExpressionParser {
         Self::parse_expression(tokens, position)
     }
 
-    /// Get operator precedence
+    /// Check whether an expression can appear on the left of '='
+    fn is_assignable(expr: &Expr) -> bool {
+        matches!(expr, Expr::Variable(_) | Expr::VariableVariable(_) | Expr::ArrayAccess { .. } | Expr::PropertyAccess { .. } | Expr::StaticProperty { .. })
+    }
+
+    /// Binary operator of a compound assignment whose operator token precedes '='
+    pub(super) fn compound_assignment_op(token: &Token) -> Option<BinaryOp> {
+        match token {
+            Token::Plus => Some(BinaryOp::Add),
+            Token::Minus => Some(BinaryOp::Subtract),
+            Token::Multiply => Some(BinaryOp::Multiply),
+            Token::Power => Some(BinaryOp::Power),
+            Token::Divide => Some(BinaryOp::Divide),
+            Token::Percent => Some(BinaryOp::Modulo),
+            Token::Dot => Some(BinaryOp::Concatenate),
+            Token::Ampersand => Some(BinaryOp::BitwiseAnd),
+            Token::Pipe => Some(BinaryOp::BitwiseOr),
+            Token::Caret => Some(BinaryOp::BitwiseXor),
+            Token::ShiftLeft => Some(BinaryOp::ShiftLeft),
+            Token::ShiftRight => Some(BinaryOp::ShiftRight),
+            _ => None,
+        }
+    }
+
+    /// Get operator precedence; as in PHP, `|` < `^` < `&` all bind looser than `==`
     fn get_precedence(op: &BinaryOp) -> u8 {
         match op {
-            BinaryOp::LogicalOr => 0,
+            BinaryOp::LogicalXor | BinaryOp::LogicalOr => 0,
             BinaryOp::LogicalAnd => 1,
-            BinaryOp::Equal | BinaryOp::NotEqual => 2,
+            BinaryOp::BitwiseOr => 2,
+            BinaryOp::BitwiseXor => 3,
+            BinaryOp::BitwiseAnd => 4,
+            BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::Identical | BinaryOp::NotIdentical => 5,
             BinaryOp::LessThan
             | BinaryOp::GreaterThan
             | BinaryOp::LessThanOrEqual
             | BinaryOp::GreaterThanOrEqual
-            | BinaryOp::Spaceship => 3,
-            BinaryOp::BitwiseAnd => 4,
-            BinaryOp::BitwiseOr => 4,
-            BinaryOp::Concatenate => 5,
-            BinaryOp::Add | BinaryOp::Subtract => 6,
-            BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Modulo => 7,
+            | BinaryOp::Spaceship => 6,
+            BinaryOp::Concatenate => 7,
+            BinaryOp::ShiftLeft | BinaryOp::ShiftRight => 8,
+            BinaryOp::Add | BinaryOp::Subtract => 9,
+            BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Modulo => 10,
+            BinaryOp::Power => 11,
         }
     }
 
@@ -580,10 +707,20 @@ impl ExpressionParser {
         }
 
         loop {
-            // Parse the value expression
-            // Support spread '...expr' (ignored flattening semantics; treat as normal expression)
+            // Spread element '...expr' unpacks another array in place
             if let Some(Token::Ellipsis) = tokens.peek() {
                 super::utils::ParserUtils::next_token(tokens, position); // consume '...'
+                let inner = Self::parse_expression(tokens, position)?;
+                elements.push(ArrayElement { key: None, value: Expr::Spread(Box::new(inner)) });
+                match tokens.peek() {
+                    Some(&Token::Comma) => {
+                        super::utils::ParserUtils::next_token(tokens, position); // consume ','
+                        if let Some(&Token::CloseBracket) = tokens.peek() { break; }
+                        continue;
+                    }
+                    Some(&Token::CloseBracket) => break,
+                    other => return Err(ParseError::ExpectedToken { expected: ", or ]".into(), found: format!("{:?}", other), position: *position }),
+                }
             }
             let value = Self::parse_expression(tokens, position)?;
 
@@ -648,6 +785,76 @@ impl ExpressionParser {
         }
     }
 
+    /// Parse `match (subject) { cond, cond => result, default => result }`
+    fn parse_match(
+        tokens: &mut Peekable<IntoIter<Token>>,
+        position: &mut usize,
+    ) -> ParseResult<Expr> {
+        Self::consume_token(tokens, position, Token::Match)?;
+        Self::consume_token(tokens, position, Token::OpenParen)?;
+        let subject = Self::parse_expression(tokens, position)?;
+        Self::consume_token(tokens, position, Token::CloseParen)?;
+        Self::consume_token(tokens, position, Token::OpenBrace)?;
+        let mut arms: Vec<(Vec<Expr>, Box<Expr>)> = Vec::new();
+        let mut default_arm: Option<Box<Expr>> = None;
+        while !matches!(tokens.peek(), Some(Token::CloseBrace) | None) {
+            if let Some(Token::Default) = tokens.peek() {
+                super::utils::ParserUtils::next_token(tokens, position); // 'default'
+                Self::consume_token(tokens, position, Token::Arrow)?;
+                default_arm = Some(Box::new(Self::parse_expression(tokens, position)?));
+            } else {
+                // One or more conditions separated by commas (a trailing comma is allowed) until '=>'
+                let mut conds = Vec::new();
+                while !matches!(tokens.peek(), Some(Token::Arrow)) {
+                    conds.push(Self::parse_expression(tokens, position)?);
+                    if let Some(Token::Comma) = tokens.peek() {
+                        super::utils::ParserUtils::next_token(tokens, position);
+                    } else {
+                        break;
+                    }
+                }
+                Self::consume_token(tokens, position, Token::Arrow)?;
+                let result_expr = Self::parse_expression(tokens, position)?;
+                arms.push((conds, Box::new(result_expr)));
+            }
+            if let Some(Token::Comma) = tokens.peek() {
+                super::utils::ParserUtils::next_token(tokens, position);
+            } else {
+                break;
+            }
+        }
+        Self::consume_token(tokens, position, Token::CloseBrace)?;
+        Ok(Expr::Match { subject: Box::new(subject), arms, default_arm })
+    }
+
+    /// Parse the member after 'Name::' (the class name and '::' are already consumed)
+    fn parse_static_member(
+        class: ClassName,
+        tokens: &mut Peekable<IntoIter<Token>>,
+        position: &mut usize,
+    ) -> ParseResult<Expr> {
+        let member = match super::utils::ParserUtils::next_token(tokens, position) {
+            Some(Token::Class) => return Ok(Expr::ClassConstant { class, name: "class".to_string() }),
+            Some(Token::Variable(name)) => {
+                let property = Expr::StaticProperty { class, name };
+                return Self::parse_postfix_access(tokens, position, property);
+            }
+            other => match other.as_ref().and_then(super::utils::ParserUtils::member_identifier) {
+                Some(member) => member,
+                None => return Err(ParseError::ExpectedToken { expected: "method or constant name".into(), found: format!("{:?}", other), position: *position }),
+            },
+        };
+        if !matches!(tokens.peek(), Some(Token::OpenParen)) {
+            return Ok(Expr::ClassConstant { class, name: member });
+        }
+        let method = member;
+        Self::consume_token(tokens, position, Token::OpenParen)?;
+        let args = Self::parse_function_args(tokens, position)?;
+        Self::consume_token(tokens, position, Token::CloseParen)?;
+        let call_expr = Expr::StaticCall { class, method, args };
+        Self::parse_postfix_access(tokens, position, call_expr)
+    }
+
     /// Parse chained array access: expr[ index ] ...
     fn parse_postfix_access(
         tokens: &mut Peekable<IntoIter<Token>>,
@@ -665,35 +872,35 @@ impl ExpressionParser {
                         index: Box::new(index_expr),
                     };
                 }
-                Some(Token::ObjectOperator) => {
-                    super::utils::ParserUtils::next_token(tokens, position); // '->'
-                    // Expect identifier for method/property
+                Some(Token::ObjectOperator) | Some(Token::NullsafeObjectOperator) => {
+                    let nullsafe = super::utils::ParserUtils::next_token(tokens, position) == Some(Token::NullsafeObjectOperator); // '->' or '?->'
+                    // Member name: identifier, or dynamic $var / {expr}
                     let name = match super::utils::ParserUtils::next_token(tokens, position) {
-                        Some(Token::Identifier(id)) => id,
-                        other => return Err(ParseError::ExpectedToken { expected: "method name".into(), found: format!("{:?}", other), position: *position })
+                        Some(Token::Variable(var)) => MemberName::Dynamic(Box::new(Expr::Variable(var))),
+                        Some(Token::OpenBrace) => {
+                            let name_expr = Self::parse_expression(tokens, position)?;
+                            Self::consume_token(tokens, position, Token::CloseBrace)?;
+                            MemberName::Dynamic(Box::new(name_expr))
+                        }
+                        other => match other.as_ref().and_then(super::utils::ParserUtils::member_identifier) {
+                            Some(id) => MemberName::Identifier(id),
+                            None => return Err(ParseError::ExpectedToken { expected: "member name".into(), found: format!("{:?}", other), position: *position }),
+                        },
                     };
                     // Optional call
                     if let Some(Token::OpenParen) = tokens.peek() {
                         super::utils::ParserUtils::next_token(tokens, position); // '('
-                        // parse args until ')'
-                        let mut args = Vec::new();
-                        if let Some(Token::CloseParen) = tokens.peek() {
-                            super::utils::ParserUtils::next_token(tokens, position); // empty
+                        let args = Self::parse_function_args(tokens, position)?;
+                        Self::consume_token(tokens, position, Token::CloseParen)?;
+                        expr = if nullsafe {
+                            Expr::NullsafeMethodCall { target: Box::new(expr), method: name, args }
                         } else {
-                            loop {
-                                let arg = Self::parse_expression(tokens, position)?;
-                                args.push(arg);
-                                match tokens.peek() {
-                                    Some(Token::Comma) => { super::utils::ParserUtils::next_token(tokens, position); }
-                                    Some(Token::CloseParen) => { super::utils::ParserUtils::next_token(tokens, position); break; }
-                                    other => return Err(ParseError::ExpectedToken { expected: ", or )".into(), found: format!("{:?}", other), position: *position }),
-                                }
-                            }
-                        }
-                        expr = Expr::MethodCall { target: Box::new(expr), method: name, args };
+                            Expr::MethodCall { target: Box::new(expr), method: name, args }
+                        };
+                    } else if nullsafe {
+                        expr = Expr::NullsafePropertyAccess { target: Box::new(expr), property: name };
                     } else {
-                        // Property fetch fallback: treat as zero-arg method call
-                        expr = Expr::MethodCall { target: Box::new(expr), method: name, args: Vec::new() };
+                        expr = Expr::PropertyAccess { target: Box::new(expr), property: name };
                     }
                 }
                 _ => break,
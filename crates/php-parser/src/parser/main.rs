@@ -10,7 +10,6 @@ use std::iter::Peekable;
 use std::vec::IntoIter;
 
 use super::control_flow::ControlFlowParser;
-use super::expressions::ExpressionParser;
 use super::statements::StatementParser;
 use super::utils::ParserUtils;
 
@@ -31,7 +30,9 @@ impl Parser {
 
     /// Parse tokens into a statement (program)
     pub fn parse(&mut self) -> ParseResult<Stmt> {
-        self.parse_block()
+        let program = self.parse_block()?;
+        ControlFlowParser::check_loop_context(&program, false)?;
+        Ok(program)
     }
 
     /// Parse a block of statements
@@ -46,9 +47,10 @@ impl Parser {
         while let Some(token) = self.tokens.peek() {
             match token {
                 Token::EOF => break,
-                Token::PhpClose => {
-                    ParserUtils::next_token(&mut self.tokens, &mut self.position); // consume ?>
-                    break;
+                Token::PhpOpen | Token::PhpClose => {
+                    // Leaving or re-entering PHP mode; inline text in between
+                    // arrives as its own token
+                    ParserUtils::next_token(&mut self.tokens, &mut self.position);
                 }
                 _ => statements.push(self.parse_statement()?),
             }
@@ -70,13 +72,31 @@ impl Parser {
         match tokens.peek() {
             Some(Token::Echo) => StatementParser::parse_echo(tokens, position),
             Some(Token::Print) => StatementParser::parse_print(tokens, position),
+            Some(Token::InlineHtml(_)) => match ParserUtils::next_token(tokens, position) {
+                Some(Token::InlineHtml(html)) => Ok(Stmt::InlineHtml(html)),
+                _ => unreachable!("peeked InlineHtml"),
+            },
+            // Tags inside a nested block, e.g. `if ($x) { ?>text<?php }`
+            Some(Token::PhpOpen) | Some(Token::PhpClose) => {
+                ParserUtils::next_token(tokens, position);
+                Ok(Stmt::Nop)
+            }
             Some(Token::Variable(_)) => StatementParser::parse_assignment_or_expression(tokens, position),
             Some(Token::OpenBracket) => StatementParser::parse_assignment_or_expression(tokens, position),
-            Some(Token::Static) => StatementParser::parse_static(tokens, position),
+            Some(Token::Static) => {
+                // static::method() is a late static binding call, not a static variable declaration
+                if matches!(tokens.clone().nth(1), Some(Token::DoubleColon)) {
+                    StatementParser::parse_expression_statement(tokens, position)
+                } else {
+                    StatementParser::parse_static(tokens, position)
+                }
+            }
             Some(Token::Const) => StatementParser::parse_const(tokens, position),
             Some(Token::Function) => StatementParser::parse_function_definition(tokens, position),
+            Some(Token::Class) => StatementParser::parse_class_definition(tokens, position),
             Some(Token::If) => ControlFlowParser::parse_if(tokens, position),
             Some(Token::While) => ControlFlowParser::parse_while(tokens, position),
+            Some(Token::Do) => ControlFlowParser::parse_do_while(tokens, position),
             Some(Token::For) => ControlFlowParser::parse_for(tokens, position),
             Some(Token::Foreach) => ControlFlowParser::parse_foreach(tokens, position),
             Some(Token::Return) => ControlFlowParser::parse_return(tokens, position),
@@ -86,6 +106,10 @@ impl Parser {
             Some(Token::Try) => ControlFlowParser::parse_try(tokens, position),
             Some(Token::Declare) => StatementParser::parse_declare(tokens, position),
             Some(Token::OpenBrace) => Self::parse_block_statement(tokens, position),
+            Some(Token::Semicolon) => {
+                ParserUtils::next_token(tokens, position); // consume lone ';'
+                Ok(Stmt::Nop)
+            }
             _ => StatementParser::parse_expression_statement(tokens, position),
         }
     }
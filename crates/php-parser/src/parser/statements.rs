@@ -6,7 +6,7 @@
 //! - Constant definitions
 //! - Expression statements
 
-use crate::ast::{Expr, Stmt};
+use crate::ast::{ClassMember, Expr, Param, Stmt};
 use crate::ast::DestructTarget;
 use crate::error::{ParseError, ParseResult};
 use php_lexer::Token;
@@ -93,7 +93,14 @@ impl StatementParser {
                     super::utils::ParserUtils::next_token(tokens, position); // variable
                     super::utils::ParserUtils::next_token(tokens, position); // '='
                     if let Some(Token::Ampersand) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); }
-                    let value = super::expressions::ExpressionParser::parse_expression(tokens, position)?;
+                    let value = super::expressions::ExpressionParser::parse_assignment_expression(tokens, position)?;
+                    // `$a = x or y` assigns first, then applies the keyword operator to the result
+                    if matches!(tokens.peek(), Some(Token::KeywordAnd) | Some(Token::KeywordOr) | Some(Token::KeywordXor)) {
+                        let assign = Expr::Assign { target: Box::new(Expr::Variable(var_name)), value: Box::new(value) };
+                        let expr = super::expressions::ExpressionParser::parse_keyword_logical_rest(tokens, position, assign, 0)?;
+                        Self::consume_semicolon(tokens, position)?;
+                        return Ok(Stmt::Expression(expr));
+                    }
                     // Semicolon or tolerant heuristic
                     match tokens.peek() {
                         Some(Token::Semicolon) => { super::utils::ParserUtils::next_token(tokens, position); }
@@ -116,43 +123,6 @@ impl StatementParser {
                     Self::consume_semicolon(tokens, position)?;
                     return Ok(Stmt::Expression(expr));
                 }
-                // Compound assignment += (currently only supporting '+=' pattern as Plus followed by Equals)
-                Some(Token::Plus) => {
-                    // Look ahead one more to see if '=' follows
-                    let mut la2 = la.clone();
-                    la2.next(); // consume Plus in lookahead
-                    if let Some(Token::Equals) = la2.peek() {
-                        // consume real variable, plus, equals
-                        super::utils::ParserUtils::next_token(tokens, position); // variable
-                        super::utils::ParserUtils::next_token(tokens, position); // plus
-                        super::utils::ParserUtils::next_token(tokens, position); // equals
-                        let rhs = super::expressions::ExpressionParser::parse_expression(tokens, position)?;
-                        // Build value = var + rhs
-                        let value_expr = Expr::Binary { left: Box::new(Expr::Variable(var_name.clone())), op: crate::ast::BinaryOp::Add, right: Box::new(rhs) };
-                        match tokens.peek() {
-                            Some(Token::Semicolon) => { super::utils::ParserUtils::next_token(tokens, position); }
-                            _ => { Self::consume_semicolon(tokens, position)?; }
-                        }
-                        return Ok(Stmt::Assignment { variable: var_name, value: value_expr });
-                    }
-                }
-                Some(Token::Dot) => {
-                    // Look ahead for '=' to form '.='
-                    let mut la2 = la.clone();
-                    la2.next();
-                    if let Some(Token::Equals) = la2.peek() {
-                        super::utils::ParserUtils::next_token(tokens, position); // variable
-                        super::utils::ParserUtils::next_token(tokens, position); // dot
-                        super::utils::ParserUtils::next_token(tokens, position); // equals
-                        let rhs = super::expressions::ExpressionParser::parse_expression(tokens, position)?;
-                        let value_expr = Expr::Binary { left: Box::new(Expr::Variable(var_name.clone())), op: crate::ast::BinaryOp::Concatenate, right: Box::new(rhs) };
-                        match tokens.peek() {
-                            Some(Token::Semicolon) => { super::utils::ParserUtils::next_token(tokens, position); }
-                            _ => { Self::consume_semicolon(tokens, position)?; }
-                        }
-                        return Ok(Stmt::Assignment { variable: var_name, value: value_expr });
-                    }
-                }
                 Some(Token::NullCoalescing) => {
                     // Look ahead for '=' to detect '??='
                     let mut la2 = la.clone();
@@ -170,7 +140,23 @@ impl StatementParser {
                         return Ok(Stmt::NullCoalesceAssign { variable: var_name, value: rhs });
                     }
                 }
-                _ => { /* fall through to generic expression parsing */ }
+                // Compound assignment `$x <op>= rhs` becomes `$x = $x <op> rhs`
+                Some(op_token) => {
+                    if let Some(op) = super::expressions::ExpressionParser::compound_assignment_op(op_token) {
+                        let mut la2 = la.clone();
+                        la2.next(); // consume operator in lookahead
+                        if let Some(Token::Equals) = la2.peek() {
+                            super::utils::ParserUtils::next_token(tokens, position); // variable
+                            super::utils::ParserUtils::next_token(tokens, position); // operator
+                            super::utils::ParserUtils::next_token(tokens, position); // '='
+                            let rhs = super::expressions::ExpressionParser::parse_expression(tokens, position)?;
+                            let value = Expr::Binary { left: Box::new(Expr::Variable(var_name.clone())), op, right: Box::new(rhs) };
+                            Self::consume_semicolon(tokens, position)?;
+                            return Ok(Stmt::Assignment { variable: var_name, value });
+                        }
+                    }
+                }
+                None => {}
             }
         }
 
@@ -202,96 +188,55 @@ impl StatementParser {
         tokens: &mut Peekable<IntoIter<Token>>,
         position: &mut usize,
     ) -> ParseResult<Stmt> {
-        let start_pos = *position;
+        // Parse the pattern on a copy so a plain array expression statement can fall back
         let mut clone = tokens.clone();
-        let mut clone_pos = start_pos;
-        // Consume '['
-        match super::utils::ParserUtils::next_token(&mut clone, &mut clone_pos) {
-            Some(Token::OpenBracket) => {}
-            _ => return Err(ParseError::InvalidStatement { message: "not destructuring".into() }),
+        let mut clone_pos = *position;
+        let targets = Self::parse_destructuring_pattern(&mut clone, &mut clone_pos)?;
+        if !matches!(clone.peek(), Some(Token::Equals)) {
+            return Err(ParseError::InvalidStatement { message: "missing = after destructuring pattern".into() });
         }
+        *tokens = clone;
+        *position = clone_pos;
+        Self::consume_token(tokens, position, Token::Equals)?;
+        let value_expr = super::expressions::ExpressionParser::parse_expression(tokens, position)?;
+        Self::consume_semicolon(tokens, position)?;
+        Ok(Stmt::DestructuringAssignment { targets, value: value_expr })
+    }
+
+    /// Parse `[$a, $b]` or `[key => $a, ...]` where each key is an arbitrary expression
+    fn parse_destructuring_pattern(
+        tokens: &mut Peekable<IntoIter<Token>>,
+        position: &mut usize,
+    ) -> ParseResult<Vec<DestructTarget>> {
+        Self::consume_token(tokens, position, Token::OpenBracket)?;
         let mut targets = Vec::new();
-    let expect_comma_or_close = false; // placeholder for future validation logic
-        loop {
-            match clone.peek() {
-                Some(Token::CloseBracket) => {
-                    super::utils::ParserUtils::next_token(&mut clone, &mut clone_pos); // consume ]
-                    break;
-                }
-                None => return Err(ParseError::UnexpectedEof),
-                _ => {
-                    if expect_comma_or_close {
-                        return Err(ParseError::InvalidStatement { message: "expected comma or ]".into() });
-                    }
-                    // Optional keyed form: String '=>' Variable
-                    let mut key: Option<String> = None;
-                    // Peek for string key
-                    if let Some(Token::String(s)) = clone.peek().cloned() {
-                        // Look ahead for =>
-                        let mut la = clone.clone();
-                        let _ = la.next(); // consume string in lookahead
-                        if let Some(Token::Arrow) = la.peek() {
-                            // consume string and => in real clone
-                            super::utils::ParserUtils::next_token(&mut clone, &mut clone_pos); // string
-                            super::utils::ParserUtils::next_token(&mut clone, &mut clone_pos); // =>
-                            key = Some(s);
-                        }
-                    }
-                    // Expect variable
-                    match super::utils::ParserUtils::next_token(&mut clone, &mut clone_pos) {
-                        Some(Token::Variable(var_name)) => {
-                            if let Some(k) = key { targets.push(DestructTarget::KeyVar(k, var_name)); } else { targets.push(DestructTarget::Var(var_name)); }
-                        }
-                        other => return Err(ParseError::ExpectedToken { expected: "variable".into(), found: format!("{:?}", other), position: clone_pos }),
-                    }
-                    // Comma or close
-                    match clone.peek() {
-                        Some(Token::Comma) => { super::utils::ParserUtils::next_token(&mut clone, &mut clone_pos); }
-                        Some(Token::CloseBracket) => {}
-                        _ => {}
-                    }
-                }
-            }
-        }
-        // Expect equals
-        match clone.peek() {
-            Some(Token::Equals) => { super::utils::ParserUtils::next_token(&mut clone, &mut clone_pos); }
-            _ => return Err(ParseError::InvalidStatement { message: "missing = after destructuring pattern".into() }),
-        }
-        // Commit: replace original iterator by consuming the same tokens
-        // Consume from original tokens now that we know it's destructuring
-        // Simpler: re-parse pattern on original tokens
-        // Consume '['
-        super::utils::ParserUtils::next_token(tokens, position);
-        let mut committed_targets = Vec::new();
         loop {
             if let Some(Token::CloseBracket) = tokens.peek() {
                 super::utils::ParserUtils::next_token(tokens, position); // ]
                 break;
             }
-            let mut key: Option<String> = None;
-            if let Some(Token::String(s)) = tokens.peek().cloned() {
-                // lookahead for =>
-                let mut la = tokens.clone();
-                let _ = la.next();
-                if let Some(Token::Arrow) = la.peek() {
-                    super::utils::ParserUtils::next_token(tokens, position); // string
-                    super::utils::ParserUtils::next_token(tokens, position); // =>
-                    key = Some(s);
-                }
-            }
+            // A lone variable is a positional target; anything else starts a key expression
+            let mut la = tokens.clone();
+            let positional = matches!(la.next(), Some(Token::Variable(_))) && matches!(la.peek(), Some(Token::Comma) | Some(Token::CloseBracket));
+            let key = if positional {
+                None
+            } else {
+                let key = super::expressions::ExpressionParser::parse_expression(tokens, position)?;
+                Self::consume_token(tokens, position, Token::Arrow)?;
+                Some(key)
+            };
             let var_name = match super::utils::ParserUtils::next_token(tokens, position) {
                 Some(Token::Variable(v)) => v,
                 other => return Err(ParseError::ExpectedToken { expected: "variable".into(), found: format!("{:?}", other), position: *position }),
             };
-            committed_targets.push(if let Some(k) = key { DestructTarget::KeyVar(k, var_name) } else { DestructTarget::Var(var_name) });
-            if let Some(Token::Comma) = tokens.peek() { super::utils::ParserUtils::next_token(tokens, position); }
+            targets.push(match key { Some(key) => DestructTarget::KeyVar(key, var_name), None => DestructTarget::Var(var_name) });
+            match tokens.peek() {
+                Some(Token::Comma) => { super::utils::ParserUtils::next_token(tokens, position); }
+                Some(Token::CloseBracket) => {}
+                other => return Err(ParseError::ExpectedToken { expected: ", or ]".into(), found: format!("{:?}", other), position: *position }),
+            }
         }
-        // '='
-        Self::consume_token(tokens, position, Token::Equals)?;
-        let value_expr = super::expressions::ExpressionParser::parse_expression(tokens, position)?;
-        Self::consume_semicolon(tokens, position)?;
-        Ok(Stmt::DestructuringAssignment { targets: committed_targets, value: value_expr })
+        Ok(targets)
     }
 
     /// Parse const statement
@@ -346,14 +291,16 @@ impl StatementParser {
     ) -> ParseResult<Stmt> {
         Self::consume_token(tokens, position, Token::Function)?;
         
-        // Parse function name
+        // Parse function name (methods may use semi-reserved names such as `match`)
         let name = match super::utils::ParserUtils::next_token(tokens, position) {
-            Some(Token::Identifier(name)) => name,
-            Some(token) => return Err(ParseError::ExpectedToken {
-                expected: "function name".to_string(),
-                found: format!("{:?}", token),
-                position: *position,
-            }),
+            Some(token) => match super::utils::ParserUtils::member_identifier(&token) {
+                Some(name) => name,
+                None => return Err(ParseError::ExpectedToken {
+                    expected: "function name".to_string(),
+                    found: format!("{:?}", token),
+                    position: *position,
+                }),
+            },
             None => return Err(ParseError::UnexpectedEof),
         };
 
@@ -366,28 +313,41 @@ impl StatementParser {
             super::utils::ParserUtils::next_token(tokens, position); // consume ')'
         } else {
             loop {
-                // Skip optional simple type hints (Identifier '|' Identifier ...)
+                // Constructor promotion modifiers: public/protected/private and readonly
+                let mut promoted = false;
+                let mut readonly = false;
                 loop {
                     match tokens.peek() {
-                        Some(Token::Identifier(_)) => { super::utils::ParserUtils::next_token(tokens, position); }
+                        Some(Token::Public) | Some(Token::Protected) | Some(Token::Private) => promoted = true,
+                        Some(Token::Identifier(id)) if id.eq_ignore_ascii_case("readonly") => readonly = true,
                         _ => break,
                     }
+                    super::utils::ParserUtils::next_token(tokens, position);
+                }
+                // Nullable type marker: ?Type
+                if let Some(Token::QuestionMark) = tokens.peek() {
+                    super::utils::ParserUtils::next_token(tokens, position);
+                }
+                // Skip optional simple type hints (Identifier '|' Identifier ...)
+                while let Some(Token::Identifier(_)) = tokens.peek() {
+                    super::utils::ParserUtils::next_token(tokens, position);
                     // Support union types: continue if next is pipe
                     if let Some(Token::Pipe) = tokens.peek() {
                         super::utils::ParserUtils::next_token(tokens, position); // consume pipe and loop
-                        continue;
                     } else {
                         break;
                     }
                 }
-                // Variadic ellipsis '...'
-                if let Some(Token::Ellipsis) = tokens.peek() {
-                    super::utils::ParserUtils::next_token(tokens, position); // consume ellipsis (ignored semantics)
-                }
                 // Optional by-reference '&'
-                if let Some(Token::Ampersand) = tokens.peek() {
+                let by_ref = matches!(tokens.peek(), Some(Token::Ampersand));
+                if by_ref {
                     super::utils::ParserUtils::next_token(tokens, position); // consume '&'
                 }
+                // Variadic ellipsis '...'
+                let variadic = matches!(tokens.peek(), Some(Token::Ellipsis));
+                if variadic {
+                    super::utils::ParserUtils::next_token(tokens, position); // consume '...'
+                }
                 // Now expect parameter variable
                 let param_name = match super::utils::ParserUtils::next_token(tokens, position) {
                     Some(Token::Variable(name)) => name,
@@ -398,13 +358,14 @@ impl StatementParser {
                     }),
                     None => return Err(ParseError::UnexpectedEof),
                 };
-                // Optional default value assignment: = expr (ignored for now; just consume tokens)
-                if let Some(Token::Equals) = tokens.peek() {
+                // Optional default value assignment: = expr
+                let default = if let Some(Token::Equals) = tokens.peek() {
                     super::utils::ParserUtils::next_token(tokens, position); // consume '='
-                    // Parse and discard expression
-                    let _default_expr = super::expressions::ExpressionParser::parse_expression(tokens, position)?;
-                }
-                parameters.push(param_name);
+                    Some(super::expressions::ExpressionParser::parse_expression(tokens, position)?)
+                } else {
+                    None
+                };
+                parameters.push(Param { name: param_name, by_ref, variadic, default, promoted: promoted || readonly, readonly });
 
                 // Check for more parameters or end
                 match tokens.peek() {
@@ -429,14 +390,10 @@ impl StatementParser {
         if let Some(Token::Colon) = tokens.peek() {
             super::utils::ParserUtils::next_token(tokens, position); // consume ':'
             // Consume one or more identifiers separated by pipes
-            loop {
-                match tokens.peek() {
-                    Some(Token::Identifier(_)) => { super::utils::ParserUtils::next_token(tokens, position); }
-                    _ => break,
-                }
+            while let Some(Token::Identifier(_)) = tokens.peek() {
+                super::utils::ParserUtils::next_token(tokens, position);
                 if let Some(Token::Pipe) = tokens.peek() {
                     super::utils::ParserUtils::next_token(tokens, position); // consume '|'
-                    continue;
                 } else {
                     break;
                 }
@@ -454,6 +411,142 @@ impl StatementParser {
         })
     }
 
+    /// Parse class declaration: class Name [extends Parent] [implements A, B] { members }
+    pub fn parse_class_definition(
+        tokens: &mut Peekable<IntoIter<Token>>,
+        position: &mut usize,
+    ) -> ParseResult<Stmt> {
+        Self::consume_token(tokens, position, Token::Class)?;
+        let name = Self::expect_identifier(tokens, position, "class name")?;
+        let (parent, interfaces, members) = Self::parse_class_tail(tokens, position)?;
+        Ok(Stmt::ClassDefinition { name, parent, interfaces, members })
+    }
+
+    /// Parse what follows a class name, also used by anonymous classes:
+    /// [extends Parent] [implements A, B] { members }
+    pub(crate) fn parse_class_tail(
+        tokens: &mut Peekable<IntoIter<Token>>,
+        position: &mut usize,
+    ) -> ParseResult<(Option<String>, Vec<String>, Vec<ClassMember>)> {
+        let mut parent = None;
+        if let Some(Token::Extends) = tokens.peek() {
+            super::utils::ParserUtils::next_token(tokens, position); // 'extends'
+            parent = Some(Self::expect_identifier(tokens, position, "parent class name")?);
+        }
+        // Interfaces are recorded but their methods are not enforced
+        let mut interfaces = Vec::new();
+        if let Some(Token::Implements) = tokens.peek() {
+            super::utils::ParserUtils::next_token(tokens, position); // 'implements'
+            loop {
+                interfaces.push(Self::expect_identifier(tokens, position, "interface name")?);
+                if let Some(Token::Comma) = tokens.peek() {
+                    super::utils::ParserUtils::next_token(tokens, position); // ','
+                } else {
+                    break;
+                }
+            }
+        }
+
+        Self::consume_token(tokens, position, Token::OpenBrace)?;
+        let mut members = Vec::new();
+        loop {
+            match tokens.peek() {
+                Some(Token::CloseBrace) => {
+                    super::utils::ParserUtils::next_token(tokens, position); // '}'
+                    break;
+                }
+                None | Some(Token::EOF) => return Err(ParseError::UnexpectedEof),
+                _ => {}
+            }
+
+            // Modifiers (visibility is parsed but not enforced)
+            let mut is_static = false;
+            let mut is_readonly = false;
+            loop {
+                match tokens.peek() {
+                    Some(Token::Public) | Some(Token::Private) | Some(Token::Protected) | Some(Token::Var) => {
+                        super::utils::ParserUtils::next_token(tokens, position);
+                    }
+                    Some(Token::Static) => {
+                        super::utils::ParserUtils::next_token(tokens, position);
+                        is_static = true;
+                    }
+                    Some(Token::Identifier(id)) if id.eq_ignore_ascii_case("readonly") => {
+                        super::utils::ParserUtils::next_token(tokens, position);
+                        is_readonly = true;
+                    }
+                    Some(Token::Identifier(id)) if matches!(id.as_str(), "abstract" | "final") => {
+                        super::utils::ParserUtils::next_token(tokens, position);
+                    }
+                    _ => break,
+                }
+            }
+
+            match tokens.peek() {
+                Some(Token::Function) => {
+                    let Stmt::FunctionDefinition { name, parameters, body } = Self::parse_function_definition(tokens, position)? else {
+                        unreachable!("parse_function_definition always yields a function definition");
+                    };
+                    members.push(ClassMember::Method { name, parameters, body, is_static });
+                }
+                Some(Token::Const) => {
+                    let Stmt::ConstantDefinition { name, value } = Self::parse_const(tokens, position)? else {
+                        unreachable!("parse_const always yields a constant definition");
+                    };
+                    members.push(ClassMember::Constant { name, value });
+                }
+                _ => {
+                    // Property declaration with optional type: [?Type] $name [= default] [, $other ...];
+                    while let Some(Token::Identifier(_)) | Some(Token::QuestionMark) | Some(Token::Pipe) = tokens.peek() {
+                        super::utils::ParserUtils::next_token(tokens, position);
+                    }
+                    loop {
+                        let prop_name = match super::utils::ParserUtils::next_token(tokens, position) {
+                            Some(Token::Variable(name)) => name,
+                            Some(token) => return Err(ParseError::ExpectedToken {
+                                expected: "property or method declaration".to_string(),
+                                found: format!("{:?}", token),
+                                position: *position,
+                            }),
+                            None => return Err(ParseError::UnexpectedEof),
+                        };
+                        let mut default = None;
+                        if let Some(Token::Equals) = tokens.peek() {
+                            super::utils::ParserUtils::next_token(tokens, position); // '='
+                            default = Some(super::expressions::ExpressionParser::parse_expression(tokens, position)?);
+                        }
+                        members.push(ClassMember::Property { name: prop_name, default, is_static, is_readonly });
+                        if let Some(Token::Comma) = tokens.peek() {
+                            super::utils::ParserUtils::next_token(tokens, position); // ','
+                        } else {
+                            break;
+                        }
+                    }
+                    Self::consume_semicolon(tokens, position)?;
+                }
+            }
+        }
+
+        Ok((parent, interfaces, members))
+    }
+
+    /// Consume an identifier token and return its name
+    fn expect_identifier(
+        tokens: &mut Peekable<IntoIter<Token>>,
+        position: &mut usize,
+        what: &str,
+    ) -> ParseResult<String> {
+        match super::utils::ParserUtils::next_token(tokens, position) {
+            Some(Token::Identifier(name)) => Ok(name),
+            Some(token) => Err(ParseError::ExpectedToken {
+                expected: what.to_string(),
+                found: format!("{:?}", token),
+                position: *position,
+            }),
+            None => Err(ParseError::UnexpectedEof),
+        }
+    }
+
     /// Parse block statements (helper for function bodies, control structures)
     fn parse_block_statements(
         tokens: &mut Peekable<IntoIter<Token>>,
@@ -490,11 +583,15 @@ impl StatementParser {
         }
     }
 
-    /// Consume semicolon
+    /// Consume the semicolon ending a statement
     fn consume_semicolon(
         tokens: &mut Peekable<IntoIter<Token>>,
         position: &mut usize,
     ) -> ParseResult<()> {
+        // `?>` terminates the statement too; leave it for the block parser
+        if let Some(Token::PhpClose) = tokens.peek() {
+            return Ok(());
+        }
         Self::consume_token(tokens, position, Token::Semicolon)
     }
 }
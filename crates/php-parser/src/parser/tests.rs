@@ -9,6 +9,7 @@ use std::iter::Peekable;
 use std::vec::IntoIter;
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use super::*;
 
@@ -153,4 +154,32 @@ mod tests {
             panic!("Expected while statement");
         }
     }
+
+    #[test]
+    fn test_control_flow_do_while() {
+        let tokens = vec![
+            Token::Do,
+            Token::OpenBrace,
+            Token::Echo,
+            Token::String("once".to_string()),
+            Token::Semicolon,
+            Token::CloseBrace,
+            Token::While,
+            Token::OpenParen,
+            Token::False,
+            Token::CloseParen,
+            Token::Semicolon,
+        ];
+        let (mut token_stream, mut position) = create_token_stream(tokens);
+
+        let result = ControlFlowParser::parse_do_while(&mut token_stream, &mut position);
+
+        if let Ok(Stmt::DoWhile { body, condition }) = result {
+            assert!(matches!(*body, Stmt::Block(_)));
+            assert!(matches!(condition, Expr::Bool(false)));
+            assert!(token_stream.next().is_none());
+        } else {
+            panic!("Expected do-while statement, got {:?}", result);
+        }
+    }
 }
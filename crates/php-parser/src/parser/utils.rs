@@ -12,6 +12,7 @@ pub struct ParserUtils;
 
 impl ParserUtils {
     /// Peek at next token
+    #[allow(dead_code)]
     pub fn peek_token(tokens: &mut Peekable<IntoIter<Token>>) -> Option<&Token> {
         tokens.peek()
     }
@@ -24,4 +25,19 @@ impl ParserUtils {
         *position += 1;
         tokens.next()
     }
+
+    /// True when the next token ends a statement: `;`, or a `?>` close tag,
+    /// which implies the final semicolon
+    pub fn at_statement_end(tokens: &mut Peekable<IntoIter<Token>>) -> bool {
+        matches!(tokens.peek(), Some(Token::Semicolon) | Some(Token::PhpClose))
+    }
+
+    /// Name for a token where PHP accepts reserved words as identifiers:
+    /// method, property and class constant names such as `->match()` or `->or()`
+    pub fn member_identifier(token: &Token) -> Option<String> {
+        match token {
+            Token::Identifier(name) => Some(name.clone()),
+            other => other.keyword_text().map(str::to_string),
+        }
+    }
 }
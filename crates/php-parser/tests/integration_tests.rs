@@ -1,7 +1,6 @@
 //! Integration tests for php-parser
 
 use php_parser::*;
-use php_parser::ast::*;
 use php_lexer::{lex, Token};
 
 #[test]
@@ -65,13 +64,11 @@ fn test_arithmetic_expression() {
     
     if let Stmt::Block(statements) = ast {
         assert_eq!(statements.len(), 1);
-        if let Stmt::Echo(expr) = &statements[0] {
-            // Should parse as 2 + (3 * 4) due to precedence
-            if let Expr::Binary { left, op, right } = expr {
-                assert!(matches!(op, BinaryOp::Add));
-                assert!(matches!(**left, Expr::Number(2.0)));
-                assert!(matches!(**right, Expr::Binary { .. }));
-            }
+        // Should parse as 2 + (3 * 4) due to precedence
+        if let Stmt::Echo(Expr::Binary { left, op, right }) = &statements[0] {
+            assert!(matches!(op, BinaryOp::Add));
+            assert!(matches!(**left, Expr::Number(2.0)));
+            assert!(matches!(**right, Expr::Binary { .. }));
         }
     }
 }
@@ -160,3 +157,217 @@ fn test_full_integration() {
         assert_eq!(statements.len(), 3); // 2 assignments, 1 echo
     }
 }
+
+#[test]
+fn test_empty_statements() {
+    let tokens = lex("<?php ;; while ($x);").expect("Failed to lex");
+    let ast = parse(tokens).expect("Failed to parse");
+
+    if let Stmt::Block(statements) = ast {
+        assert_eq!(statements.len(), 3);
+        assert!(matches!(statements[0], Stmt::Nop));
+        assert!(matches!(statements[1], Stmt::Nop));
+        if let Stmt::While { body, .. } = &statements[2] {
+            assert!(matches!(**body, Stmt::Nop));
+        } else {
+            panic!("Expected while statement");
+        }
+    }
+}
+
+#[test]
+fn test_by_reference_parameters() {
+    let tokens = lex("<?php function bump(&$value, $key) {}").expect("Failed to lex");
+    let ast = parse(tokens).expect("Failed to parse");
+
+    if let Stmt::Block(statements) = ast {
+        if let Stmt::FunctionDefinition { parameters, .. } = &statements[0] {
+            assert_eq!(parameters, &vec![Param { by_ref: true, ..Param::new("value") }, Param::new("key")]);
+        } else {
+            panic!("Expected function definition");
+        }
+    }
+}
+
+#[test]
+fn test_default_and_variadic_parameters() {
+    let tokens = lex("<?php function pad($text, $width = 8, ...$flags) {}").expect("Failed to lex");
+    let ast = parse(tokens).expect("Failed to parse");
+
+    if let Stmt::Block(statements) = ast {
+        if let Stmt::FunctionDefinition { parameters, .. } = &statements[0] {
+            assert!(parameters[0].is_required());
+            assert_eq!(parameters[1].default, Some(Expr::Int(8)));
+            assert!(parameters[2].variadic && !parameters[2].is_required());
+        } else {
+            panic!("Expected function definition");
+        }
+    }
+}
+
+#[test]
+fn test_analyze_reports_likely_mistakes() {
+    let source = r#"<?php
+        function total($items) {
+            $sum = 0;
+            foreach ($items as $item) { $sum = $sum + $item; }
+            return $sum;
+            echo "done";
+        }
+        $prices = [1, 2];
+        echo total($prices) + $undefined;
+        no_such_func($prices);
+        $fn = function($x) use ($prices) { return $x . $later; };
+        echo $missing ?? "default";
+    "#;
+    let ast = parse(lex(source).expect("Failed to lex")).expect("Failed to parse");
+    assert_eq!(analyze(&ast), vec![
+        Diagnostic::UnreachableCode { after: "return" },
+        Diagnostic::UndefinedVariable("undefined".to_string()),
+        Diagnostic::UnknownFunction("no_such_func".to_string()),
+        Diagnostic::UndefinedVariable("later".to_string()),
+    ]);
+    assert_eq!(analyze_with_functions(&ast, &["NO_SUCH_FUNC"]).len(), 3);
+}
+
+#[test]
+fn test_match_keyword_and_method_name() {
+    let tokens = lex("<?php $obj->match(1); echo match ($x) { 1, 2 => 'low', default => 'high' };").expect("Failed to lex");
+    assert!(tokens.contains(&Token::Match));
+    let ast = parse(tokens).expect("Failed to parse");
+
+    if let Stmt::Block(statements) = ast {
+        assert!(matches!(
+            &statements[0],
+            Stmt::Expression(Expr::MethodCall { method: MemberName::Identifier(name), .. }) if name == "match"
+        ));
+        if let Stmt::Echo(expr) = &statements[1] {
+            if let Expr::Match { arms, default_arm, .. } = expr {
+                assert_eq!(arms[0].0.len(), 2);
+                assert!(default_arm.is_some());
+            } else {
+                panic!("Expected match expression");
+            }
+        } else {
+            panic!("Expected echo statement");
+        }
+    }
+}
+
+#[test]
+fn test_reserved_words_as_method_and_property_names() {
+    let parse_source = |source: &str| parse(lex(source).expect("Failed to lex"));
+
+    let ast = parse_source("<?php class Q { public function or($x) {} public function and() {} function count() {} } $q->or(1)->and; $q?->xor(); $q->count();")
+        .expect("Failed to parse");
+    if let Stmt::Block(statements) = ast {
+        let Stmt::ClassDefinition { members, .. } = &statements[0] else { panic!("Expected class definition") };
+        let methods: Vec<&str> = members.iter().map(|member| match member {
+            ClassMember::Method { name, .. } => name.as_str(),
+            other => panic!("Expected method, got {:?}", other),
+        }).collect();
+        assert_eq!(methods, ["or", "and", "count"]);
+        assert_eq!(statements[1].to_string().trim_end(), "$q->or(1)->and;");
+        assert_eq!(statements[2].to_string().trim_end(), "$q?->xor();");
+        assert_eq!(statements[3].to_string().trim_end(), "$q->count();");
+    }
+}
+
+#[test]
+fn test_fn_and_yield_keywords() {
+    let tokens = lex("<?php $double = fn($x) => $x * 2; function g() { yield; yield from [1, 2]; yield 'k' => 3; }").expect("Failed to lex");
+    assert!(tokens.contains(&Token::Fn) && tokens.contains(&Token::Yield));
+    let ast = parse(tokens).expect("Failed to parse");
+
+    if let Stmt::Block(statements) = ast {
+        assert!(matches!(&statements[0], Stmt::Assignment { value: Expr::ArrowFunction { .. }, .. }));
+        if let Stmt::FunctionDefinition { body, .. } = &statements[1] {
+            let Stmt::Block(body) = &**body else { panic!("Expected function body block") };
+            assert_eq!(body[0], Stmt::Expression(Expr::Yield { key: None, value: Box::new(Expr::Null) }));
+            assert!(matches!(&body[1], Stmt::Expression(Expr::YieldFrom(source)) if matches!(**source, Expr::Array(_))));
+            assert_eq!(body[2].to_string().trim_end(), "yield \"k\" => 3;");
+        } else {
+            panic!("Expected function definition");
+        }
+    }
+}
+
+#[test]
+fn test_compound_assignment_operators() {
+    let ast = parse(lex("<?php $x -= 2; $x **= 3; $x |= 4;").expect("Failed to lex")).expect("Failed to parse");
+
+    if let Stmt::Block(statements) = ast {
+        let ops: Vec<BinaryOp> = statements.iter().map(|stmt| match stmt {
+            Stmt::Assignment { variable, value: Expr::Binary { left, op, .. } } if variable == "x" && **left == Expr::Variable("x".to_string()) => op.clone(),
+            other => panic!("Expected compound assignment, got {:?}", other),
+        }).collect();
+        assert_eq!(ops, vec![BinaryOp::Subtract, BinaryOp::Power, BinaryOp::BitwiseOr]);
+    }
+}
+
+#[test]
+fn test_compound_assignment_on_any_assignable_target() {
+    let ast = parse(lex("<?php $a[0] -= 5; $o->n .= 'x'; for ($i = 0; $i < 6; $i += 2) {}").expect("Failed to lex")).expect("Failed to parse");
+
+    if let Stmt::Block(statements) = ast {
+        let element = Expr::ArrayAccess { array: Box::new(Expr::Variable("a".to_string())), index: Box::new(Expr::Int(0)) };
+        assert_eq!(statements[0], Stmt::Expression(Expr::Assign {
+            target: Box::new(element.clone()),
+            value: Box::new(Expr::Binary { left: Box::new(element), op: BinaryOp::Subtract, right: Box::new(Expr::Int(5)) }),
+        }));
+        assert_eq!(statements[1].to_string().trim_end(), "$o->n = ($o->n . \"x\");");
+        let Stmt::For { increment: Some(update), .. } = &statements[2] else { panic!("Expected for loop") };
+        assert!(matches!(update, Expr::Assign { value, .. } if matches!(**value, Expr::Binary { op: BinaryOp::Add, .. })));
+    }
+}
+
+#[test]
+fn test_unary_minus_and_plus() {
+    let ast = parse(lex("<?php echo -2 ** 2; echo -$a * 3;").expect("Failed to lex")).expect("Failed to parse");
+
+    if let Stmt::Block(statements) = ast {
+        let Stmt::Echo(power) = &statements[0] else { panic!("Expected echo") };
+        assert!(matches!(power, Expr::Unary { op: UnaryOp::Minus, operand } if matches!(**operand, Expr::Binary { op: BinaryOp::Power, .. })));
+        let Stmt::Echo(product) = &statements[1] else { panic!("Expected echo") };
+        assert!(matches!(product, Expr::Binary { op: BinaryOp::Multiply, left, .. } if matches!(**left, Expr::Unary { op: UnaryOp::Minus, .. })));
+    }
+}
+
+#[test]
+fn test_break_and_continue_require_a_loop_or_switch() {
+    let parse_source = |source: &str| parse(lex(source).expect("Failed to lex"));
+
+    let error = parse_source("<?php echo 1; break;").expect_err("top-level break should not parse");
+    assert_eq!(error.to_string(), "Invalid statement: 'break' not in the 'loop' or 'switch' context");
+    assert!(parse_source("<?php while (true) { function f() { continue; } }").is_err());
+    assert!(parse_source("<?php class C { function m() { if (true) { break; } } }").is_err());
+
+    assert!(parse_source("<?php foreach ($xs as $x) { if ($x) { continue; } try { break; } finally {} }").is_ok());
+    assert!(parse_source("<?php switch ($x) { case 1: if ($y) { break; } echo 1; break; default: continue; }").is_ok());
+}
+
+#[test]
+fn test_break_and_continue_inside_closures_and_anonymous_classes() {
+    let parse_source = |source: &str| parse(lex(source).expect("Failed to lex"));
+
+    assert!(parse_source("<?php $f = function() { break; }; $f();").is_err());
+    assert!(parse_source("<?php while (true) { $f = function() { break; }; }").is_err());
+    assert!(parse_source("<?php foreach ($xs as $x) { $o = new class { function m() { continue; } }; }").is_err());
+
+    assert!(parse_source("<?php $f = function($xs) { foreach ($xs as $x) { break; } };").is_ok());
+}
+
+#[test]
+fn test_static_members_on_a_variable_class() {
+    let ast = parse(lex("<?php $x = $cls::CONST; $cls::run(1); $cls::$count = 2;").expect("Failed to lex")).expect("Failed to parse");
+    let dynamic = || ClassName::Dynamic(Box::new(Expr::Variable("cls".to_string())));
+
+    if let Stmt::Block(statements) = ast {
+        assert_eq!(statements[0], Stmt::Assignment { variable: "x".to_string(), value: Expr::ClassConstant { class: dynamic(), name: "CONST".to_string() } });
+        assert_eq!(statements[1], Stmt::Expression(Expr::StaticCall { class: dynamic(), method: "run".to_string(), args: vec![Expr::Int(1)] }));
+        assert_eq!(statements[2], Stmt::Expression(Expr::Assign {
+            target: Box::new(Expr::StaticProperty { class: dynamic(), name: "count".to_string() }),
+            value: Box::new(Expr::Int(2)),
+        }));
+    }
+}
@@ -0,0 +1,761 @@
+//! Array built-ins
+
+use crate::engine::{Engine, ErrorLevel};
+use php_parser::ast::Expr;
+use php_types::{PhpArray, PhpArrayKey, PhpValue};
+use std::cmp::Ordering;
+
+/// Register this module's functions
+pub(super) fn register(engine: &mut Engine) {
+    engine.register_function("count", count);
+    engine.register_function("sizeof", count);
+    engine.register_function("array_push", array_push);
+    engine.register_function("array_pop", array_pop);
+    engine.register_function("array_merge", array_merge);
+    engine.register_function("in_array", in_array);
+    engine.register_function("array_keys", array_keys);
+    engine.register_function("array_values", array_values);
+    engine.register_function("array_fill", array_fill);
+    engine.register_function("range", range);
+    engine.register_function("array_sum", array_sum);
+    engine.register_function("array_map", array_map);
+    engine.register_function("array_walk_recursive", array_walk_recursive);
+    engine.register_function("sort", |engine, args| sort_family(engine, args, "sort", SortBy::Value, false, false));
+    engine.register_function("rsort", |engine, args| sort_family(engine, args, "rsort", SortBy::Value, true, false));
+    engine.register_function("asort", |engine, args| sort_family(engine, args, "asort", SortBy::Value, false, true));
+    engine.register_function("arsort", |engine, args| sort_family(engine, args, "arsort", SortBy::Value, true, true));
+    engine.register_function("ksort", |engine, args| sort_family(engine, args, "ksort", SortBy::Key, false, true));
+    engine.register_function("krsort", |engine, args| sort_family(engine, args, "krsort", SortBy::Key, true, true));
+    engine.register_function("usort", usort);
+    engine.register_function("array_multisort", array_multisort);
+    engine.register_function("array_reduce", array_reduce);
+    engine.register_function("array_filter", array_filter);
+    engine.register_function("array_find", |engine, args| search_with_callback(engine, args, "array_find"));
+    engine.register_function("array_find_key", |engine, args| search_with_callback(engine, args, "array_find_key"));
+    engine.register_function("array_any", |engine, args| search_with_callback(engine, args, "array_any"));
+    engine.register_function("array_all", |engine, args| search_with_callback(engine, args, "array_all"));
+    engine.register_function("array_diff", |engine, args| compare_arrays(engine, args, "array_diff", Compare::Value, false));
+    engine.register_function("array_diff_key", |engine, args| compare_arrays(engine, args, "array_diff_key", Compare::Key, false));
+    engine.register_function("array_diff_assoc", |engine, args| compare_arrays(engine, args, "array_diff_assoc", Compare::Assoc, false));
+    engine.register_function("array_intersect", |engine, args| compare_arrays(engine, args, "array_intersect", Compare::Value, true));
+    engine.register_function("array_intersect_key", |engine, args| compare_arrays(engine, args, "array_intersect_key", Compare::Key, true));
+    engine.register_function("array_intersect_assoc", |engine, args| compare_arrays(engine, args, "array_intersect_assoc", Compare::Assoc, true));
+    engine.register_function("array_flip", array_flip);
+    engine.register_function("array_count_values", array_count_values);
+    engine.register_function("iterator_to_array", iterator_to_array);
+    engine.register_function("current", |engine, args| move_pointer(engine, args, "current"));
+    engine.register_function("pos", |engine, args| move_pointer(engine, args, "pos"));
+    engine.register_function("key", |engine, args| move_pointer(engine, args, "key"));
+    engine.register_function("next", |engine, args| move_pointer(engine, args, "next"));
+    engine.register_function("prev", |engine, args| move_pointer(engine, args, "prev"));
+    engine.register_function("reset", |engine, args| move_pointer(engine, args, "reset"));
+    engine.register_function("end", |engine, args| move_pointer(engine, args, "end"));
+}
+
+/// count(Countable|array $value, int $mode = COUNT_NORMAL): int
+///
+/// Keeps the pre-PHP 8 leniency: null counts as 0 and any other non-array as 1.
+fn count(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() || args.len() > 2 { return Err("count() expects 1 or 2 arguments".into()); }
+    let value = engine.evaluate_expr(&args[0])?;
+    let recursive = match args.get(1) {
+        Some(mode) => engine.evaluate_expr(mode)?.to_int() == 1,
+        None => false,
+    };
+    let total = match &value {
+        PhpValue::Array(arr) if recursive => count_recursive(arr),
+        PhpValue::Array(arr) => arr.len(),
+        PhpValue::Null => 0,
+        _ => 1,
+    };
+    Ok(PhpValue::Int(total as i64))
+}
+
+/// Element count of `arr` including the elements of every nested array
+fn count_recursive(arr: &PhpArray) -> usize {
+    arr.data.values().map(|value| match value {
+        PhpValue::Array(inner) => 1 + count_recursive(inner),
+        _ => 1,
+    }).sum()
+}
+
+/// array_merge(array ...$arrays): array
+fn array_merge(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() { return Ok(PhpValue::Array(PhpArray::new())); }
+    let mut result = PhpArray::new();
+    for expr in args {
+        // Evaluated arguments are temporaries, so their elements are moved rather than cloned
+        if let PhpValue::Array(arr) = engine.evaluate_expr(expr)? {
+            engine.check_array_size("array_merge", result.len() + arr.len())?;
+            result.merge(arr)?;
+        }
+    }
+    Ok(PhpValue::Array(result))
+}
+
+/// array_fill(int $start_index, int $count, mixed $value): array
+fn array_fill(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 3 { return Err(format!("array_fill() expects exactly 3 arguments, {} given", args.len())); }
+    let start = engine.evaluate_expr(&args[0])?.to_int();
+    let count = engine.evaluate_expr(&args[1])?.to_int();
+    let value = engine.evaluate_expr(&args[2])?;
+    if count < 0 {
+        return Err(engine.throw_error("ValueError", "array_fill(): Argument #2 ($count) must be greater than or equal to 0"));
+    }
+    engine.check_array_size("array_fill", count as usize)?;
+    let mut filled = PhpArray::new();
+    if count > 0 {
+        // Later keys follow the auto-index, which fails past PHP_INT_MAX
+        filled.insert_int(start, value.clone());
+        for _ in 1..count {
+            filled.push(value.clone())?;
+        }
+    }
+    Ok(PhpValue::Array(filled))
+}
+
+/// range(string|int|float $start, string|int|float $end, int|float $step = 1): array
+///
+/// Counts down when `$start > $end`. Single-letter strings give a character range;
+/// any float bound or step gives floats.
+fn range(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() < 2 || args.len() > 3 { return Err("range() expects 2 or 3 arguments".into()); }
+    let start = engine.evaluate_expr(&args[0])?;
+    let end = engine.evaluate_expr(&args[1])?;
+    // As of PHP 8.3 a float step without a fractional part counts as an int
+    let step = match args.get(2).map(|arg| engine.evaluate_expr(arg)).transpose()?.map(|step| php_types::to_number(&step)) {
+        Some(PhpValue::Float(f)) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => PhpValue::Int(f as i64),
+        Some(step) => step,
+        None => PhpValue::Int(1),
+    };
+    let step_size = step.to_float();
+    if step_size == 0.0 {
+        return Err(engine.throw_error("ValueError", "range(): Argument #3 ($step) cannot be 0"));
+    }
+    let letter = |value: &PhpValue| match value {
+        PhpValue::String(s) if s.len() == 1 && !s.as_bytes()[0].is_ascii_digit() => Some(s.as_bytes()[0]),
+        _ => None,
+    };
+    let (from, to) = (php_types::to_number(&start), php_types::to_number(&end));
+    if let (PhpValue::Int(first), PhpValue::Int(last), PhpValue::Int(step), false) = (&from, &to, &step, letter(&start).is_some() && letter(&end).is_some()) {
+        return int_range(engine, *first, *last, *step);
+    }
+    let ascending = from.to_float() <= to.to_float();
+    if step_size < 0.0 && ascending && from != to {
+        return Err(engine.throw_error("ValueError", "range(): Argument #3 ($step) must be greater than 0 for increasing ranges"));
+    }
+    let step_size = step_size.abs();
+    let span = (to.to_float() - from.to_float()).abs();
+    if let (Some(first), Some(last)) = (letter(&start), letter(&end)) {
+        let span = first.abs_diff(last) as f64;
+        if step_size > span && span > 0.0 {
+            return Err(engine.throw_error("ValueError", "range(): Argument #3 ($step) must not exceed the specified range"));
+        }
+        let step = step_size as usize;
+        let codes: Vec<u8> = if first <= last { (first..=last).step_by(step.max(1)).collect() } else { (last..=first).rev().step_by(step.max(1)).collect() };
+        let mut letters = PhpArray::new();
+        for code in codes {
+            letters.push(PhpValue::String((code as char).to_string()))?;
+        }
+        return Ok(PhpValue::Array(letters));
+    }
+    if step_size > span && span > 0.0 {
+        return Err(engine.throw_error("ValueError", "range(): Argument #3 ($step) must not exceed the specified range"));
+    }
+    let count = (span / step_size).floor() as usize + 1;
+    engine.check_array_size("range", count)?;
+    let direction = if ascending { 1.0 } else { -1.0 };
+    let mut values = PhpArray::new();
+    // Multiply rather than accumulate so rounding errors do not build up
+    let first = from.to_float();
+    for index in 0..count {
+        values.push(PhpValue::Float(first + index as f64 * step_size * direction))?;
+    }
+    Ok(PhpValue::Array(values))
+}
+
+/// range() over ints, computed exactly: i128 holds every span and step of two i64 values
+fn int_range(engine: &mut Engine, first: i64, last: i64, step: i64) -> Result<PhpValue, String> {
+    let (first, last, step) = (i128::from(first), i128::from(last), i128::from(step));
+    if step < 0 && first < last {
+        return Err(engine.throw_error("ValueError", "range(): Argument #3 ($step) must be greater than 0 for increasing ranges"));
+    }
+    let (span, step) = ((last - first).abs(), step.abs());
+    if step > span && span > 0 {
+        return Err(engine.throw_error("ValueError", "range(): Argument #3 ($step) must not exceed the specified range"));
+    }
+    let count = span / step + 1;
+    engine.check_array_size("range", usize::try_from(count).unwrap_or(usize::MAX))?;
+    let step = if first <= last { step } else { -step };
+    let mut values = PhpArray::new();
+    for index in 0..count {
+        // Every element lies between first and last, so it fits in an i64
+        values.push(PhpValue::Int((first + index * step) as i64))?;
+    }
+    Ok(PhpValue::Array(values))
+}
+
+/// Evaluate the single array argument of `name`
+fn array_arg(engine: &mut Engine, args: &[Expr], name: &str) -> Result<PhpArray, String> {
+    if args.len() != 1 { return Err(format!("{}() expects exactly 1 argument, {} given", name, args.len())); }
+    match engine.evaluate_expr(&args[0])? {
+        PhpValue::Array(arr) => Ok(arr),
+        other => Err(format!("{}(): Argument #1 ($array) must be of type array, {} given", name, other.type_name())),
+    }
+}
+
+/// array_flip(array $array): array
+///
+/// Values that are neither int nor string cannot become keys and are skipped (PHP warns).
+fn array_flip(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    let arr = array_arg(engine, args, "array_flip")?;
+    let mut flipped = PhpArray::new();
+    for (key, value) in arr.data {
+        if let Some(new_key) = PhpArrayKey::from_scalar(&value) {
+            flipped.insert(new_key, key.to_value());
+        }
+    }
+    Ok(PhpValue::Array(flipped))
+}
+
+/// array_count_values(array $array): array
+///
+/// Only int and string values are counted; others are skipped with a warning.
+fn array_count_values(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    let arr = array_arg(engine, args, "array_count_values")?;
+    let mut counts = PhpArray::new();
+    for value in arr.data.values() {
+        let Some(key) = PhpArrayKey::from_scalar(value) else {
+            engine.raise_error(ErrorLevel::Warning, "array_count_values(): Can only count string and integer values, entry skipped");
+            continue;
+        };
+        let count = match counts.data.get(&key) { Some(PhpValue::Int(n)) => n + 1, _ => 1 };
+        counts.insert(key, PhpValue::Int(count));
+    }
+    Ok(PhpValue::Array(counts))
+}
+
+/// array_sum(array $array): int|float
+fn array_sum(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 1 { return Err("array_sum() expects exactly 1 argument".into()); }
+    let arr_val = engine.evaluate_expr(&args[0])?;
+    match arr_val {
+        PhpValue::Array(arr) => {
+            let mut sum_f: f64 = 0.0;
+            for (_, v) in arr.data.iter() {
+                sum_f += match v {
+                    PhpValue::Int(i) => *i as f64,
+                    PhpValue::Float(f) => *f,
+                    PhpValue::String(s) => s.parse::<f64>().unwrap_or(0.0),
+                    PhpValue::Bool(true) => 1.0,
+                    _ => 0.0,
+                };
+            }
+            // If sum is integer representable, return Int else Float
+            if (sum_f.fract() - 0.0).abs() < f64::EPSILON { Ok(PhpValue::Int(sum_f as i64)) } else { Ok(PhpValue::Float(sum_f)) }
+        }
+        _ => Ok(PhpValue::Int(0))
+    }
+}
+
+/// array_map(callable $callback, array $array, array ...$arrays): array
+fn array_map(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() < 2 { return Err("array_map() expects at least 2 arguments".into()); }
+    let callback = engine.evaluate_expr(&args[0])?;
+    let mut arrays = Vec::with_capacity(args.len() - 1);
+    for (i, arg) in args[1..].iter().enumerate() {
+        match engine.evaluate_expr(arg)? {
+            PhpValue::Array(arr) => arrays.push(arr),
+            other => return Err(format!("array_map(): Argument #{} must be of type array, {} given", i + 2, other.type_name())),
+        }
+    }
+    let mut result = PhpArray::new();
+    if arrays.len() == 1 && callback.is_null() {
+        // A null callback over one array is the identity, keys included
+        return Ok(PhpValue::Array(arrays.remove(0)));
+    }
+    if arrays.len() == 1 {
+        // A single array keeps its keys
+        for (key, value) in arrays.remove(0).data {
+            let mapped = engine.call_callable(&callback, vec![value])?;
+            result.insert(key, mapped);
+        }
+    } else {
+        // Several arrays are walked in parallel, padding shorter ones with null, and re-indexed
+        let columns: Vec<Vec<PhpValue>> = arrays.into_iter().map(|arr| arr.data.into_values().collect()).collect();
+        let rows = columns.iter().map(Vec::len).max().unwrap_or(0);
+        for row in 0..rows {
+            let call_args: Vec<PhpValue> = columns.iter().map(|column| column.get(row).cloned().unwrap_or(PhpValue::Null)).collect();
+            if callback.is_null() {
+                // A null callback zips the arrays into rows
+                result.push(PhpValue::Array(call_args.into_iter().collect()))?;
+            } else {
+                result.push(engine.call_callable(&callback, call_args)?)?;
+            }
+        }
+    }
+    Ok(PhpValue::Array(result))
+}
+
+/// Evaluate the by-reference array argument of `function`, which must be a variable
+fn array_by_reference(engine: &mut Engine, args: &[Expr], function: &str) -> Result<PhpArray, String> {
+    if !matches!(args[0], Expr::Variable(_)) {
+        return Err(format!("{}(): Argument #1 ($array) could not be passed by reference", function));
+    }
+    match engine.evaluate_expr(&args[0])? {
+        PhpValue::Array(arr) => Ok(arr),
+        other => Err(format!("{}(): Argument #1 ($array) must be of type array, {} given", function, other.type_name())),
+    }
+}
+
+/// array_push(array &$array, mixed ...$values): int
+fn array_push(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() { return Err("array_push() expects at least 1 argument, 0 given".into()); }
+    let mut arr = array_by_reference(engine, args, "array_push")?;
+    engine.check_array_size("array_push", arr.len() + args.len() - 1)?;
+    for arg in &args[1..] {
+        let value = engine.evaluate_expr(arg)?;
+        arr.push(value)?;
+    }
+    let count = arr.len();
+    engine.write_back(&args[0], PhpValue::Array(arr))?;
+    Ok(PhpValue::Int(count as i64))
+}
+
+/// array_pop(array &$array): mixed
+fn array_pop(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 1 { return Err("array_pop() expects exactly 1 argument".into()); }
+    let mut arr = array_by_reference(engine, args, "array_pop")?;
+    let value = arr.pop().unwrap_or(PhpValue::Null);
+    engine.write_back(&args[0], PhpValue::Array(arr))?;
+    Ok(value)
+}
+
+/// in_array(mixed $needle, array $haystack, bool $strict = false): bool
+fn in_array(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() < 2 || args.len() > 3 { return Err("in_array() expects 2 or 3 arguments".into()); }
+    let needle = engine.evaluate_expr(&args[0])?;
+    let haystack = match engine.evaluate_expr(&args[1])? {
+        PhpValue::Array(arr) => arr,
+        other => return Err(format!("in_array(): Argument #2 ($haystack) must be of type array, {} given", other.type_name())),
+    };
+    let strict = match args.get(2) {
+        Some(arg) => engine.evaluate_expr(arg)?.is_truthy(),
+        None => false,
+    };
+    for value in haystack.data.values() {
+        let found = if strict { php_types::php_identical(&needle, value) } else { engine.loose_equals(&needle, value)? };
+        if found {
+            return Ok(PhpValue::Bool(true));
+        }
+    }
+    Ok(PhpValue::Bool(false))
+}
+
+/// array_keys(array $array, mixed $filter_value = UNSET, bool $strict = false): array
+fn array_keys(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() || args.len() > 3 { return Err("array_keys() expects 1 to 3 arguments".into()); }
+    let arr = match engine.evaluate_expr(&args[0])? {
+        PhpValue::Array(arr) => arr,
+        other => return Err(format!("array_keys(): Argument #1 ($array) must be of type array, {} given", other.type_name())),
+    };
+    let filter = args.get(1).map(|arg| engine.evaluate_expr(arg)).transpose()?;
+    let strict = match args.get(2) {
+        Some(arg) => engine.evaluate_expr(arg)?.is_truthy(),
+        None => false,
+    };
+    let mut keys = PhpArray::new();
+    for (key, value) in &arr.data {
+        let matches = match &filter {
+            Some(search) if strict => php_types::php_identical(value, search),
+            Some(search) => engine.loose_equals(value, search)?,
+            None => true,
+        };
+        if matches {
+            keys.push(key.to_value())?;
+        }
+    }
+    Ok(PhpValue::Array(keys))
+}
+
+/// array_values(array $array): array
+fn array_values(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 1 { return Err("array_values() expects exactly 1 argument".into()); }
+    let arr = match engine.evaluate_expr(&args[0])? {
+        PhpValue::Array(arr) => arr,
+        other => return Err(format!("array_values(): Argument #1 ($array) must be of type array, {} given", other.type_name())),
+    };
+    let mut values = PhpArray::new();
+    for value in arr.data.into_values() {
+        values.push(value)?;
+    }
+    Ok(PhpValue::Array(values))
+}
+
+/// array_walk_recursive(array &$array, callable $callback, mixed $arg = null): bool
+fn array_walk_recursive(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() < 2 || args.len() > 3 { return Err("array_walk_recursive() expects 2 or 3 arguments".into()); }
+    let mut arr = match engine.evaluate_expr(&args[0])? {
+        PhpValue::Array(arr) => arr,
+        other => return Err(format!("array_walk_recursive(): Argument #1 ($array) must be of type array, {} given", other.type_name())),
+    };
+    let callback = engine.evaluate_expr(&args[1])?;
+    let extra = args.get(2).map(|arg| engine.evaluate_expr(arg)).transpose()?;
+    walk_recursive(engine, &mut arr, &callback, extra.as_ref())?;
+    // The array is taken by reference so callbacks can modify leaves through `&$value`
+    engine.write_back(&args[0], PhpValue::Array(arr))?;
+    Ok(PhpValue::Bool(true))
+}
+
+/// Which part of each entry the sort family orders by
+#[derive(Clone, Copy)]
+enum SortBy {
+    Value,
+    Key,
+}
+
+/// A value prepared for sorting under a sort flag, so that comparisons do not
+/// convert or re-parse it every time
+enum SortKey {
+    /// SORT_NUMERIC: the value as a float
+    Float(f64),
+    /// SORT_STRING: the value as a string
+    Text(String),
+    /// SORT_REGULAR when every value is a number or numeric string: the number it holds
+    Number(PhpValue),
+    /// Any other SORT_REGULAR value, compared with PHP's loose comparison
+    Regular(PhpValue),
+}
+
+impl SortKey {
+    fn compare(&self, other: &SortKey) -> Ordering {
+        match (self, other) {
+            (SortKey::Float(a), SortKey::Float(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (SortKey::Text(a), SortKey::Text(b)) => a.cmp(b),
+            (SortKey::Number(a), SortKey::Number(b)) | (SortKey::Regular(a), SortKey::Regular(b)) => php_types::php_compare(a, b),
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+/// Prepare the values to sort under a flag: SORT_NUMERIC, SORT_STRING or SORT_REGULAR.
+/// Regular sorts only use numeric keys when all values are numeric, as a number
+/// orders differently against a non-numeric string than against another number.
+fn sort_keys(values: Vec<PhpValue>, flags: i64) -> Vec<SortKey> {
+    match flags {
+        1 => values.iter().map(|v| SortKey::Float(v.to_float())).collect(),
+        2 => values.iter().map(|v| SortKey::Text(v.to_string())).collect(),
+        _ => match values.iter().map(php_types::php_numeric_value).collect::<Option<Vec<_>>>() {
+            Some(numbers) => numbers.into_iter().map(SortKey::Number).collect(),
+            None => values.into_iter().map(SortKey::Regular).collect(),
+        },
+    }
+}
+
+/// sort()/rsort()/asort()/arsort()/ksort()/krsort(array &$array, int $flags = SORT_REGULAR): true
+///
+/// The sort is stable. sort and rsort re-index the result; the others keep each value's key.
+fn sort_family(engine: &mut Engine, args: &[Expr], name: &str, by: SortBy, descending: bool, keep_keys: bool) -> Result<PhpValue, String> {
+    if args.is_empty() || args.len() > 2 { return Err(format!("{}() expects 1 or 2 arguments", name)); }
+    let arr = array_by_reference(engine, args, name)?;
+    let flags = match args.get(1) { Some(arg) => engine.evaluate_expr(arg)?.to_int(), None => 0 };
+    let sort_values = arr.data.iter().map(|(key, value)| match by {
+        SortBy::Value => value.clone(),
+        SortBy::Key => key.to_value(),
+    }).collect();
+    let mut entries: Vec<(SortKey, (PhpArrayKey, PhpValue))> = sort_keys(sort_values, flags).into_iter().zip(arr.data).collect();
+    entries.sort_by(|(a, _), (b, _)| {
+        let ordering = a.compare(b);
+        if descending { ordering.reverse() } else { ordering }
+    });
+    let mut sorted = PhpArray::new();
+    for (_, (key, value)) in entries {
+        if keep_keys { sorted.insert(key, value) } else { sorted.push(value)? }
+    }
+    engine.write_back(&args[0], PhpValue::Array(sorted))?;
+    Ok(PhpValue::Bool(true))
+}
+
+/// usort(array &$array, callable $callback): true
+///
+/// The comparator returns an int below, equal to or above zero; the result is re-indexed.
+fn usort(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 2 { return Err("usort() expects exactly 2 arguments".into()); }
+    let arr = array_by_reference(engine, args, "usort")?;
+    let callback = engine.evaluate_expr(&args[1])?;
+    let values: Vec<PhpValue> = arr.data.into_values().collect();
+    let sorted = merge_sort(values, &mut |a, b| {
+        Ok(engine.call_callable(&callback, vec![a.clone(), b.clone()])?.to_int().cmp(&0))
+    })?;
+    engine.write_back(&args[0], PhpValue::Array(sorted.into_iter().collect()))?;
+    Ok(PhpValue::Bool(true))
+}
+
+/// One array taking part in array_multisort(), with the order and flags that follow it
+struct SortColumn {
+    arg: usize,
+    entries: Vec<(PhpArrayKey, PhpValue)>,
+    descending: Option<bool>,
+    flags: Option<i64>,
+}
+
+/// array_multisort(array &$array1, mixed $array1_sort_order = SORT_ASC, mixed $array1_sort_flags = SORT_REGULAR, mixed ...$rest): bool
+///
+/// Sorts the rows formed by taking the nth element of every array, comparing by the
+/// first array and breaking ties with the later ones. String keys are kept, integer keys re-indexed.
+fn array_multisort(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() { return Err("array_multisort() expects at least 1 argument, 0 given".into()); }
+    let mut columns: Vec<SortColumn> = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        match engine.evaluate_expr(arg)? {
+            PhpValue::Array(arr) => columns.push(SortColumn { arg: i, entries: arr.data.into_iter().collect(), descending: None, flags: None }),
+            PhpValue::Int(flag) if !columns.is_empty() => {
+                let column = columns.last_mut().expect("checked above");
+                // SORT_DESC (3) and SORT_ASC (4) set the order, anything else the comparison flags
+                let slot_taken = match flag {
+                    3 | 4 => column.descending.replace(flag == 3).is_some(),
+                    _ => column.flags.replace(flag).is_some(),
+                };
+                if slot_taken {
+                    let message = format!("array_multisort(): Argument #{} must be an array or a sort flag that has not already been specified", i + 1);
+                    return Err(engine.throw_error("ValueError", &message));
+                }
+            }
+            _ => {
+                let message = format!("array_multisort(): Argument #{} must be an array or a sort flag", i + 1);
+                return Err(engine.throw_error("TypeError", &message));
+            }
+        }
+    }
+    let rows = columns[0].entries.len();
+    if columns.iter().any(|column| column.entries.len() != rows) {
+        return Err(engine.throw_error("ValueError", "Array sizes are inconsistent"));
+    }
+    let keys: Vec<Vec<SortKey>> = columns.iter()
+        .map(|column| sort_keys(column.entries.iter().map(|(_, v)| v.clone()).collect(), column.flags.unwrap_or(0)))
+        .collect();
+    let mut order: Vec<usize> = (0..rows).collect();
+    order.sort_by(|&a, &b| {
+        columns.iter().zip(&keys)
+            .map(|(column, keys)| {
+                let ordering = keys[a].compare(&keys[b]);
+                if column.descending == Some(true) { ordering.reverse() } else { ordering }
+            })
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    });
+    for column in columns {
+        let mut sorted = PhpArray::new();
+        for &row in &order {
+            let (key, value) = column.entries[row].clone();
+            match key {
+                PhpArrayKey::String(_) => sorted.insert(key, value),
+                PhpArrayKey::Int(_) => sorted.push(value)?,
+            }
+        }
+        // Array literals can be sorted too; only variables get the result written back
+        if matches!(args[column.arg], Expr::Variable(_)) {
+            engine.write_back(&args[column.arg], PhpValue::Array(sorted))?;
+        }
+    }
+    Ok(PhpValue::Bool(true))
+}
+
+/// Stable merge sort with a fallible comparison, so a throwing comparator aborts
+/// the sort and an inconsistent one cannot upset it
+fn merge_sort<T>(mut items: Vec<T>, compare: &mut impl FnMut(&T, &T) -> Result<Ordering, String>) -> Result<Vec<T>, String> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+    let right = items.split_off(items.len() / 2);
+    let left = merge_sort(items, compare)?;
+    let right = merge_sort(right, compare)?;
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        // Take from the left on ties to keep equal elements in their original order
+        if compare(a, b)? == Ordering::Greater {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+/// iterator_to_array(Traversable|array $iterator): array
+fn iterator_to_array(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() { return Err("iterator_to_array() expects at least 1 argument".into()); }
+    let val = engine.evaluate_expr(&args[0])?;
+    Ok(PhpValue::Array(engine.iterate(val)?))
+}
+
+/// array_reduce(array $array, callable $callback, mixed $initial = null): mixed
+fn array_reduce(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() < 2 || args.len() > 3 { return Err("array_reduce() expects 2 or 3 arguments".into()); }
+    let arr = match engine.evaluate_expr(&args[0])? {
+        PhpValue::Array(arr) => arr,
+        other => return Err(format!("array_reduce(): Argument #1 ($array) must be of type array, {} given", other.type_name())),
+    };
+    let callback = engine.evaluate_expr(&args[1])?;
+    let mut carry = match args.get(2) { Some(arg) => engine.evaluate_expr(arg)?, None => PhpValue::Null };
+    for value in arr.data.into_values() {
+        carry = engine.call_callable(&callback, vec![carry, value])?;
+    }
+    Ok(carry)
+}
+
+/// array_filter(array $array, ?callable $callback = null, int $mode = 0): array
+///
+/// Keys are preserved. The callback receives the value, the key
+/// (ARRAY_FILTER_USE_KEY) or both (ARRAY_FILTER_USE_BOTH).
+fn array_filter(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() || args.len() > 3 { return Err("array_filter() expects 1 to 3 arguments".into()); }
+    let arr = match engine.evaluate_expr(&args[0])? {
+        PhpValue::Array(arr) => arr,
+        other => return Err(format!("array_filter(): Argument #1 ($array) must be of type array, {} given", other.type_name())),
+    };
+    let callback = match args.get(1) { Some(arg) => engine.evaluate_expr(arg)?, None => PhpValue::Null };
+    let mode = match args.get(2) { Some(arg) => engine.evaluate_expr(arg)?.to_int(), None => 0 };
+    let mut result = PhpArray::new();
+    for (key, value) in arr.data {
+        let keep = match (&callback, mode) {
+            (PhpValue::Null, _) => value.is_truthy(),
+            (callback, 1) => engine.call_callable(callback, vec![value.clone(), key.to_value()])?.is_truthy(),
+            (callback, 2) => engine.call_callable(callback, vec![key.to_value()])?.is_truthy(),
+            (callback, _) => engine.call_callable(callback, vec![value.clone()])?.is_truthy(),
+        };
+        if keep {
+            result.insert(key, value);
+        }
+    }
+    Ok(PhpValue::Array(result))
+}
+
+/// array_find()/array_find_key()/array_any()/array_all(array $array, callable $callback): mixed
+///
+/// The callback receives `($value, $key)`; the search stops at the first element that decides the result.
+fn search_with_callback(engine: &mut Engine, args: &[Expr], name: &str) -> Result<PhpValue, String> {
+    if args.len() != 2 { return Err(format!("{}() expects exactly 2 arguments", name)); }
+    let arr = match engine.evaluate_expr(&args[0])? {
+        PhpValue::Array(arr) => arr,
+        other => return Err(format!("{}(): Argument #1 ($array) must be of type array, {} given", name, other.type_name())),
+    };
+    let callback = engine.evaluate_expr(&args[1])?;
+    // array_all looks for the first element failing the predicate, the others for the first passing one
+    let wanted = name != "array_all";
+    for (key, value) in arr.data {
+        let key = match key {
+            PhpArrayKey::Int(i) => PhpValue::Int(i),
+            PhpArrayKey::String(s) => PhpValue::String(s),
+        };
+        if engine.call_callable(&callback, vec![value.clone(), key.clone()])?.is_truthy() == wanted {
+            return Ok(match name {
+                "array_find" => value,
+                "array_find_key" => key,
+                _ => PhpValue::Bool(wanted),
+            });
+        }
+    }
+    Ok(match name {
+        "array_find" | "array_find_key" => PhpValue::Null,
+        _ => PhpValue::Bool(!wanted),
+    })
+}
+
+/// What array_diff/array_intersect and their variants compare
+#[derive(Clone, Copy)]
+enum Compare {
+    /// Values only (`array_diff`, `array_intersect`)
+    Value,
+    /// Keys only (`array_diff_key`, `array_intersect_key`)
+    Key,
+    /// Keys and values (`array_diff_assoc`, `array_intersect_assoc`)
+    Assoc,
+}
+
+/// array_diff*/array_intersect*(array $array, array ...$arrays): array
+///
+/// Keeps the entries of the first array that are missing from every other array (diff),
+/// or present in all of them (intersect). Values compare by their string form, as in PHP.
+fn compare_arrays(engine: &mut Engine, args: &[Expr], name: &str, compare: Compare, intersect: bool) -> Result<PhpValue, String> {
+    if args.is_empty() { return Err(format!("{}() expects at least 1 argument", name)); }
+    let mut arrays = Vec::with_capacity(args.len());
+    for (i, arg) in args.iter().enumerate() {
+        match engine.evaluate_expr(arg)? {
+            PhpValue::Array(arr) => arrays.push(arr),
+            other => return Err(format!("{}(): Argument #{} must be of type array, {} given", name, i + 1, other.type_name())),
+        }
+    }
+    let first = arrays.remove(0);
+    let contains = |other: &PhpArray, key: &PhpArrayKey, value: &PhpValue| match compare {
+        Compare::Key => other.data.contains_key(key),
+        Compare::Value => {
+            let value = value.to_string();
+            other.data.values().any(|item| item.to_string() == value)
+        }
+        Compare::Assoc => other.data.get(key).is_some_and(|item| item.to_string() == value.to_string()),
+    };
+    let mut result = PhpArray::new();
+    for (key, value) in first.data {
+        let keep = if intersect {
+            arrays.iter().all(|other| contains(other, &key, &value))
+        } else {
+            !arrays.iter().any(|other| contains(other, &key, &value))
+        };
+        if keep {
+            result.insert(key, value);
+        }
+    }
+    Ok(PhpValue::Array(result))
+}
+
+/// current()/pos()/key()/next()/prev()/reset()/end(array &$array): mixed
+fn move_pointer(engine: &mut Engine, args: &[Expr], name: &str) -> Result<PhpValue, String> {
+    if args.len() != 1 { return Err(format!("{}() expects exactly 1 argument", name)); }
+    let mut arr = match engine.evaluate_expr(&args[0])? {
+        PhpValue::Array(arr) => arr,
+        other => return Err(format!("{}(): Argument #1 ($array) must be of type array, {} given", name, other.type_name())),
+    };
+    let entry = match name {
+        "next" => arr.advance(),
+        "prev" => arr.retreat(),
+        "reset" => arr.rewind(),
+        "end" => arr.seek_end(),
+        _ => arr.current(),
+    }.map(|(key, value)| (key.clone(), value.clone()));
+    // The array is taken by reference: store the moved pointer back into the variable
+    if !matches!(name, "current" | "pos" | "key") {
+        engine.write_back(&args[0], PhpValue::Array(arr))?;
+    }
+    Ok(match (name, entry) {
+        ("key", Some((PhpArrayKey::Int(i), _))) => PhpValue::Int(i),
+        ("key", Some((PhpArrayKey::String(s), _))) => PhpValue::String(s),
+        ("key", None) => PhpValue::Null,
+        (_, Some((_, value))) => value,
+        (_, None) => PhpValue::Bool(false),
+    })
+}
+
+/// Apply a callback to every leaf of a nested array as `callback(&$value, $key[, $extra])`
+fn walk_recursive(engine: &mut Engine, arr: &mut PhpArray, callback: &PhpValue, extra: Option<&PhpValue>) -> Result<(), String> {
+    for (key, value) in arr.data.iter_mut() {
+        if let PhpValue::Array(inner) = value {
+            walk_recursive(engine, inner, callback, extra)?;
+            continue;
+        }
+        let key = match key {
+            PhpArrayKey::Int(i) => PhpValue::Int(*i),
+            PhpArrayKey::String(s) => PhpValue::String(s.clone()),
+        };
+        let mut call_args = vec![std::mem::replace(value, PhpValue::Null), key];
+        call_args.extend(extra.cloned());
+        let result = engine.call_callable_with_refs(callback, &mut call_args);
+        *value = call_args.swap_remove(0);
+        result?;
+    }
+    Ok(())
+}
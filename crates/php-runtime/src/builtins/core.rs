@@ -0,0 +1,265 @@
+//! Core built-ins: constants, variable handling and callables
+
+use crate::engine::Engine;
+use php_parser::ast::Expr;
+use php_types::{PhpArray, PhpArrayKey, PhpValue};
+
+/// Register this module's functions
+pub(super) fn register(engine: &mut Engine) {
+    engine.register_function("define", define);
+    engine.register_function("constant", constant);
+    engine.register_function("defined", defined);
+    engine.register_function("isset", isset);
+    engine.register_function("getenv", getenv);
+    engine.register_function("set_error_handler", set_error_handler);
+    engine.register_function("filter_var", filter_var);
+    engine.register_function("eval", eval);
+    engine.register_function("call_user_func", call_user_func);
+    engine.register_function("call_user_func_array", call_user_func_array);
+    engine.register_function("get_defined_constants", get_defined_constants);
+    engine.register_function("get_defined_functions", get_defined_functions);
+    engine.register_function("get_object_vars", get_object_vars);
+    engine.register_function("gettype", gettype);
+    engine.register_function("settype", settype);
+    engine.register_function("is_null", |engine, args| is_type(engine, args, "is_null", |v| matches!(v, PhpValue::Null)));
+    engine.register_function("is_bool", |engine, args| is_type(engine, args, "is_bool", |v| matches!(v, PhpValue::Bool(_))));
+    for name in ["is_int", "is_integer", "is_long"] {
+        engine.register_function(name, move |engine, args| is_type(engine, args, name, |v| matches!(v, PhpValue::Int(_))));
+    }
+    for name in ["is_float", "is_double"] {
+        engine.register_function(name, move |engine, args| is_type(engine, args, name, |v| matches!(v, PhpValue::Float(_))));
+    }
+    engine.register_function("is_string", |engine, args| is_type(engine, args, "is_string", |v| matches!(v, PhpValue::String(_))));
+    engine.register_function("is_array", |engine, args| is_type(engine, args, "is_array", |v| matches!(v, PhpValue::Array(_))));
+    engine.register_function("is_object", |engine, args| is_type(engine, args, "is_object", |v| matches!(v, PhpValue::Object(_))));
+    engine.register_function("is_resource", |engine, args| is_type(engine, args, "is_resource", |v| matches!(v, PhpValue::Resource(_))));
+    engine.register_function("is_scalar", |engine, args| {
+        is_type(engine, args, "is_scalar", |v| matches!(v, PhpValue::Bool(_) | PhpValue::Int(_) | PhpValue::Float(_) | PhpValue::String(_)))
+    });
+    engine.register_function("is_callable", is_callable);
+}
+
+/// define(string $name, mixed $value): bool
+fn define(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 2 {
+        return Err("define() expects exactly 2 arguments".to_string());
+    }
+
+    // First argument should be the constant name (string)
+    let const_name = match engine.evaluate_expr(&args[0])? {
+        PhpValue::String(s) => s,
+        _ => return Err("define() first argument must be a string".to_string()),
+    };
+
+    // Second argument is the constant value
+    let const_value = engine.evaluate_expr(&args[1])?;
+
+    // Define the constant
+    engine.context.set_constant(const_name, const_value);
+
+    // define() returns true on success
+    Ok(PhpValue::Bool(true))
+}
+
+/// constant(string $name): mixed
+fn constant(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 1 { return Err("constant() expects exactly 1 argument".into()); }
+    let name = engine.evaluate_expr(&args[0])?.to_string();
+    if let Some((class, constant)) = name.split_once("::") {
+        let class_name = engine.resolve_class_reference(class)?;
+        return engine.class_constant(&class_name, constant);
+    }
+    engine.context.get_constant(&name).cloned().ok_or_else(|| format!("Undefined constant \"{}\"", name))
+}
+
+/// defined(string $name): bool
+fn defined(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 1 { return Err("defined() expects exactly 1 argument".into()); }
+    let name = engine.evaluate_expr(&args[0])?.to_string();
+    let exists = match name.split_once("::") {
+        Some((class, constant)) => engine.resolve_class_reference(class)
+            .map(|class_name| engine.class_constant(&class_name, constant).is_ok())
+            .unwrap_or(false),
+        None => engine.context.get_constant(&name).is_some(),
+    };
+    Ok(PhpValue::Bool(exists))
+}
+
+/// isset(mixed $var, mixed ...$vars): bool
+fn isset(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    // isset can take one or more variables/expressions; any missing or null one makes it false
+    if args.is_empty() { return Ok(PhpValue::Bool(false)); }
+    for expr in args {
+        if !engine.isset_check(expr)? {
+            return Ok(PhpValue::Bool(false));
+        }
+    }
+    Ok(PhpValue::Bool(true))
+}
+
+/// getenv(string $name): string|false
+fn getenv(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 1 { return Err("getenv() expects exactly 1 argument".into()); }
+    let name_val = engine.evaluate_expr(&args[0])?;
+    let key = name_val.to_string();
+    match std::env::var(&key) {
+        Ok(v) => Ok(PhpValue::String(v)),
+        Err(_) => Ok(PhpValue::Bool(false)),
+    }
+}
+
+/// set_error_handler(?callable $callback): ?callable
+fn set_error_handler(_engine: &mut Engine, _args: &[Expr]) -> Result<PhpValue, String> {
+    // Accept any callable, ignore for now, return null (previous handler)
+    Ok(PhpValue::Null)
+}
+
+/// filter_var(mixed $value, int $filter): mixed
+fn filter_var(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    // filter_var(value, filter) minimal: only FILTER_VALIDATE_INT
+    if args.len() < 2 { return Err("filter_var() expects at least 2 arguments".into()); }
+    let val = engine.evaluate_expr(&args[0])?;
+    let filter = engine.evaluate_expr(&args[1])?;
+    let filter_id = match filter { PhpValue::Int(i) => i, _ => 0 };
+    // We defined FILTER_VALIDATE_INT constant as 257
+    if filter_id == 257 {
+        let s = val.to_string();
+        if let Ok(i) = s.parse::<i64>() { Ok(PhpValue::Int(i)) } else { Ok(PhpValue::Bool(false)) }
+    } else {
+        Ok(val) // fallback returns original
+    }
+}
+
+/// eval(string $code): mixed
+///
+/// The code runs in the calling scope, so it can read and define its variables.
+fn eval(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 1 { return Err("eval() expects exactly 1 argument".into()); }
+    let source = engine.evaluate_expr(&args[0])?.to_string();
+    engine.eval_source(&source)
+}
+
+/// call_user_func(callable $callback, mixed ...$args): mixed
+fn call_user_func(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() { return Err("call_user_func() expects at least 1 argument".into()); }
+    let callable = engine.evaluate_expr(&args[0])?;
+    let call_args = engine.evaluate_args(&args[1..])?;
+    engine.call_callable(&callable, call_args)
+}
+
+/// call_user_func_array(callable $callback, array $args): mixed
+fn call_user_func_array(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 2 { return Err("call_user_func_array() expects exactly 2 arguments".into()); }
+    let callable = engine.evaluate_expr(&args[0])?;
+    let arr = match engine.evaluate_expr(&args[1])? {
+        PhpValue::Array(arr) => arr,
+        other => return Err(format!("call_user_func_array(): Argument #2 ($args) must be of type array, {} given", other.type_name())),
+    };
+    // Elements are passed in array order; string keys become named arguments
+    let mut positional = Vec::with_capacity(arr.len());
+    let mut named = Vec::new();
+    for (key, value) in arr.data {
+        match key {
+            PhpArrayKey::Int(_) if named.is_empty() => positional.push(value),
+            PhpArrayKey::Int(_) => return Err(engine.throw_error("Error", "Cannot use positional argument after named argument during unpacking")),
+            PhpArrayKey::String(name) => named.push((name, value)),
+        }
+    }
+    engine.call_callable_named(&callable, positional, named)
+}
+
+/// get_defined_constants(bool $categorize = false): array
+///
+/// Constants are not tracked by extension, so `$categorize` is accepted but ignored.
+fn get_defined_constants(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() > 1 { return Err("get_defined_constants() expects at most 1 argument".into()); }
+    engine.evaluate_args(args)?;
+    let mut constants: Vec<(&str, &PhpValue)> = engine.context.constants().collect();
+    constants.sort_by_key(|(name, _)| *name);
+    let mut arr = PhpArray::new();
+    for (name, value) in constants {
+        arr.insert_string(name.to_string(), value.clone());
+    }
+    Ok(PhpValue::Array(arr))
+}
+
+/// get_defined_functions(bool $exclude_disabled = true): array
+fn get_defined_functions(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() > 1 { return Err("get_defined_functions() expects at most 1 argument".into()); }
+    engine.evaluate_args(args)?;
+    let name_list = |mut names: Vec<&str>| {
+        names.sort_unstable();
+        PhpValue::Array(names.into_iter().map(|name| PhpValue::String(name.to_ascii_lowercase())).collect())
+    };
+    let mut functions = PhpArray::new();
+    functions.insert_string("internal".to_string(), name_list(engine.native_function_names().collect()));
+    functions.insert_string("user".to_string(), name_list(engine.context.user_function_names().collect()));
+    Ok(PhpValue::Array(functions))
+}
+
+/// get_object_vars(object $object): array
+fn get_object_vars(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 1 { return Err("get_object_vars() expects exactly 1 argument".into()); }
+    let PhpValue::Object(handle) = engine.evaluate_expr(&args[0])? else {
+        return Err("get_object_vars(): Argument #1 ($object) must be of type object".into());
+    };
+    let mut vars = PhpArray::new();
+    for (name, value) in handle.borrow().properties.iter() {
+        vars.insert_string(name.clone(), value.clone());
+    }
+    Ok(PhpValue::Array(vars))
+}
+
+/// gettype(mixed $value): string
+fn gettype(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 1 { return Err("gettype() expects exactly 1 argument".into()); }
+    Ok(PhpValue::String(engine.evaluate_expr(&args[0])?.type_name().to_string()))
+}
+
+/// is_null()/is_bool()/is_int()/is_float()/is_string()/is_array()/is_object()/is_resource()/is_scalar(mixed $value): bool
+fn is_type(engine: &mut Engine, args: &[Expr], name: &str, check: fn(&PhpValue) -> bool) -> Result<PhpValue, String> {
+    if args.len() != 1 { return Err(format!("{}() expects exactly 1 argument", name)); }
+    Ok(PhpValue::Bool(check(&engine.evaluate_expr(&args[0])?)))
+}
+
+/// is_callable(mixed $value): bool
+fn is_callable(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() { return Err("is_callable() expects at least 1 argument".into()); }
+    let value = engine.evaluate_expr(&args[0])?;
+    Ok(PhpValue::Bool(engine.is_callable(&value)))
+}
+
+/// settype(mixed &$var, string $type): bool
+fn settype(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 2 { return Err("settype() expects exactly 2 arguments".into()); }
+    if !matches!(args[0], Expr::Variable(_)) {
+        return Err("settype(): Argument #1 ($var) could not be passed by reference".into());
+    }
+    let value = engine.evaluate_expr(&args[0])?;
+    let converted = match engine.evaluate_expr(&args[1])?.to_string().to_ascii_lowercase().as_str() {
+        "boolean" | "bool" => PhpValue::Bool(value.to_bool()),
+        "integer" | "int" => PhpValue::Int(value.to_int()),
+        "float" | "double" => PhpValue::Float(value.to_float()),
+        "string" => PhpValue::String(value.to_string()),
+        "null" => PhpValue::Null,
+        "array" => PhpValue::Array(match value {
+            PhpValue::Array(arr) => arr,
+            PhpValue::Null => PhpArray::new(),
+            PhpValue::Object(handle) => {
+                let mut arr = PhpArray::new();
+                for (name, value) in handle.borrow().properties.iter() {
+                    arr.insert_string(name.clone(), value.clone());
+                }
+                arr
+            }
+            scalar => {
+                let mut arr = PhpArray::new();
+                arr.push(scalar)?;
+                arr
+            }
+        }),
+        _ => return Err(engine.throw_error("ValueError", "settype(): Argument #2 ($type) must be a valid type")),
+    };
+    engine.write_back(&args[0], converted)?;
+    Ok(PhpValue::Bool(true))
+}
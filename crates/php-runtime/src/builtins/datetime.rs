@@ -0,0 +1,612 @@
+//! Date and time built-ins: `time`, `microtime`, `hrtime`, `date`,
+//! `strtotime`, the default timezone and the native methods of `DateTime`
+//!
+//! There is no timezone database, so only UTC, fixed offsets such as
+//! `+05:30` and a table of zones that never observe daylight saving time
+//! are known. Dates use the proleptic Gregorian calendar.
+
+use crate::engine::{Engine, ErrorLevel};
+use php_parser::ast::Expr;
+use php_types::{PhpArray, PhpObjectRef, PhpValue};
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Register this module's functions
+pub(super) fn register(engine: &mut Engine) {
+    engine.register_function("time", time);
+    engine.register_function("microtime", microtime);
+    engine.register_function("hrtime", hrtime);
+    engine.register_function("date", date);
+    engine.register_function("strtotime", strtotime);
+    engine.register_function("date_default_timezone_set", date_default_timezone_set);
+    engine.register_function("date_default_timezone_get", date_default_timezone_get);
+}
+
+/// Zones without daylight saving time, with their UTC offset in seconds
+const FIXED_ZONES: &[(&str, i64)] = &[
+    ("UTC", 0),
+    ("GMT", 0),
+    ("Etc/UTC", 0),
+    ("Etc/GMT", 0),
+    ("Africa/Lagos", 3600),
+    ("Africa/Nairobi", 3 * 3600),
+    ("America/Phoenix", -7 * 3600),
+    ("Asia/Bangkok", 7 * 3600),
+    ("Asia/Dhaka", 6 * 3600),
+    ("Asia/Dubai", 4 * 3600),
+    ("Asia/Jakarta", 7 * 3600),
+    ("Asia/Karachi", 5 * 3600),
+    ("Asia/Kathmandu", 5 * 3600 + 45 * 60),
+    ("Asia/Kolkata", 5 * 3600 + 30 * 60),
+    ("Asia/Riyadh", 3 * 3600),
+    ("Asia/Seoul", 9 * 3600),
+    ("Asia/Shanghai", 8 * 3600),
+    ("Asia/Singapore", 8 * 3600),
+    ("Asia/Tokyo", 9 * 3600),
+    ("Europe/Moscow", 3 * 3600),
+];
+
+/// A timezone with a fixed offset from UTC
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TimeZone {
+    /// Identifier as given by the script: "UTC", "Asia/Tokyo" or "+02:00"
+    pub name: String,
+    /// Seconds east of UTC
+    pub offset: i64,
+}
+
+impl TimeZone {
+    /// The default timezone, as with `date.timezone` unset
+    pub fn utc() -> Self {
+        Self { name: "UTC".to_string(), offset: 0 }
+    }
+
+    /// Look up a zone identifier, `Z`, or a `+HH:MM`/`+HHMM`/`+HH` offset
+    pub fn parse(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("z") {
+            return Some(Self::utc());
+        }
+        if let Some(&(id, offset)) = FIXED_ZONES.iter().find(|(id, _)| id.eq_ignore_ascii_case(name)) {
+            return Some(Self { name: id.to_string(), offset });
+        }
+        let sign = match name.chars().next()? {
+            '+' => 1,
+            '-' => -1,
+            _ => return None,
+        };
+        let digits: String = name[1..].chars().filter(|&c| c != ':').collect();
+        if !digits.chars().all(|c| c.is_ascii_digit()) || !matches!(digits.len(), 2 | 4) {
+            return None;
+        }
+        let hours: i64 = digits[..2].parse().ok()?;
+        let minutes: i64 = if digits.len() == 4 { digits[2..].parse().ok()? } else { 0 };
+        if hours > 14 || minutes > 59 {
+            return None;
+        }
+        let offset = sign * (hours * 3600 + minutes * 60);
+        Some(Self { name: format_offset(offset, true), offset })
+    }
+
+    /// Whether the zone is a bare UTC offset rather than an identifier
+    fn is_offset(&self) -> bool {
+        self.name.starts_with(['+', '-'])
+    }
+}
+
+/// Broken-down local date and time; fields may overflow until normalized
+#[derive(Debug, Clone, Copy)]
+struct Civil {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+}
+
+impl Civil {
+    /// Local time of `timestamp` at the given UTC offset
+    fn from_timestamp(timestamp: i64, offset: i64) -> Self {
+        let (days, seconds) = local_days(timestamp, offset);
+        let (year, month, day) = civil_from_days(days);
+        Self { year, month, day, hour: seconds / 3600, minute: seconds / 60 % 60, second: seconds % 60 }
+    }
+
+    /// Unix timestamp of this local time; overflowing months, days and times
+    /// carry over the way PHP's mktime() does. None if it does not fit in an int.
+    fn to_timestamp(self, offset: i64) -> Option<i64> {
+        let months = self.year.checked_mul(12)?.checked_add(self.month.checked_sub(1)?)?;
+        let year = months.div_euclid(12);
+        // No year this far out has an int timestamp; the bound also keeps days_from_civil in range
+        if year.unsigned_abs() > 300_000_000_000 {
+            return None;
+        }
+        let days = days_from_civil(year, months.rem_euclid(12) + 1, 1).checked_add(self.day.checked_sub(1)?)?;
+        days.checked_mul(86_400)?
+            .checked_add(self.hour.checked_mul(3600)?)?
+            .checked_add(self.minute.checked_mul(60)?)?
+            .checked_add(self.second)?
+            .checked_sub(offset)
+    }
+}
+
+/// Day number since 1970-01-01 and second of that day of `timestamp` at a UTC offset,
+/// valid for every int timestamp
+fn local_days(timestamp: i64, offset: i64) -> (i64, i64) {
+    let local = i128::from(timestamp) + i128::from(offset);
+    (local.div_euclid(86_400) as i64, local.rem_euclid(86_400) as i64)
+}
+
+/// Days since 1970-01-01 of a Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Gregorian date of a day count since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    (if month <= 2 { year_of_era + era * 400 + 1 } else { year_of_era + era * 400 }, month, day)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+const WEEKDAYS: [&str; 7] = ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+/// `+HH:MM` (or `+HHMM` without the colon) for an offset in seconds
+fn format_offset(offset: i64, colon: bool) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = offset.abs() / 60;
+    if colon {
+        format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+    } else {
+        format!("{}{:02}{:02}", sign, minutes / 60, minutes % 60)
+    }
+}
+
+/// Render `timestamp` in `zone` using a date() format string
+pub(crate) fn format_date(format: &str, timestamp: i64, zone: &TimeZone) -> String {
+    let civil = Civil::from_timestamp(timestamp, zone.offset);
+    let days = local_days(timestamp, zone.offset).0;
+    let weekday = (days + 4).rem_euclid(7);
+    let iso_weekday = if weekday == 0 { 7 } else { weekday };
+    let day_of_year = days - days_from_civil(civil.year, 1, 1);
+    // The ISO week belongs to the year holding its Thursday
+    let thursday = days - iso_weekday + 4;
+    let iso_year = civil_from_days(thursday).0;
+    let iso_week = (thursday - days_from_civil(iso_year, 1, 1)) / 7 + 1;
+    let hour12 = if civil.hour % 12 == 0 { 12 } else { civil.hour % 12 };
+
+    let mut out = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            'd' => out.push_str(&format!("{:02}", civil.day)),
+            'D' => out.push_str(&WEEKDAYS[weekday as usize][..3]),
+            'j' => out.push_str(&civil.day.to_string()),
+            'l' => out.push_str(WEEKDAYS[weekday as usize]),
+            'N' => out.push_str(&iso_weekday.to_string()),
+            'S' => out.push_str(match civil.day {
+                1 | 21 | 31 => "st",
+                2 | 22 => "nd",
+                3 | 23 => "rd",
+                _ => "th",
+            }),
+            'w' => out.push_str(&weekday.to_string()),
+            'z' => out.push_str(&day_of_year.to_string()),
+            'W' => out.push_str(&format!("{:02}", iso_week)),
+            'F' => out.push_str(MONTHS[civil.month as usize - 1]),
+            'm' => out.push_str(&format!("{:02}", civil.month)),
+            'M' => out.push_str(&MONTHS[civil.month as usize - 1][..3]),
+            'n' => out.push_str(&civil.month.to_string()),
+            't' => out.push_str(&days_in_month(civil.year, civil.month).to_string()),
+            'L' => out.push(if is_leap_year(civil.year) { '1' } else { '0' }),
+            'o' => out.push_str(&iso_year.to_string()),
+            'Y' => out.push_str(&format!("{:04}", civil.year)),
+            'y' => out.push_str(&format!("{:02}", civil.year.rem_euclid(100))),
+            'a' => out.push_str(if civil.hour < 12 { "am" } else { "pm" }),
+            'A' => out.push_str(if civil.hour < 12 { "AM" } else { "PM" }),
+            'g' => out.push_str(&hour12.to_string()),
+            'G' => out.push_str(&civil.hour.to_string()),
+            'h' => out.push_str(&format!("{:02}", hour12)),
+            'H' => out.push_str(&format!("{:02}", civil.hour)),
+            'i' => out.push_str(&format!("{:02}", civil.minute)),
+            's' => out.push_str(&format!("{:02}", civil.second)),
+            'u' => out.push_str("000000"),
+            'v' => out.push_str("000"),
+            'e' => out.push_str(&zone.name),
+            'T' if zone.offset == 0 && !zone.is_offset() => out.push_str(if zone.name == "GMT" { "GMT" } else { "UTC" }),
+            'T' => {
+                let offset = format_offset(zone.offset, false);
+                out.push_str(offset.strip_suffix("00").unwrap_or(&offset));
+            }
+            'P' => out.push_str(&format_offset(zone.offset, true)),
+            'O' => out.push_str(&format_offset(zone.offset, false)),
+            'p' if zone.offset == 0 => out.push('Z'),
+            'p' => out.push_str(&format_offset(zone.offset, true)),
+            'Z' => out.push_str(&zone.offset.to_string()),
+            'U' => out.push_str(&timestamp.to_string()),
+            'c' => out.push_str(&format_date("Y-m-d\\TH:i:sP", timestamp, zone)),
+            'r' => out.push_str(&format_date("D, d M Y H:i:s O", timestamp, zone)),
+            '\\' => out.extend(chars.next()),
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Current Unix timestamp
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+/// Relative amounts collected while parsing, applied after absolute fields
+#[derive(Default)]
+struct Relative {
+    years: i64,
+    months: i64,
+    days: i64,
+    seconds: i64,
+    /// Target weekday (0 = Sunday) and how to move to it: 0 = this or the
+    /// next occurrence, 1 = strictly after, -1 = strictly before
+    weekday: Option<(i64, i64)>,
+}
+
+impl Relative {
+    /// Add `amount` of `unit`, returning None for an unknown unit or an overflowing amount
+    fn add(&mut self, amount: i64, unit: &str) -> Option<()> {
+        let unit = unit.strip_suffix('s').filter(|u| !u.is_empty()).unwrap_or(unit);
+        let (field, scale) = match unit {
+            "sec" | "second" => (&mut self.seconds, 1),
+            "min" | "minute" => (&mut self.seconds, 60),
+            "hour" => (&mut self.seconds, 3600),
+            "day" => (&mut self.days, 1),
+            "week" => (&mut self.days, 7),
+            "fortnight" => (&mut self.days, 14),
+            "month" => (&mut self.months, 1),
+            "year" => (&mut self.years, 1),
+            _ => return None,
+        };
+        *field = field.checked_add(amount.checked_mul(scale)?)?;
+        Some(())
+    }
+}
+
+fn weekday_index(word: &str) -> Option<i64> {
+    WEEKDAYS.iter().position(|day| {
+        let day = day.to_ascii_lowercase();
+        word == day || word == &day[..3]
+    }).map(|i| i as i64)
+}
+
+/// `YYYY-MM-DD`, `YYYY/MM/DD` or `MM/DD/YYYY`
+fn parse_date_token(token: &str) -> Option<(i64, i64, i64)> {
+    let separator = if token.contains('-') { '-' } else { '/' };
+    let parts: Vec<&str> = token.split(separator).collect();
+    if parts.len() != 3 || parts.iter().any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+    let numbers: Vec<i64> = parts.iter().map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    let (year, month, day) = if parts[0].len() == 4 {
+        (numbers[0], numbers[1], numbers[2])
+    } else if separator == '/' && parts[2].len() == 4 {
+        (numbers[2], numbers[0], numbers[1])
+    } else {
+        return None;
+    };
+    ((1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day)).then_some((year, month, day))
+}
+
+/// `HH:MM[:SS[.fraction]]` or `H[:MM]am`/`pm`
+fn parse_time_token(token: &str) -> Option<(i64, i64, i64)> {
+    let (clock, meridiem) = match token.strip_suffix("am").or_else(|| token.strip_suffix("pm")) {
+        Some(clock) => (clock, Some(token.ends_with("pm"))),
+        None => (token, None),
+    };
+    let clock = clock.split('.').next()?;
+    if !clock.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let parts: Vec<i64> = clock.split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    let (mut hour, minute, second) = match (parts.as_slice(), meridiem) {
+        ([hour, minute], _) => (*hour, *minute, 0),
+        ([hour, minute, second], _) => (*hour, *minute, *second),
+        ([hour], Some(_)) => (*hour, 0, 0),
+        _ => return None,
+    };
+    if let Some(pm) = meridiem {
+        if !(1..=12).contains(&hour) {
+            return None;
+        }
+        hour = hour % 12 + if pm { 12 } else { 0 };
+    }
+    (hour < 24 && minute < 60 && second < 60).then_some((hour, minute, second))
+}
+
+/// Parse a date/time string the way strtotime() does, relative to `base`.
+/// Returns the timestamp and the timezone named in the string, if any.
+pub(crate) fn parse_datetime(input: &str, base: i64, zone: &TimeZone) -> Option<(i64, Option<TimeZone>)> {
+    let input = input.trim().to_ascii_lowercase();
+    if let Some(seconds) = input.strip_prefix('@') {
+        return Some((seconds.parse().ok()?, Some(TimeZone::parse("+00:00")?)));
+    }
+    // Split ISO 8601 "2024-01-15T10:30:00+02:00" into date, time and zone tokens
+    let mut tokens: Vec<String> = Vec::new();
+    for word in input.split_whitespace() {
+        let word = match word.split_once('t') {
+            Some((date, time)) if parse_date_token(date).is_some() => {
+                tokens.push(date.to_string());
+                time
+            }
+            _ => word,
+        };
+        // A time may carry its zone: 10:30:00+02:00, 10:30z
+        match word.find(['+', '-', 'z']).filter(|&i| i > 0 && word[..i].contains(':')) {
+            Some(i) => {
+                tokens.push(word[..i].to_string());
+                tokens.push(word[i..].to_string());
+            }
+            None => tokens.push(word.to_string()),
+        }
+    }
+
+    let mut date = None;
+    let mut time = None;
+    let mut midnight = false;
+    let mut found_zone = None;
+    let mut relative = Relative::default();
+    let mut words = tokens.iter().map(String::as_str).peekable();
+    while let Some(word) = words.next() {
+        match word {
+            "now" => {}
+            "today" | "midnight" => midnight = true,
+            "noon" => time = Some((12, 0, 0)),
+            "tomorrow" => { relative.days += 1; midnight = true; }
+            "yesterday" => { relative.days -= 1; midnight = true; }
+            "ago" => {
+                relative.years = relative.years.checked_neg()?;
+                relative.months = relative.months.checked_neg()?;
+                relative.days = relative.days.checked_neg()?;
+                relative.seconds = relative.seconds.checked_neg()?;
+            }
+            "next" | "last" | "previous" | "this" => {
+                let direction = match word { "next" => 1, "this" => 0, _ => -1 };
+                let target = words.next()?;
+                if let Some(day) = weekday_index(target) {
+                    relative.weekday = Some((day, direction));
+                    midnight = true;
+                } else {
+                    relative.add(direction, target)?;
+                }
+            }
+            _ => {
+                if let Some(parsed) = parse_date_token(word) {
+                    date = Some(parsed);
+                } else if let Some(parsed) = parse_time_token(word) {
+                    time = Some(parsed);
+                } else if let Some(day) = weekday_index(word) {
+                    relative.weekday = Some((day, 0));
+                    midnight = true;
+                } else if let Ok(amount) = word.parse::<i64>() {
+                    relative.add(amount, words.next()?)?;
+                } else if let Some(parsed) = TimeZone::parse(word) {
+                    found_zone = Some(parsed);
+                } else {
+                    return None;
+                }
+            }
+        }
+    }
+
+    let offset = found_zone.as_ref().unwrap_or(zone).offset;
+    let mut civil = Civil::from_timestamp(base, offset);
+    if let Some((year, month, day)) = date {
+        civil = Civil { year, month, day, hour: 0, minute: 0, second: 0 };
+    }
+    if midnight {
+        (civil.hour, civil.minute, civil.second) = (0, 0, 0);
+    }
+    if let Some((hour, minute, second)) = time {
+        (civil.hour, civil.minute, civil.second) = (hour, minute, second);
+    }
+    civil.year = civil.year.checked_add(relative.years)?;
+    civil.month = civil.month.checked_add(relative.months)?;
+    civil.day = civil.day.checked_add(relative.days)?;
+    civil.second = civil.second.checked_add(relative.seconds)?;
+    let mut timestamp = civil.to_timestamp(offset)?;
+    if let Some((target, direction)) = relative.weekday {
+        let weekday = (local_days(timestamp, offset).0 + 4).rem_euclid(7);
+        let days = match direction {
+            1 => (target - weekday - 1).rem_euclid(7) + 1,
+            -1 => -((weekday - target - 1).rem_euclid(7) + 1),
+            _ => (target - weekday).rem_euclid(7),
+        };
+        timestamp = timestamp.checked_add(days * 86_400)?;
+    }
+    Some((timestamp, found_zone))
+}
+
+/// time(): int
+fn time(_engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if !args.is_empty() { return Err("time() expects exactly 0 arguments".into()); }
+    Ok(PhpValue::Int(now()))
+}
+
+/// microtime(bool $as_float = false): string|float
+///
+/// As a string, the fraction comes first: "0.25000000 1700000000".
+fn microtime(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() > 1 { return Err("microtime() expects at most 1 argument".into()); }
+    let as_float = match args.first() {
+        Some(arg) => engine.evaluate_expr(arg)?.is_truthy(),
+        None => false,
+    };
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let micros = elapsed.subsec_micros();
+    Ok(if as_float {
+        PhpValue::Float(elapsed.as_secs() as f64 + f64::from(micros) / 1e6)
+    } else {
+        PhpValue::String(format!("0.{:06}00 {}", micros, elapsed.as_secs()))
+    })
+}
+
+/// hrtime(bool $as_number = false): array|int
+///
+/// Monotonic nanoseconds since an arbitrary point, here the first call.
+fn hrtime(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    static ORIGIN: OnceLock<Instant> = OnceLock::new();
+    if args.len() > 1 { return Err("hrtime() expects at most 1 argument".into()); }
+    let as_number = match args.first() {
+        Some(arg) => engine.evaluate_expr(arg)?.is_truthy(),
+        None => false,
+    };
+    let elapsed = ORIGIN.get_or_init(Instant::now).elapsed();
+    if as_number {
+        return Ok(PhpValue::Int(elapsed.as_nanos() as i64));
+    }
+    let mut parts = PhpArray::new();
+    parts.push(PhpValue::Int(elapsed.as_secs() as i64))?;
+    parts.push(PhpValue::Int(i64::from(elapsed.subsec_nanos())))?;
+    Ok(PhpValue::Array(parts))
+}
+
+/// date(string $format, ?int $timestamp = null): string
+fn date(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() || args.len() > 2 { return Err("date() expects 1 or 2 arguments".into()); }
+    let format = engine.evaluate_expr(&args[0])?.to_string();
+    let timestamp = match args.get(1) {
+        Some(arg) => match engine.evaluate_expr(arg)? {
+            PhpValue::Null => now(),
+            value => value.to_int(),
+        },
+        None => now(),
+    };
+    Ok(PhpValue::String(format_date(&format, timestamp, &engine.timezone)))
+}
+
+/// strtotime(string $datetime, ?int $baseTimestamp = null): int|false
+fn strtotime(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() || args.len() > 2 { return Err("strtotime() expects 1 or 2 arguments".into()); }
+    let input = engine.evaluate_expr(&args[0])?.to_string();
+    let base = match args.get(1) {
+        Some(arg) => match engine.evaluate_expr(arg)? {
+            PhpValue::Null => now(),
+            value => value.to_int(),
+        },
+        None => now(),
+    };
+    Ok(match parse_datetime(&input, base, &engine.timezone) {
+        Some((timestamp, _)) => PhpValue::Int(timestamp),
+        None => PhpValue::Bool(false),
+    })
+}
+
+/// date_default_timezone_set(string $timezoneId): bool
+fn date_default_timezone_set(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 1 { return Err("date_default_timezone_set() expects exactly 1 argument".into()); }
+    let name = engine.evaluate_expr(&args[0])?.to_string();
+    // Bare offsets are valid DateTime zones but not default timezone IDs
+    match TimeZone::parse(&name).filter(|zone| !zone.is_offset()) {
+        Some(zone) => {
+            engine.timezone = zone;
+            Ok(PhpValue::Bool(true))
+        }
+        None => {
+            engine.raise_error(ErrorLevel::Notice, format!("date_default_timezone_set(): Timezone ID '{}' is invalid", name));
+            Ok(PhpValue::Bool(false))
+        }
+    }
+}
+
+/// date_default_timezone_get(): string
+fn date_default_timezone_get(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if !args.is_empty() { return Err("date_default_timezone_get() expects exactly 0 arguments".into()); }
+    Ok(PhpValue::String(engine.timezone.name.clone()))
+}
+
+/// Store a moment on a DateTime object using PHP's visible properties:
+/// `date` in local time, `timezone_type` and `timezone`
+fn set_moment(handle: &PhpObjectRef, timestamp: i64, zone: &TimeZone) {
+    let mut object = handle.borrow_mut();
+    object.set_property("date", PhpValue::String(format_date("Y-m-d H:i:s.u", timestamp, zone)));
+    object.set_property("timezone_type", PhpValue::Int(if zone.is_offset() { 1 } else { 3 }));
+    object.set_property("timezone", PhpValue::String(zone.name.clone()));
+}
+
+/// Read back the moment stored by `set_moment`
+fn get_moment(handle: &PhpObjectRef) -> Option<(i64, TimeZone)> {
+    let object = handle.borrow();
+    let zone = TimeZone::parse(&object.get_property("timezone")?.to_string())?;
+    let date = object.get_property("date")?.to_string();
+    let (day, clock) = date.split_once(' ')?;
+    let (year, month, day) = parse_date_token(day)?;
+    let (hour, minute, second) = parse_time_token(clock)?;
+    Some((Civil { year, month, day, hour, minute, second }.to_timestamp(zone.offset)?, zone))
+}
+
+/// Methods DateTime provides natively; None if `method` is not one of them
+pub(crate) fn call_method(engine: &mut Engine, object: &PhpValue, method: &str, args: Vec<PhpValue>) -> Option<Result<PhpValue, String>> {
+    let PhpValue::Object(handle) = object else { return None };
+    let arg = |i: usize| args.get(i).map(PhpValue::to_string);
+    let moment = || get_moment(handle).ok_or_else(|| "DateTime object has not been correctly initialized".to_string());
+    let result = match method {
+        "__construct" => {
+            let input = arg(0).unwrap_or_else(|| "now".to_string());
+            let zone = engine.timezone.clone();
+            match parse_datetime(&input, now(), &zone) {
+                Some((timestamp, found)) => {
+                    set_moment(handle, timestamp, found.as_ref().unwrap_or(&zone));
+                    Ok(PhpValue::Null)
+                }
+                None => Err(engine.throw_error("Exception", &format!("DateTime::__construct(): Failed to parse time string ({})", input))),
+            }
+        }
+        "format" => moment().map(|(timestamp, zone)| PhpValue::String(format_date(&arg(0).unwrap_or_default(), timestamp, &zone))),
+        "getTimestamp" => moment().map(|(timestamp, _)| PhpValue::Int(timestamp)),
+        "setTimestamp" => moment().map(|(_, zone)| {
+            set_moment(handle, args.first().map_or(0, PhpValue::to_int), &zone);
+            object.clone()
+        }),
+        "modify" => moment().map(|(timestamp, zone)| {
+            let modifier = arg(0).unwrap_or_default();
+            match parse_datetime(&modifier, timestamp, &zone) {
+                Some((modified, _)) => {
+                    set_moment(handle, modified, &zone);
+                    object.clone()
+                }
+                None => {
+                    engine.raise_error(ErrorLevel::Warning, format!("DateTime::modify(): Failed to parse time string ({})", modifier));
+                    PhpValue::Bool(false)
+                }
+            }
+        }),
+        _ => return None,
+    };
+    Some(result)
+}
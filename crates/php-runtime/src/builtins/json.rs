@@ -0,0 +1,192 @@
+//! JSON built-ins
+
+use crate::engine::Engine;
+use php_parser::ast::Expr;
+use php_types::{php_float_repr, PhpArray, PhpArrayKey, PhpObject, PhpValue};
+
+/// Register this module's functions
+pub(super) fn register(engine: &mut Engine) {
+    engine.register_function("json_encode", json_encode);
+    engine.register_function("json_decode", json_decode);
+}
+
+/// json_encode() flag bits, matching PHP's JSON_* constants
+const JSON_FORCE_OBJECT: i64 = 16;
+const JSON_UNESCAPED_SLASHES: i64 = 64;
+const JSON_PRETTY_PRINT: i64 = 128;
+const JSON_UNESCAPED_UNICODE: i64 = 256;
+
+/// json_encode(mixed $value, int $flags = 0): string
+fn json_encode(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() { return Err("json_encode() expects at least 1 argument".into()); }
+    let value = engine.evaluate_expr(&args[0])?;
+    let flags = match args.get(1) { Some(arg) => engine.evaluate_expr(arg)?.to_int(), None => 0 };
+    let mut out = String::new();
+    encode(&mut out, &value, flags, 0);
+    Ok(PhpValue::String(out))
+}
+
+/// Append a JSON string literal, escaping as PHP does unless the flags say otherwise
+fn escape_str(out: &mut String, s: &str, flags: i64) {
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '/' if flags & JSON_UNESCAPED_SLASHES == 0 => out.push_str("\\/"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            // Characters outside the BMP escape as a UTF-16 surrogate pair
+            c if (c as u32) > 0x7F && flags & JSON_UNESCAPED_UNICODE == 0 => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Start a new line at the given depth when pretty printing
+fn newline(out: &mut String, flags: i64, depth: usize) {
+    if flags & JSON_PRETTY_PRINT != 0 {
+        out.push('\n');
+        out.push_str(&"    ".repeat(depth));
+    }
+}
+
+/// Append a JSON array or object from (key, value) members; empty containers stay on one line
+fn encode_members<'a>(out: &mut String, members: impl Iterator<Item = (Option<String>, &'a PhpValue)>, object: bool, flags: i64, depth: usize) {
+    out.push(if object { '{' } else { '[' });
+    let mut empty = true;
+    for (key, value) in members {
+        if !empty { out.push(','); }
+        empty = false;
+        newline(out, flags, depth + 1);
+        if let Some(key) = key {
+            escape_str(out, &key, flags);
+            out.push_str(if flags & JSON_PRETTY_PRINT != 0 { ": " } else { ":" });
+        }
+        encode(out, value, flags, depth + 1);
+    }
+    if !empty { newline(out, flags, depth); }
+    out.push(if object { '}' } else { ']' });
+}
+
+/// Append the JSON encoding of a value
+fn encode(out: &mut String, value: &PhpValue, flags: i64, depth: usize) {
+    match value {
+        PhpValue::Null => out.push_str("null"),
+        PhpValue::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        PhpValue::Int(i) => out.push_str(&i.to_string()),
+        // Shortest round-trip digits like var_dump; whole floats keep a ".0" so they decode
+        // back as floats, which makes JSON_PRESERVE_ZERO_FRACTION always in effect
+        PhpValue::Float(f) if f.is_finite() => {
+            let repr = php_float_repr(*f).replace('E', "e");
+            out.push_str(&repr);
+            if !repr.contains(['.', 'e']) {
+                out.push_str(".0");
+            }
+        }
+        PhpValue::String(s) => escape_str(out, s, flags),
+        PhpValue::Array(arr) => {
+            // Only keys 0..n-1 in order make a JSON list
+            let is_list = flags & JSON_FORCE_OBJECT == 0
+                && arr.data.keys().enumerate().all(|(i, k)| matches!(k, PhpArrayKey::Int(n) if *n == i as i64));
+            let members = arr.data.iter().map(|(k, v)| {
+                let key = match k { PhpArrayKey::Int(i) => i.to_string(), PhpArrayKey::String(s) => s.clone() };
+                (if is_list { None } else { Some(key) }, v)
+            });
+            encode_members(out, members, !is_list, flags, depth);
+        }
+        // Properties in the order they were set; closures have none and encode as {}
+        PhpValue::Object(handle) => {
+            let object = handle.borrow();
+            encode_members(out, object.properties.iter().map(|(name, v)| (Some(name.clone()), v)), true, flags, depth);
+        }
+        _ => out.push_str("null"),
+    }
+}
+
+/// json_decode() flag bits
+const JSON_OBJECT_AS_ARRAY: i64 = 1;
+const JSON_THROW_ON_ERROR: i64 = 4194304;
+
+/// json_decode(string $json, ?bool $associative = null, int $depth = 512, int $flags = 0): mixed
+///
+/// Objects decode to stdClass unless `$associative` (or JSON_OBJECT_AS_ARRAY when it is null)
+/// asks for arrays. Invalid input yields null, or a JsonException with JSON_THROW_ON_ERROR.
+fn json_decode(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() || args.len() > 4 { return Err("json_decode() expects 1 to 4 arguments".into()); }
+    let json = engine.evaluate_expr(&args[0])?.to_string();
+    let associative = match args.get(1) { Some(arg) => engine.evaluate_expr(arg)?, None => PhpValue::Null };
+    let depth = match args.get(2) { Some(arg) => engine.evaluate_expr(arg)?.to_int(), None => 512 };
+    let flags = match args.get(3) { Some(arg) => engine.evaluate_expr(arg)?.to_int(), None => 0 };
+    if depth <= 0 {
+        return Err(engine.throw_error("ValueError", "json_decode(): Argument #3 ($depth) must be greater than 0"));
+    }
+    let assoc = match associative {
+        PhpValue::Null => flags & JSON_OBJECT_AS_ARRAY != 0,
+        other => other.to_bool(),
+    };
+    // PHP's error codes: JSON_ERROR_DEPTH is 1, JSON_ERROR_SYNTAX is 4
+    let error = match serde_json::from_str::<serde_json::Value>(&json) {
+        Ok(value) if nesting_depth(&value) <= depth as usize => return Ok(to_php(engine, &value, assoc)),
+        Ok(_) => ("Maximum stack depth exceeded", 1),
+        Err(_) => ("Syntax error", 4),
+    };
+    if flags & JSON_THROW_ON_ERROR != 0 {
+        return Err(engine.throw_error_with_code("JsonException", error.0, error.1));
+    }
+    Ok(PhpValue::Null)
+}
+
+/// How deeply arrays and objects nest; scalars have depth 0
+fn nesting_depth(value: &serde_json::Value) -> usize {
+    match value {
+        serde_json::Value::Array(items) => 1 + items.iter().map(nesting_depth).max().unwrap_or(0),
+        serde_json::Value::Object(map) => 1 + map.values().map(nesting_depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Convert a parsed JSON value, keeping object members in document order
+fn to_php(engine: &mut Engine, value: &serde_json::Value, assoc: bool) -> PhpValue {
+    match value {
+        serde_json::Value::Null => PhpValue::Null,
+        serde_json::Value::Bool(b) => PhpValue::Bool(*b),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() { PhpValue::Int(i) } else if let Some(f) = n.as_f64() { PhpValue::Float(f) } else { PhpValue::Null }
+        }
+        serde_json::Value::String(s) => PhpValue::String(s.clone()),
+        serde_json::Value::Array(items) => {
+            PhpValue::Array(items.iter().map(|item| to_php(engine, item, assoc)).collect())
+        }
+        serde_json::Value::Object(map) if assoc => {
+            let mut arr = PhpArray::new();
+            for (key, item) in map {
+                let key = PhpArrayKey::from_scalar(&PhpValue::String(key.clone())).expect("string keys are scalar");
+                let item = to_php(engine, item, assoc);
+                arr.insert(key, item);
+            }
+            PhpValue::Array(arr)
+        }
+        serde_json::Value::Object(map) => {
+            // Allocated before its members so handle numbers follow document order, as in PHP
+            let object = engine.alloc_object(PhpObject::new("stdClass"));
+            if let PhpValue::Object(handle) = &object {
+                for (key, item) in map {
+                    let item = to_php(engine, item, assoc);
+                    handle.borrow_mut().set_property(key.clone(), item);
+                }
+            }
+            object
+        }
+    }
+}
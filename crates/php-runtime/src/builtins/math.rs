@@ -0,0 +1,25 @@
+//! Math built-ins
+
+use crate::engine::Engine;
+use php_parser::ast::Expr;
+use php_types::PhpValue;
+
+/// Register this module's functions
+pub(super) fn register(engine: &mut Engine) {
+    engine.register_function("intdiv", intdiv);
+}
+
+/// intdiv(int $num1, int $num2): int
+fn intdiv(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 2 { return Err("intdiv() expects exactly 2 arguments".into()); }
+    let dividend = engine.evaluate_expr(&args[0])?.to_int();
+    let divisor = engine.evaluate_expr(&args[1])?.to_int();
+    if divisor == 0 {
+        return Err(engine.throw_error("DivisionByZeroError", "Division by zero"));
+    }
+    // Integer division truncates toward zero; only PHP_INT_MIN / -1 overflows
+    match dividend.checked_div(divisor) {
+        Some(quotient) => Ok(PhpValue::Int(quotient)),
+        None => Err(engine.throw_error("ArithmeticError", "Division of PHP_INT_MIN by -1 is not an integer")),
+    }
+}
@@ -0,0 +1,32 @@
+//! Built-in function registry
+//!
+//! Native functions receive the engine and their unevaluated argument
+//! expressions, so they can implement by-reference parameters and
+//! language constructs such as `isset`.
+
+use crate::engine::Engine;
+use php_parser::ast::Expr;
+use php_types::PhpValue;
+use std::rc::Rc;
+
+mod array;
+mod core;
+pub(crate) mod datetime;
+mod json;
+mod math;
+mod output;
+mod string;
+
+/// A native function callable from PHP code
+pub type NativeFunction = Rc<dyn Fn(&mut Engine, &[Expr]) -> Result<PhpValue, String>>;
+
+/// Register every built-in function on a freshly created engine
+pub(crate) fn register_all(engine: &mut Engine) {
+    core::register(engine);
+    array::register(engine);
+    string::register(engine);
+    output::register(engine);
+    json::register(engine);
+    math::register(engine);
+    datetime::register(engine);
+}
@@ -0,0 +1,100 @@
+//! Output built-ins: output buffering and debug dumps
+
+use crate::engine::Engine;
+use php_parser::ast::Expr;
+use php_types::PhpValue;
+
+/// Register this module's functions
+pub(super) fn register(engine: &mut Engine) {
+    engine.register_function("ob_start", ob_start);
+    engine.register_function("ob_get_clean", ob_get_clean);
+    engine.register_function("ob_get_contents", ob_get_contents);
+    engine.register_function("ob_get_length", ob_get_length);
+    engine.register_function("ob_get_level", ob_get_level);
+    engine.register_function("ob_end_clean", ob_end_clean);
+    engine.register_function("ob_end_flush", |engine, _| end_flush(engine, false));
+    engine.register_function("ob_get_flush", |engine, _| end_flush(engine, true));
+    engine.register_function("ob_flush", ob_flush);
+    engine.register_function("print_r", print_r);
+    engine.register_function("var_dump", var_dump);
+}
+
+/// ob_start(): bool
+fn ob_start(engine: &mut Engine, _args: &[Expr]) -> Result<PhpValue, String> {
+    engine.output_buffers.push(String::new());
+    Ok(PhpValue::Bool(true))
+}
+
+/// ob_get_clean(): string|false
+fn ob_get_clean(engine: &mut Engine, _args: &[Expr]) -> Result<PhpValue, String> {
+    if let Some(buf) = engine.output_buffers.pop() { Ok(PhpValue::String(buf)) } else { Ok(PhpValue::Bool(false)) }
+}
+
+/// ob_get_contents(): string|false
+fn ob_get_contents(engine: &mut Engine, _args: &[Expr]) -> Result<PhpValue, String> {
+    Ok(engine.output_buffers.last().map(|buf| PhpValue::String(buf.clone())).unwrap_or(PhpValue::Bool(false)))
+}
+
+/// ob_get_length(): int|false
+fn ob_get_length(engine: &mut Engine, _args: &[Expr]) -> Result<PhpValue, String> {
+    Ok(engine.output_buffers.last().map(|buf| PhpValue::Int(buf.len() as i64)).unwrap_or(PhpValue::Bool(false)))
+}
+
+/// ob_get_level(): int
+fn ob_get_level(engine: &mut Engine, _args: &[Expr]) -> Result<PhpValue, String> {
+    Ok(PhpValue::Int(engine.output_buffers.len() as i64))
+}
+
+/// ob_end_clean(): bool
+fn ob_end_clean(engine: &mut Engine, _args: &[Expr]) -> Result<PhpValue, String> {
+    Ok(PhpValue::Bool(engine.output_buffers.pop().is_some()))
+}
+
+/// ob_end_flush(): bool and ob_get_flush(): string|false
+fn end_flush(engine: &mut Engine, return_contents: bool) -> Result<PhpValue, String> {
+    // Flushed content goes to the enclosing buffer, or the real output at the outermost level
+    match engine.output_buffers.pop() {
+        Some(buf) => {
+            engine.write_output(&buf);
+            if return_contents { Ok(PhpValue::String(buf)) } else { Ok(PhpValue::Bool(true)) }
+        }
+        None => Ok(PhpValue::Bool(false)),
+    }
+}
+
+/// ob_flush(): bool
+fn ob_flush(engine: &mut Engine, _args: &[Expr]) -> Result<PhpValue, String> {
+    match engine.output_buffers.pop() {
+        Some(buf) => {
+            engine.write_output(&buf);
+            engine.output_buffers.push(String::new());
+            Ok(PhpValue::Bool(true))
+        }
+        None => Ok(PhpValue::Bool(false)),
+    }
+}
+
+/// print_r(mixed $value, bool $return = false): string|true
+fn print_r(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() || args.len() > 2 { return Err("print_r() expects 1 or 2 arguments".into()); }
+    let value = engine.evaluate_expr(&args[0])?;
+    let return_mode = match args.get(1) { Some(expr) => engine.evaluate_expr(expr)?.is_truthy(), None => false };
+    let text = crate::dump::print_r(&value);
+    if return_mode {
+        Ok(PhpValue::String(text))
+    } else {
+        engine.write_output(&text);
+        Ok(PhpValue::Bool(true))
+    }
+}
+
+/// var_dump(mixed $value, mixed ...$values): void
+fn var_dump(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() { return Err("var_dump() expects at least 1 argument".into()); }
+    for arg in args {
+        let value = engine.evaluate_expr(arg)?;
+        let text = crate::dump::var_dump(&value);
+        engine.write_output(&text);
+    }
+    Ok(PhpValue::Null)
+}
@@ -0,0 +1,716 @@
+//! String built-ins
+
+use crate::engine::{Engine, ErrorLevel};
+use php_parser::ast::Expr;
+use php_types::{PhpArray, PhpValue};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// Register this module's functions
+pub(super) fn register(engine: &mut Engine) {
+    engine.register_function("strlen", strlen);
+    engine.register_function("substr", substr);
+    engine.register_function("str_repeat", str_repeat);
+    engine.register_function("strtolower", |engine, args| change_case(engine, args, "strtolower", false, false));
+    engine.register_function("strtoupper", |engine, args| change_case(engine, args, "strtoupper", true, false));
+    engine.register_function("mb_strtolower", |engine, args| change_case(engine, args, "mb_strtolower", false, true));
+    engine.register_function("mb_strtoupper", |engine, args| change_case(engine, args, "mb_strtoupper", true, true));
+    engine.register_function("trim", |engine, args| trim(engine, args, "trim", true, true));
+    engine.register_function("ltrim", |engine, args| trim(engine, args, "ltrim", true, false));
+    engine.register_function("rtrim", |engine, args| trim(engine, args, "rtrim", false, true));
+    engine.register_function("str_split", |engine, args| split_chunks(engine, args, "str_split", false));
+    engine.register_function("mb_str_split", |engine, args| split_chunks(engine, args, "mb_str_split", true));
+    engine.register_function("implode", implode);
+    engine.register_function("explode", explode);
+    engine.register_function("parse_str", parse_str);
+    engine.register_function("preg_match", preg_match);
+    engine.register_function("sprintf", sprintf);
+    engine.register_function("printf", printf);
+    engine.register_function("str_replace", |engine, args| replace(engine, args, "str_replace", false));
+    engine.register_function("str_ireplace", |engine, args| replace(engine, args, "str_ireplace", true));
+    engine.register_function("strpos", |engine, args| position(engine, args, "strpos", false, false));
+    engine.register_function("stripos", |engine, args| position(engine, args, "stripos", true, false));
+    engine.register_function("strrpos", |engine, args| position(engine, args, "strrpos", false, true));
+    engine.register_function("strripos", |engine, args| position(engine, args, "strripos", true, true));
+    engine.register_function("strstr", |engine, args| substring_from(engine, args, "strstr", false));
+    engine.register_function("stristr", |engine, args| substring_from(engine, args, "stristr", true));
+    engine.register_function("strcmp", |engine, args| compare(engine, args, "strcmp", Collation::Binary, false));
+    engine.register_function("strcasecmp", |engine, args| compare(engine, args, "strcasecmp", Collation::Binary, true));
+    engine.register_function("strncmp", |engine, args| compare(engine, args, "strncmp", Collation::Prefix, false));
+    engine.register_function("strncasecmp", |engine, args| compare(engine, args, "strncasecmp", Collation::Prefix, true));
+    engine.register_function("strnatcmp", |engine, args| compare(engine, args, "strnatcmp", Collation::Natural, false));
+    engine.register_function("strnatcasecmp", |engine, args| compare(engine, args, "strnatcasecmp", Collation::Natural, true));
+}
+
+/// Fold ASCII case for case-insensitive matching; byte offsets stay valid for the original string
+fn fold(s: &str, ignore_case: bool) -> Cow<'_, str> {
+    if ignore_case { Cow::Owned(s.to_ascii_lowercase()) } else { Cow::Borrowed(s) }
+}
+
+/// Replace every occurrence of each search string in turn, counting replacements
+fn replace_all(subject: &str, pairs: &[(String, String)], ignore_case: bool, count: &mut i64) -> String {
+    let mut subject = subject.to_string();
+    for (search, replacement) in pairs {
+        if search.is_empty() { continue; }
+        let haystack = fold(&subject, ignore_case).into_owned();
+        let needle = fold(search, ignore_case);
+        let mut out = String::with_capacity(subject.len());
+        let mut last = 0;
+        for (pos, _) in haystack.match_indices(needle.as_ref()) {
+            out.push_str(&subject[last..pos]);
+            out.push_str(replacement);
+            last = pos + search.len();
+            *count += 1;
+        }
+        out.push_str(&subject[last..]);
+        subject = out;
+    }
+    subject
+}
+
+/// str_replace()/str_ireplace(array|string $search, array|string $replace, array|string $subject, int &$count = null): array|string
+fn replace(engine: &mut Engine, args: &[Expr], name: &str, ignore_case: bool) -> Result<PhpValue, String> {
+    if args.len() < 3 || args.len() > 4 { return Err(format!("{}() expects 3 or 4 arguments", name)); }
+    let search = engine.evaluate_expr(&args[0])?;
+    let replacement = engine.evaluate_expr(&args[1])?;
+    let subject = engine.evaluate_expr(&args[2])?;
+    let searches: Vec<String> = match search {
+        PhpValue::Array(arr) => arr.data.values().map(|v| v.to_string()).collect(),
+        other => vec![other.to_string()],
+    };
+    // An array of replacements pairs up with the searches, running out into empty strings
+    let pairs: Vec<(String, String)> = match replacement {
+        PhpValue::Array(arr) => {
+            let mut replacements = arr.data.values().map(|v| v.to_string());
+            searches.into_iter().map(|s| (s, replacements.next().unwrap_or_default())).collect()
+        }
+        other => {
+            let replacement = other.to_string();
+            searches.into_iter().map(|s| (s, replacement.clone())).collect()
+        }
+    };
+    let mut count = 0;
+    let result = match subject {
+        PhpValue::Array(arr) => {
+            let mut result = PhpArray::new();
+            for (key, value) in arr.data {
+                let value = match value {
+                    PhpValue::Array(_) => value,
+                    other => PhpValue::String(replace_all(&other.to_string(), &pairs, ignore_case, &mut count)),
+                };
+                result.insert(key, value);
+            }
+            PhpValue::Array(result)
+        }
+        other => PhpValue::String(replace_all(&other.to_string(), &pairs, ignore_case, &mut count)),
+    };
+    if let Some(target) = args.get(3) {
+        engine.write_back(target, PhpValue::Int(count))?;
+    }
+    Ok(result)
+}
+
+/// strpos()/stripos()/strrpos()/strripos(string $haystack, string $needle, int $offset = 0): int|false
+fn position(engine: &mut Engine, args: &[Expr], name: &str, ignore_case: bool, last: bool) -> Result<PhpValue, String> {
+    if args.len() < 2 || args.len() > 3 { return Err(format!("{}() expects 2 or 3 arguments", name)); }
+    let haystack = engine.evaluate_expr(&args[0])?.to_string();
+    let needle = engine.evaluate_expr(&args[1])?.to_string();
+    let offset = match args.get(2) { Some(arg) => engine.evaluate_expr(arg)?.to_int(), None => 0 };
+    let len = haystack.len() as i64;
+    if offset > len || offset < -len {
+        return Err(engine.throw_error("ValueError", &format!("{}(): Argument #3 ($offset) must be contained in argument #1 ($haystack)", name)));
+    }
+    let haystack = fold(&haystack, ignore_case);
+    let needle = fold(&needle, ignore_case);
+    let bytes = haystack.as_bytes();
+    let found = if last {
+        // A negative offset ends the search that many bytes from the end, as in PHP
+        let (start, end) = if offset >= 0 {
+            (offset as usize, bytes.len())
+        } else if (-offset) < needle.len() as i64 {
+            (0, bytes.len())
+        } else {
+            (0, (len + offset) as usize + needle.len())
+        };
+        bytes.get(start..end).and_then(|window| {
+            (0..=window.len().checked_sub(needle.len())?).rev()
+                .find(|&i| window[i..].starts_with(needle.as_bytes()))
+                .map(|i| i + start)
+        })
+    } else {
+        let start = if offset < 0 { (len + offset) as usize } else { offset as usize };
+        bytes[start..].windows(needle.len().max(1))
+            .position(|window| window.starts_with(needle.as_bytes()))
+            .map(|i| i + start)
+            .or_else(|| (needle.is_empty() && start == bytes.len()).then_some(start))
+    };
+    Ok(found.map_or(PhpValue::Bool(false), |pos| PhpValue::Int(pos as i64)))
+}
+
+/// strstr()/stristr(string $haystack, string $needle, bool $before_needle = false): string|false
+fn substring_from(engine: &mut Engine, args: &[Expr], name: &str, ignore_case: bool) -> Result<PhpValue, String> {
+    if args.len() < 2 || args.len() > 3 { return Err(format!("{}() expects 2 or 3 arguments", name)); }
+    let haystack = engine.evaluate_expr(&args[0])?.to_string();
+    let needle = engine.evaluate_expr(&args[1])?.to_string();
+    let before_needle = match args.get(2) { Some(arg) => engine.evaluate_expr(arg)?.is_truthy(), None => false };
+    let found = fold(&haystack, ignore_case).find(fold(&needle, ignore_case).as_ref());
+    Ok(match found {
+        Some(pos) if before_needle => PhpValue::String(haystack[..pos].to_string()),
+        Some(pos) => PhpValue::String(haystack[pos..].to_string()),
+        None => PhpValue::Bool(false),
+    })
+}
+
+/// How the strcmp family orders two strings
+#[derive(Clone, Copy)]
+enum Collation {
+    /// Byte by byte
+    Binary,
+    /// Byte by byte over at most `$length` bytes
+    Prefix,
+    /// Runs of digits compare by numeric value: "img2" < "img10"
+    Natural,
+}
+
+/// strcmp()/strcasecmp()/strnatcmp()/strnatcasecmp(string $string1, string $string2): int
+/// and strncmp()/strncasecmp(string $string1, string $string2, int $length): int
+fn compare(engine: &mut Engine, args: &[Expr], name: &str, collation: Collation, ignore_case: bool) -> Result<PhpValue, String> {
+    let expected = if matches!(collation, Collation::Prefix) { 3 } else { 2 };
+    if args.len() != expected { return Err(format!("{}() expects exactly {} arguments", name, expected)); }
+    let left = engine.evaluate_expr(&args[0])?.to_string();
+    let right = engine.evaluate_expr(&args[1])?.to_string();
+    let (left, right) = (fold(&left, ignore_case), fold(&right, ignore_case));
+    let (mut left, mut right) = (left.as_bytes(), right.as_bytes());
+    let ordering = match collation {
+        Collation::Natural => natural_cmp(left, right),
+        Collation::Binary => left.cmp(right),
+        Collation::Prefix => {
+            let length = engine.evaluate_expr(&args[2])?.to_int();
+            if length < 0 {
+                return Err(engine.throw_error("ValueError", &format!("{}(): Argument #3 ($length) must be greater than or equal to 0", name)));
+            }
+            let length = length as usize;
+            left = &left[..left.len().min(length)];
+            right = &right[..right.len().min(length)];
+            left.cmp(right)
+        }
+    };
+    Ok(PhpValue::Int(ordering as i64))
+}
+
+/// PHP's natural order comparison: leading whitespace is ignored and runs of
+/// digits compare by value, ignoring leading zeros
+fn natural_cmp(left: &[u8], right: &[u8]) -> Ordering {
+    let (mut i, mut j) = (0, 0);
+    loop {
+        while left.get(i).is_some_and(u8::is_ascii_whitespace) { i += 1; }
+        while right.get(j).is_some_and(u8::is_ascii_whitespace) { j += 1; }
+        match (left.get(i), right.get(j)) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) if a.is_ascii_digit() && b.is_ascii_digit() => {
+                let (start_i, start_j) = (i, j);
+                while left.get(i).is_some_and(u8::is_ascii_digit) { i += 1; }
+                while right.get(j).is_some_and(u8::is_ascii_digit) { j += 1; }
+                let trim = |digits: &[u8]| -> usize { digits.iter().take_while(|&&d| d == b'0').count() };
+                let a = &left[start_i + trim(&left[start_i..i])..i];
+                let b = &right[start_j + trim(&right[start_j..j])..j];
+                let ordering = a.len().cmp(&b.len()).then_with(|| a.cmp(b));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(a), Some(b)) => {
+                if a != b {
+                    return a.cmp(b);
+                }
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+}
+
+/// strlen(string $string): int
+///
+/// Counts bytes, not characters, like PHP: strlen("é") is 2.
+fn strlen(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 1 { return Err("strlen() expects exactly 1 argument".into()); }
+    Ok(PhpValue::Int(engine.evaluate_expr(&args[0])?.to_string().len() as i64))
+}
+
+/// substr(string $string, int $offset, ?int $length = null): string
+///
+/// Offsets are in bytes like PHP. A slice that splits a multibyte character
+/// has the broken bytes replaced with U+FFFD, since strings here are UTF-8.
+fn substr(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() < 2 || args.len() > 3 { return Err("substr() expects 2 or 3 arguments".into()); }
+    let string = engine.evaluate_expr(&args[0])?.to_string();
+    let offset = engine.evaluate_expr(&args[1])?.to_int();
+    let length = match args.get(2) {
+        Some(arg) => match engine.evaluate_expr(arg)? {
+            PhpValue::Null => None,
+            value => Some(value.to_int()),
+        },
+        None => None,
+    };
+    let len = string.len() as i64;
+    // Negative offsets and lengths count from the end of the string
+    let start = if offset < 0 { (len + offset).max(0) } else { offset.min(len) };
+    let end = match length {
+        None => len,
+        Some(length) if length < 0 => len + length,
+        Some(length) => start.saturating_add(length).min(len),
+    };
+    if end <= start {
+        return Ok(PhpValue::String(String::new()));
+    }
+    let bytes = &string.as_bytes()[start as usize..end as usize];
+    Ok(PhpValue::String(String::from_utf8_lossy(bytes).into_owned()))
+}
+
+/// Validate an optional `$encoding` argument of an mb_ function; only UTF-8 is supported
+fn check_encoding(engine: &mut Engine, args: &[Expr], index: usize, name: &str) -> Result<(), String> {
+    let Some(arg) = args.get(index) else { return Ok(()) };
+    let encoding = engine.evaluate_expr(arg)?;
+    if !matches!(encoding, PhpValue::Null) && !encoding.to_string().eq_ignore_ascii_case("UTF-8") {
+        let message = format!("{}(): Argument #{} ($encoding) must be a valid encoding, \"{}\" given", name, index + 1, encoding.to_string());
+        return Err(engine.throw_error("ValueError", &message));
+    }
+    Ok(())
+}
+
+/// strtolower()/strtoupper(string $string): string
+/// mb_strtolower()/mb_strtoupper(string $string, ?string $encoding = null): string
+///
+/// Like PHP 8, the plain functions change only ASCII letters and leave other bytes alone;
+/// the mb_ variants apply full Unicode case mapping to UTF-8 text.
+fn change_case(engine: &mut Engine, args: &[Expr], name: &str, upper: bool, multibyte: bool) -> Result<PhpValue, String> {
+    if multibyte && (args.is_empty() || args.len() > 2) { return Err(format!("{}() expects 1 or 2 arguments", name)); }
+    if !multibyte && args.len() != 1 { return Err(format!("{}() expects exactly 1 argument", name)); }
+    let s = engine.evaluate_expr(&args[0])?.to_string();
+    if !multibyte {
+        return Ok(PhpValue::String(if upper { s.to_ascii_uppercase() } else { s.to_ascii_lowercase() }));
+    }
+    check_encoding(engine, args, 1, name)?;
+    Ok(PhpValue::String(if upper { s.to_uppercase() } else { s.to_lowercase() }))
+}
+
+/// str_split(string $string, int $length = 1): array
+/// mb_str_split(string $string, int $length = 1, ?string $encoding = null): array
+///
+/// str_split counts bytes and mb_str_split characters. As of PHP 8.2 both return an
+/// empty array for an empty string.
+fn split_chunks(engine: &mut Engine, args: &[Expr], name: &str, multibyte: bool) -> Result<PhpValue, String> {
+    let max_args = if multibyte { 3 } else { 2 };
+    if args.is_empty() || args.len() > max_args { return Err(format!("{}() expects 1 to {} arguments", name, max_args)); }
+    let string = engine.evaluate_expr(&args[0])?.to_string();
+    let length = match args.get(1) { Some(arg) => engine.evaluate_expr(arg)?.to_int(), None => 1 };
+    if length < 1 {
+        let message = format!("{}(): Argument #2 ($length) must be greater than 0", name);
+        return Err(engine.throw_error("ValueError", &message));
+    }
+    let length = length as usize;
+    if multibyte { check_encoding(engine, args, 2, name)?; }
+    let mut chunks = PhpArray::new();
+    if multibyte {
+        let chars: Vec<char> = string.chars().collect();
+        for chunk in chars.chunks(length) {
+            chunks.push(PhpValue::String(chunk.iter().collect()))?;
+        }
+    } else {
+        for chunk in string.as_bytes().chunks(length) {
+            chunks.push(PhpValue::String(String::from_utf8_lossy(chunk).into_owned()))?;
+        }
+    }
+    Ok(PhpValue::Array(chunks))
+}
+
+/// Characters trim() strips by default
+const TRIM_CHARACTERS: &str = " \t\n\r\0\x0B";
+
+/// Expand a trim() character mask, where `a..z` stands for the whole range
+fn trim_mask(mask: &str) -> Vec<char> {
+    let chars: Vec<char> = mask.chars().collect();
+    let mut expanded = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if i + 3 < chars.len() && chars[i + 1] == '.' && chars[i + 2] == '.' && chars[i] <= chars[i + 3] {
+            expanded.extend(chars[i]..=chars[i + 3]);
+            i += 4;
+        } else {
+            expanded.push(chars[i]);
+            i += 1;
+        }
+    }
+    expanded
+}
+
+/// trim()/ltrim()/rtrim(string $string, string $characters = " \n\r\t\v\0"): string
+fn trim(engine: &mut Engine, args: &[Expr], name: &str, left: bool, right: bool) -> Result<PhpValue, String> {
+    if args.is_empty() || args.len() > 2 { return Err(format!("{}() expects 1 or 2 arguments", name)); }
+    let s = engine.evaluate_expr(&args[0])?.to_string();
+    let mask = match args.get(1) {
+        Some(arg) => trim_mask(&engine.evaluate_expr(arg)?.to_string()),
+        None => TRIM_CHARACTERS.chars().collect(),
+    };
+    let strip = |c: char| mask.contains(&c);
+    let trimmed = match (left, right) {
+        (true, true) => s.trim_matches(strip),
+        (true, false) => s.trim_start_matches(strip),
+        _ => s.trim_end_matches(strip),
+    };
+    Ok(PhpValue::String(trimmed.to_string()))
+}
+
+/// str_repeat(string $string, int $times): string
+fn str_repeat(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() != 2 { return Err("str_repeat() expects exactly 2 arguments".into()); }
+    let input_val = engine.evaluate_expr(&args[0])?;
+    let times_val = engine.evaluate_expr(&args[1])?;
+    let s = input_val.to_string();
+    let times: i64 = match times_val {
+        PhpValue::Int(i) => i,
+        PhpValue::Float(f) => f as i64,
+        PhpValue::String(ref st) => st.parse::<i64>().unwrap_or(0),
+        _ => 0,
+    };
+    if times <= 0 { return Ok(PhpValue::String(String::new())); }
+    engine.check_string_length("str_repeat", s.len().saturating_mul(times as usize))?;
+    let repeated = s.repeat(times as usize);
+    Ok(PhpValue::String(repeated))
+}
+
+/// implode(string $separator, array $array): string
+fn implode(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() { return Err("implode() expects at least 1 argument".into()); }
+    let (glue, pieces_expr_index) = if args.len() == 1 { ("".to_string(), 0usize) } else { (engine.evaluate_expr(&args[0])?.to_string(), 1usize) };
+    let pieces_val = engine.evaluate_expr(&args[pieces_expr_index])?;
+    match pieces_val {
+        PhpValue::Array(arr) => {
+            let mut parts = Vec::new();
+            for (_, v) in arr.data.iter() { parts.push(v.to_string()); }
+            Ok(PhpValue::String(parts.join(&glue)))
+        }
+        other => Ok(PhpValue::String(other.to_string()))
+    }
+}
+
+/// explode(string $separator, string $string, int $limit = PHP_INT_MAX): array
+fn explode(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.len() < 2 || args.len() > 3 { return Err("explode() expects 2 or 3 arguments".into()); }
+    let separator = engine.evaluate_expr(&args[0])?.to_string();
+    let string = engine.evaluate_expr(&args[1])?.to_string();
+    let limit = match args.get(2) {
+        Some(limit) => engine.evaluate_expr(limit)?.to_int(),
+        None => i64::MAX,
+    };
+    if separator.is_empty() {
+        return Err(engine.throw_error("ValueError", "explode(): Argument #1 ($separator) cannot be empty"));
+    }
+    let pieces: Vec<&str> = if limit >= 0 {
+        // A zero limit behaves like 1; the last piece keeps the rest of the string
+        string.splitn(limit.max(1).try_into().unwrap_or(usize::MAX), separator.as_str()).collect()
+    } else {
+        // A negative limit drops that many pieces from the end
+        let mut pieces: Vec<&str> = string.split(separator.as_str()).collect();
+        pieces.truncate(pieces.len().saturating_sub(limit.unsigned_abs().try_into().unwrap_or(usize::MAX)));
+        pieces
+    };
+    let mut result = PhpArray::new();
+    for piece in pieces {
+        result.push(PhpValue::String(piece.to_string()))?;
+    }
+    Ok(PhpValue::Array(result))
+}
+
+/// parse_str(string $string, array &$result): void
+fn parse_str(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    // Expect 2 arguments: query string, target array variable (passed as variable expression in source)
+    if args.len() != 2 {
+        return Err("parse_str() expects exactly 2 arguments".into());
+    }
+    // Evaluate first argument to string
+    let query_val = engine.evaluate_expr(&args[0])?;
+    let query_str = query_val.to_string();
+    // Determine variable name from second arg expression (must be variable)
+    let target_var_name = match &args[1] {
+        Expr::Variable(name) => name.clone(),
+        _ => return Err("parse_str() second argument must be a variable".into()),
+    };
+    // Parse query string into PhpArray
+    let mut arr = PhpArray::new();
+    for pair in query_str.split('&') {
+        if pair.is_empty() { continue; }
+        let mut kv = pair.splitn(2, '=');
+        let raw_key = kv.next().unwrap_or("");
+        let raw_val = kv.next().unwrap_or("");
+        let key = percent_decode(raw_key);
+        let val_str = percent_decode(raw_val);
+        arr.insert_string(key, PhpValue::String(val_str));
+    }
+    engine.context.set_variable(&target_var_name, PhpValue::Array(arr));
+    Ok(PhpValue::Null)
+}
+
+/// preg_match(string $pattern, string $subject, array &$matches = null): int
+fn preg_match(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    // preg_match(pattern, subject, matches?)
+    if args.len() < 2 { return Err("preg_match() expects at least 2 parameters".into()); }
+    let pattern_raw = engine.evaluate_expr(&args[0])?.to_string();
+    let subject = engine.evaluate_expr(&args[1])?.to_string();
+    // Strip delimiters if pattern like /.../
+    let pattern = if pattern_raw.len() >= 2 && pattern_raw.starts_with('/') {
+        if let Some(last) = pattern_raw.rfind('/') { pattern_raw[1..last].to_string() } else { pattern_raw.clone() }
+    } else { pattern_raw.clone() };
+    match regex::Regex::new(&pattern) {
+        Ok(re) => {
+            if let Some(caps) = re.captures(&subject) {
+                // If third argument variable provided populate
+                if args.len() >= 3 {
+                    if let Expr::Variable(var_name) = &args[2] {
+                        let mut arr = PhpArray::new();
+                        for (i, cap) in caps.iter().enumerate() {
+                            if let Some(m) = cap { arr.insert_int(i as i64, PhpValue::String(m.as_str().to_string())); }
+                        }
+                        engine.context.set_variable(var_name, PhpValue::Array(arr));
+                    }
+                }
+                Ok(PhpValue::Int(1))
+            } else { Ok(PhpValue::Int(0)) }
+        }
+        Err(_) => Ok(PhpValue::Int(0))
+    }
+}
+
+/// sprintf(string $format, mixed ...$values): string
+fn sprintf(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() { return Err("sprintf() expects at least 1 argument".into()); }
+    let values = engine.evaluate_args(args)?;
+    let format = values[0].to_string();
+    Ok(PhpValue::String(format_string(engine, "sprintf", &format, &values[1..])?))
+}
+
+/// printf(string $format, mixed ...$values): int
+fn printf(engine: &mut Engine, args: &[Expr]) -> Result<PhpValue, String> {
+    if args.is_empty() { return Err("printf() expects at least 1 argument".into()); }
+    let values = engine.evaluate_args(args)?;
+    let format = values[0].to_string();
+    let out = format_string(engine, "printf", &format, &values[1..])?;
+    engine.write_output(&out);
+    Ok(PhpValue::Int(out.len() as i64))
+}
+
+/// One parsed `%[argnum$][flags][width][.precision]specifier` conversion
+struct Conversion {
+    left: bool,
+    plus: bool,
+    pad: char,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Conversion {
+    /// Pad a converted value out to the field width; zero padding goes after a leading sign
+    fn pad(&self, body: String, numeric: bool) -> String {
+        let len = body.len();
+        if len >= self.width { return body; }
+        let fill: String = std::iter::repeat_n(self.pad, self.width - len).collect();
+        if self.left {
+            body + &fill
+        } else if numeric && self.pad == '0' && (body.starts_with('-') || body.starts_with('+')) {
+            format!("{}{}{}", &body[..1], fill, &body[1..])
+        } else {
+            fill + &body
+        }
+    }
+
+    /// Prefix a '+' on non-negative numbers when the '+' flag was given
+    fn sign(&self, body: String) -> String {
+        if self.plus && !body.starts_with('-') { format!("+{}", body) } else { body }
+    }
+}
+
+/// Format a float the way PHP's %e does: no exponent padding, explicit exponent sign
+fn format_exponent(value: f64, precision: usize, upper: bool) -> String {
+    let formatted = format!("{:.*e}", precision, value);
+    let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    let exponent = if exponent.starts_with('-') { exponent.to_string() } else { format!("+{}", exponent) };
+    format!("{}{}{}", mantissa, if upper { 'E' } else { 'e' }, exponent)
+}
+
+/// Format a float the way PHP's %g does: the shorter of %e and %f, without trailing zeros
+fn format_general(value: f64, precision: usize, upper: bool) -> String {
+    let precision = precision.max(1);
+    if value == 0.0 { return "0".into(); }
+    let exponent = value.abs().log10().floor() as i64;
+    let strip = |s: String| if s.contains('.') { s.trim_end_matches('0').trim_end_matches('.').to_string() } else { s };
+    if exponent < -4 || exponent >= precision as i64 {
+        let formatted = format_exponent(value, precision - 1, upper);
+        let (mantissa, exponent) = formatted.split_at(formatted.find(['e', 'E']).unwrap_or(formatted.len()));
+        format!("{}{}", strip(mantissa.to_string()), exponent)
+    } else {
+        strip(format!("{:.*}", (precision as i64 - 1 - exponent).max(0) as usize, value))
+    }
+}
+
+/// Largest width, precision or argument number PHP accepts in a conversion
+const MAX_FORMAT_NUMBER: usize = i32::MAX as usize;
+
+/// Most digits PHP prints after the decimal point of a float conversion
+const MAX_FLOAT_PRECISION: usize = 53;
+
+/// Expand a printf-style format string against its values; shared by sprintf() and printf()
+fn format_string(engine: &mut Engine, function: &str, format: &str, values: &[PhpValue]) -> Result<String, String> {
+    let chars: Vec<char> = format.chars().collect();
+    let mut out = String::with_capacity(format.len());
+    let mut next_arg = 0usize;
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '%' {
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        i += 1;
+        if chars.get(i) == Some(&'%') {
+            out.push('%');
+            i += 1;
+            continue;
+        }
+        // Optional argnum$ prefix: digits followed by '$'
+        let mut argnum = None;
+        let digits = chars[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 && chars.get(i + digits) == Some(&'$') {
+            let n: usize = chars[i..i + digits].iter().collect::<String>().parse().unwrap_or(0);
+            if n == 0 || n >= MAX_FORMAT_NUMBER {
+                return Err(engine.throw_error("ValueError", "Argument number specifier must be greater than zero and less than 2147483647"));
+            }
+            argnum = Some(n - 1);
+            i += digits + 1;
+        }
+        let mut spec = Conversion { left: false, plus: false, pad: ' ', width: 0, precision: None };
+        loop {
+            match chars.get(i) {
+                Some('-') => spec.left = true,
+                Some('+') => spec.plus = true,
+                Some('0') => spec.pad = '0',
+                Some(' ') => spec.pad = ' ',
+                Some('\'') => {
+                    i += 1;
+                    match chars.get(i) {
+                        Some(&c) => spec.pad = c,
+                        None => return Err(engine.throw_error("ValueError", "Missing padding character")),
+                    }
+                }
+                _ => break,
+            }
+            i += 1;
+        }
+        while let Some(d) = chars.get(i).and_then(|c| c.to_digit(10)) {
+            spec.width = spec.width.saturating_mul(10).saturating_add(d as usize);
+            i += 1;
+        }
+        if spec.width >= MAX_FORMAT_NUMBER {
+            return Err(engine.throw_error("ValueError", "Width must be greater than zero and less than 2147483647"));
+        }
+        if chars.get(i) == Some(&'.') {
+            i += 1;
+            let mut precision: usize = 0;
+            while let Some(d) = chars.get(i).and_then(|c| c.to_digit(10)) {
+                precision = precision.saturating_mul(10).saturating_add(d as usize);
+                i += 1;
+            }
+            if precision >= MAX_FORMAT_NUMBER {
+                return Err(engine.throw_error("ValueError", "Precision must be greater than zero and less than 2147483647"));
+            }
+            spec.precision = Some(precision);
+        }
+        let Some(&conversion) = chars.get(i) else {
+            return Err(engine.throw_error("ValueError", "Missing format specifier at end of string"));
+        };
+        i += 1;
+        if !"bcdeEfFgGosuxX".contains(conversion) {
+            return Err(engine.throw_error("ValueError", &format!("Unknown format specifier \"{}\"", conversion)));
+        }
+        let index = argnum.unwrap_or_else(|| { next_arg += 1; next_arg - 1 });
+        let Some(value) = values.get(index) else {
+            let message = format!("{} arguments are required, {} given", index + 2, values.len() + 1);
+            return Err(engine.throw_error("ArgumentCountError", &message));
+        };
+        // The field width alone can ask for a huge string
+        engine.check_string_length(function, out.len().saturating_add(spec.width))?;
+        let formatted = match conversion {
+            's' => {
+                let mut text = value.to_string();
+                if let Some(precision) = spec.precision {
+                    // Precision caps the byte length, backing off to a character boundary
+                    let mut end = precision.min(text.len());
+                    while !text.is_char_boundary(end) { end -= 1; }
+                    text.truncate(end);
+                }
+                spec.pad(text, false)
+            }
+            'd' => spec.pad(spec.sign(value.to_int().to_string()), true),
+            'u' => spec.pad((value.to_int() as u64).to_string(), true),
+            'c' => char::from(value.to_int() as u8).to_string(),
+            'b' => spec.pad(format!("{:b}", value.to_int() as u64), true),
+            'o' => spec.pad(format!("{:o}", value.to_int() as u64), true),
+            'x' => spec.pad(format!("{:x}", value.to_int() as u64), true),
+            'X' => spec.pad(format!("{:X}", value.to_int() as u64), true),
+            _ => {
+                let number = value.to_float();
+                let mut precision = spec.precision.unwrap_or(6);
+                if precision > MAX_FLOAT_PRECISION {
+                    let message = format!("Requested precision of {} digits was truncated to PHP maximum of {} digits", precision, MAX_FLOAT_PRECISION);
+                    engine.raise_error(ErrorLevel::Notice, message);
+                    precision = MAX_FLOAT_PRECISION;
+                }
+                let body = if number.is_nan() {
+                    "NaN".to_string()
+                } else if number.is_infinite() {
+                    if number < 0.0 { "-Inf".to_string() } else { "Inf".to_string() }
+                } else {
+                    match conversion {
+                        'e' | 'E' => format_exponent(number, precision, conversion == 'E'),
+                        'g' | 'G' => format_general(number, precision, conversion == 'G'),
+                        _ => format!("{:.*}", precision, number),
+                    }
+                };
+                spec.pad(spec.sign(body), true)
+            }
+        };
+        out.push_str(&formatted);
+    }
+    Ok(out)
+}
+
+/// Simple percent-decoding helper (handles + -> space and %XX hex sequences)
+fn percent_decode(input: &str) -> String {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut chars = input.as_bytes().iter().cloned().peekable();
+    while let Some(b) = chars.next() {
+        match b {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let h1 = chars.next();
+                let h2 = chars.next();
+                if let (Some(c1), Some(c2)) = (h1, h2) {
+                    let hex = [c1, c2];
+                    if let Ok(s) = std::str::from_utf8(&hex) {
+                        if let Ok(v) = u8::from_str_radix(s, 16) { bytes.push(v); continue; }
+                    }
+                    // Fallback: push literal
+                    bytes.push(b'%'); bytes.push(c1); bytes.push(c2);
+                } else {
+                    bytes.push(b'%');
+                    if let Some(c1) = h1 { bytes.push(c1); }
+                    if let Some(c2) = h2 { bytes.push(c2); }
+                }
+            }
+            _ => bytes.push(b),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
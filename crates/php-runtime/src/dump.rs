@@ -0,0 +1,114 @@
+//! Debug representations of PHP values (print_r, var_dump)
+//!
+//! These only build strings; the engine decides whether the text is written
+//! to the output (honouring output buffers) or returned to the caller.
+
+use php_types::{php_float_repr, PhpArrayKey, PhpObjectRef, PhpValue};
+use std::rc::Rc;
+
+/// Spaces added per nesting level by print_r
+const PRINT_R_INDENT: usize = 4;
+
+/// Render a value the way `print_r` does
+pub fn print_r(value: &PhpValue) -> String {
+    let mut out = String::new();
+    let mut seen = Vec::new();
+    print_r_into(&mut out, value, 0, &mut seen);
+    out
+}
+
+fn print_r_into(out: &mut String, value: &PhpValue, indent: usize, seen: &mut Vec<*const ()>) {
+    match value {
+        PhpValue::Array(arr) => {
+            out.push_str("Array\n");
+            let entries: Vec<(String, &PhpValue)> = arr.data.iter().map(|(k, v)| (k.to_string(), v)).collect();
+            print_r_entries(out, &entries, indent, seen);
+        }
+        PhpValue::Object(handle) => {
+            let object = handle.borrow();
+            out.push_str(&object.class_name);
+            out.push_str(" Object\n");
+            let ptr = Rc::as_ptr(handle) as *const ();
+            if seen.contains(&ptr) {
+                out.push_str(" *RECURSION*");
+                return;
+            }
+            seen.push(ptr);
+            let entries: Vec<(String, &PhpValue)> = object.properties.iter().map(|(k, v)| (k.clone(), v)).collect();
+            print_r_entries(out, &entries, indent, seen);
+            seen.pop();
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+fn print_r_entries(out: &mut String, entries: &[(String, &PhpValue)], indent: usize, seen: &mut Vec<*const ()>) {
+    out.push_str(&" ".repeat(indent));
+    out.push_str("(\n");
+    for (key, value) in entries {
+        out.push_str(&" ".repeat(indent + PRINT_R_INDENT));
+        out.push_str(&format!("[{}] => ", key));
+        print_r_into(out, value, indent + PRINT_R_INDENT * 2, seen);
+        out.push('\n');
+    }
+    out.push_str(&" ".repeat(indent));
+    out.push_str(")\n");
+}
+
+/// Render a value the way `var_dump` does, including the trailing newline
+pub fn var_dump(value: &PhpValue) -> String {
+    let mut out = String::new();
+    let mut seen = Vec::new();
+    var_dump_into(&mut out, value, 1, &mut seen);
+    out
+}
+
+fn var_dump_into(out: &mut String, value: &PhpValue, level: usize, seen: &mut Vec<*const ()>) {
+    if level > 1 {
+        out.push_str(&" ".repeat(level - 1));
+    }
+    match value {
+        PhpValue::Null => out.push_str("NULL\n"),
+        PhpValue::Bool(b) => out.push_str(&format!("bool({})\n", b)),
+        PhpValue::Int(i) => out.push_str(&format!("int({})\n", i)),
+        PhpValue::Float(f) => out.push_str(&format!("float({})\n", php_float_repr(*f))),
+        PhpValue::String(s) => out.push_str(&format!("string({}) \"{}\"\n", s.len(), s)),
+        PhpValue::Array(arr) => {
+            out.push_str(&format!("array({}) {{\n", arr.len()));
+            for (key, item) in arr.data.iter() {
+                match key {
+                    PhpArrayKey::Int(i) => out.push_str(&format!("{}[{}]=>\n", " ".repeat(level + 1), i)),
+                    PhpArrayKey::String(s) => out.push_str(&format!("{}[\"{}\"]=>\n", " ".repeat(level + 1), s)),
+                }
+                var_dump_into(out, item, level + 2, seen);
+            }
+            close_brace(out, level);
+        }
+        PhpValue::Object(handle) => var_dump_object(out, handle, level, seen),
+        PhpValue::Resource(r) => out.push_str(&format!("resource({}) of type (stream)\n", r)),
+    }
+}
+
+fn var_dump_object(out: &mut String, handle: &PhpObjectRef, level: usize, seen: &mut Vec<*const ()>) {
+    let ptr = Rc::as_ptr(handle) as *const ();
+    if seen.contains(&ptr) {
+        out.push_str("*RECURSION*\n");
+        return;
+    }
+    seen.push(ptr);
+    let object = handle.borrow();
+    out.push_str(&format!("object({})#{} ({}) {{\n", object.class_name, object.id, object.properties.len()));
+    for (name, item) in object.properties.iter() {
+        out.push_str(&format!("{}[\"{}\"]=>\n", " ".repeat(level + 1), name));
+        var_dump_into(out, item, level + 2, seen);
+    }
+    close_brace(out, level);
+    seen.pop();
+}
+
+fn close_brace(out: &mut String, level: usize) {
+    if level > 1 {
+        out.push_str(&" ".repeat(level - 1));
+    }
+    out.push_str("}\n");
+}
@@ -1,50 +1,275 @@
 //! PHP Runtime Engine
 
-use php_types::{PhpValue, PhpArrayKey, PhpArray};
-use php_parser::ast::{Stmt, Expr, DestructTarget};
-use std::collections::HashMap;
+use php_types::{PhpValue, PhpArrayKey, PhpArray, PhpObject, PhpObjectRef};
+use php_parser::ast::{Stmt, Expr, BinaryOp, CatchClause, DestructTarget, ClassMember, ClassName, MemberName, Param};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use crate::builtins::NativeFunction;
+use crate::interner::StringInterner;
+use crate::generator::{stmt_yields, Generator, YieldPoint};
 
 /// PHP execution context with variable scoping
 #[derive(Debug)]
 pub struct ExecutionContext {
-    /// Variable storage
-    variables: HashMap<String, PhpValue>,
+    /// Variable slots of the current scope, indexed by interned name id
+    variables: Vec<Option<PhpValue>>,
+    /// Interned variable names shared by all scopes
+    names: StringInterner,
+    /// Ids of recently used names by the address of the name's text; see `name_id`
+    id_cache: [Cell<(usize, u32)>; ID_CACHE_SLOTS],
+    /// Identifier of the current scope
+    frame_id: u64,
+    /// Identifier given to the most recently entered scope
+    last_frame_id: u64,
+    /// Suspended caller scopes, innermost last
+    saved_frames: Vec<SavedScope>,
     /// Constant storage
     constants: HashMap<String, PhpValue>,
     /// Function definitions
     functions: HashMap<String, Function>,
+    /// Class definitions
+    classes: HashMap<String, Class>,
     /// Output buffer
     output: String,
 }
 
+/// Number of entries in the variable id cache
+const ID_CACHE_SLOTS: usize = 256;
+
+/// Variables of a scope that is not running, with the scope's identifier
+#[derive(Debug)]
+pub(crate) struct SavedScope {
+    id: u64,
+    variables: Vec<Option<PhpValue>>,
+}
+
 /// Function definition
 #[derive(Debug, Clone)]
 pub struct Function {
     /// Function parameters
-    pub params: Vec<String>,
+    pub params: Vec<Param>,
     /// Function body
     pub body: Stmt,
+    /// Variables captured by a closure, bound in every call before the parameters
+    pub captured: Vec<(String, PhpValue)>,
+    /// Variables captured by a closure with `use (&$var)`
+    pub references: Vec<CapturedReference>,
+    /// Whether the body contains `yield`, so calls return a Generator instead of running it
+    pub is_generator: bool,
+}
+
+/// A variable a closure captured by reference. Calls read it from the defining scope
+/// and write it back on return; once that scope has ended, the closure keeps its own copy.
+#[derive(Debug, Clone)]
+pub struct CapturedReference {
+    /// Variable name
+    pub name: String,
+    /// Identifier of the scope the closure was created in
+    pub frame: u64,
+    /// Value shared by all calls after the defining scope has ended
+    pub value: Rc<RefCell<PhpValue>>,
+}
+
+/// Variables visible in every scope without a `global` declaration
+const SUPERGLOBALS: &[&str] = &["_GET", "_POST", "_COOKIE", "_FILES", "_SERVER", "_ENV", "_REQUEST", "_SESSION"];
+
+/// Built-in classes and their parents; Exception and Error are the roots of the throwables
+const BUILTIN_CLASSES: &[(&str, Option<&str>)] = &[
+    ("Exception", None),
+    ("Error", None),
+    ("ErrorException", Some("Exception")),
+    ("RuntimeException", Some("Exception")),
+    ("LogicException", Some("Exception")),
+    ("JsonException", Some("Exception")),
+    ("InvalidArgumentException", Some("LogicException")),
+    ("DomainException", Some("LogicException")),
+    ("LengthException", Some("LogicException")),
+    ("OutOfRangeException", Some("LogicException")),
+    ("BadFunctionCallException", Some("LogicException")),
+    ("BadMethodCallException", Some("BadFunctionCallException")),
+    ("OutOfBoundsException", Some("RuntimeException")),
+    ("RangeException", Some("RuntimeException")),
+    ("OverflowException", Some("RuntimeException")),
+    ("UnderflowException", Some("RuntimeException")),
+    ("UnexpectedValueException", Some("RuntimeException")),
+    ("TypeError", Some("Error")),
+    ("ValueError", Some("Error")),
+    ("ArithmeticError", Some("Error")),
+    ("DivisionByZeroError", Some("ArithmeticError")),
+    ("ArgumentCountError", Some("TypeError")),
+    ("CompileError", Some("Error")),
+    ("ParseError", Some("CompileError")),
+    ("DateTime", None),
+];
+
+/// Class definition
+#[derive(Debug, Clone)]
+pub struct Class {
+    /// Class name
+    pub name: String,
+    /// Parent class name
+    pub parent: Option<String>,
+    /// Interfaces the class declares it implements
+    pub interfaces: Vec<String>,
+    /// Instance properties with their default value expressions, in declaration order
+    pub properties: Vec<(String, Option<Expr>)>,
+    /// Readonly instance properties; they start uninitialized and may be assigned once
+    pub readonly_properties: HashSet<String>,
+    /// Static property values
+    pub static_properties: HashMap<String, PhpValue>,
+    /// Class constants
+    pub constants: HashMap<String, PhpValue>,
+    /// Methods by name
+    pub methods: HashMap<String, Method>,
+}
+
+/// Method definition
+#[derive(Debug, Clone)]
+pub struct Method {
+    /// Method body and parameters
+    pub function: Function,
+    /// Declared static
+    pub is_static: bool,
+}
+
+impl Default for ExecutionContext {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ExecutionContext {
     /// Create new execution context
     pub fn new() -> Self {
         Self {
-            variables: HashMap::new(),
+            variables: Vec::new(),
+            names: StringInterner::new(),
+            id_cache: std::array::from_fn(|_| Cell::new((0, 0))),
+            frame_id: 0,
+            last_frame_id: 0,
+            saved_frames: Vec::new(),
             constants: HashMap::new(),
             functions: HashMap::new(),
+            classes: HashMap::new(),
             output: String::new(),
         }
     }
 
+    /// Id of an interned name. Names mostly come from the AST, whose strings stay put while it
+    /// runs, so ids are cached by the address of the text: a hit is one comparison of the name
+    /// against the interned one instead of hashing it. The comparison catches reused addresses.
+    fn name_id(&self, name: &str) -> Option<u32> {
+        let address = name.as_ptr() as usize;
+        let slot = &self.id_cache[(address >> 3) % ID_CACHE_SLOTS];
+        let (cached_address, id) = slot.get();
+        if cached_address == address && self.names.resolve(id) == Some(name) {
+            return Some(id);
+        }
+        let id = self.names.lookup(name)?;
+        slot.set((address, id));
+        Some(id)
+    }
+
+    /// Id of a name, interning it on first sight
+    fn intern_name(&mut self, name: &str) -> u32 {
+        match self.name_id(name) {
+            Some(id) => id,
+            None => self.names.intern(name),
+        }
+    }
+
     /// Get variable value
     pub fn get_variable(&self, name: &str) -> Option<&PhpValue> {
-        self.variables.get(name)
+        let id = self.name_id(name)?;
+        self.variables.get(id as usize)?.as_ref()
+    }
+
+    /// Get mutable access to a variable's value
+    pub fn get_variable_mut(&mut self, name: &str) -> Option<&mut PhpValue> {
+        let id = self.name_id(name)?;
+        self.variables.get_mut(id as usize)?.as_mut()
     }
 
     /// Set variable value
-    pub fn set_variable(&mut self, name: String, value: PhpValue) {
-        self.variables.insert(name, value);
+    pub fn set_variable(&mut self, name: &str, value: PhpValue) {
+        let id = self.intern_name(name) as usize;
+        if id >= self.variables.len() {
+            self.variables.resize_with(id + 1, || None);
+        }
+        self.variables[id] = Some(value);
+    }
+
+    /// Variables defined in the current scope, in no particular order
+    pub fn defined_variables(&self) -> impl Iterator<Item = (&str, &PhpValue)> {
+        self.variables.iter().enumerate().filter_map(|(id, slot)| {
+            Some((self.names.resolve(id as u32)?, slot.as_ref()?))
+        })
+    }
+
+    /// Remove a variable from the current scope, returning its value
+    pub fn remove_variable(&mut self, name: &str) -> Option<PhpValue> {
+        let id = self.name_id(name)?;
+        self.variables.get_mut(id as usize)?.take()
+    }
+
+    /// Identifier of the current scope
+    pub fn frame_id(&self) -> u64 {
+        self.frame_id
+    }
+
+    /// Suspend the current scope and start an empty one
+    pub fn enter_frame(&mut self) {
+        self.last_frame_id += 1;
+        self.resume_frame(SavedScope { id: self.last_frame_id, variables: Vec::new() });
+    }
+
+    /// Discard the current scope and resume the caller's
+    pub fn leave_frame(&mut self) {
+        self.suspend_frame();
+    }
+
+    /// Suspend the current scope and continue one that `suspend_frame` returned
+    pub(crate) fn resume_frame(&mut self, scope: SavedScope) {
+        let variables = std::mem::replace(&mut self.variables, scope.variables);
+        self.saved_frames.push(SavedScope { id: self.frame_id, variables });
+        self.frame_id = scope.id;
+    }
+
+    /// Leave the current scope for the caller's, returning it so it can be resumed later;
+    /// None in the outermost scope
+    pub(crate) fn suspend_frame(&mut self) -> Option<SavedScope> {
+        let caller = self.saved_frames.pop()?;
+        let variables = std::mem::replace(&mut self.variables, caller.variables);
+        let id = std::mem::replace(&mut self.frame_id, caller.id);
+        Some(SavedScope { id, variables })
+    }
+
+    /// Variables of the scope `frame`, or None when it has ended
+    fn frame_mut(&mut self, frame: u64) -> Option<&mut Vec<Option<PhpValue>>> {
+        if frame == self.frame_id {
+            return Some(&mut self.variables);
+        }
+        self.saved_frames.iter_mut().rev().find(|scope| scope.id == frame).map(|scope| &mut scope.variables)
+    }
+
+    /// Value of a variable in the scope `frame`: None when the scope has ended,
+    /// Some(Null) when the variable is unset there
+    pub fn frame_variable(&mut self, frame: u64, name: &str) -> Option<PhpValue> {
+        let id = self.name_id(name);
+        let variables = self.frame_mut(frame)?;
+        Some(id.and_then(|id| variables.get(id as usize).cloned().flatten()).unwrap_or(PhpValue::Null))
+    }
+
+    /// Set a variable in the scope `frame`, if it has not ended
+    pub fn set_frame_variable(&mut self, frame: u64, name: &str, value: PhpValue) {
+        let id = self.intern_name(name) as usize;
+        if let Some(variables) = self.frame_mut(frame) {
+            if id >= variables.len() {
+                variables.resize_with(id + 1, || None);
+            }
+            variables[id] = Some(value);
+        }
     }
 
     /// Get constant value
@@ -57,6 +282,16 @@ impl ExecutionContext {
         self.constants.insert(name, value);
     }
 
+    /// All defined constants, in no particular order
+    pub fn constants(&self) -> impl Iterator<Item = (&str, &PhpValue)> {
+        self.constants.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Names of the script's own functions, excluding closures
+    pub fn user_function_names(&self) -> impl Iterator<Item = &str> {
+        self.functions.keys().map(String::as_str).filter(|name| !name.starts_with("__closure_"))
+    }
+
     /// Get output
     pub fn get_output(&self) -> &str {
         &self.output
@@ -71,43 +306,234 @@ impl ExecutionContext {
 /// PHP Runtime Engine
 pub struct Engine {
     /// Current execution context
-    context: ExecutionContext,
+    pub(crate) context: ExecutionContext,
     /// Persistent storage for static variables per function
     static_storage: std::collections::HashMap<String, std::collections::HashMap<String, PhpValue>>,
     /// Stack tracking static vars declared in current function frame
-    static_var_stack: Vec<(String, Vec<String>)>,
+    pub(crate) static_var_stack: Vec<(String, Vec<String>)>,
     /// Current function name if inside call
-    current_function: Option<String>,
+    pub(crate) current_function: Option<String>,
     /// Output buffering stack (top-of-stack is active buffer)
-    output_buffers: Vec<String>,
+    pub(crate) output_buffers: Vec<String>,
+    /// Handle number given to the most recently created object
+    last_object_id: u64,
+    /// Exception propagating through the `Err` channel, with the error message it was raised as
+    pub(crate) thrown: Option<(PhpValue, String)>,
+    /// Fatal error propagating through the `Err` channel; catch and finally blocks let it pass
+    fatal: Option<String>,
+    /// Native functions by name, looked up before user-defined functions
+    builtins: HashMap<String, NativeFunction>,
+    /// Yields passed by the generator body statements being evaluated, innermost last
+    pub(crate) yield_points: Vec<YieldPoint>,
+    /// Generators by Generator object handle number
+    pub(crate) generators: HashMap<u64, Generator>,
+    /// Size limits enforced by built-ins that build arrays or strings
+    limits: Limits,
+    /// Warnings, notices and deprecations raised so far, in order
+    raised_errors: Vec<RaisedError>,
+    /// Number of eval() calls currently executing
+    eval_depth: usize,
+    /// Timezone used by date functions, set with date_default_timezone_set()
+    pub(crate) timezone: crate::builtins::datetime::TimeZone,
+}
+
+/// Severity of a non-fatal error; the script keeps running after it is raised
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorLevel {
+    /// E_WARNING
+    Warning,
+    /// E_NOTICE
+    Notice,
+    /// E_DEPRECATED
+    Deprecated,
+}
+
+impl ErrorLevel {
+    /// Value of the matching `E_*` constant
+    pub fn code(self) -> i64 {
+        match self {
+            ErrorLevel::Warning => 2,
+            ErrorLevel::Notice => 8,
+            ErrorLevel::Deprecated => 8192,
+        }
+    }
+
+    /// Label PHP prints before the message, e.g. "Deprecated: ..."
+    pub fn label(self) -> &'static str {
+        match self {
+            ErrorLevel::Warning => "Warning",
+            ErrorLevel::Notice => "Notice",
+            ErrorLevel::Deprecated => "Deprecated",
+        }
+    }
+}
+
+/// A warning, notice or deprecation raised while a script ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaisedError {
+    /// Severity
+    pub level: ErrorLevel,
+    /// Message without the level prefix
+    pub message: String,
+}
+
+/// Upper bounds on the arrays and strings a single built-in call may build, so a script
+/// like `array_fill(0, 1e9, 1)` fails with an error instead of exhausting host memory,
+/// and on how deeply eval() may nest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum number of elements in an array built by one call
+    pub max_array_elements: usize,
+    /// Maximum length in bytes of a string built by one call
+    pub max_string_length: usize,
+    /// Maximum number of eval() calls active at once
+    pub max_eval_depth: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self { max_array_elements: 10_000_000, max_string_length: 128 * 1024 * 1024, max_eval_depth: 32 }
+    }
 }
 
 /// Internal control flow signal for break/continue/return
-enum ExecSignal {
+pub(crate) enum ExecSignal {
     None,
     Break,
     Continue,
     Return(Option<PhpValue>),
 }
 
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Engine {
     /// Create new engine
     pub fn new() -> Self {
         let mut ctx = ExecutionContext::new();
         // Initialize superglobals minimal
-        ctx.set_variable("_GET".to_string(), PhpValue::Array(PhpArray::new()));
-        // Initialize commonly used JSON / filter constants (simplified integer values)
-        ctx.set_constant("JSON_UNESCAPED_SLASHES".to_string(), PhpValue::Int(1));
-        ctx.set_constant("JSON_UNESCAPED_UNICODE".to_string(), PhpValue::Int(2));
-        ctx.set_constant("JSON_THROW_ON_ERROR".to_string(), PhpValue::Int(4));
+        ctx.set_variable("_GET", PhpValue::Array(PhpArray::new()));
+        // Initialize commonly used JSON / filter constants
+        ctx.set_constant("JSON_OBJECT_AS_ARRAY".to_string(), PhpValue::Int(1));
+        ctx.set_constant("JSON_FORCE_OBJECT".to_string(), PhpValue::Int(16));
+        ctx.set_constant("JSON_UNESCAPED_SLASHES".to_string(), PhpValue::Int(64));
+        ctx.set_constant("JSON_PRETTY_PRINT".to_string(), PhpValue::Int(128));
+        ctx.set_constant("JSON_UNESCAPED_UNICODE".to_string(), PhpValue::Int(256));
+        ctx.set_constant("JSON_PRESERVE_ZERO_FRACTION".to_string(), PhpValue::Int(1024));
+        ctx.set_constant("JSON_THROW_ON_ERROR".to_string(), PhpValue::Int(4194304));
         ctx.set_constant("FILTER_VALIDATE_INT".to_string(), PhpValue::Int(257));
-        Self { context: ctx, static_storage: std::collections::HashMap::new(), static_var_stack: Vec::new(), current_function: None, output_buffers: Vec::new() }
+        ctx.set_constant("PHP_EOL".to_string(), PhpValue::String("\n".to_string()));
+        ctx.set_constant("PHP_INT_MAX".to_string(), PhpValue::Int(i64::MAX));
+        ctx.set_constant("PHP_INT_MIN".to_string(), PhpValue::Int(i64::MIN));
+        ctx.set_constant("PHP_INT_SIZE".to_string(), PhpValue::Int(8));
+        // The CLI's standard streams are the only resources; their ids match PHP's
+        ctx.set_constant("STDIN".to_string(), PhpValue::Resource(1));
+        ctx.set_constant("STDOUT".to_string(), PhpValue::Resource(2));
+        ctx.set_constant("STDERR".to_string(), PhpValue::Resource(3));
+        ctx.set_constant("COUNT_NORMAL".to_string(), PhpValue::Int(0));
+        ctx.set_constant("COUNT_RECURSIVE".to_string(), PhpValue::Int(1));
+        ctx.set_constant("SORT_REGULAR".to_string(), PhpValue::Int(0));
+        ctx.set_constant("SORT_NUMERIC".to_string(), PhpValue::Int(1));
+        ctx.set_constant("SORT_STRING".to_string(), PhpValue::Int(2));
+        ctx.set_constant("SORT_DESC".to_string(), PhpValue::Int(3));
+        ctx.set_constant("SORT_ASC".to_string(), PhpValue::Int(4));
+        ctx.set_constant("ARRAY_FILTER_USE_BOTH".to_string(), PhpValue::Int(1));
+        ctx.set_constant("ARRAY_FILTER_USE_KEY".to_string(), PhpValue::Int(2));
+        for level in [ErrorLevel::Warning, ErrorLevel::Notice, ErrorLevel::Deprecated] {
+            ctx.set_constant(format!("E_{}", level.label().to_ascii_uppercase()), PhpValue::Int(level.code()));
+        }
+        for (name, parent) in BUILTIN_CLASSES {
+            ctx.classes.insert(name.to_string(), Class {
+                name: name.to_string(),
+                parent: parent.map(|p| p.to_string()),
+                interfaces: Vec::new(),
+                properties: Vec::new(),
+                readonly_properties: HashSet::new(),
+                static_properties: HashMap::new(),
+                constants: HashMap::new(),
+                methods: HashMap::new(),
+            });
+        }
+        let mut engine = Self { context: ctx, static_storage: std::collections::HashMap::new(), static_var_stack: Vec::new(), current_function: None, output_buffers: Vec::new(), last_object_id: 0, thrown: None, fatal: None, builtins: HashMap::new(), yield_points: Vec::new(), generators: HashMap::new(), limits: Limits::default(), raised_errors: Vec::new(), eval_depth: 0, timezone: crate::builtins::datetime::TimeZone::utc() };
+        crate::builtins::register_all(&mut engine);
+        engine
+    }
+
+    /// Register a native function callable from PHP, replacing any built-in of the same name.
+    /// The function receives the unevaluated argument expressions; use `evaluate_expr` to get their values.
+    pub fn register_function<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&mut Engine, &[Expr]) -> Result<PhpValue, String> + 'static,
+    {
+        self.builtins.insert(name.to_string(), std::rc::Rc::new(function));
+    }
+
+    /// Warnings, notices and deprecations raised so far, in the order they happened
+    pub fn raised_errors(&self) -> &[RaisedError] {
+        &self.raised_errors
+    }
+
+    /// Record a non-fatal error; execution continues
+    pub(crate) fn raise_error(&mut self, level: ErrorLevel, message: impl Into<String>) {
+        self.raised_errors.push(RaisedError { level, message: message.into() });
+    }
+
+    /// Size limits enforced by array- and string-building built-ins
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Replace the size limits enforced by array- and string-building built-ins
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Fail before `function` builds an array of `elements` entries beyond the limit
+    pub(crate) fn check_array_size(&mut self, function: &str, elements: usize) -> Result<(), String> {
+        if elements > self.limits.max_array_elements {
+            return Err(self.fatal_error(format!("{}(): Memory limit exceeded: array of {} elements requested, at most {} allowed", function, elements, self.limits.max_array_elements)));
+        }
+        Ok(())
+    }
+
+    /// Fail before `function` builds a string of `length` bytes beyond the limit
+    pub(crate) fn check_string_length(&mut self, function: &str, length: usize) -> Result<(), String> {
+        if length > self.limits.max_string_length {
+            return Err(self.fatal_error(format!("{}(): Memory limit exceeded: string of {} bytes requested, at most {} allowed", function, length, self.limits.max_string_length)));
+        }
+        Ok(())
+    }
+
+    /// Names of all native functions, in no particular order
+    pub fn native_function_names(&self) -> impl Iterator<Item = &str> {
+        self.builtins.keys().map(String::as_str)
+    }
+
+    /// Expose a host (Rust) function to scripts. Arguments are evaluated before the call;
+    /// `php_types::php_arg` and `PhpConvert` convert between them and Rust types.
+    pub fn register_host_function<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[PhpValue]) -> Result<PhpValue, String> + 'static,
+    {
+        self.register_function(name, move |engine, args| {
+            let values = engine.evaluate_args(args)?;
+            function(&values)
+        });
     }
 
     /// Execute a statement
     pub fn execute_stmt(&mut self, stmt: &Stmt) -> Result<(), String> {
-        let signal = self.exec(stmt)?;
-        match signal {
+        self.thrown = None;
+        self.fatal = None;
+        let signal = self.exec(stmt);
+        // Buffers still open at the end of the script are flushed, as PHP does on shutdown
+        while let Some(buffer) = self.output_buffers.pop() {
+            self.write_output(&buffer);
+        }
+        match signal? {
             ExecSignal::None => Ok(()),
             ExecSignal::Break | ExecSignal::Continue => Ok(()),
             ExecSignal::Return(val_opt) => {
@@ -117,7 +543,7 @@ impl Engine {
         }
     }
 
-    fn exec(&mut self, stmt: &Stmt) -> Result<ExecSignal, String> {
+    pub(crate) fn exec(&mut self, stmt: &Stmt) -> Result<ExecSignal, String> {
         match stmt {
             Stmt::Expression(expr) => {
                 self.evaluate_expr(expr)?;
@@ -133,16 +559,34 @@ impl Engine {
                 self.write_output(&value.to_string());
                 Ok(ExecSignal::None)
             }
+            Stmt::InlineHtml(html) => {
+                self.write_output(html);
+                Ok(ExecSignal::None)
+            }
+            // `$s .= expr` appends in place instead of copying $s into a new string
+            Stmt::Assignment { variable, value: Expr::Binary { left, op: BinaryOp::Concatenate, right } }
+                if matches!(&**left, Expr::Variable(name) if name == variable) =>
+            {
+                let suffix = self.evaluate_expr(right)?.to_string();
+                match self.context.get_variable_mut(variable) {
+                    Some(PhpValue::String(s)) => s.push_str(&suffix),
+                    _ => {
+                        let current = self.context.get_variable(variable).cloned().unwrap_or(PhpValue::Null);
+                        self.context.set_variable(variable, PhpValue::String(current.to_string() + &suffix));
+                    }
+                }
+                Ok(ExecSignal::None)
+            }
             Stmt::Assignment { variable, value } => {
                 let val = self.evaluate_expr(value)?;
-                self.context.set_variable(variable.clone(), val);
+                self.context.set_variable(variable, val);
                 Ok(ExecSignal::None)
             }
             Stmt::NullCoalesceAssign { variable, value } => {
                 let current = self.context.get_variable(variable).cloned().unwrap_or(PhpValue::Null);
                 if current.is_null() {
                     let new_val = self.evaluate_expr(value)?;
-                    self.context.set_variable(variable.clone(), new_val);
+                    self.context.set_variable(variable, new_val);
                 }
                 Ok(ExecSignal::None)
             }
@@ -191,6 +635,17 @@ impl Engine {
                 }
                 Ok(ExecSignal::None)
             }
+            Stmt::DoWhile { body, condition } => {
+                loop {
+                    match self.exec(body)? {
+                        ExecSignal::None | ExecSignal::Continue => {}
+                        ExecSignal::Break => break,
+                        ExecSignal::Return(v) => return Ok(ExecSignal::Return(v)),
+                    }
+                    if !self.evaluate_expr(condition)?.is_truthy() { break; }
+                }
+                Ok(ExecSignal::None)
+            }
             Stmt::For { init, condition, increment, body } => {
                 // Execute initialization
                 if let Some(init_stmt) = init {
@@ -227,35 +682,25 @@ impl Engine {
                 Ok(ExecSignal::None)
             }
             Stmt::Foreach { array, value_var, key_var, body } => {
-                let array_value = self.evaluate_expr(array)?;
-                
-                // For now, handle arrays as basic iteration
-                // This is a simplified implementation - real PHP foreach is more complex
-                match array_value {
-                    PhpValue::Array(ref arr) => {
-                        for (array_key, value) in &arr.data {
-                            // Set the key variable if specified
-                            if let Some(key_name) = key_var {
-                                let key_value = match array_key {
-                                    PhpArrayKey::Int(i) => PhpValue::Int(*i),
-                                    PhpArrayKey::String(s) => PhpValue::String(s.clone()),
-                                };
-                                self.context.set_variable(key_name.clone(), key_value);
-                            }
-                            
-                            // Set the value variable
-                            self.context.set_variable(value_var.clone(), value.clone());
-                            
-                            // Execute the body
-                            match self.exec(body)? {
-                                ExecSignal::None => {}
-                                ExecSignal::Break => break,
-                                ExecSignal::Continue => continue,
-                                ExecSignal::Return(v) => return Ok(ExecSignal::Return(v)),
-                            }
-                        }
+                // Generators advance one value per iteration; other Traversables are drained up front
+                let subject = self.evaluate_expr(array)?;
+                let mut iteration = self.iteration(subject)?;
+                while let Some((key, value)) = self.next_pair(&mut iteration)? {
+                    // Set the key variable if specified
+                    if let Some(key_name) = key_var {
+                        self.context.set_variable(key_name, key);
+                    }
+
+                    // Set the value variable
+                    self.context.set_variable(value_var, value);
+
+                    // Execute the body
+                    match self.exec(body)? {
+                        ExecSignal::None => {}
+                        ExecSignal::Break => break,
+                        ExecSignal::Continue => continue,
+                        ExecSignal::Return(v) => return Ok(ExecSignal::Return(v)),
                     }
-                    _ => return Err(format!("Cannot iterate over non-array value in foreach")),
                 }
                 Ok(ExecSignal::None)
             }
@@ -264,7 +709,7 @@ impl Engine {
                 let mut matched = false;
                 for case in cases {
                     let case_val = self.evaluate_expr(&case.value)?;
-                    if php_types::php_equals(&discr, &case_val) {
+                    if self.loose_equals(&discr, &case_val)? {
                         matched = true;
                         for stmt in &case.statements {
                             match stmt {
@@ -305,7 +750,7 @@ impl Engine {
             }
             Stmt::FunctionDefinition { name, parameters, body } => {
                 // Store function definition
-                let func = Function { params: parameters.clone(), body: *body.clone() };
+                let func = Function { params: parameters.clone(), body: *body.clone(), captured: Vec::new(), references: Vec::new(), is_generator: stmt_yields(body) };
                 self.context.functions.insert(name.clone(), func);
                 Ok(ExecSignal::None)
             }
@@ -317,11 +762,11 @@ impl Engine {
                         Some(self.evaluate_expr(&cloned)?)
                     } else { None };
                     // Now borrow static_storage
-                    let entry = self.static_storage.entry(current_fn_name.clone()).or_insert_with(std::collections::HashMap::new);
+                    let entry = self.static_storage.entry(current_fn_name.clone()).or_default();
                     if !entry.contains_key(name) {
                         entry.insert(name.clone(), init_eval.clone().unwrap_or(PhpValue::Null));
                     }
-                    if let Some(val) = entry.get(name).cloned() { self.context.set_variable(name.clone(), val); }
+                    if let Some(val) = entry.get(name).cloned() { self.context.set_variable(name, val); }
                     if let Some((fn_name, list)) = self.static_var_stack.last_mut() {
                         if *fn_name == current_fn_name && !list.contains(name) { list.push(name.clone()); }
                     }
@@ -338,23 +783,89 @@ impl Engine {
                         match target {
                             DestructTarget::Var(var) => {
                                 let val = arr.get_int(auto_index).cloned().unwrap_or(PhpValue::Null);
-                                self.context.set_variable(var.clone(), val);
+                                self.context.set_variable(var, val);
                                 auto_index += 1;
                             }
                             DestructTarget::KeyVar(key, var) => {
-                                let val = arr.get_string(&key).cloned().unwrap_or(PhpValue::Null);
-                                self.context.set_variable(var.clone(), val);
+                                let key = self.evaluate_expr(key)?;
+                                let val = PhpArrayKey::from_scalar(&key)
+                                    .and_then(|key| arr.data.get(&key).cloned())
+                                    .unwrap_or(PhpValue::Null);
+                                self.context.set_variable(var, val);
+                            }
+                        }
+                    }
+                }
+                Ok(ExecSignal::None)
+            }
+            Stmt::Nop => Ok(ExecSignal::None),
+            Stmt::Try { body, catches, finally } => {
+                let result = match self.exec(body) {
+                    Err(message) => self.handle_exception(catches, message),
+                    ok => ok,
+                };
+                let Some(finally) = finally else { return result };
+                // Fatal errors end the script without running finally blocks, as in PHP
+                if result.as_ref().is_err_and(|message| self.is_fatal(message)) {
+                    return result;
+                }
+                // finally always runs; a pending exception is parked so the block can use try/catch itself
+                let pending = self.thrown.take();
+                match self.exec(finally)? {
+                    ExecSignal::None => {
+                        self.thrown = pending;
+                        result
+                    }
+                    // return/break/continue in finally overrides the try outcome, discarding any exception
+                    signal => Ok(signal),
+                }
+            }
+            Stmt::ClassDefinition { name, parent, interfaces, members } => {
+                let mut class = Class {
+                    name: name.clone(),
+                    parent: parent.clone(),
+                    interfaces: interfaces.clone(),
+                    properties: Vec::new(),
+                    readonly_properties: HashSet::new(),
+                    static_properties: HashMap::new(),
+                    constants: HashMap::new(),
+                    methods: HashMap::new(),
+                };
+                for member in members {
+                    match member {
+                        ClassMember::Property { name, is_static: false, is_readonly: true, .. } => {
+                            class.readonly_properties.insert(name.clone());
+                        }
+                        ClassMember::Property { name, default, is_static: false, .. } => {
+                            class.properties.push((name.clone(), default.clone()));
+                        }
+                        ClassMember::Property { name, default, is_static: true, .. } => {
+                            let value = match default { Some(expr) => self.evaluate_expr(expr)?, None => PhpValue::Null };
+                            class.static_properties.insert(name.clone(), value);
+                        }
+                        ClassMember::Method { name, parameters, body, is_static } => {
+                            let mut body = *body.clone();
+                            if name.eq_ignore_ascii_case("__construct") {
+                                body = Self::promote_constructor_params(&mut class, parameters, body);
                             }
+                            let is_generator = stmt_yields(&body);
+                            let function = Function { params: parameters.clone(), body, captured: Vec::new(), references: Vec::new(), is_generator };
+                            class.methods.insert(name.clone(), Method { function, is_static: *is_static });
+                        }
+                        ClassMember::Constant { name, value } => {
+                            let value = self.evaluate_expr(value)?;
+                            class.constants.insert(name.clone(), value);
                         }
                     }
                 }
+                self.context.classes.insert(name.clone(), class);
                 Ok(ExecSignal::None)
             }
         }
     }
 
     /// Write output respecting active output buffer
-    fn write_output(&mut self, text: &str) {
+    pub(crate) fn write_output(&mut self, text: &str) {
         if let Some(last) = self.output_buffers.last_mut() {
             last.push_str(text);
         } else {
@@ -369,6 +880,15 @@ impl Engine {
                 // Undefined variable returns null (PHP notice ignored)
                 Ok(self.context.get_variable(name).cloned().unwrap_or(PhpValue::Null))
             }
+            Expr::VariableVariable(inner) => {
+                let name = self.evaluate_expr(inner)?.to_string();
+                Ok(self.context.get_variable(&name).cloned().unwrap_or(PhpValue::Null))
+            }
+            Expr::Assign { target, value } => {
+                let val = self.evaluate_expr(value)?;
+                self.assign_to(target, val.clone())?;
+                Ok(val)
+            }
             Expr::Constant(name) => {
                 self.context
                     .get_constant(name)
@@ -376,28 +896,50 @@ impl Engine {
                     .ok_or_else(|| format!("Undefined constant: {}", name))
             }
             Expr::Number(n) => Ok(PhpValue::Float(*n)),
-            Expr::String(s) => {
-                let interpolated = self.interpolate_string(s);
+            Expr::Int(n) => Ok(PhpValue::Int(*n)),
+            Expr::String(s) => Ok(PhpValue::String(s.clone())),
+            Expr::InterpolatedString(s) => {
+                let interpolated = self.interpolate_string(s)?;
                 Ok(PhpValue::String(interpolated))
             }
             Expr::Bool(b) => Ok(PhpValue::Bool(*b)),
             Expr::Null => Ok(PhpValue::Null),
+            // && and || only evaluate the right operand when the left one does not decide the result
+            Expr::Binary { left, op: op @ (BinaryOp::LogicalAnd | BinaryOp::LogicalOr), right } => {
+                let left_truthy = self.evaluate_expr(left)?.is_truthy();
+                let short_circuit = match op { BinaryOp::LogicalAnd => !left_truthy, _ => left_truthy };
+                if short_circuit {
+                    return Ok(PhpValue::Bool(left_truthy));
+                }
+                Ok(PhpValue::Bool(self.evaluate_expr(right)?.is_truthy()))
+            }
             Expr::Binary { left, op, right } => {
                 let left_val = self.evaluate_expr(left)?;
                 let right_val = self.evaluate_expr(right)?;
                 
-                use php_parser::ast::BinaryOp;
                 match op {
-                    BinaryOp::Add => Ok(php_types::php_add(&left_val, &right_val)),
-                    BinaryOp::Subtract => Ok(php_types::php_subtract(&left_val, &right_val)),
-                    BinaryOp::Multiply => Ok(php_types::php_multiply(&left_val, &right_val)),
-                    BinaryOp::Divide => php_types::php_divide(&left_val, &right_val),
+                    BinaryOp::Add => php_types::php_add(&left_val, &right_val)
+                        .map_err(|message| self.throw_error("TypeError", &message)),
+                    BinaryOp::Subtract => php_types::php_subtract(&left_val, &right_val)
+                        .map_err(|message| self.throw_error("TypeError", &message)),
+                    BinaryOp::Multiply => php_types::php_multiply(&left_val, &right_val)
+                        .map_err(|message| self.throw_error("TypeError", &message)),
+                    BinaryOp::Power => php_types::php_power(&left_val, &right_val)
+                        .map_err(|message| self.throw_error("TypeError", &message)),
+                    BinaryOp::Divide => php_types::php_divide(&left_val, &right_val)
+                        .map_err(|message| self.throw_error("DivisionByZeroError", &message)),
+                    BinaryOp::Modulo => php_types::php_modulo(&left_val, &right_val)
+                        .map_err(|message| self.throw_error("DivisionByZeroError", &message)),
                     BinaryOp::Concatenate => Ok(php_types::php_concatenate(&left_val, &right_val)),
-                    BinaryOp::Equal => Ok(PhpValue::Bool(php_types::php_equals(&left_val, &right_val))),
-                    BinaryOp::NotEqual => Ok(PhpValue::Bool(!php_types::php_equals(&left_val, &right_val))),
+                    BinaryOp::Equal => Ok(PhpValue::Bool(self.loose_equals(&left_val, &right_val)?)),
+                    BinaryOp::NotEqual => Ok(PhpValue::Bool(!self.loose_equals(&left_val, &right_val)?)),
+                    BinaryOp::Identical => Ok(PhpValue::Bool(php_types::php_identical(&left_val, &right_val))),
+                    BinaryOp::NotIdentical => Ok(PhpValue::Bool(!php_types::php_identical(&left_val, &right_val))),
                     BinaryOp::LessThan => Ok(PhpValue::Bool(php_types::php_less_than(&left_val, &right_val))),
-                    BinaryOp::LessThanOrEqual => Ok(PhpValue::Bool(php_types::php_less_than_or_equal(&left_val, &right_val))),
-                    BinaryOp::GreaterThan => Ok(PhpValue::Bool(php_types::php_greater_than(&left_val, &right_val))),
+                    BinaryOp::LessThanOrEqual => php_types::php_less_than_or_equal(&left_val, &right_val)
+                        .map(PhpValue::Bool).map_err(|error| self.fatal_error(error.to_string())),
+                    BinaryOp::GreaterThan => php_types::php_greater_than(&left_val, &right_val)
+                        .map(PhpValue::Bool).map_err(|error| self.fatal_error(error.to_string())),
                     BinaryOp::GreaterThanOrEqual => Ok(PhpValue::Bool(php_types::php_greater_than_or_equal(&left_val, &right_val))),
                     BinaryOp::Spaceship => {
                         // Basic comparison: convert to numeric if both numeric else string cmp
@@ -412,6 +954,7 @@ impl Engine {
                         let res = match ordering { Ordering::Less => -1, Ordering::Equal => 0, Ordering::Greater => 1 };
                         Ok(PhpValue::Int(res))
                     }
+                    BinaryOp::LogicalXor => Ok(PhpValue::Bool(left_val.is_truthy() != right_val.is_truthy())),
                     BinaryOp::BitwiseAnd => {
                         let l = left_val.to_int();
                         let r = right_val.to_int();
@@ -422,12 +965,9 @@ impl Engine {
                         let r = right_val.to_int();
                         Ok(PhpValue::Int(l | r))
                     }
-                    BinaryOp::LogicalAnd => {
-                        Ok(PhpValue::Bool(left_val.is_truthy() && right_val.is_truthy()))
-                    }
-                    BinaryOp::LogicalOr => {
-                        Ok(PhpValue::Bool(left_val.is_truthy() || right_val.is_truthy()))
-                    }
+                    BinaryOp::BitwiseXor => Ok(PhpValue::Int(left_val.to_int() ^ right_val.to_int())),
+                    BinaryOp::ShiftLeft | BinaryOp::ShiftRight => php_types::php_shift(&left_val, &right_val, *op == BinaryOp::ShiftLeft)
+                        .map_err(|message| self.throw_error("ArithmeticError", &message)),
                     _ => Err("Binary operator not implemented".to_string()),
                 }
             }
@@ -435,35 +975,51 @@ impl Engine {
                 self.call_function(name, args)
             }
             Expr::ArrowFunction { params, body } => {
-                // Represent closure as stored function with generated id
-                let id = format!("__closure_{}", self.context.functions.len());
-                let func = Function { params: params.clone(), body: Stmt::Return(Some(*body.clone())) }; // wrap expression in implicit return
-                self.context.functions.insert(id.clone(), func);
-                Ok(PhpValue::String(id)) // Temporary representation (string id). TODO: dedicated closure value type.
+                // Arrow functions capture by value the variables their body uses, plus $this
+                let mut names = HashSet::from(["this".to_string()]);
+                collect_arrow_captures(body, &mut names);
+                let captured = names.into_iter()
+                    .filter(|name| !SUPERGLOBALS.contains(&name.as_str()) && !params.iter().any(|param| &param.name == name))
+                    .filter_map(|name| self.context.get_variable(&name).cloned().map(|value| (name, value)))
+                    .collect();
+                // wrap expression in implicit return
+                let body = Stmt::Return(Some(*body.clone()));
+                let is_generator = stmt_yields(&body);
+                Ok(self.define_closure(Function { params: params.clone(), body, captured, references: Vec::new(), is_generator }))
             }
-            Expr::DynamicCall { target, args } => {
-                // Evaluate target to string id referencing stored closure
-                let target_val = self.evaluate_expr(target)?;
-                if let PhpValue::String(id) = target_val {
-                    // Look up stored function by id
-                    if let Some(func) = self.context.functions.get(&id).cloned() {
-                        if func.params.len() != args.len() { return Err(format!("Closure expects {} args, got {}", func.params.len(), args.len())); }
-                        let saved_vars = self.context.variables.clone();
-                        for (p, arg_expr) in func.params.iter().zip(args.iter()) {
-                            let val = self.evaluate_expr(arg_expr)?;
-                            self.context.set_variable(p.clone(), val);
+            Expr::Closure { params, uses, body } => {
+                // Only `use` variables are captured, plus $this; `&$var` captures stay tied to this scope
+                let mut captured: Vec<(String, PhpValue)> = Vec::new();
+                let mut references = Vec::new();
+                for var in uses {
+                    let value = self.context.get_variable(&var.name).cloned();
+                    if var.by_ref {
+                        if value.is_none() {
+                            self.context.set_variable(&var.name, PhpValue::Null);
                         }
-                        let result = match self.exec(&func.body)? {
-                            ExecSignal::Return(v) => v.unwrap_or(PhpValue::Null),
-                            _ => PhpValue::Null,
-                        };
-                        self.context.variables = saved_vars;
-                        Ok(result)
+                        let value = Rc::new(RefCell::new(value.unwrap_or(PhpValue::Null)));
+                        references.push(CapturedReference { name: var.name.clone(), frame: self.context.frame_id(), value });
                     } else {
-                        Err("Undefined closure id".into())
+                        captured.push((var.name.clone(), value.unwrap_or(PhpValue::Null)));
                     }
-                } else {
-                    Err("Attempted to call non-closure value".into())
+                }
+                if let Some(this) = self.context.get_variable("this") {
+                    captured.push(("this".to_string(), this.clone()));
+                }
+                Ok(self.define_closure(Function { params: params.clone(), body: *body.clone(), captured, references, is_generator: stmt_yields(body) }))
+            }
+            Expr::DynamicCall { target, args } => {
+                match self.evaluate_expr(target)? {
+                    PhpValue::String(name) if !name.contains("::") => self.call_function(&name, args),
+                    PhpValue::Object(handle) if handle.borrow().closure.is_some() => {
+                        let id = handle.borrow().closure.clone().unwrap_or_default();
+                        self.call_function(&id, args)
+                    }
+                    callable @ (PhpValue::String(_) | PhpValue::Array(_)) => {
+                        let args = self.evaluate_args(args)?;
+                        self.call_callable(&callable, args)
+                    }
+                    other => Err(format!("Value of type {} is not callable", other.type_name())),
                 }
             }
             Expr::Unary { op, operand } => {
@@ -475,16 +1031,11 @@ impl Engine {
                             let current_val = self.context
                                 .get_variable(var_name)
                                 .cloned()
-                                .unwrap_or(PhpValue::Int(0));
+                                .unwrap_or(PhpValue::Null);
                             
-                            // Increment the variable
-                            let new_val = match current_val {
-                                PhpValue::Int(i) => PhpValue::Int(i + 1),
-                                PhpValue::Float(f) => PhpValue::Float(f + 1.0),
-                                _ => PhpValue::Int(1), // Convert to int and increment
-                            };
+                            let new_val = php_types::php_increment(&current_val);
                             
-                            self.context.set_variable(var_name.clone(), new_val);
+                            self.context.set_variable(var_name, new_val);
                             Ok(current_val) // Return the old value
                         } else {
                             Err("Increment operator can only be applied to variables".to_string())
@@ -496,16 +1047,11 @@ impl Engine {
                             let current_val = self.context
                                 .get_variable(var_name)
                                 .cloned()
-                                .unwrap_or(PhpValue::Int(0));
+                                .unwrap_or(PhpValue::Null);
                             
-                            // Decrement the variable
-                            let new_val = match current_val {
-                                PhpValue::Int(i) => PhpValue::Int(i - 1),
-                                PhpValue::Float(f) => PhpValue::Float(f - 1.0),
-                                _ => PhpValue::Int(-1), // Convert to int and decrement
-                            };
+                            let new_val = php_types::php_decrement(&current_val);
                             
-                            self.context.set_variable(var_name.clone(), new_val);
+                            self.context.set_variable(var_name, new_val);
                             Ok(current_val) // Return the old value
                         } else {
                             Err("Decrement operator can only be applied to variables".to_string())
@@ -513,78 +1059,89 @@ impl Engine {
                     }
                     UnaryOp::PreIncrement => {
                         if let Expr::Variable(var_name) = operand.as_ref() {
-                            let current_val = self.context.get_variable(var_name).cloned().unwrap_or(PhpValue::Int(0));
-                            let new_val = match current_val {
-                                PhpValue::Int(i) => PhpValue::Int(i + 1),
-                                PhpValue::Float(f) => PhpValue::Float(f + 1.0),
-                                _ => PhpValue::Int(1),
-                            };
-                            self.context.set_variable(var_name.clone(), new_val.clone());
+                            let current_val = self.context.get_variable(var_name).cloned().unwrap_or(PhpValue::Null);
+                            let new_val = php_types::php_increment(&current_val);
+                            self.context.set_variable(var_name, new_val.clone());
                             Ok(new_val)
                         } else { Err("Increment operator can only be applied to variables".to_string()) }
                     }
                     UnaryOp::PreDecrement => {
                         if let Expr::Variable(var_name) = operand.as_ref() {
-                            let current_val = self.context.get_variable(var_name).cloned().unwrap_or(PhpValue::Int(0));
-                            let new_val = match current_val {
-                                PhpValue::Int(i) => PhpValue::Int(i - 1),
-                                PhpValue::Float(f) => PhpValue::Float(f - 1.0),
-                                _ => PhpValue::Int(-1),
-                            };
-                            self.context.set_variable(var_name.clone(), new_val.clone());
+                            let current_val = self.context.get_variable(var_name).cloned().unwrap_or(PhpValue::Null);
+                            let new_val = php_types::php_decrement(&current_val);
+                            self.context.set_variable(var_name, new_val.clone());
                             Ok(new_val)
                         } else { Err("Decrement operator can only be applied to variables".to_string()) }
                     }
-                    _ => Err("Unary operator not implemented".to_string()),
+                    UnaryOp::Minus => Ok(php_types::php_negate(&self.evaluate_expr(operand)?)),
+                    UnaryOp::Plus => Ok(php_types::to_number(&self.evaluate_expr(operand)?)),
+                    UnaryOp::Not => Ok(PhpValue::Bool(!self.evaluate_expr(operand)?.is_truthy())),
                 }
             }
             Expr::Array(elements) => {
                 // Build PHP array value
                 let mut arr = PhpArray::new();
                 for element in elements.iter() {
+                    if let Expr::Spread(inner) = &element.value {
+                        // Move the unpacked temporary's elements instead of cloning them
+                        match self.evaluate_expr(inner)? {
+                            PhpValue::Array(spread) => arr.merge(spread)?,
+                            traversable @ PhpValue::Object(_) => {
+                                // Like merging, integer keys are renumbered and string keys kept
+                                for (key, value) in self.traverse(traversable)? {
+                                    match key {
+                                        PhpValue::String(key) => arr.insert_string(key, value),
+                                        _ => arr.push(value)?,
+                                    }
+                                }
+                            }
+                            _ => return Err("Only arrays and Traversables can be unpacked".to_string()),
+                        }
+                        continue;
+                    }
                     // Evaluate value
                     let value = self.evaluate_expr(&element.value)?;
                     if let Some(ref key_expr) = element.key {
                         let key_val = self.evaluate_expr(key_expr)?;
-                        match key_val {
-                            PhpValue::Int(i_key) => arr.insert_int(i_key, value),
-                            PhpValue::String(s_key) => arr.insert_string(s_key, value),
-                            // Fallback: convert to string
-                            other => arr.insert_string(other.to_string(), value),
-                        }
+                        arr.insert(Self::array_key(key_val), value);
                     } else {
                         // Auto index
-                        arr.push(value);
+                        arr.push(value)?;
                     }
                 }
                 Ok(PhpValue::Array(arr))
             }
+            Expr::Spread(_) => Err("Spread operator is not supported here".to_string()),
+            // Native functions evaluate their arguments one by one and take named ones in order
+            Expr::NamedArgument { value, .. } => self.evaluate_expr(value),
+            Expr::Throw(inner) => {
+                let exception = self.evaluate_expr(inner)?;
+                match &exception {
+                    PhpValue::Object(handle) if self.is_throwable(&handle.borrow().class_name) => Err(self.raise(exception.clone())),
+                    _ => Err("Can only throw objects that implement Throwable".to_string()),
+                }
+            }
             Expr::ArrayAccess { array, index } => {
                 let array_val = self.evaluate_expr(array)?;
                 let index_val = self.evaluate_expr(index)?;
                 match array_val {
-                    PhpValue::Array(arr) => {
-                        let result = match index_val {
-                            PhpValue::Int(i) => arr.get_int(i).cloned(),
-                            PhpValue::String(s) => arr.get_string(&s).cloned(),
-                            other => {
-                                // Try numeric then string
-                                let numeric = other.to_int();
-                                arr.get_int(numeric).cloned().or_else(|| arr.get_string(&other.to_string()).cloned())
-                            }
-                        };
-                        Ok(result.unwrap_or(PhpValue::Null))
+                    PhpValue::Array(arr) => Ok(Self::array_lookup(&arr, &index_val).cloned().unwrap_or(PhpValue::Null)),
+                    PhpValue::Object(handle) => {
+                        let class_name = handle.borrow().class_name.clone();
+                        if self.find_method(&class_name, "offsetGet").is_none() {
+                            return Err(format!("Cannot use object of type {} as array", class_name));
+                        }
+                        self.call_method(&PhpValue::Object(handle), &class_name, "offsetGet", vec![index_val])
                     }
                     // PHP would emit a notice and return null; we silently return null for now
                     _ => Ok(PhpValue::Null),
                 }
             }
             Expr::NullCoalesce { left, right } => {
-                let left_val = self.evaluate_expr(left)?;
-                if left_val.is_null() {
-                    self.evaluate_expr(right)
-                } else {
-                    Ok(left_val)
+                // Probe the left side like isset() so missing ArrayAccess offsets skip offsetGet()
+                match self.isset_lookup(left)? {
+                    Some(left_val) if !left_val.is_null() => Ok(left_val),
+                    _ => self.evaluate_expr(right),
                 }
             }
             Expr::Ternary { condition, then_expr, else_expr } => {
@@ -601,7 +1158,8 @@ impl Engine {
                 for (conds, result) in arms {
                     for cond in conds {
                         let cval = self.evaluate_expr(cond)?;
-                        if php_types::php_equals(&subj_val, &cval) {
+                        // Unlike switch, match compares with ===
+                        if php_types::php_identical(&subj_val, &cval) {
                             return self.evaluate_expr(result);
                         }
                     }
@@ -609,458 +1167,1174 @@ impl Engine {
                 if let Some(def) = default_arm { return self.evaluate_expr(def); }
                 Ok(PhpValue::Null)
             }
-            Expr::Yield { value } => {
-                // Evaluate yielded value but ignore (no generator semantics yet)
-                let _ = self.evaluate_expr(value)?;
-                Ok(PhpValue::Null)
-            }
-            Expr::MethodCall { target: _target, method: _method, args } => {
-                // Evaluate args for side effects
-                for a in args { let _ = self.evaluate_expr(a)?; }
-                Ok(PhpValue::Null) // placeholder
-            }
-        }
-    }
-
-    /// Call a function
-    fn call_function(&mut self, name: &str, args: &[Expr]) -> Result<PhpValue, String> {
-        match name {
-            "define" => {
-                if args.len() != 2 {
-                    return Err("define() expects exactly 2 arguments".to_string());
-                }
-                
-                // First argument should be the constant name (string)
-                let const_name = match self.evaluate_expr(&args[0])? {
-                    PhpValue::String(s) => s,
-                    _ => return Err("define() first argument must be a string".to_string()),
-                };
-                
-                // Second argument is the constant value
-                let const_value = self.evaluate_expr(&args[1])?;
-                
-                // Define the constant
-                self.context.set_constant(const_name, const_value);
-                
-                // define() returns true on success
-                Ok(PhpValue::Bool(true))
-            }
-            "isset" => {
-                // isset can take one or more variables/expressions. We'll evaluate each; if any is undefined or null -> false.
-                if args.is_empty() { return Ok(PhpValue::Bool(false)); }
-                for expr in args {
-                    // Only treat simple variable references as per minimal implementation; other expressions fallback to evaluated value
-                    let val = self.evaluate_expr(expr)?;
-                    if val.is_null() { return Ok(PhpValue::Bool(false)); }
-                }
-                Ok(PhpValue::Bool(true))
-            }
-            "parse_str" => {
-                // Expect 2 arguments: query string, target array variable (passed as variable expression in source)
-                if args.len() != 2 {
-                    return Err("parse_str() expects exactly 2 arguments".into());
-                }
-                // Evaluate first argument to string
-                let query_val = self.evaluate_expr(&args[0])?;
-                let query_str = query_val.to_string();
-                // Determine variable name from second arg expression (must be variable)
-                let target_var_name = match &args[1] {
-                    Expr::Variable(name) => name.clone(),
-                    _ => return Err("parse_str() second argument must be a variable".into()),
-                };
-                // Parse query string into PhpArray
-                let mut arr = PhpArray::new();
-                for pair in query_str.split('&') {
-                    if pair.is_empty() { continue; }
-                    let mut kv = pair.splitn(2, '=');
-                    let raw_key = kv.next().unwrap_or("");
-                    let raw_val = kv.next().unwrap_or("");
-                    let key = Self::percent_decode(raw_key);
-                    let val_str = Self::percent_decode(raw_val);
-                    arr.insert_string(key, PhpValue::String(val_str));
-                }
-                self.context.set_variable(target_var_name, PhpValue::Array(arr));
-                Ok(PhpValue::Null)
+            Expr::Yield { key, value } => self.evaluate_yield(key.as_deref(), value),
+            Expr::YieldFrom(source) => self.evaluate_yield_from(source),
+            Expr::MethodCall { .. } | Expr::PropertyAccess { .. } | Expr::NullsafeMethodCall { .. } | Expr::NullsafePropertyAccess { .. } => {
+                Ok(self.evaluate_chain(expr)?.unwrap_or(PhpValue::Null))
             }
-            "array_merge" => {
-                if args.is_empty() { return Ok(PhpValue::Array(PhpArray::new())); }
-                let mut result = PhpArray::new();
-                for expr in args {
-                    let val = self.evaluate_expr(expr)?;
-                    if let PhpValue::Array(arr) = val {
-                        // For simplicity: numeric keys appended (preserving insertion order), string keys overwrite
-                        for (k, v) in arr.data.iter() {
-                            match k {
-                                PhpArrayKey::Int(_) => { result.push(v.clone()); }
-                                PhpArrayKey::String(s) => { result.insert_string(s.clone(), v.clone()); }
+            Expr::StaticCall { class, method, args } => {
+                let class_name = self.static_class_name(class)?;
+                let Some(found) = self.find_method(&class_name, method) else {
+                    // parent::__construct() and friends on built-in exception classes
+                    if let Some(this) = self.context.get_variable("this").cloned() {
+                        if self.is_throwable(&class_name) {
+                            let args = self.evaluate_args(args)?;
+                            if let Some(result) = self.call_throwable_method(&this, method, args) {
+                                return result;
                             }
                         }
                     }
-                }
-                Ok(PhpValue::Array(result))
-            }
-            "getenv" => {
-                if args.len() != 1 { return Err("getenv() expects exactly 1 argument".into()); }
-                let name_val = self.evaluate_expr(&args[0])?;
-                let key = name_val.to_string();
-                match std::env::var(&key) {
-                    Ok(v) => Ok(PhpValue::String(v)),
-                    Err(_) => Ok(PhpValue::Bool(false)),
-                }
-            }
-            "array_sum" => {
-                if args.len() != 1 { return Err("array_sum() expects exactly 1 argument".into()); }
-                let arr_val = self.evaluate_expr(&args[0])?;
-                match arr_val {
-                    PhpValue::Array(arr) => {
-                        let mut sum_f: f64 = 0.0;
-                        for (_, v) in arr.data.iter() {
-                            sum_f += match v {
-                                PhpValue::Int(i) => *i as f64,
-                                PhpValue::Float(f) => *f,
-                                PhpValue::String(s) => s.parse::<f64>().unwrap_or(0.0),
-                                PhpValue::Bool(b) => if *b { 1.0 } else { 0.0 },
-                                _ => 0.0,
-                            };
-                        }
-                        // If sum is integer representable, return Int else Float
-                        if (sum_f.fract() - 0.0).abs() < std::f64::EPSILON { Ok(PhpValue::Int(sum_f as i64)) } else { Ok(PhpValue::Float(sum_f)) }
+                    // parent::missing() from an instance method goes to __call, Class::missing() to __callStatic
+                    let this = self.context.get_variable("this").cloned();
+                    let magic = if this.is_some() { "__call" } else { "__callStatic" };
+                    if let Some(found) = self.find_method(&class_name, magic) {
+                        let args = self.evaluate_args(args)?;
+                        let this = if found.is_static { None } else { this };
+                        return self.invoke_magic_call(&class_name, magic, &found.function, method, args.into_iter().collect(), this);
                     }
-                    _ => Ok(PhpValue::Int(0))
-                }
-            }
-            "str_repeat" => {
-                if args.len() != 2 { return Err("str_repeat() expects exactly 2 arguments".into()); }
-                let input_val = self.evaluate_expr(&args[0])?;
-                let times_val = self.evaluate_expr(&args[1])?;
-                let s = input_val.to_string();
-                let times: i64 = match times_val {
-                    PhpValue::Int(i) => i,
-                    PhpValue::Float(f) => f as i64,
-                    PhpValue::String(ref st) => st.parse::<i64>().unwrap_or(0),
-                    _ => 0,
+                    return Err(self.undefined_method(&class_name, method));
                 };
-                if times <= 0 { return Ok(PhpValue::String(String::new())); }
-                // Basic guard to avoid huge allocations; mimic simplified behavior
-                if times as usize > 100_000 { return Err("str_repeat(): Second argument too large".into()); }
-                let repeated = s.repeat(times as usize);
-                Ok(PhpValue::String(repeated))
-            }
-            "usort" => {
-                if args.len() != 2 { return Err("usort() expects exactly 2 arguments".into()); }
-                use php_parser::ast::Expr as AstExpr;
-                let arr_expr = &args[0];
-                // Evaluate array
-                let arr_value = self.evaluate_expr(arr_expr)?;
-                if let PhpValue::Array(arr) = arr_value {
-                    // Extract values
-                    let mut values: Vec<PhpValue> = arr.data.iter().map(|(_, v)| v.clone()).collect();
-                    // Very naive sort: compare string representations (mimics comparator returning strcmp semantics)
-                    let len = values.len();
-                    for i in 0..len {
-                        for j in 0..len - 1 - i {
-                            let a_s = values[j].to_string();
-                            let b_s = values[j + 1].to_string();
-                            if a_s > b_s { values.swap(j, j + 1); }
-                        }
+                // self::/parent:: calls to instance methods keep the current $this
+                let this = if found.is_static {
+                    None
+                } else {
+                    match self.context.get_variable("this").cloned() {
+                        Some(this) => Some(this),
+                        None => return Err(format!("Non-static method {}::{}() cannot be called statically", class_name, method)),
                     }
-                    // Rebuild numeric array
-                    let mut new_arr = PhpArray::new();
-                    for v in values { new_arr.push(v); }
-                    if let AstExpr::Variable(var_name) = arr_expr { self.context.set_variable(var_name.clone(), PhpValue::Array(new_arr)); }
-                    Ok(PhpValue::Bool(true))
-                } else { Ok(PhpValue::Bool(false)) }
-            }
-            "iterator_to_array" => {
-                if args.len() < 1 { return Err("iterator_to_array() expects at least 1 argument".into()); }
-                let val = self.evaluate_expr(&args[0])?;
-                match val {
-                    PhpValue::Array(a) => Ok(PhpValue::Array(a)),
-                    _ => Ok(PhpValue::Array(PhpArray::new()))
+                };
+                let name = format!("{}::{}", class_name, method);
+                let args = self.bind_args(&name, args, &found.function.params)?;
+                self.invoke_function(&name, &found.function, args, this)
+            }
+            Expr::ClassConstant { class, name } => {
+                let class_name = self.static_class_name(class)?;
+                if name == "class" {
+                    return Ok(PhpValue::String(class_name));
                 }
+                self.class_constant(&class_name, name)
             }
-            "json_encode" => {
-                if args.is_empty() { return Err("json_encode() expects at least 1 argument".into()); }
-                let value = self.evaluate_expr(&args[0])?;
-                let mut flags: i64 = 0;
-                if args.len() >= 2 { flags = match self.evaluate_expr(&args[1])? { PhpValue::Int(i) => i, PhpValue::Float(f) => f as i64, _ => 0 }; }
-                let unescaped_slashes = (flags & 1) != 0; // using placeholder bit positions (not exact PHP mapping)
-                let unescaped_unicode = (flags & 2) != 0;
-                fn escape_str(s: &str, unesc_slash: bool, unesc_unicode: bool) -> String {
-                    let mut out = String::new();
-                    for ch in s.chars() {
-                        match ch {
-                            '"' => out.push_str("\\\""),
-                            '\\' => out.push_str("\\\\"),
-                            '/' => { if unesc_slash { out.push('/'); } else { out.push_str("\\/"); } },
-                            '\n' => out.push_str("\\n"),
-                            '\r' => out.push_str("\\r"),
-                            '\t' => out.push_str("\\t"),
-                            c if c < ' ' => {
-                                out.push_str(&format!("\\u{:04x}", c as u32));
-                            }
-                            c => {
-                                if !unesc_unicode && (c as u32) > 0x7F { out.push_str(&format!("\\u{:04x}", c as u32)); } else { out.push(c); }
-                            }
+            Expr::StaticProperty { class, name } => {
+                let class_name = self.static_class_name(class)?;
+                let declaring = self.static_property_class(&class_name, name)?;
+                Ok(self.context.classes[&declaring].static_properties[name].clone())
+            }
+            Expr::New { class, args } => {
+                let class_name = match class {
+                    ClassName::Identifier(name) => name.clone(),
+                    ClassName::Anonymous { name, parent, interfaces, members } => {
+                        // Declared on first evaluation; later evaluations reuse the class
+                        if !self.context.classes.contains_key(name) {
+                            let definition = Stmt::ClassDefinition { name: name.clone(), parent: parent.clone(), interfaces: interfaces.clone(), members: members.clone() };
+                            self.exec(&definition)?;
                         }
+                        name.clone()
                     }
-                    out
-                }
-                fn encode(value: &PhpValue, unesc_slash: bool, unesc_unicode: bool) -> String {
-                    match value {
-                        PhpValue::Null => "null".to_string(),
-                        PhpValue::Bool(b) => if *b { "true".to_string() } else { "false".to_string() },
-                        PhpValue::Int(i) => i.to_string(),
-                        PhpValue::Float(f) => {
-                            if f.is_finite() { f.to_string() } else { "null".to_string() }
-                        }
-                        PhpValue::String(s) => format!("\"{}\"", escape_str(s, unesc_slash, unesc_unicode)),
-                        PhpValue::Array(arr) => {
-                            // Detect list: keys 0..n-1 all int sequential
-                            let mut is_list = true;
-                            let mut expected_index: i64 = 0;
-                            for (k, _) in arr.data.iter() {
-                                match k {
-                                    PhpArrayKey::Int(i) => { if *i != expected_index { is_list = false; break; } expected_index += 1; }
-                                    PhpArrayKey::String(_) => { is_list = false; break; }
-                                }
-                            }
-                            if is_list {
-                                let mut parts = Vec::new();
-                                for (_, v) in arr.data.iter() { parts.push(encode(v, unesc_slash, unesc_unicode)); }
-                                format!("[{}]", parts.join(","))
-                            } else {
-                                let mut parts = Vec::new();
-                                for (k, v) in arr.data.iter() {
-                                    let key_str = match k { PhpArrayKey::Int(i) => i.to_string(), PhpArrayKey::String(s) => s.clone() };
-                                    parts.push(format!("\"{}\":{}", escape_str(&key_str, unesc_slash, unesc_unicode), encode(v, unesc_slash, unesc_unicode)));
-                                }
-                                format!("{{{}}}", parts.join(","))
+                    ClassName::Dynamic(expr) => match self.evaluate_expr(expr)? {
+                        // new $obj creates another instance of the object's class
+                        PhpValue::Object(handle) => handle.borrow().class_name.clone(),
+                        PhpValue::String(name) => name.trim_start_matches('\\').to_string(),
+                        other => return Err(format!("Cannot instantiate {}: class name must be a valid object or a string", other.type_name())),
+                    },
+                };
+                self.instantiate(&class_name, args)
+            }
+        }
+    }
+
+    /// Store a value into an assignable expression
+    pub(crate) fn assign_to(&mut self, target: &Expr, value: PhpValue) -> Result<(), String> {
+        match target {
+            Expr::Variable(name) => {
+                self.context.set_variable(name, value);
+                Ok(())
+            }
+            Expr::VariableVariable(inner) => {
+                let name = self.evaluate_expr(inner)?.to_string();
+                self.context.set_variable(&name, value);
+                Ok(())
+            }
+            Expr::PropertyAccess { target, property } => {
+                let object = self.evaluate_expr(target)?;
+                let property_name = self.resolve_member_name(property)?;
+                match object {
+                    PhpValue::Object(handle) => {
+                        let class_name = handle.borrow().class_name.clone();
+                        if let Some(declaring) = self.readonly_declaring_class(&class_name, &property_name) {
+                            if handle.borrow().get_property(&property_name).is_some() {
+                                return Err(self.throw_error("Error", &format!("Cannot modify readonly property {}::${}", declaring, property_name)));
                             }
-                        }
-                        _ => "null".to_string(),
-                    }
-                }
-                let json = encode(&value, unescaped_slashes, unescaped_unicode);
-                Ok(PhpValue::String(json))
-            }
-            "json_decode" => {
-                if args.is_empty() { return Err("json_decode() expects at least 1 argument".into()); }
-                let json_val = self.evaluate_expr(&args[0])?;
-                let json_str = json_val.to_string();
-                // second param assoc = bool (default true for us for simpler mapping)
-                let mut assoc = true;
-                if args.len() >= 2 {
-                    assoc = match self.evaluate_expr(&args[1])? { PhpValue::Bool(b) => b, PhpValue::Int(i) => i != 0, _ => true };
-                }
-                match serde_json::from_str::<serde_json::Value>(&json_str) {
-                    Ok(v) => {
-                        fn to_php(v: &serde_json::Value, assoc: bool) -> PhpValue {
-                            match v {
-                                serde_json::Value::Null => PhpValue::Null,
-                                serde_json::Value::Bool(b) => PhpValue::Bool(*b),
-                                serde_json::Value::Number(n) => {
-                                    if let Some(i) = n.as_i64() { PhpValue::Int(i) } else if let Some(f) = n.as_f64() { PhpValue::Float(f) } else { PhpValue::Null }
-                                }
-                                serde_json::Value::String(s) => PhpValue::String(s.clone()),
-                                serde_json::Value::Array(arr) => {
-                                    let mut a = PhpArray::new();
-                                    for item in arr { a.push(to_php(item, assoc)); }
-                                    PhpValue::Array(a)
-                                }
-                                serde_json::Value::Object(map) => {
-                                    let mut a = PhpArray::new();
-                                    for (k, val) in map.iter() {
-                                        if assoc {
-                                            a.insert_string(k.clone(), to_php(val, assoc));
-                                        }
-                                    }
-                                    PhpValue::Array(a)
-                                }
+                            // Only code of the declaring class may initialize it
+                            let scope = self.method_scope();
+                            if scope.as_deref() != Some(declaring.as_str()) {
+                                let scope = scope.map_or("global scope".to_string(), |name| format!("scope {}", name));
+                                let message = format!("Cannot initialize readonly property {}::${} from {}", declaring, property_name, scope);
+                                return Err(self.throw_error("Error", &message));
                             }
                         }
-                        Ok(to_php(&v, assoc))
+                        handle.borrow_mut().set_property(property_name, value);
+                        Ok(())
                     }
-                    Err(_) => Ok(PhpValue::Null)
+                    other => Err(format!("Attempt to assign property \"{}\" on {}", property_name, other.type_name())),
                 }
             }
-            "set_error_handler" => {
-                // Accept any callable, ignore for now, return null (previous handler)
-                Ok(PhpValue::Null)
+            Expr::StaticProperty { class, name } => {
+                let class_name = self.static_class_name(class)?;
+                let declaring = self.static_property_class(&class_name, name)?;
+                if let Some(class) = self.context.classes.get_mut(&declaring) {
+                    class.static_properties.insert(name.clone(), value);
+                }
+                Ok(())
             }
-            "preg_match" => {
-                // preg_match(pattern, subject, matches?)
-                if args.len() < 2 { return Err("preg_match() expects at least 2 parameters".into()); }
-                use php_parser::ast::Expr as AstExpr;
-                let pattern_raw = self.evaluate_expr(&args[0])?.to_string();
-                let subject = self.evaluate_expr(&args[1])?.to_string();
-                // Strip delimiters if pattern like /.../
-                let pattern = if pattern_raw.len() >= 2 && pattern_raw.starts_with('/') {
-                    if let Some(last) = pattern_raw.rfind('/') { pattern_raw[1..last].to_string() } else { pattern_raw.clone() }
-                } else { pattern_raw.clone() };
-                match regex::Regex::new(&pattern) {
-                    Ok(re) => {
-                        if let Some(caps) = re.captures(&subject) {
-                            // If third argument variable provided populate
-                            if args.len() >= 3 {
-                                if let AstExpr::Variable(var_name) = &args[2] {
-                                    let mut arr = PhpArray::new();
-                                    for (i, cap) in caps.iter().enumerate() {
-                                        if let Some(m) = cap { arr.insert_int(i as i64, PhpValue::String(m.as_str().to_string())); }
-                                    }
-                                    self.context.set_variable(var_name.clone(), PhpValue::Array(arr));
-                                }
-                            }
-                            Ok(PhpValue::Int(1))
-                        } else { Ok(PhpValue::Int(0)) }
-                    }
-                    Err(_) => Ok(PhpValue::Int(0))
+            Expr::ArrayAccess { .. } => self.assign_to_element(target, value),
+            other => Err(format!("Cannot assign to {}", other)),
+        }
+    }
+
+    /// Store `$base[$i]...[$j] = value`, creating arrays for null levels and writing the base back once
+    fn assign_to_element(&mut self, target: &Expr, value: PhpValue) -> Result<(), String> {
+        let mut indexes = Vec::new();
+        let mut base = target;
+        while let Expr::ArrayAccess { array, index } = base {
+            indexes.push(&**index);
+            base = array;
+        }
+        let indexes = indexes.into_iter().rev().map(|index| self.evaluate_expr(index)).collect::<Result<Vec<_>, _>>()?;
+        // Move a variable's value out so nested arrays are updated without copying them
+        let mut container = match base {
+            Expr::Variable(name) => self.context.remove_variable(name).unwrap_or(PhpValue::Null),
+            other => self.isset_lookup(other)?.unwrap_or(PhpValue::Null),
+        };
+        let stored = self.store_element(&mut container, &indexes, value);
+        if matches!(base, Expr::Variable(_)) || !matches!(container, PhpValue::Object(_)) {
+            self.assign_to(base, container)?;
+        }
+        stored
+    }
+
+    /// Write `value` at the index path inside `container`
+    fn store_element(&mut self, container: &mut PhpValue, indexes: &[PhpValue], value: PhpValue) -> Result<(), String> {
+        let Some((index, rest)) = indexes.split_first() else {
+            *container = value;
+            return Ok(());
+        };
+        if matches!(container, PhpValue::Null | PhpValue::Bool(false)) {
+            *container = PhpValue::Array(PhpArray::new());
+        }
+        match container {
+            PhpValue::Array(arr) => {
+                let key = Self::array_key(index.clone());
+                if rest.is_empty() {
+                    arr.insert(key, value);
+                    return Ok(());
                 }
+                if arr.get_mut(&key).is_none() {
+                    arr.insert(key.clone(), PhpValue::Null);
+                }
+                let child = arr.get_mut(&key).expect("element was just inserted");
+                self.store_element(child, rest, value)
             }
-            "filter_var" => {
-                // filter_var(value, filter) minimal: only FILTER_VALIDATE_INT
-                if args.len() < 2 { return Err("filter_var() expects at least 2 arguments".into()); }
-                let val = self.evaluate_expr(&args[0])?;
-                let filter = self.evaluate_expr(&args[1])?;
-                let filter_id = match filter { PhpValue::Int(i) => i, _ => 0 };
-                // We defined FILTER_VALIDATE_INT constant as 257
-                if filter_id == 257 {
-                    let s = val.to_string();
-                    if let Ok(i) = s.parse::<i64>() { Ok(PhpValue::Int(i)) } else { Ok(PhpValue::Bool(false)) }
-                } else {
-                    Ok(val) // fallback returns original
+            PhpValue::Object(handle) => {
+                let class_name = handle.borrow().class_name.clone();
+                if self.find_method(&class_name, "offsetSet").is_none() {
+                    return Err(self.throw_error("Error", &format!("Cannot use object of type {} as array", class_name)));
                 }
+                let this = PhpValue::Object(handle.clone());
+                if rest.is_empty() {
+                    self.call_method(&this, &class_name, "offsetSet", vec![index.clone(), value])?;
+                    return Ok(());
+                }
+                // Nested levels modify what offsetGet() returns; only objects pass changes through
+                let mut child = self.call_method(&this, &class_name, "offsetGet", vec![index.clone()])?;
+                self.store_element(&mut child, rest, value)
             }
-            "ob_start" => {
-                self.output_buffers.push(String::new());
-                Ok(PhpValue::Bool(true))
-            }
-            "ob_get_clean" => {
-                if let Some(buf) = self.output_buffers.pop() { Ok(PhpValue::String(buf)) } else { Ok(PhpValue::Bool(false)) }
-            }
-            "printf" => {
-                if args.is_empty() { return Ok(PhpValue::Int(0)); }
-                let fmt = self.evaluate_expr(&args[0])?.to_string();
-                let mut arg_index = 1usize;
-                let mut out = String::new();
-                let chars: Vec<char> = fmt.chars().collect();
-                let mut i = 0;
-                while i < chars.len() {
-                    if chars[i] == '%' {
-                        i += 1;
-                        if i >= chars.len() { break; }
-                        let spec = chars[i];
-                        match spec {
-                            '%' => { out.push('%'); }
-                            's' | 'd' | 'f' => {
-                                if arg_index < args.len() {
-                                    let val = self.evaluate_expr(&args[arg_index])?;
-                                    let formatted = match spec {
-                                        'd' => val.to_int().to_string(),
-                                        'f' => {
-                                            let f = val.to_float();
-                                            format!("{}", f)
-                                        }
-                                        _ => val.to_string(),
-                                    };
-                                    out.push_str(&formatted);
-                                    arg_index += 1;
-                                }
-                            }
-                            _ => { out.push('%'); out.push(spec); }
-                        }
-                    } else {
-                        out.push(chars[i]);
-                    }
-                    i += 1;
+            PhpValue::String(s) if rest.is_empty() => {
+                let len = s.len() as i64;
+                let offset = index.to_int();
+                let offset = if offset < 0 { offset + len } else { offset };
+                if offset < 0 {
+                    self.raise_error(ErrorLevel::Warning, format!("Illegal string offset {}", index.to_int()));
+                    return Ok(());
                 }
-                let len = out.len() as i64;
-                self.write_output(&out);
-                Ok(PhpValue::Int(len))
-            }
-            "implode" => {
-                if args.is_empty() { return Err("implode() expects at least 1 argument".into()); }
-                let (glue, pieces_expr_index) = if args.len() == 1 { ("".to_string(), 0usize) } else { (self.evaluate_expr(&args[0])?.to_string(), 1usize) };
-                let pieces_val = self.evaluate_expr(&args[pieces_expr_index])?;
-                match pieces_val {
-                    PhpValue::Array(arr) => {
-                        let mut parts = Vec::new();
-                        for (_, v) in arr.data.iter() { parts.push(v.to_string()); }
-                        Ok(PhpValue::String(parts.join(&glue)))
-                    }
-                    other => Ok(PhpValue::String(other.to_string()))
+                let replacement = value.to_string().chars().next().map_or(" ".to_string(), String::from);
+                let mut bytes = std::mem::take(s).into_bytes();
+                if offset as usize >= bytes.len() {
+                    bytes.resize(offset as usize + 1, b' ');
                 }
+                bytes.splice(offset as usize..offset as usize + 1, replacement.into_bytes());
+                *s = String::from_utf8_lossy(&bytes).into_owned();
+                Ok(())
             }
-            _ => {
-                // User-defined function?
-                if let Some(func) = self.context.functions.get(name).cloned() {
-                    // Evaluate args
-                    if args.len() != func.params.len() {
-                        return Err(format!("Function {} expects {} arguments, got {}", name, func.params.len(), args.len()));
-                    }
-                    // Save current variables (shallow)
-                    let saved_vars = self.context.variables.clone();
-                    let prev_function = self.current_function.clone();
-                    self.current_function = Some(name.to_string());
-                    self.static_var_stack.push((name.to_string(), Vec::new()));
-                    // Bind parameters
-                    for (param, expr) in func.params.iter().zip(args.iter()) {
-                        let val = self.evaluate_expr(expr)?;
-                        self.context.set_variable(param.clone(), val);
-                    }
-                    // Execute body
-                    let result = match self.exec(&func.body)? {
-                        ExecSignal::Return(v) => v.unwrap_or(PhpValue::Null),
-                        _ => PhpValue::Null,
-                    };
-                    // Persist static vars back
-                    if let Some((fn_name, vars)) = self.static_var_stack.pop() {
-                        if let Some(store) = self.static_storage.get_mut(&fn_name) {
-                            for var in vars {
-                                if let Some(val) = self.context.get_variable(&var).cloned() {
-                                    store.insert(var, val);
-                                }
+            PhpValue::String(_) => Err(self.throw_error("Error", "Cannot use string offset as an array")),
+            _ => Err(self.throw_error("Error", "Cannot use a scalar value as an array")),
+        }
+    }
+
+    /// Key under which an index is stored, as array literals convert them
+    fn array_key(index: PhpValue) -> PhpArrayKey {
+        match index {
+            PhpValue::Int(key) => PhpArrayKey::Int(key),
+            PhpValue::String(key) => PhpArrayKey::String(key),
+            // Like PHP, float and bool keys are truncated to integers
+            PhpValue::Float(key) => PhpArrayKey::Int(key as i64),
+            PhpValue::Bool(key) => PhpArrayKey::Int(key as i64),
+            // Fallback: convert to string
+            other => PhpArrayKey::String(other.to_string()),
+        }
+    }
+
+    /// Store the result of a by-reference argument; values passed as temporaries are dropped
+    pub(crate) fn write_back(&mut self, target: &Expr, value: PhpValue) -> Result<(), String> {
+        match target {
+            Expr::Variable(_) | Expr::VariableVariable(_) | Expr::ArrayAccess { .. } | Expr::PropertyAccess { .. } | Expr::StaticProperty { .. } => self.assign_to(target, value),
+            _ => Ok(()),
+        }
+    }
+
+    /// Look up an array element, trying integer keys for non-string indexes
+    fn array_lookup<'a>(arr: &'a PhpArray, index: &PhpValue) -> Option<&'a PhpValue> {
+        match index {
+            PhpValue::Int(i) => arr.get_int(*i),
+            PhpValue::String(s) => arr.get_string(s),
+            other => arr.get_int(other.to_int()).or_else(|| arr.get_string(&other.to_string())),
+        }
+    }
+
+    /// Walk a variable/array/property chain for isset, stopping at the first missing or non-container level
+    pub(crate) fn isset_lookup(&mut self, expr: &Expr) -> Result<Option<PhpValue>, String> {
+        match expr {
+            Expr::Variable(name) => Ok(self.context.get_variable(name).cloned()),
+            Expr::VariableVariable(inner) => {
+                let name = self.evaluate_expr(inner)?.to_string();
+                Ok(self.context.get_variable(&name).cloned())
+            }
+            Expr::ArrayAccess { array, index } => {
+                let Some(container) = self.isset_lookup(array)? else { return Ok(None) };
+                let index = self.evaluate_expr(index)?;
+                self.dim_lookup(container, index)
+            }
+            Expr::PropertyAccess { target, property } => {
+                let Some(container) = self.isset_lookup(target)? else { return Ok(None) };
+                let property_name = self.resolve_member_name(property)?;
+                Ok(match container {
+                    PhpValue::Object(handle) => handle.borrow().get_property(&property_name).cloned(),
+                    _ => None,
+                })
+            }
+            other => self.evaluate_expr(other).map(Some),
+        }
+    }
+
+    /// isset() on one expression: a final ArrayAccess level asks offsetExists() alone, so a stored null still counts
+    pub(crate) fn isset_check(&mut self, expr: &Expr) -> Result<bool, String> {
+        if let Expr::ArrayAccess { array, index } = expr {
+            let Some(container) = self.isset_lookup(array)? else { return Ok(false) };
+            let index = self.evaluate_expr(index)?;
+            return match container {
+                PhpValue::Object(handle) => self.offset_exists(handle, index),
+                container => Ok(self.dim_lookup(container, index)?.is_some_and(|val| !val.is_null())),
+            };
+        }
+        Ok(self.isset_lookup(expr)?.is_some_and(|val| !val.is_null()))
+    }
+
+    /// Read one `[$index]` level of an isset/`??` chain without warnings
+    fn dim_lookup(&mut self, container: PhpValue, index: PhpValue) -> Result<Option<PhpValue>, String> {
+        Ok(match container {
+            PhpValue::Array(arr) => Self::array_lookup(&arr, &index).cloned(),
+            PhpValue::String(s) => {
+                let offset = index.to_int();
+                let offset = if offset < 0 { offset + s.len() as i64 } else { offset };
+                usize::try_from(offset).ok()
+                    .and_then(|offset| s.get(offset..offset + 1))
+                    .map(|c| PhpValue::String(c.to_string()))
+            }
+            PhpValue::Object(handle) => self.offset_lookup(handle, index)?,
+            _ => None,
+        })
+    }
+
+    /// Resolve a member name after '->' to a string, evaluating dynamic names
+    fn resolve_member_name(&mut self, member: &MemberName) -> Result<String, String> {
+        match member {
+            MemberName::Identifier(name) => Ok(name.clone()),
+            MemberName::Dynamic(expr) => Ok(self.evaluate_expr(expr)?.to_string()),
+        }
+    }
+
+    /// Resolve the class on the left of '::', which may be a name as written or a runtime string or object
+    fn static_class_name(&mut self, class: &ClassName) -> Result<String, String> {
+        match class {
+            ClassName::Identifier(name) => self.resolve_class_reference(name),
+            ClassName::Dynamic(expr) => match self.evaluate_expr(expr)? {
+                PhpValue::Object(handle) => Ok(handle.borrow().class_name.clone()),
+                PhpValue::String(name) => Ok(name.trim_start_matches('\\').to_string()),
+                _ => Err(self.throw_error("Error", "Class name must be a valid object or a string")),
+            },
+            ClassName::Anonymous { name, .. } => Ok(name.clone()),
+        }
+    }
+
+    /// Find the class declaring a static property, walking up from `class_name`; subclasses share the parent's slot
+    fn static_property_class(&mut self, class_name: &str, name: &str) -> Result<String, String> {
+        let mut current = self.context.classes.get(class_name);
+        while let Some(class) = current {
+            if class.static_properties.contains_key(name) {
+                return Ok(class.name.clone());
+            }
+            current = class.parent.as_ref().and_then(|parent| self.context.classes.get(parent));
+        }
+        if !self.context.classes.contains_key(class_name) {
+            return Err(self.throw_error("Error", &format!("Class \"{}\" not found", class_name)));
+        }
+        Err(self.throw_error("Error", &format!("Access to undeclared static property {}::${}", class_name, name)))
+    }
+
+    /// Resolve a class name as written in source, expanding self, parent and static
+    pub(crate) fn resolve_class_reference(&self, class: &str) -> Result<String, String> {
+        // self and parent refer to the class declaring the running method, not the object's class
+        let scope = self.method_scope().or_else(|| {
+            self.current_function.as_deref()
+                .and_then(|name| name.split_once("::"))
+                .map(|(class_name, _)| class_name.to_string())
+        });
+        match class.to_ascii_lowercase().as_str() {
+            "self" => scope.ok_or_else(|| "Cannot use \"self\" when no class scope is active".to_string()),
+            "parent" => {
+                let scope = scope.ok_or_else(|| "Cannot use \"parent\" when no class scope is active".to_string())?;
+                self.context.classes.get(&scope)
+                    .and_then(|c| c.parent.clone())
+                    .ok_or_else(|| "Cannot use \"parent\" when current class scope has no parent".to_string())
+            }
+            // Late static binding: the runtime class of $this, falling back to the defining class
+            "static" => match self.context.get_variable("this") {
+                Some(PhpValue::Object(handle)) => Ok(handle.borrow().class_name.clone()),
+                _ => scope.ok_or_else(|| "Cannot use \"static\" when no class scope is active".to_string()),
+            },
+            _ => Ok(class.trim_start_matches('\\').to_string()),
+        }
+    }
+
+    /// Call a PHP callable value: a function name, closure, "Class::method" string or [$objOrClass, "method"] array
+    pub(crate) fn call_callable(&mut self, callable: &PhpValue, mut args: Vec<PhpValue>) -> Result<PhpValue, String> {
+        self.call_callable_with_refs(callable, &mut args)
+    }
+
+    /// Call a PHP callable, storing the final values of by-reference parameters back into `args`
+    pub(crate) fn call_callable_with_refs(&mut self, callable: &PhpValue, args: &mut [PhpValue]) -> Result<PhpValue, String> {
+        self.dispatch_callable(callable, args, Vec::new())
+    }
+
+    /// Call a PHP callable with named arguments after the positional ones, as call_user_func_array() passes string keys
+    pub(crate) fn call_callable_named(&mut self, callable: &PhpValue, mut args: Vec<PhpValue>, named: Vec<(String, PhpValue)>) -> Result<PhpValue, String> {
+        self.dispatch_callable(callable, &mut args, named)
+    }
+
+    /// Resolve a callable value and call it; by-reference parameters write back into `args`
+    fn dispatch_callable(&mut self, callable: &PhpValue, args: &mut [PhpValue], named: Vec<(String, PhpValue)>) -> Result<PhpValue, String> {
+        match callable {
+            PhpValue::String(name) => {
+                if let Some((class, method)) = name.split_once("::") {
+                    let class_name = self.resolve_class_reference(class)?;
+                    return self.call_static_callable(&class_name, method, args, named);
+                }
+                if let Some(func) = self.context.functions.get(name).cloned() {
+                    return self.invoke_callable(name, &func, args, named, None);
+                }
+                self.call_native_callable(name, args, named)
+            }
+            PhpValue::Array(arr) if arr.len() == 2 => {
+                let target = arr.get_int(0).cloned().unwrap_or(PhpValue::Null);
+                let method = arr.get_int(1).map(|m| m.to_string()).unwrap_or_default();
+                match target {
+                    PhpValue::Object(handle) => {
+                        let class_name = handle.borrow().class_name.clone();
+                        let Some(found) = self.find_method(&class_name, &method) else {
+                            if let Some(magic) = self.find_method(&class_name, "__call") {
+                                let args = Self::magic_call_args(args, named);
+                                return self.invoke_magic_call(&class_name, "__call", &magic.function, &method, args, Some(PhpValue::Object(handle.clone())));
                             }
-                        }
+                            return Err(self.undefined_method(&class_name, &method));
+                        };
+                        let this = if found.is_static { None } else { Some(PhpValue::Object(handle.clone())) };
+                        self.invoke_callable(&format!("{}::{}", class_name, method), &found.function, args, named, this)
                     }
-                    self.current_function = prev_function;
-                    // Restore variables (simple approach - constants/functions persist)
-                    self.context.variables = saved_vars;
-                    Ok(result)
-                } else {
-                    Err(format!("Unknown function: {}", name))
+                    PhpValue::String(class) => {
+                        let class_name = self.resolve_class_reference(&class)?;
+                        self.call_static_callable(&class_name, &method, args, named)
+                    }
+                    _ => Err("Array callback must have exactly two elements: an object or class name and a method name".to_string()),
+                }
+            }
+            PhpValue::Object(handle) if handle.borrow().closure.is_some() => {
+                let id = handle.borrow().closure.clone().unwrap_or_default();
+                let func = self.context.functions.get(&id).cloned().ok_or_else(|| format!("Undefined closure {}", id))?;
+                self.invoke_callable(&id, &func, args, named, None)
+            }
+            _ => Err(format!("Value of type {} is not callable", callable.type_name())),
+        }
+    }
+
+    /// Invoke a user function reached through a callable, moving named arguments to their parameters
+    fn invoke_callable(&mut self, name: &str, func: &Function, args: &mut [PhpValue], named: Vec<(String, PhpValue)>, this: Option<PhpValue>) -> Result<PhpValue, String> {
+        if named.is_empty() {
+            return self.invoke_function_with_refs(name, func, args, this);
+        }
+        let mut values = self.bind_named(name, &func.params, args.to_vec(), named)?;
+        self.invoke_function_with_refs(name, func, &mut values, this)
+    }
+
+    /// Call a built-in through a callable. Builtins take argument expressions, so the values are bound
+    /// to temporaries in a scope of their own, out of reach of PHP code and of nested callable calls.
+    /// Named arguments follow in order, as native functions have no parameter names.
+    fn call_native_callable(&mut self, name: &str, args: &mut [PhpValue], named: Vec<(String, PhpValue)>) -> Result<PhpValue, String> {
+        let this = self.context.get_variable("this").cloned();
+        self.enter_call_frame(None);
+        if let Some(this) = this {
+            self.context.set_variable("this", this);
+        }
+        let values = args.iter().cloned().chain(named.into_iter().map(|(_, value)| value));
+        let temps: Vec<String> = values.enumerate().map(|(i, value)| {
+            let temp = format!("\0callable_arg{}", i);
+            self.context.set_variable(&temp, value);
+            temp
+        }).collect();
+        let arg_exprs: Vec<Expr> = temps.iter().map(|temp| Expr::Variable(temp.clone())).collect();
+        let result = self.call_function(name, &arg_exprs);
+        // By-reference built-ins wrote into the temporaries; hand the values back to the caller
+        for (temp, arg) in temps.iter().zip(args.iter_mut()) {
+            if let Some(value) = self.context.remove_variable(temp) {
+                *arg = value;
+            }
+        }
+        self.leave_call_frame();
+        result
+    }
+
+    /// The `$arguments` array of __call()/__callStatic(): positional values, then named ones under their names
+    fn magic_call_args(args: &[PhpValue], named: Vec<(String, PhpValue)>) -> PhpArray {
+        let mut arguments: PhpArray = args.iter().cloned().collect();
+        for (name, value) in named {
+            arguments.insert_string(name, value);
+        }
+        arguments
+    }
+
+    /// Evaluate a member access chain, returning None when a nullsafe link short-circuits it.
+    /// A short-circuit skips every later link in the chain, including call arguments.
+    fn evaluate_chain(&mut self, expr: &Expr) -> Result<Option<PhpValue>, String> {
+        match expr {
+            Expr::MethodCall { target, method, args } | Expr::NullsafeMethodCall { target, method, args } => {
+                let Some(object) = self.evaluate_chain(target)? else { return Ok(None) };
+                if matches!(expr, Expr::NullsafeMethodCall { .. }) && matches!(object, PhpValue::Null) {
+                    return Ok(None);
+                }
+                self.call_method_on(object, method, args).map(Some)
+            }
+            Expr::PropertyAccess { target, property } | Expr::NullsafePropertyAccess { target, property } => {
+                let Some(object) = self.evaluate_chain(target)? else { return Ok(None) };
+                if matches!(expr, Expr::NullsafePropertyAccess { .. }) && matches!(object, PhpValue::Null) {
+                    return Ok(None);
+                }
+                let property_name = self.resolve_member_name(property)?;
+                match object {
+                    PhpValue::Object(handle) => Ok(Some(handle.borrow().get_property(&property_name).cloned().unwrap_or(PhpValue::Null))),
+                    // PHP emits a warning and yields null when reading a property of a non-object
+                    _ => Ok(Some(PhpValue::Null)),
+                }
+            }
+            _ => self.evaluate_expr(expr).map(Some),
+        }
+    }
+
+    /// Call a method on an already evaluated object, evaluating the arguments only once the call happens
+    fn call_method_on(&mut self, object: PhpValue, method: &MemberName, args: &[Expr]) -> Result<PhpValue, String> {
+        let method_name = self.resolve_member_name(method)?;
+        let PhpValue::Object(handle) = &object else {
+            let message = format!("Call to a member function {}() on {}", method_name, object.type_name());
+            return Err(self.throw_error("Error", &message));
+        };
+        let class_name = handle.borrow().class_name.clone();
+        let Some(method) = self.find_method(&class_name, &method_name) else {
+            if self.is_throwable(&class_name) {
+                let args = self.evaluate_args(args)?;
+                if let Some(result) = self.call_throwable_method(&object, &method_name, args) {
+                    return result;
+                }
+            } else if self.is_subclass_of(&class_name, "DateTime") {
+                let args = self.evaluate_args(args)?;
+                if let Some(result) = crate::builtins::datetime::call_method(self, &object, &method_name, args) {
+                    return result;
+                }
+            } else if class_name == "Generator" {
+                let args = self.evaluate_args(args)?;
+                if let Some(result) = self.call_generator_method(&object, &method_name, args) {
+                    return result;
+                }
+            }
+            if let Some(magic) = self.find_method(&class_name, "__call") {
+                let args = self.evaluate_args(args)?;
+                return self.invoke_magic_call(&class_name, "__call", &magic.function, &method_name, args.into_iter().collect(), Some(object.clone()));
+            }
+            return Err(self.undefined_method(&class_name, &method_name));
+        };
+        let this = if method.is_static { None } else { Some(object.clone()) };
+        let name = format!("{}::{}", class_name, method_name);
+        let args = self.bind_args(&name, args, &method.function.params)?;
+        self.invoke_function(&name, &method.function, args, this)
+    }
+
+    /// Whether a value can be called: a function name, "Class::method", a closure or an [object|class, method] pair
+    pub(crate) fn is_callable(&self, value: &PhpValue) -> bool {
+        let method_exists = |class_name: &str, method: &str, magic: &str| {
+            self.find_method(class_name, method).is_some() || self.find_method(class_name, magic).is_some()
+        };
+        match value {
+            PhpValue::String(name) => match name.split_once("::") {
+                Some((class, method)) => self.resolve_class_reference(class).is_ok_and(|class_name| method_exists(&class_name, method, "__callStatic")),
+                None => self.context.functions.contains_key(name) || self.builtins.contains_key(name),
+            },
+            PhpValue::Array(arr) if arr.len() == 2 => {
+                let method = arr.get_int(1).map(|m| m.to_string()).unwrap_or_default();
+                match arr.get_int(0) {
+                    Some(PhpValue::Object(handle)) => method_exists(&handle.borrow().class_name, &method, "__call"),
+                    Some(PhpValue::String(class)) => self.resolve_class_reference(class).is_ok_and(|class_name| method_exists(&class_name, &method, "__callStatic")),
+                    _ => false,
                 }
             }
+            PhpValue::Object(handle) => handle.borrow().closure.is_some(),
+            _ => false,
+        }
+    }
+
+    /// Throw the Error PHP raises for a call to a method a class does not have
+    fn undefined_method(&mut self, class_name: &str, method: &str) -> String {
+        self.throw_error("Error", &format!("Call to undefined method {}::{}()", class_name, method))
+    }
+
+    /// Forward a call to a missing method to `__call` or `__callStatic` as `($name, $arguments)`
+    fn invoke_magic_call(&mut self, class_name: &str, magic: &str, function: &Function, method: &str, args: PhpArray, this: Option<PhpValue>) -> Result<PhpValue, String> {
+        let args = vec![PhpValue::String(method.to_string()), PhpValue::Array(args)];
+        self.invoke_function(&format!("{}::{}", class_name, magic), function, args, this)
+    }
+
+    /// Call a static method referenced by a callable string or array
+    fn call_static_callable(&mut self, class_name: &str, method: &str, args: &mut [PhpValue], named: Vec<(String, PhpValue)>) -> Result<PhpValue, String> {
+        if !self.context.classes.contains_key(class_name) {
+            return Err(format!("Class \"{}\" not found", class_name));
+        }
+        let Some(found) = self.find_method(class_name, method) else {
+            if let Some(magic) = self.find_method(class_name, "__callStatic") {
+                let args = Self::magic_call_args(args, named);
+                return self.invoke_magic_call(class_name, "__callStatic", &magic.function, method, args, None);
+            }
+            return Err(self.undefined_method(class_name, method));
+        };
+        if !found.is_static {
+            return Err(format!("Non-static method {}::{}() cannot be called statically", class_name, method));
+        }
+        self.invoke_callable(&format!("{}::{}", class_name, method), &found.function, args, named, None)
+    }
+
+    /// Look up a class constant, walking up the parent chain
+    pub(crate) fn class_constant(&self, class_name: &str, name: &str) -> Result<PhpValue, String> {
+        if !self.context.classes.contains_key(class_name) {
+            return Err(format!("Class \"{}\" not found", class_name));
+        }
+        let mut current = self.context.classes.get(class_name);
+        while let Some(class) = current {
+            if let Some(value) = class.constants.get(name) {
+                return Ok(value.clone());
+            }
+            current = class.parent.as_ref().and_then(|parent| self.context.classes.get(parent));
+        }
+        Err(format!("Undefined constant {}::{}", class_name, name))
+    }
+
+    /// Look up a method on a class, walking up the parent chain
+    fn find_method(&self, class_name: &str, method_name: &str) -> Option<Method> {
+        let mut current = self.context.classes.get(class_name);
+        while let Some(class) = current {
+            if let Some(method) = class.methods.get(method_name) {
+                return Some(method.clone());
+            }
+            current = class.parent.as_ref().and_then(|parent| self.context.classes.get(parent));
+        }
+        None
+    }
+
+    /// Wrap a new object in a shared handle, giving it the next handle number
+    pub(crate) fn alloc_object(&mut self, mut object: PhpObject) -> PhpValue {
+        self.last_object_id += 1;
+        object.id = self.last_object_id;
+        PhpValue::object(object)
+    }
+
+    /// Create an object of the given class, initializing properties and running the constructor
+    fn instantiate(&mut self, class_name: &str, args: &[Expr]) -> Result<PhpValue, String> {
+        if class_name == "stdClass" && !self.context.classes.contains_key(class_name) {
+            return Ok(self.alloc_object(PhpObject::new(class_name)));
+        }
+        // Collect the inheritance chain, root class first, so child defaults override parents
+        let mut chain = Vec::new();
+        let mut current = Some(class_name.to_string());
+        while let Some(name) = current {
+            let class = self.context.classes.get(&name).cloned()
+                .ok_or_else(|| format!("Class \"{}\" not found", name))?;
+            current = class.parent.clone();
+            chain.push(class);
+        }
+        let mut object = PhpObject::new(chain[0].name.clone());
+        let throwable = self.is_throwable(class_name);
+        if throwable {
+            for (name, value) in [("message", PhpValue::String(String::new())), ("code", PhpValue::Int(0)), ("previous", PhpValue::Null)] {
+                object.set_property(name, value);
+            }
+        }
+        for class in chain.iter().rev() {
+            for (name, default) in &class.properties {
+                let value = match default { Some(expr) => self.evaluate_expr(expr)?, None => PhpValue::Null };
+                object.set_property(name.clone(), value);
+            }
         }
+        let value = self.alloc_object(object);
+        if let Some(constructor) = self.find_method(&chain[0].name, "__construct") {
+            let name = format!("{}::__construct", chain[0].name);
+            let args = self.bind_args(&name, args, &constructor.function.params)?;
+            self.invoke_function(&name, &constructor.function, args, Some(value.clone()))?;
+        } else if throwable {
+            let args = self.evaluate_args(args)?;
+            if let Some(result) = self.call_throwable_method(&value, "__construct", args) {
+                result?;
+            }
+        } else if self.is_subclass_of(class_name, "DateTime") {
+            let args = self.evaluate_args(args)?;
+            if let Some(result) = crate::builtins::datetime::call_method(self, &value, "__construct", args) {
+                result?;
+            }
+        }
+        Ok(value)
+    }
+
+    /// Declare the promoted constructor parameters as properties of `class` and
+    /// prefix the constructor body with their `$this->name = $name` assignments
+    fn promote_constructor_params(class: &mut Class, params: &[Param], body: Stmt) -> Stmt {
+        let mut statements = Vec::new();
+        for param in params.iter().filter(|param| param.promoted) {
+            if param.readonly {
+                class.readonly_properties.insert(param.name.clone());
+            } else {
+                class.properties.push((param.name.clone(), None));
+            }
+            statements.push(Stmt::Expression(Expr::Assign {
+                target: Box::new(Expr::PropertyAccess {
+                    target: Box::new(Expr::Variable("this".to_string())),
+                    property: MemberName::Identifier(param.name.clone()),
+                }),
+                value: Box::new(Expr::Variable(param.name.clone())),
+            }));
+        }
+        if statements.is_empty() {
+            return body;
+        }
+        match body {
+            Stmt::Block(rest) => statements.extend(rest),
+            other => statements.push(other),
+        }
+        Stmt::Block(statements)
+    }
+
+    /// The class whose method is running, i.e. the class that declares it; None outside methods
+    fn method_scope(&self) -> Option<String> {
+        let (class_name, method) = self.current_function.as_deref()?.split_once("::")?;
+        let mut current = self.context.classes.get(class_name);
+        while let Some(class) = current {
+            if class.methods.contains_key(method) {
+                return Some(class.name.clone());
+            }
+            current = class.parent.as_ref().and_then(|parent| self.context.classes.get(parent));
+        }
+        None
+    }
+
+    /// The class declaring `property` readonly, searching up from `class_name`
+    fn readonly_declaring_class(&self, class_name: &str, property: &str) -> Option<String> {
+        let mut current = self.context.classes.get(class_name);
+        while let Some(class) = current {
+            if class.readonly_properties.contains(property) {
+                return Some(class.name.clone());
+            }
+            current = class.parent.as_ref().and_then(|parent| self.context.classes.get(parent));
+        }
+        None
+    }
+
+    /// Check whether a class is, or extends, the given class (case-insensitive, like PHP class names)
+    fn is_subclass_of(&self, class_name: &str, ancestor: &str) -> bool {
+        let mut current = Some(class_name.to_string());
+        while let Some(name) = current {
+            if name.eq_ignore_ascii_case(ancestor) {
+                return true;
+            }
+            current = self.context.classes.get(&name).and_then(|class| class.parent.clone());
+        }
+        false
+    }
+
+    /// Check whether a class or one of its ancestors declares that it implements `interface`
+    fn implements_interface(&self, class_name: &str, interface: &str) -> bool {
+        let mut current = self.context.classes.get(class_name);
+        while let Some(class) = current {
+            if class.interfaces.iter().any(|name| name.eq_ignore_ascii_case(interface)) {
+                return true;
+            }
+            current = class.parent.as_ref().and_then(|parent| self.context.classes.get(parent));
+        }
+        false
+    }
+
+    /// Check whether objects of a class are Traversable: generators, Iterators and IteratorAggregates
+    pub(crate) fn is_traversable(&self, class_name: &str) -> bool {
+        class_name == "Generator"
+            || self.implements_interface(class_name, "Iterator")
+            || self.implements_interface(class_name, "IteratorAggregate")
+    }
+
+    /// Check whether objects of a class can be thrown (it extends Exception or Error)
+    fn is_throwable(&self, class_name: &str) -> bool {
+        self.is_subclass_of(class_name, "Exception") || self.is_subclass_of(class_name, "Error")
+    }
+
+    /// Start propagating an exception object, returning the error message that carries it
+    fn raise(&mut self, exception: PhpValue) -> String {
+        let message = match &exception {
+            PhpValue::Object(handle) => {
+                let object = handle.borrow();
+                let text = object.get_property("message").map(|m| m.to_string()).unwrap_or_default();
+                format!("Uncaught {}: {}", object.class_name, text)
+            }
+            other => format!("Uncaught {}", other),
+        };
+        self.thrown = Some((exception, message.clone()));
+        message
+    }
+
+    /// Turn an error into the exception it carries; plain runtime errors become `Error` objects
+    fn take_exception(&mut self, message: String) -> PhpValue {
+        match self.thrown.take() {
+            Some((exception, raised_as)) if raised_as == message => exception,
+            _ => self.new_throwable("Error", message),
+        }
+    }
+
+    /// Create an instance of a built-in throwable class with the given message
+    fn new_throwable(&mut self, class_name: &str, message: String) -> PhpValue {
+        let mut error = PhpObject::new(class_name);
+        error.set_property("message", PhpValue::String(message));
+        error.set_property("code", PhpValue::Int(0));
+        error.set_property("previous", PhpValue::Null);
+        self.alloc_object(error)
+    }
+
+    /// Raise a fatal error, such as an engine limit being hit, that no catch block can intercept
+    pub(crate) fn fatal_error(&mut self, message: String) -> String {
+        self.fatal = Some(message.clone());
+        message
     }
 
-    /// Simple percent-decoding helper (handles + -> space and %XX hex sequences)
-    fn percent_decode(input: &str) -> String {
-        let mut bytes = Vec::with_capacity(input.len());
-        let mut chars = input.as_bytes().iter().cloned().peekable();
-        while let Some(b) = chars.next() {
-            match b {
-                b'+' => bytes.push(b' '),
-                b'%' => {
-                    let h1 = chars.next();
-                    let h2 = chars.next();
-                    if let (Some(c1), Some(c2)) = (h1, h2) {
-                        let hex = [c1, c2];
-                        if let Ok(s) = std::str::from_utf8(&hex) {
-                            if let Ok(v) = u8::from_str_radix(s, 16) { bytes.push(v); continue; }
+    /// Loose `==` comparison; comparing objects that refer back to themselves is a fatal error
+    pub(crate) fn loose_equals(&mut self, left: &PhpValue, right: &PhpValue) -> Result<bool, String> {
+        php_types::php_equals(left, right).map_err(|error| self.fatal_error(error.to_string()))
+    }
+
+    /// Check whether `message` is the fatal error currently propagating
+    pub(crate) fn is_fatal(&self, message: &str) -> bool {
+        self.fatal.as_deref() == Some(message)
+    }
+
+    /// Raise a built-in error such as `DivisionByZeroError`, returning the message to propagate
+    pub(crate) fn throw_error(&mut self, class_name: &str, message: &str) -> String {
+        let error = self.new_throwable(class_name, message.to_string());
+        self.raise(error)
+    }
+
+    /// Raise a built-in exception carrying an error code, such as `JsonException`
+    pub(crate) fn throw_error_with_code(&mut self, class_name: &str, message: &str, code: i64) -> String {
+        let error = self.new_throwable(class_name, message.to_string());
+        if let PhpValue::Object(handle) = &error {
+            handle.borrow_mut().set_property("code", PhpValue::Int(code));
+        }
+        self.raise(error)
+    }
+
+    /// Run the first catch clause matching the exception behind `message`, or keep propagating it
+    fn handle_exception(&mut self, catches: &[CatchClause], message: String) -> Result<ExecSignal, String> {
+        let index = self.catch_clause(catches, message)?;
+        self.exec(&catches[index].body)
+    }
+
+    /// Find the first catch clause matching the exception behind `message` and bind the exception
+    /// to its variable, or keep propagating it
+    pub(crate) fn catch_clause(&mut self, catches: &[CatchClause], message: String) -> Result<usize, String> {
+        if self.is_fatal(&message) {
+            return Err(message);
+        }
+        let exception = self.take_exception(message);
+        let class_name = match &exception {
+            PhpValue::Object(handle) => handle.borrow().class_name.clone(),
+            _ => String::new(),
+        };
+        let index = catches.iter().position(|clause| {
+            clause.types.iter().any(|ty| ty.eq_ignore_ascii_case("Throwable") || self.is_subclass_of(&class_name, ty))
+        });
+        match index {
+            Some(index) => {
+                if let Some(variable) = &catches[index].variable {
+                    self.context.set_variable(variable, exception);
+                }
+                Ok(index)
+            }
+            None => Err(self.raise(exception)),
+        }
+    }
+
+    /// Methods Exception and Error provide natively; None if `method` is not one of them
+    fn call_throwable_method(&mut self, object: &PhpValue, method: &str, args: Vec<PhpValue>) -> Option<Result<PhpValue, String>> {
+        let PhpValue::Object(handle) = object else { return None };
+        let property = |name: &str| handle.borrow().get_property(name).cloned().unwrap_or(PhpValue::Null);
+        let result = match method {
+            "__construct" => {
+                let mut args = args.into_iter();
+                let mut object = handle.borrow_mut();
+                if let Some(message) = args.next() { object.set_property("message", PhpValue::String(message.to_string())); }
+                if let Some(code) = args.next() { object.set_property("code", PhpValue::Int(code.to_int())); }
+                if let Some(previous) = args.next() { object.set_property("previous", previous); }
+                PhpValue::Null
+            }
+            "getMessage" => property("message"),
+            "getCode" => property("code"),
+            "getPrevious" => property("previous"),
+            "getFile" => PhpValue::String(String::new()),
+            "getLine" => PhpValue::Int(0),
+            "getTrace" => PhpValue::Array(PhpArray::new()),
+            "getTraceAsString" => PhpValue::String("#0 {main}".to_string()),
+            "__toString" => {
+                let class_name = handle.borrow().class_name.clone();
+                PhpValue::String(format!("{}: {}", class_name, property("message")))
+            }
+            _ => return None,
+        };
+        Some(Ok(result))
+    }
+
+    /// Call a built-in or user-defined function
+    fn call_function(&mut self, name: &str, args: &[Expr]) -> Result<PhpValue, String> {
+        if let Some(native) = self.builtins.get(name).cloned() {
+            return native(self, args);
+        }
+        // User-defined function?
+        if let Some(func) = self.context.functions.get(name).cloned() {
+            let mut values = self.bind_args(name, args, &func.params)?;
+            let result = self.invoke_function_with_refs(name, &func, &mut values, None)?;
+            // By-reference parameters write their final value back into the argument
+            for ((param, arg), value) in func.params.iter().zip(args).zip(values) {
+                if param.by_ref && !param.variadic {
+                    self.write_back(arg, value)?;
+                }
+            }
+            Ok(result)
+        } else {
+            Err(self.throw_error("Error", &format!("Call to undefined function {}()", name)))
+        }
+    }
+
+    /// Store a closure as a generated function, returning the Closure object that refers to it
+    fn define_closure(&mut self, function: Function) -> PhpValue {
+        let id = format!("__closure_{}", self.context.functions.len());
+        self.context.functions.insert(id.clone(), function);
+        self.alloc_object(PhpObject::closure(id))
+    }
+
+    /// Evaluate call arguments left to right in the caller's scope, unpacking `...$args`.
+    /// Named arguments are taken in order, as native functions have no parameter names.
+    pub(crate) fn evaluate_args(&mut self, args: &[Expr]) -> Result<Vec<PhpValue>, String> {
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            match arg {
+                Expr::Spread(inner) => {
+                    let unpacked = self.evaluate_expr(inner)?;
+                    values.extend(self.traverse(unpacked)?.into_iter().map(|(_, value)| value));
+                }
+                Expr::NamedArgument { value, .. } => values.push(self.evaluate_expr(value)?),
+                other => values.push(self.evaluate_expr(other)?),
+            }
+        }
+        Ok(values)
+    }
+
+    /// Evaluate the arguments of a call to the user function `function`, moving named
+    /// arguments (and string keys of unpacked arrays) to their parameter's position.
+    /// Parameters skipped over by named arguments take their defaults.
+    fn bind_args(&mut self, function: &str, args: &[Expr], params: &[Param]) -> Result<Vec<PhpValue>, String> {
+        let mut positional = Vec::with_capacity(args.len());
+        let mut named: Vec<(String, PhpValue)> = Vec::new();
+        for arg in args {
+            match arg {
+                Expr::Spread(inner) => {
+                    let unpacked = self.evaluate_expr(inner)?;
+                    for (key, value) in self.traverse(unpacked)? {
+                        match key {
+                            PhpValue::String(name) => named.push((name, value)),
+                            _ => positional.push(value),
                         }
-                        // Fallback: push literal
-                        bytes.push(b'%'); bytes.push(c1); bytes.push(c2);
-                    } else {
-                        bytes.push(b'%');
-                        if let Some(c1) = h1 { bytes.push(c1); }
-                        if let Some(c2) = h2 { bytes.push(c2); }
                     }
                 }
-                _ => bytes.push(b),
+                Expr::NamedArgument { name, value } => {
+                    let value = self.evaluate_expr(value)?;
+                    named.push((name.clone(), value));
+                }
+                other if named.is_empty() => positional.push(self.evaluate_expr(other)?),
+                _ => return Err(self.throw_error("Error", "Cannot use positional argument after named argument")),
+            }
+        }
+        if named.is_empty() {
+            return Ok(positional);
+        }
+        self.bind_named(function, params, positional, named)
+    }
+
+    /// Place named arguments after the positional ones at their parameter's position, filling skipped parameters with defaults
+    fn bind_named(&mut self, function: &str, params: &[Param], positional: Vec<PhpValue>, named: Vec<(String, PhpValue)>) -> Result<Vec<PhpValue>, String> {
+        let mut slots: Vec<Option<PhpValue>> = positional.into_iter().map(Some).collect();
+        for (name, value) in named {
+            let Some(index) = params.iter().position(|param| param.name == name && !param.variadic) else {
+                return Err(self.throw_error("Error", &format!("Unknown named parameter ${}", name)));
+            };
+            if slots.len() <= index {
+                slots.resize(index + 1, None);
+            }
+            if slots[index].is_some() {
+                return Err(self.throw_error("Error", &format!("Named parameter ${} overwrites previous argument", name)));
+            }
+            slots[index] = Some(value);
+        }
+        let mut values = Vec::with_capacity(slots.len());
+        for (index, slot) in slots.into_iter().enumerate() {
+            let value = match (slot, &params[index].default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => self.evaluate_expr(default)?,
+                (None, None) => {
+                    let message = format!("{}(): Argument #{} (${}) not passed", function, index + 1, params[index].name);
+                    return Err(self.throw_error("ArgumentCountError", &message));
+                }
+            };
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    /// Invoke a user-defined function or method body, binding `$this` for instance methods
+    fn invoke_function(&mut self, name: &str, func: &Function, mut args: Vec<PhpValue>, this: Option<PhpValue>) -> Result<PhpValue, String> {
+        self.invoke_function_with_refs(name, func, &mut args, this)
+    }
+
+    /// Invoke a user-defined function, storing the final values of by-reference parameters back into `args`
+    fn invoke_function_with_refs(&mut self, name: &str, func: &Function, args: &mut [PhpValue], this: Option<PhpValue>) -> Result<PhpValue, String> {
+        // Parameters up to the last required one must be supplied; like PHP, extra
+        // arguments are collected by a variadic parameter or otherwise ignored
+        let required = func.params.iter().rposition(Param::is_required).map_or(0, |index| index + 1);
+        if args.len() < required {
+            let bound = if required == func.params.len() { "exactly" } else { "at least" };
+            let message = format!("Too few arguments to function {}(), {} passed and {} {} expected", name, args.len(), bound, required);
+            return Err(self.throw_error("ArgumentCountError", &message));
+        }
+        // Start a fresh frame: callees only see their parameters, statics and superglobals
+        self.enter_call_frame(None);
+        let prev_function = self.current_function.clone();
+        self.current_function = Some(name.to_string());
+        self.static_var_stack.push((name.to_string(), Vec::new()));
+        for (name, value) in &func.captured {
+            self.context.set_variable(name, value.clone());
+        }
+        self.bind_references(func);
+        if let Some(this) = this {
+            self.context.set_variable("this", this);
+        }
+        // Bind parameters, falling back to defaults evaluated in the callee's frame
+        let mut result = Ok(ExecSignal::None);
+        for (index, param) in func.params.iter().enumerate() {
+            let value = if param.variadic {
+                PhpValue::Array(args.iter().skip(index).cloned().collect())
+            } else if let Some(value) = args.get_mut(index) {
+                // By-value arguments stay intact for callers that reuse the slice
+                if param.by_ref { std::mem::replace(value, PhpValue::Null) } else { value.clone() }
+            } else if let Some(default) = &param.default {
+                match self.evaluate_expr(default) {
+                    Ok(value) => value,
+                    Err(error) => { result = Err(error); break; }
+                }
+            } else {
+                PhpValue::Null
+            };
+            self.context.set_variable(&param.name, value);
+        }
+        // Execute body; a generator's body only runs once the Generator it returns is advanced
+        if result.is_ok() && !func.is_generator {
+            result = self.exec(&func.body);
+        }
+        for (param, val) in func.params.iter().zip(args.iter_mut()) {
+            if param.by_ref && !param.variadic {
+                *val = self.context.get_variable(&param.name).cloned().unwrap_or(PhpValue::Null);
+            }
+        }
+        self.store_references(func);
+        self.save_statics();
+        self.current_function = prev_function;
+        let scope = self.leave_call_frame();
+        let result = result?;
+        if func.is_generator {
+            return Ok(self.create_generator(name, func, scope));
+        }
+        match result {
+            ExecSignal::Return(v) => Ok(v.unwrap_or(PhpValue::Null)),
+            _ => Ok(PhpValue::Null),
+        }
+    }
+
+    /// Enter a new variable scope for a call, or resume a suspended one, carrying the superglobals into it
+    pub(crate) fn enter_call_frame(&mut self, scope: Option<SavedScope>) {
+        let superglobals: Vec<(&str, PhpValue)> = SUPERGLOBALS.iter()
+            .filter_map(|name| self.context.get_variable(name).map(|value| (*name, value.clone())))
+            .collect();
+        match scope {
+            Some(scope) => self.context.resume_frame(scope),
+            None => self.context.enter_frame(),
+        }
+        for (name, value) in superglobals {
+            self.context.set_variable(name, value);
+        }
+    }
+
+    /// Restore the caller's scope, keeping any superglobal updates made by the callee;
+    /// returns the callee's scope
+    pub(crate) fn leave_call_frame(&mut self) -> Option<SavedScope> {
+        let superglobals: Vec<(&str, PhpValue)> = SUPERGLOBALS.iter()
+            .filter_map(|name| self.context.remove_variable(name).map(|value| (*name, value)))
+            .collect();
+        let scope = self.context.suspend_frame();
+        for (name, value) in superglobals {
+            self.context.set_variable(name, value);
+        }
+        scope
+    }
+
+    /// Read the variables a closure captured by reference from their defining scope
+    pub(crate) fn bind_references(&mut self, func: &Function) {
+        for reference in &func.references {
+            let value = self.context.frame_variable(reference.frame, &reference.name)
+                .unwrap_or_else(|| reference.value.borrow().clone());
+            self.context.set_variable(&reference.name, value);
+        }
+    }
+
+    /// Write by-reference captures back to the defining scope, if it is still running
+    pub(crate) fn store_references(&mut self, func: &Function) {
+        for reference in &func.references {
+            let value = self.context.get_variable(&reference.name).cloned().unwrap_or(PhpValue::Null);
+            *reference.value.borrow_mut() = value.clone();
+            self.context.set_frame_variable(reference.frame, &reference.name, value);
+        }
+    }
+
+    /// Pop the returning function's static variable list, persisting the statics it declared
+    pub(crate) fn save_statics(&mut self) -> Vec<String> {
+        let Some((fn_name, vars)) = self.static_var_stack.pop() else { return Vec::new() };
+        if let Some(store) = self.static_storage.get_mut(&fn_name) {
+            for var in &vars {
+                if let Some(val) = self.context.get_variable(var).cloned() {
+                    store.insert(var.clone(), val);
+                }
             }
         }
-        String::from_utf8_lossy(&bytes).into_owned()
+        vars
+    }
+
+    /// Drain a Traversable into an array, as iterator_to_array() does: later pairs
+    /// overwrite earlier ones with the same key
+    pub(crate) fn iterate(&mut self, value: PhpValue) -> Result<PhpArray, String> {
+        if let PhpValue::Array(arr) = value {
+            return Ok(arr);
+        }
+        let mut arr = PhpArray::new();
+        for (key, value) in self.traverse(value)? {
+            match key {
+                PhpValue::Int(key) => arr.insert_int(key, value),
+                PhpValue::Null => arr.push(value)?,
+                key => arr.insert_string(key.to_string(), value),
+            }
+        }
+        Ok(arr)
+    }
+
+    /// The key/value pairs of an array or Traversable, in order and with any repeated keys:
+    /// a generator, an `Iterator` or an `IteratorAggregate` via getIterator()
+    pub(crate) fn traverse(&mut self, value: PhpValue) -> Result<Vec<(PhpValue, PhpValue)>, String> {
+        let handle = match value {
+            PhpValue::Array(arr) => return Ok(arr.data.into_iter().map(|(key, value)| (key.to_value(), value)).collect()),
+            PhpValue::Object(handle) => handle,
+            other => return Err(format!("{} is not traversable", other.type_name())),
+        };
+        let class_name = handle.borrow().class_name.clone();
+        if class_name == "Generator" {
+            return self.generator_pairs(PhpValue::Object(handle));
+        }
+        let this = PhpValue::Object(handle.clone());
+        if self.implements_interface(&class_name, "IteratorAggregate") {
+            let inner = self.call_method(&this, &class_name, "getIterator", Vec::new())?;
+            let traversable = matches!(&inner, PhpValue::Object(inner) if self.is_traversable(&inner.borrow().class_name));
+            if !traversable {
+                let message = format!("{}::getIterator() must return a Traversable, {} returned", class_name, inner.type_name());
+                return Err(self.throw_error("TypeError", &message));
+            }
+            return self.traverse(inner);
+        }
+        if !self.implements_interface(&class_name, "Iterator") {
+            return Err(self.throw_error("TypeError", &format!("{} is not Traversable", class_name)));
+        }
+        let mut pairs = Vec::new();
+        self.call_method(&this, &class_name, "rewind", Vec::new())?;
+        while self.call_method(&this, &class_name, "valid", Vec::new())?.is_truthy() {
+            let value = self.call_method(&this, &class_name, "current", Vec::new())?;
+            let key = self.call_method(&this, &class_name, "key", Vec::new())?;
+            pairs.push((key, value));
+            self.call_method(&this, &class_name, "next", Vec::new())?;
+        }
+        Ok(pairs)
+    }
+
+    /// Call an instance method of an object the engine uses through a protocol (Iterator, ArrayAccess)
+    fn call_method(&mut self, this: &PhpValue, class_name: &str, method: &str, args: Vec<PhpValue>) -> Result<PhpValue, String> {
+        let found = self.find_method(class_name, method).ok_or_else(|| format!("Call to undefined method {}::{}()", class_name, method))?;
+        self.invoke_function(&format!("{}::{}", class_name, method), &found.function, args, Some(this.clone()))
+    }
+
+    /// Read `$object[$offset]` through ArrayAccess::offsetExists()/offsetGet(), as `??` and nested isset() levels do:
+    /// a missing offset yields None without calling offsetGet()
+    fn offset_lookup(&mut self, handle: PhpObjectRef, offset: PhpValue) -> Result<Option<PhpValue>, String> {
+        if !self.offset_exists(handle.clone(), offset.clone())? {
+            return Ok(None);
+        }
+        let class_name = handle.borrow().class_name.clone();
+        self.call_method(&PhpValue::Object(handle), &class_name, "offsetGet", vec![offset]).map(Some)
+    }
+
+    /// Ask ArrayAccess::offsetExists() whether `$object[$offset]` is set; objects without it have no offsets
+    fn offset_exists(&mut self, handle: PhpObjectRef, offset: PhpValue) -> Result<bool, String> {
+        let class_name = handle.borrow().class_name.clone();
+        if self.find_method(&class_name, "offsetExists").is_none() {
+            return Ok(false);
+        }
+        Ok(self.call_method(&PhpValue::Object(handle), &class_name, "offsetExists", vec![offset])?.is_truthy())
     }
 
     /// Get execution output
@@ -1068,42 +2342,252 @@ impl Engine {
         self.context.get_output()
     }
 
-    /// Perform simple variable interpolation in strings: replaces $var with its string value
-    fn interpolate_string(&self, input: &str) -> String {
-        // Simple state machine scan
+    /// Interpolate variables into a double-quoted string: `$var`, `$arr[key]`,
+    /// `$obj->prop`, complex `{$expr}` and the deprecated `${var}`, which also
+    /// raises E_DEPRECATED. Undefined variables interpolate as an empty string.
+    /// The lexer doubles backslashes before a `$` and adds one before an escaped
+    /// `\$`, so a `$` after an odd run of backslashes is literal.
+    fn interpolate_string(&mut self, input: &str) -> Result<String, String> {
         let mut result = String::with_capacity(input.len());
-        let chars: Vec<char> = input.chars().collect();
-        let mut i = 0;
-        while i < chars.len() {
-            let c = chars[i];
-            if c == '$' {
-                // Attempt variable name
-                let mut j = i + 1;
-                if j < chars.len() && (chars[j].is_ascii_alphabetic() || chars[j] == '_') {
-                    j += 1;
-                    while j < chars.len() && (chars[j].is_ascii_alphanumeric() || chars[j] == '_') {
-                        j += 1;
-                    }
-                    let var_name: String = chars[i+1..j].iter().collect();
-                    if let Some(val) = self.context.get_variable(&var_name) {
-                        result.push_str(&val.to_string());
-                    } else {
-                        // Undefined stays as is
-                        result.push('$');
-                        result.push_str(&var_name);
-                    }
-                    i = j;
+        let mut rest = input;
+        while let Some(offset) = rest.find(['$', '{', '\\']) {
+            result.push_str(&rest[..offset]);
+            rest = &rest[offset..];
+            if rest.starts_with('\\') {
+                let run = rest.len() - rest.trim_start_matches('\\').len();
+                if !rest[run..].starts_with('$') {
+                    result.push_str(&rest[..run]);
+                    rest = &rest[run..];
                     continue;
-                } else {
+                }
+                result.push_str(&rest[..run / 2]);
+                rest = &rest[run..];
+                if run % 2 == 1 {
                     result.push('$');
-                    i += 1;
+                    rest = &rest[1..];
+                }
+                continue;
+            }
+            // Complex syntax: any expression starting with `$` inside `{...}`
+            if rest.starts_with("{$") {
+                if let Some(close) = complex_interpolation_end(rest) {
+                    let value = self.evaluate_interpolated(&rest[1..close])?;
+                    result.push_str(&value.to_string());
+                    rest = &rest[close + 1..];
                     continue;
                 }
             }
-            result.push(c);
-            i += 1;
+            if let Some(name) = rest.strip_prefix("${").and_then(|inner| interpolation_name(inner).filter(|name| inner[name.len()..].starts_with('}'))) {
+                self.raise_error(ErrorLevel::Deprecated, "Using ${var} in strings is deprecated, use {$var} instead");
+                if let Some(val) = self.context.get_variable(name) {
+                    result.push_str(&val.to_string());
+                }
+                rest = &rest[name.len() + 3..];
+                continue;
+            }
+            if let Some(name) = rest.strip_prefix('$').and_then(interpolation_name) {
+                rest = &rest[name.len() + 1..];
+                let mut expr = Expr::Variable(name.to_string());
+                // Simple syntax allows one level of `[key]` or `->prop` after the name
+                if let Some((index, len)) = simple_interpolation_index(rest) {
+                    expr = Expr::ArrayAccess { array: Box::new(expr), index: Box::new(index) };
+                    rest = &rest[len..];
+                } else if let Some(prop) = rest.strip_prefix("->").and_then(interpolation_name) {
+                    expr = Expr::PropertyAccess { target: Box::new(expr), property: MemberName::Identifier(prop.to_string()) };
+                    rest = &rest[prop.len() + 2..];
+                }
+                result.push_str(&self.evaluate_expr(&expr)?.to_string());
+                continue;
+            }
+            result.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    /// Evaluate the source of a `{$...}` interpolation as an expression
+    fn evaluate_interpolated(&mut self, source: &str) -> Result<PhpValue, String> {
+        let syntax_error = |e: String| format!("Syntax error in string interpolation {{{}}}: {}", source, e);
+        let tokens = php_lexer::lex(&format!("<?php {};", source)).map_err(|e| syntax_error(e.to_string()))?;
+        match php_parser::parse(tokens).map_err(|e| syntax_error(e.to_string()))? {
+            Stmt::Block(statements) => match statements.as_slice() {
+                [Stmt::Expression(expr)] => self.evaluate_expr(expr),
+                _ => Err(syntax_error("expected a single expression".to_string())),
+            },
+            _ => Err(syntax_error("expected a single expression".to_string())),
+        }
+    }
+
+    /// Run `source` as PHP code in the current scope, as eval() does, returning
+    /// the value of its top-level `return` or null. Syntax errors throw ParseError.
+    pub(crate) fn eval_source(&mut self, source: &str) -> Result<PhpValue, String> {
+        if self.eval_depth >= self.limits.max_eval_depth {
+            return Err(self.fatal_error(format!("eval(): Maximum nesting level of {} reached", self.limits.max_eval_depth)));
+        }
+        let parsed = php_lexer::lex(&format!("<?php {}", source))
+            .map_err(|e| e.to_string())
+            .and_then(|tokens| php_parser::parse(tokens).map_err(|e| e.to_string()));
+        let program = match parsed {
+            Ok(program) => program,
+            Err(message) => return Err(self.throw_error("ParseError", &format!("syntax error in eval()'d code: {}", message))),
+        };
+        self.eval_depth += 1;
+        let signal = self.exec(&program);
+        self.eval_depth -= 1;
+        match signal? {
+            ExecSignal::Return(value) => Ok(value.unwrap_or(PhpValue::Null)),
+            _ => Ok(PhpValue::Null),
         }
-        result
     }
 
 }
+
+/// Collect the variables an arrow function body reads from its defining scope: every `$name`
+/// in it, including nested arrow functions and the `use` lists of nested closures. Names that
+/// turn out to be unused, such as a nested arrow function's parameters, are harmless extras.
+fn collect_arrow_captures(expr: &Expr, names: &mut HashSet<String>) {
+    let class = |class: &ClassName, names: &mut HashSet<String>| {
+        if let ClassName::Dynamic(expr) = class {
+            collect_arrow_captures(expr, names);
+        }
+    };
+    let member = |member: &MemberName, names: &mut HashSet<String>| {
+        if let MemberName::Dynamic(expr) = member {
+            collect_arrow_captures(expr, names);
+        }
+    };
+    match expr {
+        Expr::Variable(name) => {
+            names.insert(name.clone());
+        }
+        Expr::InterpolatedString(template) => {
+            for (index, _) in template.match_indices('$') {
+                if let Some(name) = interpolation_name(template[index + 1..].trim_start_matches('{')) {
+                    names.insert(name.to_string());
+                }
+            }
+        }
+        Expr::Closure { uses, .. } => names.extend(uses.iter().map(|var| var.name.clone())),
+        Expr::VariableVariable(inner) | Expr::YieldFrom(inner) | Expr::Throw(inner) | Expr::Spread(inner)
+        | Expr::NamedArgument { value: inner, .. } | Expr::Unary { operand: inner, .. } | Expr::ArrowFunction { body: inner, .. } => {
+            collect_arrow_captures(inner, names);
+        }
+        Expr::Assign { target: left, value: right } | Expr::Binary { left, right, .. }
+        | Expr::ArrayAccess { array: left, index: right } | Expr::NullCoalesce { left, right } => {
+            collect_arrow_captures(left, names);
+            collect_arrow_captures(right, names);
+        }
+        Expr::Yield { key, value } => {
+            if let Some(key) = key {
+                collect_arrow_captures(key, names);
+            }
+            collect_arrow_captures(value, names);
+        }
+        Expr::MethodCall { target, method, args } | Expr::NullsafeMethodCall { target, method, args } => {
+            collect_arrow_captures(target, names);
+            member(method, names);
+            args.iter().for_each(|arg| collect_arrow_captures(arg, names));
+        }
+        Expr::PropertyAccess { target, property } | Expr::NullsafePropertyAccess { target, property } => {
+            collect_arrow_captures(target, names);
+            member(property, names);
+        }
+        Expr::StaticCall { class: name, args, .. } | Expr::New { class: name, args } => {
+            class(name, names);
+            args.iter().for_each(|arg| collect_arrow_captures(arg, names));
+        }
+        Expr::ClassConstant { class: name, .. } | Expr::StaticProperty { class: name, .. } => class(name, names),
+        Expr::FunctionCall { args, .. } => args.iter().for_each(|arg| collect_arrow_captures(arg, names)),
+        Expr::DynamicCall { target, args } => {
+            collect_arrow_captures(target, names);
+            args.iter().for_each(|arg| collect_arrow_captures(arg, names));
+        }
+        Expr::Array(elements) => {
+            for element in elements {
+                if let Some(key) = &element.key {
+                    collect_arrow_captures(key, names);
+                }
+                collect_arrow_captures(&element.value, names);
+            }
+        }
+        Expr::Ternary { condition, then_expr, else_expr } => {
+            collect_arrow_captures(condition, names);
+            if let Some(then_expr) = then_expr {
+                collect_arrow_captures(then_expr, names);
+            }
+            collect_arrow_captures(else_expr, names);
+        }
+        Expr::Match { subject, arms, default_arm } => {
+            collect_arrow_captures(subject, names);
+            for (conditions, result) in arms {
+                conditions.iter().for_each(|condition| collect_arrow_captures(condition, names));
+                collect_arrow_captures(result, names);
+            }
+            if let Some(default_arm) = default_arm {
+                collect_arrow_captures(default_arm, names);
+            }
+        }
+        Expr::Constant(_) | Expr::Number(_) | Expr::Int(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null => {}
+    }
+}
+
+/// Byte offset of the `}` closing the `{$...}` interpolation at the start of
+/// `input`, skipping braces nested in the expression or in quoted keys
+fn complex_interpolation_end(input: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (i, c) in input.char_indices() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '\'' | '"' => quote = Some(c),
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The `[key]` following a variable in simple interpolation, as an index
+/// expression plus the length consumed. Keys may be bare words (`$a[key]`),
+/// quoted (`$a['key']`), integers (`$a[0]`, `$a[-1]`) or variables (`$a[$i]`).
+fn simple_interpolation_index(input: &str) -> Option<(Expr, usize)> {
+    let close = input.strip_prefix('[')?.find(']')? + 1;
+    let key = &input[1..close];
+    let index = if let Some(name) = key.strip_prefix('$') {
+        Expr::Variable(interpolation_name(name).filter(|n| n.len() == name.len())?.to_string())
+    } else if let Ok(int) = key.parse::<i64>() {
+        Expr::Int(int)
+    } else if key.len() >= 2 && (key.starts_with('\'') && key.ends_with('\'') || key.starts_with('"') && key.ends_with('"')) {
+        Expr::String(key[1..key.len() - 1].to_string())
+    } else {
+        Expr::String(interpolation_name(key).filter(|n| n.len() == key.len())?.to_string())
+    };
+    Some((index, close + 1))
+}
+
+/// The variable name at the start of `input`, if it starts with one
+fn interpolation_name(input: &str) -> Option<&str> {
+    let first = input.chars().next()?;
+    if !(first.is_ascii_alphabetic() || first == '_') {
+        return None;
+    }
+    let end = input.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(input.len());
+    Some(&input[..end])
+}
@@ -0,0 +1,737 @@
+//! Lazy generators
+//!
+//! Calling a function whose body contains `yield` binds its arguments and returns a
+//! Generator without running the body. The body runs when the generator is advanced and
+//! stops at the next `yield`. The interpreter cannot suspend its own call stack, so a
+//! generator body runs through `resume_stmt`, which records in a `Frame` how far each
+//! statement containing a `yield` got: the position in a block, the branch taken, the
+//! stage of a loop. Resuming walks back down those frames; statements without a `yield`
+//! run as usual.
+//!
+//! A statement suspended at a `yield` is evaluated again from the start on resume, and the
+//! yields it already passed evaluate to the values sent to them instead of yielding again
+//! (see `YieldPoint`). Operands evaluated before a `yield` in the same statement, such as
+//! `f()` in `$x = f() + yield;`, therefore run again on every resume.
+
+use crate::engine::{Engine, ExecSignal, Function, SavedScope};
+use php_parser::ast::{ClassName, DestructTarget, Expr, MemberName, Stmt};
+use php_types::{PhpObject, PhpValue};
+
+/// Error a `yield` returns to unwind the statement it suspends; `replay` turns it back into a suspension
+const SUSPENDED: &str = "\0generator suspended";
+
+/// A Generator object's function call
+pub(crate) struct Generator {
+    /// Name the function was called by
+    name: String,
+    /// The generator function
+    function: Function,
+    /// Variables of the suspended body; None once the body has finished
+    scope: Option<SavedScope>,
+    /// Static variables the body has declared
+    statics: Vec<String>,
+    /// How far the statements the body is suspended in got, outermost first
+    frames: Vec<Frame>,
+    /// Key and value of the yield the body is suspended at
+    current: Option<(PhpValue, PhpValue)>,
+    /// Key of the next `yield` without an explicit key
+    next_key: i64,
+    /// Whether the body has run up to its first yield
+    started: bool,
+    /// Whether the generator moved past its first yield, after which it cannot be rewound
+    advanced: bool,
+    /// Value of the body's `return` once it finished without an exception
+    return_value: Option<PhpValue>,
+}
+
+impl Generator {
+    /// Key of a yielded pair: without an explicit key the generator numbers its values, and like PHP,
+    /// an explicit integer key moves the numbering past it unless `yield from` passed it on
+    fn key(&mut self, key: Option<PhpValue>, delegated: bool) -> PhpValue {
+        match key {
+            Some(PhpValue::Int(key)) if !delegated => {
+                self.next_key = self.next_key.max(key.saturating_add(1));
+                PhpValue::Int(key)
+            }
+            Some(key) => key,
+            None => {
+                self.next_key += 1;
+                PhpValue::Int(self.next_key - 1)
+            }
+        }
+    }
+}
+
+/// Progress of a foreach loop or a `yield from`
+pub(crate) enum Iteration {
+    /// Remaining pairs of an array, of an object's properties or of a drained Iterator
+    Pairs(std::vec::IntoIter<(PhpValue, PhpValue)>),
+    /// A generator, advanced one value at a time, by handle number; whether its first value was taken
+    Generator(u64, bool),
+}
+
+/// The yields one statement of a generator body passed, so evaluating it again on resume
+/// answers them instead of yielding again
+#[derive(Default)]
+pub(crate) struct YieldPoint {
+    /// Values the yields passed so far evaluated to, in evaluation order
+    results: Vec<PhpValue>,
+    /// Number of yields the current evaluation has passed
+    passed: usize,
+    /// Pair the statement suspended with: explicit key, value, and whether `yield from` passed it on
+    yielded: Option<(Option<PhpValue>, PhpValue, bool)>,
+    /// Iteration a `yield from` the statement is suspended in delegates to
+    delegate: Option<Iteration>,
+    /// Value sent in on resume, passed on to a delegate generator
+    sent: Option<PhpValue>,
+}
+
+impl YieldPoint {
+    /// Value of the next yield if the statement passed it before
+    fn replayed(&mut self) -> Option<PhpValue> {
+        let result = self.results.get(self.passed).cloned()?;
+        self.passed += 1;
+        Some(result)
+    }
+
+    /// Record the value a yield evaluated to
+    fn pass(&mut self, result: PhpValue) {
+        self.results.push(result);
+        self.passed += 1;
+    }
+
+    /// Take the value sent into the generator when it resumes at this point
+    fn receive(&mut self, sent: PhpValue) {
+        if self.delegate.is_some() {
+            self.sent = Some(sent);
+        } else {
+            self.results.push(sent);
+        }
+    }
+}
+
+/// How far a statement of a suspended generator body got
+enum Frame {
+    /// Index of the statement running in a block
+    Block(usize),
+    /// `if`: the branch taken, once the condition was evaluated
+    If(Option<bool>),
+    /// while, do-while or for loop
+    Loop(LoopStage),
+    /// foreach: the iteration and whether the body is running, once the subject was evaluated
+    Foreach(Option<(Iteration, bool)>),
+    /// switch: the case matched (None for default) and the index of the statement running in it
+    Switch(Option<(Option<usize>, usize)>),
+    /// try/catch/finally
+    Try(TryStage),
+    /// A statement without nested statements, suspended at a yield
+    Replay(Box<YieldPoint>),
+}
+
+/// Part of a loop that is running
+#[derive(Clone, Copy)]
+enum LoopStage {
+    Init,
+    Condition,
+    Body,
+    Increment,
+}
+
+/// Block of a try statement that is running
+enum TryStage {
+    Body,
+    /// Catch clause, by index
+    Catch(usize),
+    /// finally, with the outcome of the try or catch block
+    Finally(Box<Parked>),
+}
+
+/// Outcome of a try or catch block, held while the finally block runs
+struct Parked {
+    result: Result<ExecSignal, String>,
+    /// Exception the outcome propagates, parked so the finally block can use try/catch itself
+    pending: Option<(PhpValue, String)>,
+}
+
+/// Outcome of resuming a statement
+enum Step {
+    Done(ExecSignal),
+    Suspended,
+}
+
+/// Pass on the outcome of a nested statement, dropping the frames from `depth` on unless it suspended
+fn settle(frames: &mut Vec<Frame>, depth: usize, outcome: Result<Step, String>) -> Result<Step, String> {
+    if !matches!(outcome, Ok(Step::Suspended)) {
+        frames.truncate(depth);
+    }
+    outcome
+}
+
+/// Handle number of a Generator object
+fn generator_id(value: &PhpValue) -> Option<u64> {
+    match value {
+        PhpValue::Object(handle) if handle.borrow().class_name == "Generator" => Some(handle.borrow().id),
+        _ => None,
+    }
+}
+
+impl Engine {
+    /// Create the Generator a call to a generator function returns; `scope` holds the bound arguments
+    pub(crate) fn create_generator(&mut self, name: &str, function: &Function, scope: Option<SavedScope>) -> PhpValue {
+        let object = self.alloc_object(PhpObject::new("Generator"));
+        let generator = Generator {
+            name: name.to_string(),
+            function: function.clone(),
+            scope,
+            statics: Vec::new(),
+            frames: Vec::new(),
+            current: None,
+            next_key: 0,
+            started: false,
+            advanced: false,
+            return_value: None,
+        };
+        if let Some(id) = generator_id(&object) {
+            self.generators.insert(id, generator);
+        }
+        object
+    }
+
+    /// Run a generator's body from where it stopped up to its next yield or its end;
+    /// `sent` is the value of the yield it stopped at
+    fn resume_generator(&mut self, id: u64, sent: PhpValue) -> Result<(), String> {
+        let Some(mut generator) = self.generators.remove(&id) else {
+            return Err(self.throw_error("Error", "Cannot resume an already running generator"));
+        };
+        let Some(scope) = generator.scope.take() else {
+            self.generators.insert(id, generator);
+            return Ok(());
+        };
+        if let Some(Frame::Replay(point)) = generator.frames.last_mut() {
+            point.receive(sent);
+        }
+        generator.started = true;
+        generator.current = None;
+        self.enter_call_frame(Some(scope));
+        self.bind_references(&generator.function);
+        let prev_function = self.current_function.replace(generator.name.clone());
+        self.static_var_stack.push((generator.name.clone(), std::mem::take(&mut generator.statics)));
+        let outcome = self.resume_stmt(&generator.function.body, &mut generator.frames, 0);
+        self.store_references(&generator.function);
+        generator.statics = self.save_statics();
+        self.current_function = prev_function;
+        let scope = self.leave_call_frame();
+        let result = match outcome {
+            Ok(Step::Suspended) => {
+                generator.scope = scope;
+                let yielded = match generator.frames.last_mut() {
+                    Some(Frame::Replay(point)) => point.yielded.take(),
+                    _ => None,
+                };
+                if let Some((key, value, delegated)) = yielded {
+                    generator.current = Some((generator.key(key, delegated), value));
+                }
+                Ok(())
+            }
+            Ok(Step::Done(signal)) => {
+                let value = match signal { ExecSignal::Return(Some(value)) => value, _ => PhpValue::Null };
+                generator.return_value = Some(value);
+                Ok(())
+            }
+            Err(message) => Err(message),
+        };
+        if generator.scope.is_none() {
+            generator.frames.clear();
+        }
+        self.generators.insert(id, generator);
+        result
+    }
+
+    /// Run a generator up to its first yield, unless it already did
+    fn generator_start(&mut self, id: u64) -> Result<(), String> {
+        match self.generators.get(&id) {
+            Some(generator) if !generator.started => self.resume_generator(id, PhpValue::Null),
+            _ => Ok(()),
+        }
+    }
+
+    /// Resume a generator with `value` as the result of the yield it stopped at
+    fn generator_send(&mut self, id: u64, value: PhpValue) -> Result<(), String> {
+        self.generator_start(id)?;
+        if let Some(generator) = self.generators.get_mut(&id) {
+            generator.advanced = true;
+        }
+        self.resume_generator(id, value)
+    }
+
+    /// Start a generator for a new traversal, which only works before it moved past its first yield
+    fn generator_rewind(&mut self, id: u64) -> Result<(), String> {
+        self.generator_start(id)?;
+        if self.generators.get(&id).is_some_and(|generator| generator.advanced) {
+            return Err(self.throw_error("Exception", "Cannot rewind a generator that was already run"));
+        }
+        Ok(())
+    }
+
+    /// Key and value the generator is suspended at; None once it finished or while it runs
+    fn generator_current(&self, id: u64) -> Option<(PhpValue, PhpValue)> {
+        self.generators.get(&id).and_then(|generator| generator.current.clone())
+    }
+
+    /// Methods of the Generator class; None if `method` is not one of them
+    pub(crate) fn call_generator_method(&mut self, object: &PhpValue, method: &str, args: Vec<PhpValue>) -> Option<Result<PhpValue, String>> {
+        let id = generator_id(object)?;
+        let current = |engine: &Engine, pick: fn((PhpValue, PhpValue)) -> PhpValue| {
+            engine.generator_current(id).map_or(PhpValue::Null, pick)
+        };
+        let result = match method {
+            "current" => self.generator_start(id).map(|()| current(self, |(_, value)| value)),
+            "key" => self.generator_start(id).map(|()| current(self, |(key, _)| key)),
+            "next" => self.generator_send(id, PhpValue::Null).map(|()| PhpValue::Null),
+            "send" => {
+                let value = args.into_iter().next().unwrap_or(PhpValue::Null);
+                self.generator_send(id, value).map(|()| current(self, |(_, value)| value))
+            }
+            "valid" => self.generator_start(id).map(|()| PhpValue::Bool(self.generator_current(id).is_some())),
+            "rewind" => self.generator_rewind(id).map(|()| PhpValue::Null),
+            "getReturn" => self.generator_start(id).and_then(|()| {
+                match self.generators.get(&id).and_then(|generator| generator.return_value.clone()) {
+                    Some(value) => Ok(value),
+                    None => Err(self.throw_error("Exception", "Cannot get return value of a generator that hasn't returned")),
+                }
+            }),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// Start iterating over an array, an object's properties or a Traversable for foreach.
+    /// Generators are advanced one value per step; other Traversables are drained up front.
+    pub(crate) fn iteration(&mut self, subject: PhpValue) -> Result<Iteration, String> {
+        if let Some(id) = generator_id(&subject) {
+            if self.generators.get(&id).is_some_and(|generator| generator.scope.is_none()) {
+                return Err(self.throw_error("Exception", "Cannot traverse an already closed generator"));
+            }
+            self.generator_rewind(id)?;
+            return Ok(Iteration::Generator(id, false));
+        }
+        let pairs: Vec<(PhpValue, PhpValue)> = match subject {
+            PhpValue::Array(arr) => arr.data.into_iter().map(|(key, value)| (key.to_value(), value)).collect(),
+            PhpValue::Object(handle) if !self.is_traversable(&handle.borrow().class_name) => {
+                handle.borrow().properties.iter().map(|(name, value)| (PhpValue::String(name.clone()), value.clone())).collect()
+            }
+            object @ PhpValue::Object(_) => self.traverse(object)?,
+            _ => return Err("Cannot iterate over non-array value in foreach".to_string()),
+        };
+        Ok(Iteration::Pairs(pairs.into_iter()))
+    }
+
+    /// Next key/value pair of an iteration
+    pub(crate) fn next_pair(&mut self, iteration: &mut Iteration) -> Result<Option<(PhpValue, PhpValue)>, String> {
+        match iteration {
+            Iteration::Pairs(pairs) => Ok(pairs.next()),
+            Iteration::Generator(id, taken) => {
+                if *taken {
+                    self.generator_send(*id, PhpValue::Null)?;
+                }
+                *taken = true;
+                Ok(self.generator_current(*id))
+            }
+        }
+    }
+
+    /// Every remaining pair of a generator, up to `Limits::max_array_elements`
+    pub(crate) fn generator_pairs(&mut self, generator: PhpValue) -> Result<Vec<(PhpValue, PhpValue)>, String> {
+        let limit = self.limits().max_array_elements;
+        let mut iteration = self.iteration(generator)?;
+        let mut pairs = Vec::new();
+        while let Some(pair) = self.next_pair(&mut iteration)? {
+            if pairs.len() >= limit {
+                return Err(self.fatal_error(format!("Memory limit exceeded: generator yielded more than {} values", limit)));
+            }
+            pairs.push(pair);
+        }
+        Ok(pairs)
+    }
+
+    /// Evaluate `yield`: suspend the generator with the pair, or on resume, the value sent in
+    pub(crate) fn evaluate_yield(&mut self, key: Option<&Expr>, value: &Expr) -> Result<PhpValue, String> {
+        let point = self.yield_points.last_mut().ok_or("Cannot yield outside of a function")?;
+        if let Some(result) = point.replayed() {
+            return Ok(result);
+        }
+        let key = match key {
+            Some(key) => Some(self.evaluate_expr(key)?),
+            None => None,
+        };
+        let value = self.evaluate_expr(value)?;
+        if let Some(point) = self.yield_points.last_mut() {
+            point.yielded = Some((key, value, false));
+        }
+        Err(SUSPENDED.to_string())
+    }
+
+    /// Evaluate `yield from`: suspend the generator with each pair of the source in turn; the result
+    /// is the return value of a source generator, or null
+    pub(crate) fn evaluate_yield_from(&mut self, source: &Expr) -> Result<PhpValue, String> {
+        let point = self.yield_points.last_mut().ok_or("Cannot use \"yield from\" outside of a function")?;
+        if let Some(result) = point.replayed() {
+            return Ok(result);
+        }
+        let (delegate, sent) = (point.delegate.take(), point.sent.take());
+        let mut delegate = match delegate {
+            Some(delegate) => delegate,
+            None => {
+                let source = self.evaluate_expr(source)?;
+                match generator_id(&source) {
+                    Some(id) => {
+                        self.generator_start(id)?;
+                        Iteration::Generator(id, false)
+                    }
+                    None => Iteration::Pairs(self.traverse(source)?.into_iter()),
+                }
+            }
+        };
+        // Values sent into this generator go on to the generator it delegates to
+        let pair = match (&mut delegate, sent) {
+            (Iteration::Generator(id, _), Some(sent)) => {
+                self.generator_send(*id, sent)?;
+                self.generator_current(*id)
+            }
+            (iteration, _) => self.next_pair(iteration)?,
+        };
+        let result = match (&pair, &delegate) {
+            (Some(_), _) => PhpValue::Null,
+            (None, Iteration::Generator(id, _)) => self.generators.get(id).and_then(|generator| generator.return_value.clone()).unwrap_or(PhpValue::Null),
+            (None, Iteration::Pairs(_)) => PhpValue::Null,
+        };
+        let point = self.yield_points.last_mut().ok_or("Cannot use \"yield from\" outside of a function")?;
+        match pair {
+            Some((key, value)) => {
+                point.delegate = Some(delegate);
+                point.yielded = Some((Some(key), value, true));
+                Err(SUSPENDED.to_string())
+            }
+            None => {
+                point.pass(result.clone());
+                Ok(result)
+            }
+        }
+    }
+
+    /// Run `run` for the statement at `depth`, answering the yields it passed before; None if it suspended
+    fn replay<T>(&mut self, frames: &mut Vec<Frame>, depth: usize, run: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<Option<T>, String> {
+        let mut point = if frames.len() > depth {
+            match frames.pop() {
+                Some(Frame::Replay(point)) => *point,
+                _ => unreachable!("statements suspend at a yield"),
+            }
+        } else {
+            YieldPoint::default()
+        };
+        point.passed = 0;
+        self.yield_points.push(point);
+        let result = run(self);
+        match (result, self.yield_points.pop()) {
+            (Err(message), Some(point)) if message == SUSPENDED => {
+                frames.push(Frame::Replay(Box::new(point)));
+                Ok(None)
+            }
+            (result, _) => result.map(Some),
+        }
+    }
+
+    /// Run a statement of a generator body until it finishes or suspends at a yield, continuing
+    /// from `frames[depth..]` when it suspended before
+    fn resume_stmt(&mut self, stmt: &Stmt, frames: &mut Vec<Frame>, depth: usize) -> Result<Step, String> {
+        if frames.len() == depth && !stmt_yields(stmt) {
+            return self.exec(stmt).map(Step::Done);
+        }
+        match stmt {
+            Stmt::Block(statements) => {
+                if frames.len() == depth {
+                    frames.push(Frame::Block(0));
+                }
+                while let Frame::Block(index) = frames[depth] {
+                    let Some(stmt) = statements.get(index) else { break };
+                    match self.resume_stmt(stmt, frames, depth + 1) {
+                        Ok(Step::Done(ExecSignal::None)) => frames[depth] = Frame::Block(index + 1),
+                        outcome => return settle(frames, depth, outcome),
+                    }
+                }
+                frames.truncate(depth);
+                Ok(Step::Done(ExecSignal::None))
+            }
+            Stmt::If { condition, then_stmt, else_stmt } => {
+                if frames.len() == depth {
+                    frames.push(Frame::If(None));
+                }
+                let branch = match frames[depth] {
+                    Frame::If(Some(branch)) => branch,
+                    _ => {
+                        let Some(value) = self.replay(frames, depth + 1, |engine| engine.evaluate_expr(condition))? else {
+                            return Ok(Step::Suspended);
+                        };
+                        frames[depth] = Frame::If(Some(value.is_truthy()));
+                        value.is_truthy()
+                    }
+                };
+                let outcome = match (branch, else_stmt) {
+                    (true, _) => self.resume_stmt(then_stmt, frames, depth + 1),
+                    (false, Some(else_stmt)) => self.resume_stmt(else_stmt, frames, depth + 1),
+                    (false, None) => Ok(Step::Done(ExecSignal::None)),
+                };
+                settle(frames, depth, outcome)
+            }
+            Stmt::While { .. } | Stmt::DoWhile { .. } | Stmt::For { .. } => self.resume_loop(stmt, frames, depth),
+            Stmt::Foreach { array, value_var, key_var, body } => {
+                if frames.len() == depth {
+                    frames.push(Frame::Foreach(None));
+                }
+                if matches!(frames[depth], Frame::Foreach(None)) {
+                    let Some(subject) = self.replay(frames, depth + 1, |engine| engine.evaluate_expr(array))? else {
+                        return Ok(Step::Suspended);
+                    };
+                    frames[depth] = Frame::Foreach(Some((self.iteration(subject)?, false)));
+                }
+                while let Frame::Foreach(Some((iteration, in_body))) = &mut frames[depth] {
+                    if !*in_body {
+                        let Some((key, value)) = self.next_pair(iteration)? else { break };
+                        *in_body = true;
+                        if let Some(key_name) = key_var {
+                            self.context.set_variable(key_name, key);
+                        }
+                        self.context.set_variable(value_var, value);
+                    }
+                    match self.resume_stmt(body, frames, depth + 1) {
+                        Ok(Step::Done(ExecSignal::None | ExecSignal::Continue)) => {
+                            if let Frame::Foreach(Some((_, in_body))) = &mut frames[depth] {
+                                *in_body = false;
+                            }
+                        }
+                        Ok(Step::Done(ExecSignal::Break)) => break,
+                        outcome => return settle(frames, depth, outcome),
+                    }
+                }
+                frames.truncate(depth);
+                Ok(Step::Done(ExecSignal::None))
+            }
+            Stmt::Switch { expression, cases, default } => {
+                if frames.len() == depth {
+                    frames.push(Frame::Switch(None));
+                }
+                let (case, mut index) = match frames[depth] {
+                    Frame::Switch(Some(position)) => position,
+                    _ => {
+                        // The subject and the case values are replayed together
+                        let matched = self.replay(frames, depth + 1, |engine| {
+                            let subject = engine.evaluate_expr(expression)?;
+                            for (index, case) in cases.iter().enumerate() {
+                                let value = engine.evaluate_expr(&case.value)?;
+                                if engine.loose_equals(&subject, &value)? {
+                                    return Ok(Some(index));
+                                }
+                            }
+                            Ok(None)
+                        })?;
+                        let Some(case) = matched else { return Ok(Step::Suspended) };
+                        (case, 0)
+                    }
+                };
+                let statements: &[Stmt] = match (case, default) {
+                    (Some(case), _) => &cases[case].statements,
+                    (None, Some(default)) => default,
+                    (None, None) => &[],
+                };
+                while let Some(stmt) = statements.get(index) {
+                    frames[depth] = Frame::Switch(Some((case, index)));
+                    match self.resume_stmt(stmt, frames, depth + 1) {
+                        Ok(Step::Done(ExecSignal::None)) => index += 1,
+                        Ok(Step::Done(ExecSignal::Break)) => break,
+                        outcome => return settle(frames, depth, outcome),
+                    }
+                }
+                frames.truncate(depth);
+                Ok(Step::Done(ExecSignal::None))
+            }
+            Stmt::Try { body, catches, finally } => {
+                if frames.len() == depth {
+                    frames.push(Frame::Try(TryStage::Body));
+                }
+                let catch = match frames[depth] {
+                    Frame::Try(TryStage::Body) => None,
+                    Frame::Try(TryStage::Catch(index)) => Some(index),
+                    _ => return self.resume_finally(finally.as_deref().unwrap_or(&Stmt::Nop), frames, depth),
+                };
+                let result = match self.resume_stmt(catch.map_or(&**body, |index| &catches[index].body), frames, depth + 1) {
+                    Ok(Step::Suspended) => return Ok(Step::Suspended),
+                    Ok(Step::Done(signal)) => Ok(signal),
+                    Err(message) => {
+                        frames.truncate(depth + 1);
+                        match catch {
+                            Some(_) => Err(message),
+                            None => match self.catch_clause(catches, message) {
+                                Ok(index) => {
+                                    frames[depth] = Frame::Try(TryStage::Catch(index));
+                                    return self.resume_stmt(stmt, frames, depth);
+                                }
+                                Err(message) => Err(message),
+                            },
+                        }
+                    }
+                };
+                // Fatal errors end the script without running finally blocks, as in PHP
+                let Some(finally) = finally.as_deref().filter(|_| !result.as_ref().is_err_and(|message| self.is_fatal(message))) else {
+                    frames.truncate(depth);
+                    return result.map(Step::Done);
+                };
+                let pending = self.thrown.take();
+                frames[depth] = Frame::Try(TryStage::Finally(Box::new(Parked { result, pending })));
+                self.resume_finally(finally, frames, depth)
+            }
+            other => match self.replay(frames, depth, |engine| engine.exec(other))? {
+                Some(signal) => Ok(Step::Done(signal)),
+                None => Ok(Step::Suspended),
+            },
+        }
+    }
+
+    /// Resume a while, do-while or for loop
+    fn resume_loop(&mut self, stmt: &Stmt, frames: &mut Vec<Frame>, depth: usize) -> Result<Step, String> {
+        let (init, condition, increment, body, start) = match stmt {
+            Stmt::While { condition, body } => (None, Some(condition), None, body, LoopStage::Condition),
+            Stmt::DoWhile { body, condition } => (None, Some(condition), None, body, LoopStage::Body),
+            Stmt::For { init, condition, increment, body } => (init.as_deref(), condition.as_ref(), increment.as_ref(), body, LoopStage::Init),
+            _ => unreachable!("resume_loop is only called for loops"),
+        };
+        if frames.len() == depth {
+            frames.push(Frame::Loop(start));
+        }
+        while let Frame::Loop(stage) = frames[depth] {
+            let next = match stage {
+                LoopStage::Init => {
+                    if let Some(init) = init {
+                        if let Step::Suspended = self.resume_stmt(init, frames, depth + 1)? {
+                            return Ok(Step::Suspended);
+                        }
+                    }
+                    LoopStage::Condition
+                }
+                LoopStage::Condition => {
+                    if let Some(condition) = condition {
+                        let Some(value) = self.replay(frames, depth + 1, |engine| engine.evaluate_expr(condition))? else {
+                            return Ok(Step::Suspended);
+                        };
+                        if !value.is_truthy() {
+                            break;
+                        }
+                    }
+                    LoopStage::Body
+                }
+                LoopStage::Body => match self.resume_stmt(body, frames, depth + 1) {
+                    Ok(Step::Done(ExecSignal::None | ExecSignal::Continue)) => LoopStage::Increment,
+                    Ok(Step::Done(ExecSignal::Break)) => break,
+                    outcome => return settle(frames, depth, outcome),
+                },
+                LoopStage::Increment => {
+                    if let Some(increment) = increment {
+                        if self.replay(frames, depth + 1, |engine| engine.evaluate_expr(increment))?.is_none() {
+                            return Ok(Step::Suspended);
+                        }
+                    }
+                    LoopStage::Condition
+                }
+            };
+            frames[depth] = Frame::Loop(next);
+        }
+        frames.truncate(depth);
+        Ok(Step::Done(ExecSignal::None))
+    }
+
+    /// Resume the finally block of a try statement, then finish with the outcome it parked
+    fn resume_finally(&mut self, finally: &Stmt, frames: &mut Vec<Frame>, depth: usize) -> Result<Step, String> {
+        let outcome = self.resume_stmt(finally, frames, depth + 1);
+        if let Ok(Step::Suspended) = outcome {
+            return outcome;
+        }
+        frames.truncate(depth + 1);
+        let Some(Frame::Try(TryStage::Finally(parked))) = frames.pop() else {
+            unreachable!("a finally block runs in the Finally stage");
+        };
+        let Parked { result, pending } = *parked;
+        match outcome? {
+            Step::Done(ExecSignal::None) => {
+                self.thrown = pending;
+                result.map(Step::Done)
+            }
+            // return/break/continue in finally overrides the try outcome, discarding any exception
+            step => Ok(step),
+        }
+    }
+}
+
+/// Whether a statement contains `yield`, making a function body containing it a generator.
+/// Functions, closures and classes declared inside it do not count.
+pub(crate) fn stmt_yields(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Expression(expr) | Stmt::Echo(expr) | Stmt::Print(expr) | Stmt::Assignment { value: expr, .. }
+        | Stmt::NullCoalesceAssign { value: expr, .. } | Stmt::ConstantDefinition { value: expr, .. } => expr_yields(expr),
+        Stmt::Return(expr) | Stmt::StaticVar { initial: expr, .. } => expr.as_ref().is_some_and(expr_yields),
+        Stmt::Block(statements) => statements.iter().any(stmt_yields),
+        Stmt::If { condition, then_stmt, else_stmt } => {
+            expr_yields(condition) || stmt_yields(then_stmt) || else_stmt.as_deref().is_some_and(stmt_yields)
+        }
+        Stmt::While { condition, body } | Stmt::DoWhile { body, condition } => expr_yields(condition) || stmt_yields(body),
+        Stmt::For { init, condition, increment, body } => {
+            init.as_deref().is_some_and(stmt_yields)
+                || condition.as_ref().is_some_and(expr_yields)
+                || increment.as_ref().is_some_and(expr_yields)
+                || stmt_yields(body)
+        }
+        Stmt::Foreach { array, body, .. } => expr_yields(array) || stmt_yields(body),
+        Stmt::Switch { expression, cases, default } => {
+            expr_yields(expression)
+                || cases.iter().any(|case| expr_yields(&case.value) || case.statements.iter().any(stmt_yields))
+                || default.iter().flatten().any(stmt_yields)
+        }
+        Stmt::DestructuringAssignment { targets, value } => {
+            expr_yields(value) || targets.iter().any(|target| matches!(target, DestructTarget::KeyVar(key, _) if expr_yields(key)))
+        }
+        Stmt::Try { body, catches, finally } => {
+            stmt_yields(body) || catches.iter().any(|clause| stmt_yields(&clause.body)) || finally.as_deref().is_some_and(stmt_yields)
+        }
+        Stmt::InlineHtml(_) | Stmt::Break | Stmt::Continue | Stmt::Nop | Stmt::FunctionDefinition { .. } | Stmt::ClassDefinition { .. } => false,
+    }
+}
+
+/// Whether an expression contains `yield`, outside of the closures it defines
+fn expr_yields(expr: &Expr) -> bool {
+    let class = |class: &ClassName| matches!(class, ClassName::Dynamic(expr) if expr_yields(expr));
+    let member = |member: &MemberName| matches!(member, MemberName::Dynamic(expr) if expr_yields(expr));
+    match expr {
+        Expr::Yield { .. } | Expr::YieldFrom(_) => true,
+        Expr::Variable(_) | Expr::Constant(_) | Expr::Number(_) | Expr::Int(_) | Expr::String(_) | Expr::InterpolatedString(_)
+        | Expr::Bool(_) | Expr::Null | Expr::ArrowFunction { .. } | Expr::Closure { .. } => false,
+        Expr::VariableVariable(inner) | Expr::Throw(inner) | Expr::Spread(inner)
+        | Expr::NamedArgument { value: inner, .. } | Expr::Unary { operand: inner, .. } => expr_yields(inner),
+        Expr::Assign { target: left, value: right } | Expr::Binary { left, right, .. }
+        | Expr::ArrayAccess { array: left, index: right } | Expr::NullCoalesce { left, right } => expr_yields(left) || expr_yields(right),
+        Expr::MethodCall { target, method, args } | Expr::NullsafeMethodCall { target, method, args } => {
+            expr_yields(target) || member(method) || args.iter().any(expr_yields)
+        }
+        Expr::PropertyAccess { target, property } | Expr::NullsafePropertyAccess { target, property } => expr_yields(target) || member(property),
+        Expr::StaticCall { class: name, args, .. } | Expr::New { class: name, args } => class(name) || args.iter().any(expr_yields),
+        Expr::ClassConstant { class: name, .. } | Expr::StaticProperty { class: name, .. } => class(name),
+        Expr::FunctionCall { args, .. } => args.iter().any(expr_yields),
+        Expr::DynamicCall { target, args } => expr_yields(target) || args.iter().any(expr_yields),
+        Expr::Array(elements) => elements.iter().any(|element| element.key.as_ref().is_some_and(expr_yields) || expr_yields(&element.value)),
+        Expr::Ternary { condition, then_expr, else_expr } => {
+            expr_yields(condition) || then_expr.as_deref().is_some_and(expr_yields) || expr_yields(else_expr)
+        }
+        Expr::Match { subject, arms, default_arm } => {
+            expr_yields(subject)
+                || arms.iter().any(|(conditions, result)| conditions.iter().any(expr_yields) || expr_yields(result))
+                || default_arm.as_deref().is_some_and(expr_yields)
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! String interning for variable names
+//!
+//! Variable names are mapped to dense `u32` ids once, so scopes can store
+//! their values in a vector indexed by id instead of a `String`-keyed map.
+
+use std::collections::HashMap;
+
+/// String interner handing out dense ids for names
+#[derive(Debug, Default, Clone)]
+pub struct StringInterner {
+    strings: Vec<String>,
+    map: HashMap<String, u32>,
+}
+
+impl StringInterner {
+    /// Create an empty interner
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the id for `name`, allocating a new one on first sight
+    pub fn intern(&mut self, name: &str) -> u32 {
+        if let Some(&id) = self.map.get(name) {
+            return id;
+        }
+        let id = self.strings.len() as u32;
+        self.map.insert(name.to_string(), id);
+        self.strings.push(name.to_string());
+        id
+    }
+
+    /// Return the id for `name` without interning it
+    pub fn lookup(&self, name: &str) -> Option<u32> {
+        self.map.get(name).copied()
+    }
+
+    /// Return the name for an id
+    pub fn resolve(&self, id: u32) -> Option<&str> {
+        self.strings.get(id as usize).map(|s| s.as_str())
+    }
+
+    /// Number of interned names
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Check if no names have been interned
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_is_stable() {
+        let mut interner = StringInterner::new();
+        let a = interner.intern("a");
+        let b = interner.intern("b");
+        assert_ne!(a, b);
+        assert_eq!(interner.intern("a"), a);
+        assert_eq!(interner.lookup("b"), Some(b));
+        assert_eq!(interner.lookup("c"), None);
+        assert_eq!(interner.resolve(a), Some("a"));
+        assert_eq!(interner.len(), 2);
+    }
+}
@@ -6,5 +6,11 @@
 #![warn(clippy::all)]
 
 pub mod engine;
+pub mod interner;
+mod builtins;
+mod dump;
+mod generator;
 
-pub use engine::{Engine, ExecutionContext, Function};
+pub use engine::{Engine, ErrorLevel, ExecutionContext, Function, Limits, RaisedError};
+pub use interner::StringInterner;
+pub use builtins::NativeFunction;
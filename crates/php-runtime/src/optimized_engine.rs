@@ -4,6 +4,7 @@
 use php_types::{PhpValue, PhpArrayKey, PhpArray};
 use std::collections::HashMap;
 use std::sync::Arc;
+use crate::interner::StringInterner;
 
 /// Optimized variable storage with faster lookups
 pub struct OptimizedVariableStore {
@@ -15,36 +16,6 @@ pub struct OptimizedVariableStore {
     name_interner: StringInterner,
 }
 
-/// String interner to reduce allocations for variable names
-pub struct StringInterner {
-    strings: Vec<String>,
-    map: fxhash::FxHashMap<String, usize>,
-}
-
-impl StringInterner {
-    pub fn new() -> Self {
-        Self {
-            strings: Vec::new(),
-            map: fxhash::FxHashMap::default(),
-        }
-    }
-    
-    pub fn intern(&mut self, s: String) -> usize {
-        if let Some(&id) = self.map.get(&s) {
-            id
-        } else {
-            let id = self.strings.len();
-            self.map.insert(s.clone(), id);
-            self.strings.push(s);
-            id
-        }
-    }
-    
-    pub fn get(&self, id: usize) -> Option<&str> {
-        self.strings.get(id).map(|s| s.as_str())
-    }
-}
-
 impl OptimizedVariableStore {
     pub fn new() -> Self {
         Self {
@@ -168,6 +139,9 @@ impl OptimizedExpressionEvaluator {
             php_parser::ast::Expr::Number(n) => {
                 CompiledExpression::Constant(PhpValue::Float(*n))
             }
+            php_parser::ast::Expr::Int(n) => {
+                CompiledExpression::Constant(PhpValue::Int(*n))
+            }
             php_parser::ast::Expr::String(s) => {
                 CompiledExpression::Constant(PhpValue::String(s.clone()))
             }
@@ -205,9 +179,9 @@ impl OptimizedExpressionEvaluator {
                 
                 use php_parser::ast::BinaryOp;
                 match op {
-                    BinaryOp::Add => Ok(php_types::php_add(&left_val, &right_val)),
-                    BinaryOp::Subtract => Ok(php_types::php_subtract(&left_val, &right_val)),
-                    BinaryOp::Multiply => Ok(php_types::php_multiply(&left_val, &right_val)),
+                    BinaryOp::Add => php_types::php_add(&left_val, &right_val),
+                    BinaryOp::Subtract => php_types::php_subtract(&left_val, &right_val),
+                    BinaryOp::Multiply => php_types::php_multiply(&left_val, &right_val),
                     BinaryOp::Concatenate => Ok(php_types::php_concatenate(&left_val, &right_val)),
                     _ => Err("Unsupported binary operation".to_string()),
                 }
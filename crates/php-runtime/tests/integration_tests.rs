@@ -2,8 +2,2045 @@
 
 use php_runtime::*;
 
+/// Lex, parse and execute a PHP snippet, returning its output
+fn run(code: &str) -> String {
+    let tokens = php_lexer::lex(code).expect("Failed to lex");
+    let ast = php_parser::parse(tokens).expect("Failed to parse");
+    let mut engine = Engine::new();
+    engine.execute_stmt(&ast).expect("Failed to execute");
+    engine.get_output().to_string()
+}
+
+#[test]
+fn test_for_loop_with_empty_body() {
+    let output = run("<?php for ($i = 0; $i < 3; $i++); echo $i;");
+    assert_eq!(output, "3");
+}
+
+#[test]
+fn test_stray_semicolons() {
+    let output = run("<?php ;; echo 'ok';;");
+    assert_eq!(output, "ok");
+}
+
+#[test]
+fn test_variable_variables() {
+    let output = run(r#"<?php $name = "x"; $$name = 5; echo $x;"#);
+    assert_eq!(output, "5");
+
+    let output = run(r#"<?php $x = "hello"; $name = "x"; echo ${$name} . " " . $$name;"#);
+    assert_eq!(output, "hello hello");
+}
+
+#[test]
+fn test_classes_properties_and_methods() {
+    let output = run(r#"<?php
+        class Point {
+            public $x = 0;
+            function __construct($x) { $this->x = $x; }
+            function getX() { return $this->x; }
+        }
+        class Point3 extends Point {
+            public $z = 3;
+        }
+        $p = new Point3(7);
+        echo $p->x . "," . $p->getX() . "," . $p->z;
+    "#);
+    assert_eq!(output, "7,7,3");
+
+    let output = run(r#"<?php $o = new stdClass(); $o->name = "Ann"; $b = $o; $b->name = "Bo"; echo $o->name;"#);
+    assert_eq!(output, "Bo");
+}
+
+#[test]
+fn test_dynamic_property_and_method_names() {
+    let output = run(r#"<?php
+        class Point {
+            public $x = 0;
+            function __construct($x) { $this->x = $x; }
+            function getX() { return $this->x; }
+        }
+        $p = new Point(7);
+        $prop = "x";
+        $method = "getX";
+        echo $p->$prop . "," . $p->{$prop} . "," . $p->$method();
+    "#);
+    assert_eq!(output, "7,7,7");
+
+    let output = run(r#"<?php $o = new stdClass(); $k = "name"; $o->$k = "Ann"; echo $o->name;"#);
+    assert_eq!(output, "Ann");
+}
+
+#[test]
+fn test_dynamic_class_instantiation() {
+    let output = run(r#"<?php
+        class Point {
+            public $x;
+            public $y;
+            function __construct($x, $y) { $this->x = $x; $this->y = $y; }
+        }
+        $c = "Point";
+        $p = new $c(1, 2);
+        $q = new $p(3, 4);
+        $classes = ["point" => "Point"];
+        $r = new $classes["point"](5, 6);
+        echo $p->x . $p->y . $q->x . $q->y . $r->x . $r->y;
+    "#);
+    assert_eq!(output, "123456");
+}
+
+#[test]
+fn test_static_method_callable_strings() {
+    let output = run(r#"<?php
+        class MathUtil {
+            public static function add($a, $b) { return $a + $b; }
+            public static function twice($a) { return self::add($a, $a); }
+        }
+        echo call_user_func("MathUtil::add", 2, 3) . ",";
+        echo call_user_func(["MathUtil", "twice"], 4) . ",";
+        echo call_user_func_array("MathUtil::add", [10, 20]) . ",";
+        echo MathUtil::twice(6);
+    "#);
+    assert_eq!(output, "5,8,30,12");
+}
+
+#[test]
+fn test_call_user_func_array_order_and_named_arguments() {
+    let output = run(r#"<?php
+        function f($a, $b = 'B', $c = 'C') { return "$a-$b-$c"; }
+        class M { static function __callStatic($name, $args) { return $name . json_encode($args); } }
+        echo call_user_func_array('f', [2 => 'x', 0 => 'y']) . ",";
+        echo call_user_func_array('f', ['c' => 3, 'a' => 1]) . ",";
+        echo call_user_func_array('f', [1, 'c' => 3]) . ",";
+        echo call_user_func_array(['M', 'go'], [1, 'x' => 2]) . ",";
+        echo call_user_func('call_user_func_array', 'str_repeat', ['ab', 2]) . ",";
+        try { call_user_func_array('f', ['a' => 1, 2]); } catch (Error $e) { echo $e->getMessage() . ","; }
+        try { call_user_func_array('f', ['zz' => 1]); } catch (Error $e) { echo $e->getMessage(); }
+    "#);
+    assert_eq!(output, "x-y-C,1-B-3,1-B-3,go{\"0\":1,\"x\":2},abab,Cannot use positional argument after named argument during unpacking,Unknown named parameter $zz");
+}
+
+#[test]
+fn test_parent_call_resolves_from_declaring_class() {
+    let output = run(r#"<?php
+        class A { function foo() { echo "A"; } }
+        class B extends A { function foo() { echo "B"; parent::foo(); } }
+        class C extends B {}
+        (new C)->foo();
+    "#);
+    assert_eq!(output, "BA");
+}
+
+#[test]
+fn test_self_constant_resolves_from_declaring_class() {
+    let output = run(r#"<?php
+        class X { const V = 1; function v() { return self::V; } }
+        class Y extends X { const V = 2; }
+        echo (new Y)->v() . "," . call_user_func([new Y, "v"]);
+    "#);
+    assert_eq!(output, "1,1");
+}
+
+#[test]
+fn test_array_spread_and_merge() {
+    let output = run(r#"<?php
+        $a = [1, 2];
+        $b = ["x" => "a", "y" => "b"];
+        $c = [...$a, 3, ...$b, ...["x" => "z"]];
+        echo array_sum($c) . $c["x"] . $c["y"] . ",";
+        $m = array_merge($a, [3], $b, ["y" => "c"]);
+        echo array_sum($m) . $m["x"] . $m["y"];
+    "#);
+    assert_eq!(output, "6zb,6ac");
+}
+
+/// Timing check for merging large arrays; run with `cargo test -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_array_merge_large() {
+    let items: Vec<String> = (0..10_000).map(|i| i.to_string()).collect();
+    let code = format!(r#"<?php
+        $a = [{items}];
+        $m = array_merge($a, $a, $a, $a);
+        $s = [...$a, ...$a, ...$a, ...$a];
+        echo array_sum($m) + array_sum($s);
+    "#, items = items.join(", "));
+    let start = std::time::Instant::now();
+    let output = run(&code);
+    println!("merged 4 x 10k-element arrays twice in {:?}", start.elapsed());
+    assert_eq!(output, (8 * (0..10_000).sum::<i64>()).to_string());
+}
+
+/// Timing check for building a long string with `.=`; run with `cargo test -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_concat_assign_large() {
+    let code = r#"<?php
+        $s = "";
+        for ($i = 0; $i < 100000; $i++) { $s .= "x"; }
+        echo $s == str_repeat("x", 100000) ? "built" : "wrong";
+    "#;
+    let start = std::time::Instant::now();
+    let output = run(code);
+    println!("built a 100k-character string with .= in {:?}", start.elapsed());
+    assert_eq!(output, "built");
+}
+
+/// Timing check for sorting numeric strings; run with `cargo test --release -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_sort_numeric_strings() {
+    let items: Vec<String> = (0..100_000).map(|i| format!("\"{}\"", (i * 7919) % 100_000)).collect();
+    let code = format!(r#"<?php
+        $a = [{items}];
+        sort($a);
+        echo $a[0] . "," . $a[9] . "," . $a[99999];
+    "#, items = items.join(", "));
+    let start = std::time::Instant::now();
+    let output = run(&code);
+    println!("sorted 100k numeric strings in {:?}", start.elapsed());
+    assert_eq!(output, "0,9,99999");
+}
+
+#[test]
+fn test_functions_do_not_see_caller_locals() {
+    let output = run(r#"<?php
+        function show() { return $x ?? "null"; }
+        function outer() { $x = "outer"; return show(); }
+        $x = "global";
+        echo show() . "," . outer() . "," . $x;
+    "#);
+    assert_eq!(output, "null,null,global");
+
+    let output = run(r#"<?php
+        class Counter {
+            public $n = 0;
+            function bump($by) { $this->n = $this->n + $by; return $by; }
+        }
+        $by = 100;
+        $c = new Counter();
+        $c->bump(2);
+        echo $c->n . "," . $by;
+    "#);
+    assert_eq!(output, "2,100");
+}
+
+/// Timing check for variable access in a hot loop; run with `cargo test --release -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_loop_variable_access() {
+    let code = r#"<?php
+        $sum = 0;
+        for ($i = 0; $i < 1000000; $i++) { $sum = $sum + $i; }
+        echo $sum;
+    "#;
+    let start = std::time::Instant::now();
+    let output = run(code);
+    println!("1M-iteration loop in {:?}", start.elapsed());
+    assert_eq!(output, "499999500000");
+}
+
+#[test]
+fn test_dump_functions_respect_output_buffers() {
+    let output = run(r#"<?php
+        ob_start();
+        var_dump(true, "hi");
+        $dumped = ob_get_clean();
+        echo "[" . $dumped . "]";
+    "#);
+    assert_eq!(output, "[bool(true)\nstring(2) \"hi\"\n]");
+
+    let output = run(r#"<?php
+        ob_start();
+        echo "outer:";
+        ob_start();
+        print_r(["a" => 1]);
+        $inner = ob_get_clean();
+        $returned = print_r([true], true);
+        $outer = ob_get_clean();
+        echo $outer . "|" . $inner . "|" . $returned;
+    "#);
+    assert_eq!(output, "outer:|Array\n(\n    [a] => 1\n)\n|Array\n(\n    [0] => 1\n)\n");
+
+    let output = run(r#"<?php ob_start(); echo "a"; ob_start(); echo "b"; ob_end_flush(); echo "c"; ob_start(); echo "unflushed";"#);
+    assert_eq!(output, "abcunflushed");
+}
+
+#[test]
+fn test_var_dump_nested_array() {
+    let output = run(r#"<?php var_dump(["list" => [1.5]]);"#);
+    assert_eq!(output, "array(1) {\n  [\"list\"]=>\n  array(1) {\n    [0]=>\n    float(1.5)\n  }\n}\n");
+}
+
+#[test]
+fn test_var_dump_scalars() {
+    let output = run(r#"<?php var_dump(5, 3.14, -0.0, "hello", false, null, []);"#);
+    assert_eq!(output, "int(5)\nfloat(3.14)\nfloat(-0)\nstring(5) \"hello\"\nbool(false)\nNULL\narray(0) {\n}\n");
+}
+
+#[test]
+fn test_short_circuit_operators() {
+    let output = run(r#"<?php
+        function side($label) { echo $label; return true; }
+        $a = false && side("and");
+        $b = true || side("or");
+        $c = "set" ?? side("coalesce");
+        $d = "truthy" ?: side("elvis");
+        $e = true ? "then" : side("else");
+        $f = false ? side("then") : "else";
+        echo "|";
+        $g = true && side("A");
+        $h = false || side("B");
+        $i = null ?? side("C");
+        $j = "" ?: side("D");
+        echo "|" . $c . $d . $e . $f;
+    "#);
+    assert_eq!(output, "|ABCD|settruthythenelse");
+}
+
+#[test]
+fn test_logical_operators_skip_unneeded_right_operand() {
+    // explode_bomb() is undefined, so evaluating it would fail the script
+    let output = run(r#"<?php
+        if (true || explode_bomb()) { echo "or;"; }
+        if (false && explode_bomb()) { echo "unreachable"; }
+        $x = null;
+        if ($x != null && $x->foo()) { echo "unreachable"; }
+        echo "done";
+    "#);
+    assert_eq!(output, "or;done");
+}
+
+#[test]
+fn test_keyword_logical_operators() {
+    let output = run(r#"<?php
+        $a = false or true;
+        var_dump($a);
+        $b = true and false;
+        var_dump($b);
+        $c = (true xor true);
+        $d = (true xor false);
+        var_dump($c, $d);
+        $e = (false or true and false);
+        var_dump($e);
+        $f = false || true;
+        var_dump($f);
+    "#);
+    assert_eq!(output, "bool(false)\nbool(true)\nbool(false)\nbool(true)\nbool(false)\nbool(true)\n");
+}
+
+#[test]
+fn test_constant_lookup_by_name() {
+    let output = run(r#"<?php
+        class Base { const GREETING = "hi"; }
+        class Child extends Base {
+            const NAME = "child";
+            public static function describe() { return self::NAME . "/" . static::class; }
+        }
+        define("MY_CONST", "mine");
+        echo "eol" . constant("PHP_" . "EOL");
+        echo constant("MY_" . "CONST") . ";";
+        echo constant("Child::GREETING") . ";";
+        echo Child::NAME . ";" . Child::describe() . ";" . Base::class . ";";
+        var_dump(defined("MY_CONST"), defined("NOPE"), defined("Child::NAME"));
+    "#);
+    assert_eq!(output, "eol\nmine;hi;child;child/Child;Base;bool(true)\nbool(false)\nbool(true)\n");
+}
+
+#[test]
+fn test_static_members_on_a_runtime_class() {
+    let output = run(r#"<?php
+        class Config {
+            const VERSION = "1.2";
+            public static $count = 3;
+            public static function label($x) { return "label:" . $x . ":" . self::$count; }
+        }
+        class Child extends Config {}
+        $cls = "Config";
+        echo $cls::VERSION . ";" . $cls::label(7) . ";" . $cls::$count . ";";
+        $obj = new Child();
+        echo $obj::VERSION . ";" . $obj::class . ";";
+        $cls::$count = 11;
+        echo Child::$count . ";";
+        try {
+            echo $cls::$missing;
+        } catch (Error $e) {
+            echo $e->getMessage() . ";";
+        }
+    "#);
+    assert_eq!(output, "1.2;label:7:3;3;1.2;Child;11;Access to undeclared static property Config::$missing;");
+}
+
+#[test]
+fn test_return_in_finally_overrides_try() {
+    let output = run(r#"<?php
+        function f() { try { return 1; } finally { return 2; } }
+        function g() { try { throw new Exception("lost"); } finally { return "kept"; } }
+        echo f() . ";" . g() . ";";
+        for ($i = 0; $i < 3; $i++) {
+            try { break; } finally { echo "f$i"; continue; }
+        }
+        echo ";" . $i;
+    "#);
+    assert_eq!(output, "2;kept;f0f1f2;3");
+}
+
+#[test]
+fn test_finally_runs_before_uncaught_exception() {
+    let tokens = php_lexer::lex(r#"<?php
+        function g() { try { throw new RuntimeException("boom"); } finally { echo "cleanup;"; } }
+        try { g(); } catch (LogicException $e) { echo "wrong"; } finally { echo "outer;"; }
+        echo "unreached";
+    "#).expect("Failed to lex");
+    let ast = php_parser::parse(tokens).expect("Failed to parse");
+    let mut engine = Engine::new();
+    let result = engine.execute_stmt(&ast);
+    assert_eq!(result, Err("Uncaught RuntimeException: boom".to_string()));
+    assert_eq!(engine.get_output(), "cleanup;outer;");
+}
+
+#[test]
+fn test_comparing_recursive_objects_is_fatal() {
+    let tokens = php_lexer::lex(r#"<?php
+        $a = new stdClass(); $a->self = $a;
+        $b = new stdClass(); $b->self = $b;
+        echo ($a == $a ? "same;" : "") . (in_array($a, [1, $a]) ? "found;" : "");
+        try {
+            in_array($a, [$b]);
+        } catch (Throwable $e) {
+            echo "caught;";
+        } finally {
+            echo "finally;";
+        }
+    "#).expect("Failed to lex");
+    let ast = php_parser::parse(tokens).expect("Failed to parse");
+    let mut engine = Engine::new();
+    assert_eq!(engine.execute_stmt(&ast), Err("Nesting level too deep - recursive dependency?".to_string()));
+    assert_eq!(engine.get_output(), "same;found;");
+}
+
+#[test]
+fn test_undefined_function_throws_but_engine_limits_stay_fatal() {
+    let output = run(r#"<?php
+        try {
+            missing_function(1);
+        } catch (Error $e) {
+            echo $e->getMessage() . ";";
+        }
+    "#);
+    assert_eq!(output, "Call to undefined function missing_function();");
+
+    let tokens = php_lexer::lex(r#"<?php
+        try {
+            $s = str_repeat("ab", 5);
+        } catch (Throwable $e) {
+            echo "caught;";
+        } finally {
+            echo "finally;";
+        }
+        echo "unreached";
+    "#).expect("Failed to lex");
+    let ast = php_parser::parse(tokens).expect("Failed to parse");
+    let mut engine = Engine::new();
+    engine.set_limits(Limits { max_string_length: 8, ..Limits::default() });
+    let error = engine.execute_stmt(&ast).unwrap_err();
+    assert!(error.starts_with("str_repeat(): Memory limit exceeded"), "{}", error);
+    assert_eq!(engine.get_output(), "");
+
+    let mut engine = Engine::new();
+    engine.set_limits(Limits { max_eval_depth: 4, ..Limits::default() });
+    let ast = php_parser::parse(php_lexer::lex("<?php $code = 'try { return eval($code); } catch (Throwable $e) { return 0; }'; eval($code);").expect("Failed to lex")).expect("Failed to parse");
+    assert_eq!(engine.execute_stmt(&ast).unwrap_err(), "eval(): Maximum nesting level of 4 reached");
+}
+
+#[test]
+fn test_catch_exception_hierarchy() {
+    let output = run(r#"<?php
+        class MyException extends InvalidArgumentException {}
+        try { throw new MyException("bad", 3); }
+        catch (RuntimeException | LogicException $e) { echo $e->getMessage() . $e->getCode() . ";"; }
+        try { echo 1 / 0; } catch (Throwable $e) { echo "error;"; }
+        try { throw new Exception("x"); } catch (Exception $e) { echo "caught"; } finally { echo ";done"; }
+    "#);
+    assert_eq!(output, "bad3;error;caught;done");
+}
+
+#[test]
+fn test_array_pointer_functions() {
+    let output = run(r#"<?php
+        $a = [10, 20, 30];
+        $sum = reset($a);
+        $steps = 1;
+        while (next($a)) { $sum = $sum + current($a); $steps++; }
+        echo $sum . ";" . $steps . ";";
+        var_dump(current($a), key($a), next($a));
+        $last = end($a);
+        $k = key($a);
+        $before = prev($a);
+        echo ($last + $before) . ";" . $a[$k] . ";";
+        $empty = [];
+        var_dump(reset($empty), end($empty));
+    "#);
+    assert_eq!(output, "60;3;bool(false)\nNULL\nbool(false)\n50;30;bool(false)\nbool(false)\n");
+}
+
+#[test]
+fn test_array_walk_recursive_and_array_map_keys() {
+    let output = run(r#"<?php
+        $data = ['a' => 1, 'b' => ['c' => 2, 'd' => 3]];
+        array_walk_recursive($data, function (&$value, $key) { $value++; });
+        echo $data['a'] . $data['b']['c'] . $data['b']['d'] . ";";
+        function add(&$value, $key, $amount) { $value = $value + $amount; }
+        array_walk_recursive($data, 'add', 10);
+        echo $data['a'] . "," . $data['b']['d'] . ";";
+        $doubled = array_map(fn($x) => $x * 2, ['x' => 1, 'y' => 2]);
+        echo $doubled['x'] . $doubled['y'] . ";";
+        $joined = array_map(fn($a, $b) => $a . $b, ['p' => 'l'], ['q' => 'r']);
+        echo $joined[0];
+    "#);
+    assert_eq!(output, "234;12,14;24;lr");
+}
+
+#[test]
+fn test_isset_on_access_chains() {
+    let output = run(r#"<?php
+        class Box { public $inner = null; public $items = ['k' => 1]; }
+        $a = ['x' => 5, 's' => "ab", 'n' => null];
+        $box = new Box();
+        var_dump(isset($a['x']['y']), isset($a['x']), isset($a['n']), isset($a['missing']['deep']['er']));
+        var_dump(isset($a['s'][1]), isset($a['s'][2]), isset($undefined['x']));
+        var_dump(isset($box->items['k']), isset($box->inner->value), isset($a['x']->prop), isset($a['x'], $box->items));
+    "#);
+    assert_eq!(output, "bool(false)\nbool(true)\nbool(false)\nbool(false)\nbool(true)\nbool(false)\nbool(false)\nbool(true)\nbool(false)\nbool(false)\nbool(true)\n");
+}
+
+#[test]
+fn test_register_native_function() {
+    let tokens = php_lexer::lex("<?php echo double(21) . ';' . double(strval(4)) . ';' . str_repeat('a', 2);").expect("Failed to lex");
+    let ast = php_parser::parse(tokens).expect("Failed to parse");
+    let mut engine = Engine::new();
+    engine.register_function("double", |engine, args| {
+        let value = engine.evaluate_expr(&args[0])?;
+        Ok(php_types::PhpValue::Int(value.to_int() * 2))
+    });
+    engine.register_function("strval", |engine, args| Ok(php_types::PhpValue::String(engine.evaluate_expr(&args[0])?.to_string())));
+    engine.execute_stmt(&ast).expect("Failed to execute");
+    assert_eq!(engine.get_output(), "42;8;aa");
+}
+
+#[test]
+fn test_host_function_bridge() {
+    use php_types::{php_arg, PhpConvert};
+    use std::collections::HashMap;
+
+    let tokens = php_lexer::lex(r#"<?php
+        $stats = word_stats(['apple', 'fig', 'kiwi'], '-');
+        echo $stats['count'] . ';' . $stats['letters'] . ';' . $stats['label'] . ';';
+        echo word_stats('oops');
+    "#).expect("Failed to lex");
+    let ast = php_parser::parse(tokens).expect("Failed to parse");
+    let mut engine = Engine::new();
+    engine.register_host_function("word_stats", |args| {
+        let words: Vec<String> = php_arg("word_stats", args, 0)?;
+        let separator: Option<String> = php_arg("word_stats", args, 1).unwrap_or(None);
+        let mut stats = HashMap::new();
+        stats.insert("count".to_string(), (words.len() as i64).to_php_value());
+        stats.insert("letters".to_string(), words.iter().map(|w| w.len() as i64).sum::<i64>().to_php_value());
+        stats.insert("label".to_string(), format!("words{}", separator.unwrap_or_default()).to_php_value());
+        Ok(stats.to_php_value())
+    });
+    let result = engine.execute_stmt(&ast);
+    assert_eq!(engine.get_output(), "3;12;words-;");
+    assert_eq!(result, Err("word_stats(): Argument #1 must be of type array, string given".to_string()));
+}
+
+#[test]
+fn test_key_aware_set_operations() {
+    let output = run(r#"<?php
+        print_r(array_diff_key(["a" => 1, "b" => 2], ["a" => 9]));
+        print_r(array_intersect_key(["a" => 1, "b" => 2, "c" => 3], ["a" => 0, "c" => 0], ["c" => 0]));
+        print_r(array_diff_assoc(["a" => "x", "b" => "y"], ["a" => "x", "b" => "z"]));
+        print_r(array_intersect_assoc(["a" => "x", "b" => "y"], ["a" => "x", "b" => "z"]));
+        print_r(array_diff(["k" => "red", "l" => "blue"], ["blue"]));
+        print_r(array_intersect(["k" => "red", "l" => "blue"], ["blue"]));
+    "#);
+    assert_eq!(output, "Array\n(\n    [b] => 2\n)\nArray\n(\n    [c] => 3\n)\nArray\n(\n    [b] => y\n)\nArray\n(\n    [a] => x\n)\nArray\n(\n    [k] => red\n)\nArray\n(\n    [l] => blue\n)\n");
+}
+
+#[test]
+fn test_case_insensitive_string_search() {
+    let output = run(r#"<?php
+        echo str_ireplace("WORLD", "PHP", "hello world") . ";";
+        echo str_replace("o", "0", "foo boo", $count) . $count . ";";
+        echo str_ireplace(["A", "b"], ["1"], "aAbB") . ";";
+        var_dump(strpos("Hello", "l"), stripos("Hello", "LL"), strrpos("hello", "l"), strripos("HELLO", "l"), strpos("abc", "d"));
+        var_dump(strrpos("0123456789a123456789b123456789c", "7", 0 - 5), strpos("abcabc", "c", 0 - 2));
+        echo stristr("USER@Example.com", "@example") . ";" . strstr("user@example.com", "@", true) . ";";
+        var_dump(strstr("abc", "z"));
+        try {
+            strpos("abc", "a", 4);
+        } catch (ValueError $e) {
+            echo $e->getMessage() . ";";
+        }
+    "#);
+    assert_eq!(output, "hello PHP;f00 b004;11;int(2)\nint(2)\nint(3)\nint(3)\nbool(false)\nint(17)\nint(5)\n@Example.com;user;bool(false)\n\
+strpos(): Argument #3 ($offset) must be contained in argument #1 ($haystack);");
+}
+
+#[test]
+fn test_array_reduce_and_find() {
+    let output = run(r#"<?php
+        var_dump(array_reduce([1, 2, 3], fn($carry, $x) => $carry + $x));
+        var_dump(array_reduce([], fn($carry, $x) => $carry + $x));
+        echo array_reduce(["a", "b", "c"], fn($carry, $x) => $carry . $x, ">") . ";";
+        echo array_find([1, 2, 3, 4], fn($x) => $x > 2) . ";";
+        echo array_find_key(["a" => 1, "b" => 5], fn($x) => $x > 2) . ";";
+        var_dump(array_find([1, 2], fn($x) => $x > 5));
+        var_dump(array_all([2, 4, 6], fn($x) => $x > 1), array_all([2, 4, 6], fn($x) => $x > 2));
+        var_dump(array_any([1, 2], fn($x, $k) => $k == 1), array_any([], fn($x) => true), array_all([], fn($x) => false));
+    "#);
+    assert_eq!(output, "int(6)\nNULL\n>abc;3;b;NULL\nbool(true)\nbool(false)\nbool(true)\nbool(false)\nbool(true)\n");
+}
+
+#[test]
+fn test_modulo_and_intdiv_signs() {
+    let output = run(r#"<?php
+        $neg7 = 0 - 7;
+        $neg3 = 0 - 3;
+        var_dump(7 % 3, $neg7 % 3, 7 % $neg3, $neg7 % $neg3, 10 % 4 + 7 % 3, 7.9 % 2);
+        var_dump(intdiv($neg7, 2), intdiv(7, 2), intdiv(7, $neg3), PHP_INT_MIN % (0 - 1));
+        try { echo 1 % 0; } catch (DivisionByZeroError $e) { echo $e->getMessage() . ";"; }
+        try { echo intdiv(1, 0); } catch (ArithmeticError $e) { echo $e->getMessage() . ";"; }
+        try { echo intdiv(PHP_INT_MIN, 0 - 1); } catch (DivisionByZeroError $e) { echo "wrong"; } catch (ArithmeticError $e) { echo "overflow"; }
+    "#);
+    assert_eq!(output, "int(1)\nint(-1)\nint(1)\nint(-1)\nint(3)\nint(1)\nint(-3)\nint(3)\nint(-2)\nint(0)\nModulo by zero;Division by zero;overflow");
+}
+
+#[test]
+fn test_closure_scope_isolation() {
+    let output = run(r#"<?php
+        $secret = "hidden";
+        $greeting = "hi";
+        $peek = function() { return isset($secret) ? "leak" : "isolated"; };
+        echo $peek() . ";";
+        $greet = function($name) use ($greeting) { return $greeting . " " . $name; };
+        $greeting = "bye";
+        echo $greet("bob") . ";";
+        $shadow = function() use ($greeting) { $greeting = "changed"; return $greeting; };
+        echo $shadow() . $greeting . ";";
+        $factor = 3;
+        $triple = fn($x) => $x * $factor;
+        echo $triple(2) . ";";
+    "#);
+    assert_eq!(output, "isolated;hi bob;changedbye;6;");
+}
+
+#[test]
+fn test_arrow_function_captures_only_used_variables() {
+    let output = run(r#"<?php
+        $secret = "hidden";
+        $name = "secret";
+        $peek = fn() => isset($$name) ? "leak" : "isolated";
+        echo $peek() . ";";
+        $factor = 3;
+        $outer = fn($x) => fn($y) => $x * $y * $factor;
+        $inner = $outer(2);
+        echo $inner(5) . ";";
+        $label = fn() => "factor=$factor";
+        echo $label() . ";";
+    "#);
+    assert_eq!(output, "isolated;30;factor=3;");
+}
+
+#[test]
+fn test_closure_by_reference_capture() {
+    let output = run(r#"<?php
+        $x = 1;
+        $inc = function () use (&$x) { $x++; };
+        $inc();
+        $inc();
+        echo $x . ";";
+        $x = 10;
+        $inc();
+        echo $x . ";";
+        $sum = 0;
+        array_map(function ($n) use (&$sum) { $sum += $n; }, [1, 2, 3]);
+        echo $sum . ";";
+        function make_counter() {
+            $count = 0;
+            return function () use (&$count) { return ++$count; };
+        }
+        $next = make_counter();
+        $next();
+        echo $next() . ";";
+        $fact = function ($n) use (&$fact) { return $n <= 1 ? 1 : $n * $fact($n - 1); };
+        echo $fact(5) . ";";
+        $created = function () use (&$fresh) { $fresh = "set"; };
+        $created();
+        echo $fresh;
+    "#);
+    assert_eq!(output, "3;11;6;2;120;set");
+}
+
+#[test]
+fn test_argument_count_with_defaults_and_variadics() {
+    let output = run(r#"<?php
+        function greet($name, $greeting = "Hello") { return $greeting . " " . $name . ";"; }
+        echo greet("Ann");
+        echo greet("Bob", "Hi");
+        echo greet("Cy", "Hey", "ignored");
+        function total($first, ...$rest) { return $first + array_sum($rest); }
+        echo total(1) . ";" . total(1, 2, 3) . ";";
+        try { greet(); } catch (ArgumentCountError $e) { echo $e->getMessage() . ";"; }
+        function pair($a, $b) { return $a . $b; }
+        try { pair(1); } catch (TypeError $e) { echo $e->getMessage() . ";"; }
+        $nums = [4, 5];
+        array_walk_recursive($nums, function($value) {});
+        echo implode(",", $nums);
+    "#);
+    assert_eq!(output, "Hello Ann;Hi Bob;Hey Cy;1;6;Too few arguments to function greet(), 0 passed and at least 1 expected;Too few arguments to function pair(), 1 passed and exactly 2 expected;4,5");
+}
+
+#[test]
+fn test_closure_serialization() {
+    let output = run(r#"<?php
+        $double = fn($x) => $x * 2;
+        var_dump($double);
+        print_r($double);
+        echo json_encode($double) . ";" . $double(4);
+    "#);
+    assert_eq!(output, "object(Closure)#1 (0) {\n}\nClosure Object\n(\n)\n{};8");
+}
+
+#[test]
+fn test_spread_generators_and_iterators() {
+    let output = run(r#"<?php
+        function gen() { yield 1; yield 2; yield 3; }
+        echo implode(",", [...gen()]) . ";";
+        echo implode(",", [10, ...gen(), 100]) . ";";
+        $keyed = iterator_to_array(gen());
+        echo $keyed[0] . $keyed[1] . $keyed[2] . ";";
+        class Countdown implements Iterator {
+            private $n = 2;
+            public function rewind() { $this->n = 2; }
+            public function valid() { return $this->n > 0; }
+            public function current() { return $this->n; }
+            public function key() { return 2 - $this->n; }
+            public function next() { $this->n = $this->n - 1; }
+        }
+        $counted = iterator_to_array(new Countdown());
+        echo $counted[0] . $counted[1] . implode(",", [...new Countdown()]) . ";";
+        $once = gen();
+        $drained = [...$once];
+        try { $again = [...$once]; } catch (Exception $e) { echo $e->getMessage(); }
+    "#);
+    assert_eq!(output, "1,2,3;10,1,2,3,100;123;212,1;Cannot traverse an already closed generator");
+}
+
+#[test]
+fn test_get_defined_constants_and_functions() {
+    let output = run(r#"<?php
+        define("APP_VERSION", "1.2");
+        function my_helper() {}
+        $constants = get_defined_constants();
+        echo $constants["APP_VERSION"] . ";" . $constants["PHP_EOL"];
+        $functions = get_defined_functions();
+        var_dump(array_any($functions["user"], fn($name) => $name == "my_helper"));
+        var_dump(array_any($functions["user"], fn($name) => $name == "json_encode"));
+        var_dump(array_any($functions["internal"], fn($name) => $name == "json_encode"));
+    "#);
+    assert_eq!(output, "1.2;\nbool(true)\nbool(false)\nbool(true)\n");
+}
+
+#[test]
+fn test_arrays_preserve_insertion_order() {
+    let output = run(r#"<?php
+        $mixed = [3 => 'a', 1 => 'b', 'x' => 'c', 2 => 'd'];
+        foreach ($mixed as $k => $v) { echo $k . "=" . $v . " "; }
+        echo json_encode($mixed) . "\n";
+        echo json_encode([2 => 'z', 0 => 'y', 1 => 'x']) . "\n";
+        print_r(array_merge(['b' => 1, 5 => 2], ['a' => 3, 9 => 4]));
+    "#);
+    assert_eq!(output, "3=a 1=b x=c 2=d {\"3\":\"a\",\"1\":\"b\",\"x\":\"c\",\"2\":\"d\"}\n{\"2\":\"z\",\"0\":\"y\",\"1\":\"x\"}\nArray\n(\n    [b] => 1\n    [0] => 2\n    [a] => 3\n    [1] => 4\n)\n");
+}
+
+#[test]
+fn test_null_coalesce_on_array_access_objects() {
+    let output = run(r#"<?php
+        class Settings {
+            private $values = ['theme' => 'dark', 'empty' => null];
+            public function offsetExists($key) { echo "exists(" . $key . ")"; return $key === "theme" || $key === "empty"; }
+            public function offsetGet($key) { echo "get(" . $key . ")"; return $this->values[$key]; }
+        }
+        $settings = new Settings();
+        echo ($settings['missing'] ?? 'default') . ";";
+        echo ($settings['theme'] ?? 'default') . ";";
+        $missing = isset($settings['missing']);
+        var_dump($missing);
+        $empty = isset($settings['empty']);
+        var_dump($empty);
+        echo ($settings['empty'] ?? 'default') . ";";
+        echo $settings['theme'] . ";";
+    "#);
+    assert_eq!(output, "exists(missing)default;exists(theme)get(theme)dark;exists(missing)bool(false)\nexists(empty)bool(true)\nexists(empty)get(empty)default;get(theme)dark;");
+}
+
+#[test]
+fn test_integer_and_float_literal_types() {
+    let output = run(r#"<?php
+        var_dump(10, 10.5, 1e3, 0.0, 2 + 3, 2 + 0.5, null + 1, "4" * 2);
+    "#);
+    assert_eq!(output, "int(10)\nfloat(10.5)\nfloat(1000)\nfloat(0)\nint(5)\nfloat(2.5)\nint(1)\nint(8)\n");
+}
+
+#[test]
+fn test_string_operands_use_their_numeric_prefix() {
+    let output = run(r#"<?php
+        var_dump("5 apples" + 1, " 1.5" + 1, "1.5 " + 1, "abc" + 1, +"2.5x", "1e2x" * 1, "-.5e" - 1, "99999999999999999999" + 0);
+    "#);
+    assert_eq!(output, "int(6)\nfloat(2.5)\nfloat(2.5)\nint(1)\nfloat(2.5)\nfloat(100)\nfloat(-1.5)\nfloat(1.0E+20)\n");
+}
+
+#[test]
+fn test_array_count_values_and_flip() {
+    let code = r#"<?php
+        print_r(array_count_values(["a", "b", "a", "c", "a"]));
+        print_r(array_count_values([1, "1", 2.5, "x", null]));
+        print_r(array_flip(["a" => 1, "b" => "x", "c" => 1]));
+    "#;
+    let ast = php_parser::parse(php_lexer::lex(code).expect("Failed to lex")).expect("Failed to parse");
+    let mut engine = Engine::new();
+    engine.execute_stmt(&ast).expect("Failed to execute");
+    let warning = RaisedError {
+        level: ErrorLevel::Warning,
+        message: "array_count_values(): Can only count string and integer values, entry skipped".to_string(),
+    };
+    assert_eq!(engine.raised_errors(), &[warning.clone(), warning]);
+    assert_eq!(engine.get_output(), "Array\n(\n    [a] => 3\n    [b] => 1\n    [c] => 1\n)\nArray\n(\n    [1] => 2\n    [x] => 1\n)\nArray\n(\n    [1] => c\n    [x] => b\n)\n");
+}
+
+#[test]
+fn test_match_keyword_and_match_method() {
+    let output = run(r#"<?php
+        class Router {
+            public function match($path) { return "route:" . $path; }
+        }
+        function say($text) { echo $text; }
+        $r = new Router();
+        echo $r->match("/home") . ";";
+        $code = 404;
+        match ($code) {
+            200, 201 => say("ok"),
+            404 => say("missing"),
+            default => say("error"),
+        };
+        echo ";" . match (true) { $code > 400 => "client", default => "other" };
+    "#);
+    assert_eq!(output, "route:/home;missing;client");
+}
+
+#[test]
+fn test_yield_from_delegates_to_inner_iterables() {
+    let output = run(r#"<?php
+        function inner() { yield 2; yield 3; }
+        function outer() { yield 1; yield from inner(); yield from [4, 5]; }
+        $square = fn($n) => $n * $n;
+        foreach (outer() as $n) { echo $square($n) . " "; }
+    "#);
+    assert_eq!(output, "1 4 9 16 25 ");
+}
+
+#[test]
+fn test_generator_keys_and_traversable_checks() {
+    let output = run(r#"<?php
+        function inner() { yield 1; yield 2; }
+        function outer() { yield 0; yield from inner(); yield 3; }
+        foreach (outer() as $key => $value) { echo $key . "=" . $value . " "; }
+        echo implode(",", [...outer()]) . ";";
+        class Plain { public $a = 1; }
+        foreach (new Plain() as $name => $value) { echo $name . "=" . $value . ";"; }
+        try { $items = [...new Plain()]; } catch (TypeError $e) { echo $e->getMessage() . ";"; }
+        try { iterator_to_array(new Plain()); } catch (TypeError $e) { echo $e->getMessage() . ";"; }
+        class Listing implements IteratorAggregate {
+            public function getIterator() { return ["x"]; }
+        }
+        try { foreach (new Listing() as $item) {} } catch (TypeError $e) { echo $e->getMessage() . ";"; }
+        class Wrapped implements IteratorAggregate {
+            public function getIterator() { return outer(); }
+        }
+        echo implode(",", iterator_to_array(new Wrapped()));
+    "#);
+    assert_eq!(output, "0=0 0=1 1=2 1=3 0,1,2,3;a=1;Plain is not Traversable;Plain is not Traversable;\
+Listing::getIterator() must return a Traversable, array returned;1,3");
+}
+
+#[test]
+fn test_draining_a_generator_is_limited() {
+    let code = r#"<?php
+        function naturals() { $n = 0; while (true) { yield $n++; } }
+        foreach (naturals() as $n) { if ($n > 3) { break; } echo $n; }
+        $all = iterator_to_array(naturals());
+    "#;
+    let ast = php_parser::parse(php_lexer::lex(code).expect("Failed to lex")).expect("Failed to parse");
+    let mut engine = Engine::new();
+    engine.set_limits(Limits { max_array_elements: 100, ..Limits::default() });
+    let error = engine.execute_stmt(&ast).unwrap_err();
+    assert_eq!(engine.get_output(), "0123");
+    assert_eq!(error, "Memory limit exceeded: generator yielded more than 100 values");
+}
+
+#[test]
+fn test_generators_run_lazily() {
+    let output = run(r#"<?php
+        function g() { echo "start,"; yield 1; echo "mid,"; yield 2; }
+        $gen = g();
+        echo "created,";
+        foreach ($gen as $v) { echo $v . ","; }
+        function never() { if (false) { yield; } echo "ran,"; return 5; }
+        $never = never();
+        echo "called,";
+        var_dump($never);
+        echo $never->getReturn() . ";";
+        function running_total() {
+            $total = 0;
+            while (($x = yield $total) !== null) { $total = $total + $x; }
+            return $total;
+        }
+        $sum = running_total();
+        echo $sum->current() . "," . $sum->send(3) . "," . $sum->send(4) . ",";
+        $sum->next();
+        var_dump($sum->valid());
+        echo $sum->getReturn() . ";";
+        try { $sum->rewind(); } catch (Exception $e) { echo $e->getMessage() . ";"; }
+        function guarded() {
+            try { yield 1; throw new Exception("boom"); }
+            catch (Exception $e) { yield $e->getMessage(); }
+            finally { echo "finally,"; }
+        }
+        foreach (guarded() as $v) { echo $v . ","; }
+    "#);
+    assert_eq!(output, "created,start,1,mid,2,called,object(Generator)#2 (0) {\n}\nran,5;0,3,7,bool(false)\n7;\
+Cannot rewind a generator that was already run;1,boom,finally,");
+}
+
+#[test]
+fn test_object_properties_keep_insertion_order() {
+    let output = run(r#"<?php
+        class Point { public $z; }
+        $p = new Point();
+        $p->y = 2;
+        $p->x = 1;
+        $p->z = 3;
+        echo json_encode($p) . ";";
+        foreach ($p as $name => $value) { echo $name . "=" . $value . ","; }
+        echo json_encode(get_object_vars($p)) . ";";
+        var_dump($p);
+    "#);
+    assert_eq!(output, "{\"z\":3,\"y\":2,\"x\":1};z=3,y=2,x=1,{\"z\":3,\"y\":2,\"x\":1};object(Point)#1 (3) {\n  [\"z\"]=>\n  int(3)\n  [\"y\"]=>\n  int(2)\n  [\"x\"]=>\n  int(1)\n}\n");
+}
+
+#[test]
+fn test_compound_assignment_operators() {
+    let output = run(r#"<?php
+        $x = 10;
+        $x -= 2; echo $x . ",";
+        $x *= 3; echo $x . ",";
+        $x %= 5; echo $x . ",";
+        $x **= 3; echo $x . ",";
+        $x /= 4; echo $x . ",";
+        $x = 6;
+        $x &= 3; echo $x . ",";
+        $x |= 8; echo $x . ",";
+        $s = "a"; $s .= "b"; echo $s . ",";
+        var_dump(2 ** 3 ** 2, 2 ** 0.5 > 1.41, 2 ** 62, 2 ** 64 > 2 ** 62, 3 * 2 ** 2);
+    "#);
+    assert_eq!(output, "8,24,4,64,16,2,10,ab,int(512)\nbool(true)\nint(4611686018427387904)\nbool(true)\nint(12)\n");
+}
+
+#[test]
+fn test_compound_assignment_on_elements_and_properties() {
+    let output = run(r#"<?php
+        for ($i = 0; $i < 6; $i += 2) { echo $i; }
+        $a = [10, 20];
+        $a[0] -= 5; $a[2] = 9;
+        $m['a']['b'] = 2; $m['a']['b'] *= 10;
+        $arr = ['k' => 'a']; $arr['k'] .= 'x';
+        $o = new stdClass(); $o->n = 1; $o->n += 2;
+        $o->list = []; $o->list['z'] = 5;
+        $x = 1; $y = ($x += 2);
+        $s = "abc"; $s[1] = 'X';
+        echo "," . json_encode($a) . json_encode($m) . $arr['k'] . $o->n . $o->list['z'] . $y . $x . $s;
+        $n = 5;
+        try { $n[0] = 1; } catch (Error $e) { echo "," . $e->getMessage(); }
+    "#);
+    assert_eq!(output, "024,[5,20,9]{\"a\":{\"b\":20}}ax3533aXc,Cannot use a scalar value as an array");
+}
+
+#[test]
+fn test_size_limits_reject_huge_arrays_and_strings() {
+    let execute = |engine: &mut Engine, code: &str| {
+        let ast = php_parser::parse(php_lexer::lex(code).expect("Failed to lex")).expect("Failed to parse");
+        engine.execute_stmt(&ast)
+    };
+    let mut engine = Engine::new();
+    let error = execute(&mut engine, "<?php $a = array_fill(0, 100000000, 1);").unwrap_err();
+    assert_eq!(error, "array_fill(): Memory limit exceeded: array of 100000000 elements requested, at most 10000000 allowed");
+
+    engine.set_limits(Limits { max_array_elements: 4, max_string_length: 8, ..Limits::default() });
+    execute(&mut engine, "<?php echo json_encode(array_fill(5, 3, 'x')) . str_repeat('ab', 4);").expect("within limits");
+    assert_eq!(engine.get_output(), "{\"5\":\"x\",\"6\":\"x\",\"7\":\"x\"}abababab");
+    assert!(execute(&mut engine, "<?php $s = str_repeat('ab', 5);").unwrap_err().starts_with("str_repeat(): Memory limit exceeded"));
+    assert!(execute(&mut engine, "<?php $m = array_merge([1, 2, 3], [4, 5]);").unwrap_err().starts_with("array_merge(): Memory limit exceeded"));
+}
+
+#[test]
+fn test_auto_index_stops_at_php_int_max() {
+    let output = run(r#"<?php
+        var_dump(array_keys(array_fill(PHP_INT_MAX, 1, 0)), array_flip([PHP_INT_MAX]), [PHP_INT_MAX => 1]);
+        $attempts = [
+            fn() => array_fill(PHP_INT_MAX, 2, 0),
+            fn() => [PHP_INT_MAX => 1, 2],
+            function () { $a = [PHP_INT_MAX => 1]; array_push($a, 2); },
+        ];
+        foreach ($attempts as $attempt) {
+            try { $attempt(); } catch (Error $e) { echo $e->getMessage() . "\n"; }
+        }
+    "#);
+    let error = "Cannot add element to the array as the next element is already occupied\n";
+    assert_eq!(output, format!("array(1) {{\n  [0]=>\n  int(9223372036854775807)\n}}\narray(1) {{\n  [9223372036854775807]=>\n  int(0)\n}}\narray(1) {{\n  [9223372036854775807]=>\n  int(1)\n}}\n{}", error.repeat(3)));
+}
+
+#[test]
+fn test_keyed_destructuring_with_expression_keys() {
+    let output = run(r#"<?php
+        const NAME_KEY = "name";
+        $row = ["id" => 7, "name" => "Ada", 3 => "three", "user_role" => "admin"];
+        $field = "id";
+        $prefix = "user_";
+        [$field => $id, NAME_KEY => $name, 1 + 2 => $third, $prefix . "role" => $role, "missing" => $none] = $row;
+        echo $id . "," . $name . "," . $third . "," . $role . ",";
+        var_dump($none);
+        [$first, $second] = ["a", "b"];
+        echo $first . $second;
+    "#);
+    assert_eq!(output, "7,Ada,three,admin,NULL\nab");
+}
+
+#[test]
+fn test_do_while_runs_body_at_least_once() {
+    let output = run(r#"<?php
+        $i = 10;
+        do { echo "body" . $i . ";"; $i++; } while ($i < 5);
+        $n = 0;
+        do {
+            $n++;
+            if ($n == 2) { continue; }
+            if ($n == 4) { break; }
+            echo $n;
+        } while (true);
+        function first_even($start) {
+            do { if ($start % 2 == 0) { return $start; } $start++; } while ($start < 100);
+            return 0;
+        }
+        echo ";" . first_even(7);
+    "#);
+    assert_eq!(output, "body10;13;8");
+}
+
+#[test]
+fn test_concat_assign_appends_in_place() {
+    let output = run(r#"<?php
+        $s = "a";
+        for ($i = 0; $i < 3; $i++) { $s .= $i; }
+        $s .= 1.5;
+        $s = $s . "!";
+        $n = 4;
+        $n .= "2";
+        $t = null;
+        $t .= "x";
+        $u = "u";
+        $u .= $u . $u;
+        var_dump($s, $n, $t, $u);
+    "#);
+    assert_eq!(output, "string(8) \"a0121.5!\"\nstring(2) \"42\"\nstring(1) \"x\"\nstring(3) \"uuu\"\n");
+}
+
+#[test]
+fn test_bitwise_xor_operator() {
+    let output = run(r#"<?php
+        $flags = 6;
+        $flags ^= 2;
+        var_dump(5 ^ 3, "12" ^ 10, $flags, 1 ^ 2 == 3, 6 & 3 ^ 1 | 8, true xor false);
+        $a = true and false;
+        var_dump($a);
+    "#);
+    assert_eq!(output, "int(6)\nint(6)\nint(4)\nint(1)\nint(11)\nbool(true)\nbool(true)\n");
+}
+
+#[test]
+fn test_echo_and_var_dump_float_precision() {
+    let output = run(r#"<?php
+        $third = 1 / 3;
+        echo $third . ";" . 0.1 . ";";
+        var_dump($third, 0.1, 7.0);
+        print_r([$third]);
+    "#);
+    assert_eq!(output, "0.33333333333333;0.1;float(0.3333333333333333)\nfloat(0.1)\nfloat(7)\nArray\n(\n    [0] => 0.33333333333333\n)\n");
+}
+
+#[test]
+fn test_shift_operators() {
+    let output = run(r#"<?php
+        $bits = 3;
+        $bits <<= 2;
+        $min = PHP_INT_MIN;
+        var_dump(1 << 4, 256 >> 2, $bits, 1 << 2 + 1, 1 << 64, $min >> 70, "8" >> 1);
+        try { echo 1 << (0 - 1); } catch (ArithmeticError $e) { echo "ArithmeticError: " . $e->getMessage(); }
+    "#);
+    assert_eq!(output, "int(16)\nint(64)\nint(12)\nint(8)\nint(0)\nint(-1)\nint(4)\nArithmeticError: Bit shift by negative number");
+}
+
+#[test]
+fn test_braced_interpolation_and_dollar_brace_deprecation() {
+    let tokens = php_lexer::lex(r#"<?php
+        $name = "Ada";
+        $n = 3;
+        echo "{$name}s: {$n}x, $name, {name}, $ 5, {$missing}.";
+        echo "|${name}|";
+    "#).expect("Failed to lex");
+    let ast = php_parser::parse(tokens).expect("Failed to parse");
+    let mut engine = Engine::new();
+    engine.execute_stmt(&ast).expect("Failed to execute");
+    assert_eq!(engine.get_output(), "Adas: 3x, Ada, {name}, $ 5, .|Ada|");
+    assert_eq!(engine.raised_errors(), &[RaisedError {
+        level: ErrorLevel::Deprecated,
+        message: "Using ${var} in strings is deprecated, use {$var} instead".to_string(),
+    }]);
+    assert_eq!(run("<?php echo E_DEPRECATED;"), "8192");
+}
+
+#[test]
+fn test_unary_minus_and_plus() {
+    let output = run(r#"<?php
+        $x = 4;
+        $f = 1.5;
+        var_dump(-5, -$x, -(2 + 3), - -$x, -$f, +"7", -"2.5", -2 ** 2, -PHP_INT_MIN, 10 - -3);
+        $y = 4;
+        echo --$y;
+    "#);
+    assert_eq!(output, "int(-5)\nint(-4)\nint(-5)\nint(4)\nfloat(-1.5)\nint(7)\nfloat(-2.5)\nint(-4)\nfloat(9.223372036854776E+18)\nint(13)\n3");
+}
+
+#[test]
+fn test_inline_html_between_php_islands() {
+    assert_eq!(run("<?php echo 1; ?> middle <?php echo 2; ?>"), "1 middle 2");
+    // Inline text is literal: no interpolation, and the loop repeats it
+    assert_eq!(run("<?php for ($i = 0; $i < 2; $i++) { ?>[$i]<?php } ?>\ndone"), "[$i][$i]done");
+}
+
+#[test]
+fn test_logical_not_operator() {
+    assert_eq!(run(r#"<?php var_dump(!true, !0, !"0", !"hello");"#), "bool(false)\nbool(true)\nbool(true)\nbool(false)\n");
+    assert_eq!(run("<?php if (!isset($x)) { echo \"unset\"; } if (1 != 2) { echo \"!\"; }"), "unset!");
+    // ! binds tighter than comparison: (!1) == false
+    assert_eq!(run("<?php var_dump(!1 == false);"), "bool(true)\n");
+}
+
+#[test]
+fn test_close_tag_implies_semicolon() {
+    assert_eq!(run(r#"<?php echo "hi" ?>"#), "hi");
+    assert_eq!(run("<?php $x = 2; $x++ ?> is <?php echo $x ?>"), " is 3");
+    assert_eq!(run("<?php function f() { return ?>x<?php } f(); echo \"done\" ?>"), "done");
+}
+
+#[test]
+fn test_complex_and_array_interpolation() {
+    let code = r#"<?php
+$user = ["name" => "Ada", "tags" => ["x", "y"]];
+$count = 3;
+$i = 1;
+$obj = new stdClass();
+$obj->prop = "P";
+echo "Hello {$user['name']}\n";
+echo "Total: ${count}\n";
+echo "$user[name] {$user['tags'][$i]} {$obj->prop} $obj->prop\n";
+echo "[$missing] {$count}{$count}\n";
+"#;
+    assert_eq!(run(code), "Hello Ada\nTotal: 3\nAda y P P\n[] 33\n");
+}
+
+#[test]
+fn test_non_capturing_catch() {
+    let code = r#"<?php
+try {
+    throw new InvalidArgumentException("bad");
+} catch (RuntimeException | InvalidArgumentException) {
+    echo "caught";
+}
+echo isset($e) ? " bound" : " unbound";
+"#;
+    assert_eq!(run(code), "caught unbound");
+}
+
+#[test]
+fn test_single_quoted_strings_are_literal() {
+    let code = r#"<?php
+$x = 5;
+echo '\n' . "|" . "\n" . "|";
+echo '$x' . "|" . "$x" . "|" . '{$x}' . "|" . 'it\'s \\ done';
+"#;
+    assert_eq!(run(code), "\\n|\n|$x|5|{$x}|it's \\ done");
+}
+
+#[test]
+fn test_readonly_property_can_only_be_written_once() {
+    let code = r#"<?php
+class Point {
+    public readonly int $x;
+    public function __construct(int $x) { $this->x = $x; }
+}
+$p = new Point(3);
+echo $p->x;
+try {
+    $p->x = 4;
+} catch (Error $e) {
+    echo " " . $e->getMessage();
+}
+echo " " . $p->x;
+"#;
+    assert_eq!(run(code), "3 Cannot modify readonly property Point::$x 3");
+}
+
+#[test]
+fn test_constructor_property_promotion() {
+    let code = r#"<?php
+class Point {
+    public function __construct(public int $x, private string $name, public readonly ?int $z = null) {
+        echo "init ";
+    }
+    public function name() { return $this->name; }
+}
+$p = new Point(1, "a");
+echo $p->x . $p->name() . ($p->z ?? "none");
+try {
+    $p->z = 5;
+} catch (Error $e) {
+    echo " " . $e->getMessage();
+}
+"#;
+    assert_eq!(run(code), "init 1anone Cannot modify readonly property Point::$z");
+}
+
+#[test]
+fn test_null_coalesce_evaluates_left_operand_once() {
+    let code = r#"<?php
+function counter($value) { static $calls = 0; $calls++; echo "call" . $calls . " "; return $value; }
+function fallback() { echo "fallback "; return "fb"; }
+$a = counter(null) ?? fallback();
+$b = counter("x") ?? fallback();
+$c = counter([1])[0] ?? fallback();
+echo $a . $b . $c;
+"#;
+    assert_eq!(run(code), "call1 fallback call2 call3 fbx1");
+}
+
+#[test]
+fn test_gettype_and_settype() {
+    let code = r#"<?php
+echo gettype(3.0) . " " . gettype(1) . " " . gettype("s") . " " . gettype(true) . " " . gettype([]) . " " . gettype(null) . " " . gettype(new stdClass()) . "\n";
+$x = "42";
+var_dump(settype($x, "integer"), $x);
+settype($x, "array");
+var_dump($x);
+"#;
+    assert_eq!(run(code), "double integer string boolean array NULL object\nbool(true)\nint(42)\narray(1) {\n  [0]=>\n  int(42)\n}\n");
+}
+
+#[test]
+fn test_dump_object_cycles_and_nesting() {
+    let setup = r#"<?php
+class A {}
+class B {}
+$a = new A();
+$b = new B();
+$a->b = $b;
+$b->items = [$a];
+"#;
+    assert_eq!(
+        run(&format!("{}print_r($a);", setup)),
+        "A Object\n(\n    [b] => B Object\n        (\n            [items] => Array\n                (\n                    [0] => A Object\n *RECURSION*\n                )\n\n        )\n\n)\n"
+    );
+    assert_eq!(
+        run(&format!("{}var_dump($a);", setup)),
+        "object(A)#1 (1) {\n  [\"b\"]=>\n  object(B)#2 (1) {\n    [\"items\"]=>\n    array(1) {\n      [0]=>\n      *RECURSION*\n    }\n  }\n}\n"
+    );
+}
+
+#[test]
+fn test_count_and_sizeof() {
+    let code = r#"<?php
+$nested = [1, [2, 3], ["a" => [4]]];
+echo count([]) . " " . count($nested) . " " . count($nested, COUNT_RECURSIVE) . " " . sizeof($nested) . " " . count(null) . " " . count("x");
+"#;
+    assert_eq!(run(code), "0 3 7 3 0 1");
+}
+
+#[test]
+fn test_string_comparison_functions() {
+    let code = r#"<?php
+echo strcmp("a", "b") . " " . strcmp("b", "a") . " " . strcmp("a", "a") . " " . strcasecmp("HELLO", "hello") . "\n";
+echo strncmp("abcd", "abef", 2) . " " . strncasecmp("ABcd", "abef", 3) . "\n";
+echo strnatcmp("img10", "img2") . " " . strcmp("img10", "img2") . " " . strnatcasecmp("IMG2", "img10") . " " . strnatcmp("x007", "x7") . "\n";
+"#;
+    assert_eq!(run(code), "-1 1 0 0\n0 -1\n1 -1 -1 0\n");
+}
+
+#[test]
+fn test_explode_limits() {
+    let code = r#"<?php
+echo implode("|", explode(",", "a,b,c")) . "\n";
+echo implode("|", explode(",", "a,b,c", 2)) . "\n";
+echo implode("|", explode(",", "a,b,c", 0)) . "\n";
+echo implode("|", explode(",", "a,b,c", -1)) . "\n";
+echo count(explode(",", "a,b,c", -5)) . " " . count(explode(",", "")) . "\n";
+try {
+    explode("", "abc");
+} catch (ValueError $e) {
+    echo $e->getMessage();
+}
+"#;
+    assert_eq!(run(code), "a|b|c\na|b,c\na,b,c\na|b\n0 1\nexplode(): Argument #1 ($separator) cannot be empty");
+}
+
+#[test]
+fn test_array_map_key_semantics() {
+    // Expected output taken from PHP 8.3
+    let code = r#"<?php
+$keyed = ["b" => 1, 5 => 2, "a" => 3];
+print_r(array_map(fn($v) => $v * 10, $keyed));
+print_r(array_map(fn($x, $y) => $x . $y, $keyed, ["k" => "x", "y"]));
+print_r(array_map(null, $keyed));
+print_r(array_map(null, [1, 2], ["a"]));
+"#;
+    assert_eq!(run(code), "Array\n(\n    [b] => 10\n    [5] => 20\n    [a] => 30\n)\n\
+Array\n(\n    [0] => 1x\n    [1] => 2y\n    [2] => 3\n)\n\
+Array\n(\n    [b] => 1\n    [5] => 2\n    [a] => 3\n)\n\
+Array\n(\n    [0] => Array\n        (\n            [0] => 1\n            [1] => a\n        )\n\n    [1] => Array\n        (\n            [0] => 2\n            [1] => \n        )\n\n)\n");
+}
+
+#[test]
+fn test_strlen_strpos_substr() {
+    let code = r#"<?php
+var_dump(strlen("hello"), strlen("héllo"), strpos("hello", "z"), strpos("hello", "l"), strpos("héllo", "l"));
+echo substr("hello", -3) . "|" . substr("hello", 1, 3) . "|" . substr("hello", 1, -1) . "|" . substr("hello", -3, -4) . "|" . substr("hello", 10) . "|" . substr("héllo", 1, 2) . "\n";
+"#;
+    assert_eq!(run(code), "int(5)\nint(6)\nbool(false)\nint(2)\nint(3)\nllo|ell|ell|||é\n");
+}
+
+#[test]
+fn test_date_time_and_strtotime() {
+    let code = r#"<?php
+var_dump(strtotime("1970-01-01 00:00:00 UTC"), strtotime("+1 day", 0), strtotime("next monday", 0), strtotime("2024-03-10T08:00:00+02:00"), strtotime("not a date"));
+echo date("Y-m-d H:i:s", 86400) . "|" . date("D, jS F Y g:ia", 0) . "\n";
+echo date_default_timezone_get() . "\n";
+date_default_timezone_set("Asia/Dhaka");
+echo date_default_timezone_get() . " " . date("H:i T", 0) . "\n";
+var_dump(date_default_timezone_set("Mars/Olympus"));
+$d = new DateTime("2024-01-15 10:30:00");
+echo $d->modify("+1 month")->format("D, d M Y H:i e") . "\n";
+echo $d->getTimestamp() . "\n";
+"#;
+    assert_eq!(run(code), "int(0)\nint(86400)\nint(345600)\nint(1710050400)\nbool(false)\n1970-01-02 00:00:00|Thu, 1st January 1970 12:00am\nUTC\nAsia/Dhaka 06:00 +06\nbool(false)\nThu, 15 Feb 2024 10:30 Asia/Dhaka\n1707971400\n");
+}
+
+#[test]
+fn test_strtotime_overflow_returns_false() {
+    let code = r#"<?php
+var_dump(strtotime("+9999999999999 years"), strtotime("+9223372036854775807 weeks", 0), strtotime("-9223372036854775807 seconds ago", 0));
+var_dump(strtotime("@9223372036854775807 +1 second"), strtotime("+1 second", PHP_INT_MAX - 1));
+echo date("Y-m-d", PHP_INT_MAX) . "|" . date("Y", PHP_INT_MIN) . "\n";
+"#;
+    assert_eq!(run(code), "bool(false)\nbool(false)\nint(9223372036854775807)\nbool(false)\nint(9223372036854775807)\n292277026596-12-04|-292277022657\n");
+}
+
+#[test]
+fn test_array_push_and_pop() {
+    let code = r#"<?php
+$stack = [1];
+echo array_push($stack, 2, 3, "four") . "\n";
+echo implode(",", $stack) . "\n";
+while (count($stack) > 0) {
+    echo array_pop($stack) . ";";
+}
+echo "\n" . count($stack) . "\n";
+var_dump(array_pop($stack));
+$list = [5 => "a", 6 => "b"];
+array_pop($list);
+array_push($list, "c");
+print_r($list);
+"#;
+    assert_eq!(run(code), "4\n1,2,3,four\nfour;3;2;1;\n0\nNULL\nArray\n(\n    [5] => a\n    [6] => c\n)\n");
+}
+
+#[test]
+fn test_hrtime_and_microtime() {
+    let code = r#"<?php
+$start = hrtime(true);
+$sum = 0;
+for ($i = 0; $i < 1000; $i++) {
+    $sum += $i;
+}
+$end = hrtime(true);
+var_dump($end > $start, count(hrtime()), gettype(microtime(true)), count(explode(" ", microtime())));
+"#;
+    assert_eq!(run(code), "bool(true)\nint(2)\nstring(6) \"double\"\nint(2)\n");
+}
+
+#[test]
+fn test_in_array_loose_and_strict() {
+    let code = r#"<?php
+var_dump(in_array("1", [1]), in_array("1", [1], true), in_array(1, [1], true), in_array("x", ["a" => "x"]), in_array(2, [1, 3]), in_array([1], [[1], 2], true));
+"#;
+    assert_eq!(run(code), "bool(true)\nbool(false)\nbool(true)\nbool(true)\nbool(false)\nbool(true)\n");
+}
+
+#[test]
+fn test_array_keys_and_values() {
+    let code = r#"<?php
+$ages = ["ann" => 31, 7 => 25, "bob" => 31, 2 => "31"];
+print_r(array_keys($ages));
+print_r(array_values($ages));
+print_r(array_keys($ages, 31));
+print_r(array_keys($ages, 31, true));
+"#;
+    assert_eq!(run(code), "Array\n(\n    [0] => ann\n    [1] => 7\n    [2] => bob\n    [3] => 2\n)\nArray\n(\n    [0] => 31\n    [1] => 25\n    [2] => 31\n    [3] => 31\n)\nArray\n(\n    [0] => ann\n    [1] => bob\n    [2] => 2\n)\nArray\n(\n    [0] => ann\n    [1] => bob\n)\n");
+}
+
+#[test]
+fn test_string_increment_and_decrement() {
+    let code = r#"<?php
+$s = "Az";
+$s++;
+echo $s . "\n";
+foreach (["a", "Zz", "zz", "a9", "9", "1.5", ""] as $v) {
+    $v++;
+    echo $v . "|";
+}
+echo "\n";
+$d = "abc";
+$d--;
+$n = "10";
+--$n;
+var_dump($d, $n);
+"#;
+    assert_eq!(run(code), "Ba\nb|AAa|aaa|b0|10|2.5|1|\nstring(3) \"abc\"\nint(9)\n");
+}
+
+#[test]
+fn test_array_map_callback_forms() {
+    let code = r#"<?php
+echo implode(",", array_map(fn($x) => $x * 2, [1, 2, 3])) . "\n";
+echo implode(",", array_map("strlen", ["a", "bcd"])) . "\n";
+function shout($word) { return $word . "!"; }
+echo implode(",", array_map('shout', ["hi", "yo"])) . "\n";
+$factor = 3;
+$triple = function ($x) use ($factor) { return $x * $factor; };
+echo implode(",", array_map($triple, [1, 2])) . "\n";
+echo implode(",", array_map(fn($a, $b) => $a + $b, [1, 2, 3], [10, 20, 30])) . "\n";
+"#;
+    assert_eq!(run(code), "2,4,6\n1,3\nhi!,yo!\n3,6\n11,22,33\n");
+}
+
+#[test]
+fn test_increment_and_decrement_on_null_and_bool() {
+    let code = r#"<?php
+$x = null;
+$x--;
+var_dump($x);
+$x++;
+var_dump($x);
+var_dump($unset++, $unset);
+$t = true;
+$t++;
+$f = false;
+--$f;
+var_dump($t, $f);
+"#;
+    assert_eq!(run(code), "NULL\nint(1)\nNULL\nint(1)\nbool(true)\nbool(false)\n");
+}
+
+#[test]
+fn test_array_filter_modes() {
+    let code = r#"<?php
+print_r(array_filter([1, 0, "", "a", null, [], "0", 2]));
+print_r(array_filter([1, 2, 3, 4, 5], fn($v) => $v % 2 == 1));
+print_r(array_filter(["a" => 1, "bb" => 2, "ccc" => 3], fn($k) => strlen($k) > 1, ARRAY_FILTER_USE_KEY));
+print_r(array_filter(["a" => 1, "b" => 2, "c" => 3], fn($v, $k) => $k != "b" && $v > 1, ARRAY_FILTER_USE_BOTH));
+"#;
+    assert_eq!(run(code), "Array\n(\n    [0] => 1\n    [3] => a\n    [7] => 2\n)\n\
+Array\n(\n    [0] => 1\n    [2] => 3\n    [4] => 5\n)\n\
+Array\n(\n    [bb] => 2\n    [ccc] => 3\n)\n\
+Array\n(\n    [c] => 3\n)\n");
+}
+
+#[test]
+fn test_array_union_and_union_assignment() {
+    let code = r#"<?php
+$a = ["x" => 1];
+$a += ["x" => 9, "y" => 2];
+print_r($a);
+print_r([1, 2] + [5, 6, 7]);
+$n = 1;
+$n += 2;
+echo $n . "\n";
+foreach ([fn() => [1, 2] + 1, fn() => 2 - [1], fn() => [] * 1.5, fn() => [2] ** 2, fn() => null + new stdClass()] as $op) {
+    try {
+        $op();
+    } catch (TypeError $e) {
+        echo $e->getMessage() . "\n";
+    }
+}
+"#;
+    assert_eq!(run(code), "Array\n(\n    [x] => 1\n    [y] => 2\n)\nArray\n(\n    [0] => 1\n    [1] => 2\n    [2] => 7\n)\n3\n\
+Unsupported operand types: array + int\nUnsupported operand types: int - array\nUnsupported operand types: array * float\n\
+Unsupported operand types: array ** int\nUnsupported operand types: null + stdClass\n");
+}
+
+#[test]
+fn test_array_reduce_initial_values() {
+    let code = r#"<?php
+$sum = fn($c, $x) => $c + $x;
+function product($carry, $item) { return $carry * $item; }
+var_dump(array_reduce([1, 2, 3, 4], $sum, 0), array_reduce([1, 2, 3, 4], $sum, 10), array_reduce([1, 2, 3, 4], 'product', 1), array_reduce([], $sum));
+"#;
+    assert_eq!(run(code), "int(10)\nint(20)\nint(24)\nNULL\n");
+}
+
+#[test]
+fn test_eval_shares_scope_and_returns_values() {
+    let code = r#"<?php
+var_dump(eval('return 1 + 1;'));
+eval('$defined = "from eval";');
+echo $defined . "\n";
+function scoped($x) {
+    eval('$y = $x * 2;');
+    return $y;
+}
+echo scoped(21) . "\n";
+var_dump(eval('echo "side effect\n";'));
+try {
+    eval('return 1 +;');
+} catch (ParseError $e) {
+    echo "caught ParseError\n";
+}
+"#;
+    assert_eq!(run(code), "int(2)\nfrom eval\n42\nside effect\nNULL\ncaught ParseError\n");
+
+    let mut engine = Engine::new();
+    engine.set_limits(Limits { max_eval_depth: 8, ..Limits::default() });
+    let ast = php_parser::parse(php_lexer::lex("<?php $code = 'return eval($code);'; eval($code);").expect("Failed to lex")).expect("Failed to parse");
+    assert_eq!(engine.execute_stmt(&ast).unwrap_err(), "eval(): Maximum nesting level of 8 reached");
+}
+
+#[test]
+fn test_variables_of_discarded_eval_code_stay_distinct() {
+    // Each eval'd AST is freed before the next, so its name strings may reuse addresses
+    let output = run(r#"<?php
+        for ($i = 0; $i < 30; $i++) { eval('$v' . $i . ' = ' . $i . ';'); }
+        echo $v3 . "," . $v7 . "," . ($v12 + $v29);
+    "#);
+    assert_eq!(output, "3,7,41");
+}
+
+#[test]
+fn test_sort_family() {
+    let code = r#"<?php
+$numbers = [10, 9, "8", 1.5, 100];
+sort($numbers);
+echo implode(",", $numbers) . "\n";
+rsort($numbers);
+echo implode(",", $numbers) . "\n";
+$words = ["banana", "apple", "10", "9"];
+sort($words);
+echo implode(",", $words) . "\n";
+sort($words, SORT_STRING);
+echo implode(",", $words) . "\n";
+$ages = ["peter" => 35, "ben" => 37, "joe" => 35, "amy" => 20];
+asort($ages);
+print_r($ages);
+arsort($ages);
+echo implode(",", array_keys($ages)) . "\n";
+ksort($ages);
+echo implode(",", array_keys($ages)) . "\n";
+krsort($ages);
+echo implode(",", array_keys($ages)) . "\n";
+"#;
+    assert_eq!(run(code), "1.5,8,9,10,100\n100,10,9,8,1.5\n9,10,apple,banana\n10,9,apple,banana\n\
+Array\n(\n    [amy] => 20\n    [peter] => 35\n    [joe] => 35\n    [ben] => 37\n)\n\
+ben,peter,joe,amy\namy,ben,joe,peter\npeter,joe,ben,amy\n");
+}
+
+#[test]
+fn test_anonymous_classes() {
+    let code = r#"<?php
+$counter = new class {
+    public $hits = 0;
+    public function increment() {
+        $this->hits = $this->hits + 1;
+        return $this;
+    }
+};
+echo $counter->increment()->increment()->hits . "\n";
+
+class Greeter {
+    public function __construct(public string $name) {}
+    public function greet() { return "Hello, " . $this->name; }
+}
+$loud = new class("world") extends Greeter {
+    public function greet() { return parent::greet() . "!"; }
+};
+echo $loud->greet() . "\n";
+
+$made = [];
+for ($i = 0; $i < 3; $i++) {
+    array_push($made, new class($i) { public function __construct(public int $id) {} });
+}
+echo $made[0]->id + $made[2]->id;
+echo "\n";
+"#;
+    assert_eq!(run(code), "2\nHello, world!\n2\n");
+}
+
+#[test]
+fn test_spread_and_named_arguments_in_calls() {
+    let code = r#"<?php
+class Calculator {
+    public function __construct(public string $label = "calc", public int $scale = 1) {}
+    public function sub($a, $b) { return ($a - $b) * $this->scale; }
+    public function sum(...$numbers) { return array_sum($numbers); }
+    public function tag($text, $open = "[", $close = "]") { return $open . $text . $close; }
+    public static function mul($a, $b) { return $a * $b; }
+}
+$c = new Calculator(scale: 2);
+echo $c->label . " " . $c->sub(...[9, 1]) . " " . $c->sub(b: 1, a: 3) . "\n";
+echo $c->sum(...[1, 2], ...[3, 4]) . " " . Calculator::mul(...[6, 7]) . "\n";
+echo $c->tag("x", close: ">") . " " . $c->tag(...["text" => "y", "open" => "<"]) . "\n";
+function pair($first, $second) { return $first . "-" . $second; }
+echo pair(second: "b", first: "a") . "\n";
+try {
+    $c->sub(1, c: 2);
+} catch (Error $e) {
+    echo $e->getMessage() . "\n";
+}
+"#;
+    assert_eq!(run(code), "calc 16 4\n10 42\n[x> <y]\na-b\nUnknown named parameter $c\n");
+}
+
+#[test]
+fn test_usort_uses_the_comparator() {
+    let code = r#"<?php
+$numbers = [3, 10, 1, 22];
+usort($numbers, fn($a, $b) => $b <=> $a);
+echo implode(",", $numbers) . "\n";
+$words = ["pear", "fig", "banana", "kiwi"];
+usort($words, function ($a, $b) { return strlen($a) - strlen($b); });
+echo implode(",", $words) . "\n";
+$people = ["x" => ["name" => "Ann", "age" => 40], "y" => ["name" => "Bob", "age" => 25]];
+usort($people, fn($a, $b) => $a["age"] <=> $b["age"]);
+echo $people[0]["name"] . "," . $people[1]["name"] . "\n";
+"#;
+    assert_eq!(run(code), "22,10,3,1\nfig,pear,kiwi,banana\nBob,Ann\n");
+}
+
+#[test]
+fn test_range_sequences() {
+    let code = r#"<?php
+echo implode(",", range(1, 5)) . "\n";
+echo implode(",", range(5, 1)) . "\n";
+echo implode(",", range(0, 1, 0.25)) . "\n";
+echo implode(",", range('a', 'c')) . "|" . implode(",", range('e', 'a', 2)) . "\n";
+echo implode(",", range(0, 10, 3)) . "|" . implode(",", range(10, 0, -5)) . "\n";
+var_dump(range(1, 2, 1.0)[1]);
+try {
+    range(1, 5, 0);
+} catch (ValueError $e) {
+    echo $e->getMessage() . "\n";
+}
+try {
+    range(1, 5, -1);
+} catch (ValueError $e) {
+    echo $e->getMessage() . "\n";
+}
+"#;
+    assert_eq!(run(code), "1,2,3,4,5\n5,4,3,2,1\n0,0.25,0.5,0.75,1\na,b,c|e,c,a\n0,3,6,9|10,5,0\nint(2)\n\
+range(): Argument #3 ($step) cannot be 0\nrange(): Argument #3 ($step) must be greater than 0 for increasing ranges\n");
+}
+
+#[test]
+fn test_int_range_at_the_i64_edges() {
+    let code = r#"<?php
+echo implode(",", range(9007199254740993, 9007199254740996)) . "\n";
+echo implode(",", range(PHP_INT_MAX - 3, PHP_INT_MAX)) . "\n";
+echo implode(",", range(PHP_INT_MIN, PHP_INT_MIN + 5, 2)) . "\n";
+echo implode(",", range(PHP_INT_MAX, PHP_INT_MAX - 2)) . "\n";
+echo implode(",", range(PHP_INT_MIN, PHP_INT_MAX, PHP_INT_MAX)) . "\n";
+"#;
+    assert_eq!(run(code), "9007199254740993,9007199254740994,9007199254740995,9007199254740996\n\
+9223372036854775804,9223372036854775805,9223372036854775806,9223372036854775807\n\
+-9223372036854775808,-9223372036854775806,-9223372036854775804\n\
+9223372036854775807,9223372036854775806,9223372036854775805\n\
+-9223372036854775808,-1,9223372036854775806\n");
+}
+
+#[test]
+fn test_undefined_method_errors_and_magic_calls() {
+    let code = r#"<?php
+class Plain {}
+$obj = new Plain();
+try {
+    $obj->nonexistent();
+} catch (Error $e) {
+    echo "Error: " . $e->getMessage() . "\n";
+}
+try {
+    Plain::missing();
+} catch (Error $e) {
+    echo $e->getMessage() . "\n";
+}
+try {
+    $nothing = null;
+    $nothing->run();
+} catch (Error $e) {
+    echo "caught call on null\n";
+}
+class Proxy {
+    public function __call($name, $arguments) { return $name . "(" . implode(",", $arguments) . ")"; }
+    public static function __callStatic($name, $arguments) { return "static " . $name . "(" . count($arguments) . ")"; }
+}
+$proxy = new Proxy();
+echo $proxy->anything(1, 2) . "\n";
+echo Proxy::build("a") . "\n";
+echo call_user_func([$proxy, "viaCallable"], "x") . "\n";
+"#;
+    assert_eq!(run(code), "Error: Call to undefined method Plain::nonexistent()\nCall to undefined method Plain::missing()\n\
+caught call on null\nanything(1,2)\nstatic build(1)\nviaCallable(x)\n");
+}
+
+#[test]
+fn test_case_trim_and_array_str_replace() {
+    let code = r#"<?php
+echo str_replace(["apple", "pear"], ["kiwi", "plum"], "apple pie, pear tart", $count) . " (" . $count . ")\n";
+echo str_replace(["a", "b"], "-", ["abc", "bcd"])[1] . "\n";
+echo strtoupper("Hello, wörld") . "|" . strtolower("MiXeD 123") . "\n";
+echo "[" . trim("  padded \n") . "][" . ltrim("  left ") . "][" . rtrim(" right  ") . "]\n";
+echo trim("xxhixx", "x") . "|" . trim("123abc456", "0..9") . "|" . rtrim("1.500", "0") . "\n";
+"#;
+    assert_eq!(run(code), "kiwi pie, plum tart (2)\n-cd\nHELLO, WöRLD|mixed 123\n[padded][left ][ right]\nhi|abc|1.5\n");
+}
+
+#[test]
+fn test_nullsafe_chain_short_circuits() {
+    let code = r#"<?php
+class Node {
+    public $next;
+    public $label;
+    public function __construct($label, $next = null) { $this->label = $label; $this->next = $next; }
+    public function shout($suffix) { echo "shouted\n"; return $this->label . $suffix; }
+}
+function suffix() { echo "argument evaluated\n"; return "!"; }
+$head = new Node("head");
+var_dump($head?->next?->shout(suffix()));
+$missing = null;
+var_dump($missing?->next->label->shout(suffix()));
+$chain = new Node("a", new Node("b"));
+echo $chain?->next?->shout("?") . "\n";
+var_dump($missing?->label);
+"#;
+    assert_eq!(run(code), "NULL\nNULL\nshouted\nb?\nNULL\n");
+}
+
+#[test]
+fn test_sprintf_and_printf_formatting() {
+    let code = r#"<?php
+echo sprintf("%05.2f", 3.1) . "|" . sprintf("%-10s|", "hi") . "\n";
+echo sprintf("%5d|%05d|%+d|%.3s|%x", 42, -3, 7, "abcdef", 255) . "\n";
+echo sprintf('%\'*8s|%2$s %1$s', "right", "left") . "\n";
+$written = printf("%s=%d\n", "x", 10);
+echo $written . "\n";
+try {
+    sprintf("%d %d", 1);
+} catch (ArgumentCountError $e) {
+    echo $e->getMessage() . "\n";
+}
+"#;
+    assert_eq!(run(code), "03.10|hi        |\n   42|-0003|+7|abc|ff\n***right|left right\nx=10\n5\n3 arguments are required, 2 given\n");
+}
+
+#[test]
+fn test_sprintf_rejects_oversized_width_and_precision() {
+    let code = r#"<?php
+foreach (["%.999999999999f", "%99999999999d"] as $format) {
+    try {
+        sprintf($format, 1);
+    } catch (ValueError $e) {
+        echo $e->getMessage() . "\n";
+    }
+}
+echo strlen(sprintf("%.60f", 1.0)) . "\n";
+"#;
+    let ast = php_parser::parse(php_lexer::lex(code).expect("Failed to lex")).expect("Failed to parse");
+    let mut engine = Engine::new();
+    engine.execute_stmt(&ast).expect("Failed to execute");
+    assert_eq!(engine.get_output(), "Precision must be greater than zero and less than 2147483647\n\
+Width must be greater than zero and less than 2147483647\n55\n");
+    assert_eq!(engine.raised_errors(), &[RaisedError {
+        level: ErrorLevel::Notice,
+        message: "Requested precision of 60 digits was truncated to PHP maximum of 53 digits".to_string(),
+    }]);
+
+    engine.set_limits(Limits { max_string_length: 64, ..Limits::default() });
+    let ast = php_parser::parse(php_lexer::lex("<?php sprintf('%2000000000d', 1);").expect("Failed to lex")).expect("Failed to parse");
+    assert!(engine.execute_stmt(&ast).unwrap_err().starts_with("sprintf(): Memory limit exceeded"));
+}
+
+#[test]
+fn test_byte_and_multibyte_case_mapping() {
+    let code = r#"<?php
+echo strtoupper("café") . "|" . mb_strtoupper("café") . "\n";
+echo strtolower("ÉCOLE") . "|" . mb_strtolower("ÉCOLE", "UTF-8") . "\n";
+echo mb_strtoupper("straße") . "\n";
+"#;
+    assert_eq!(run(code), "CAFé|CAFÉ\nÉcole|école\nSTRASSE\n");
+}
+
+#[test]
+fn test_type_inspection_of_closures_and_resources() {
+    let code = r#"<?php
+$double = function ($x) { return $x * 2; };
+echo gettype($double) . "|" . gettype(STDOUT) . "\n";
+var_dump(is_object($double), is_callable($double), is_resource($double));
+var_dump(is_resource(STDERR), is_object(STDERR), is_callable(STDERR));
+var_dump(STDIN);
+var_dump(is_callable("strlen"), is_callable("missing_function"), is_int(5), is_float(5));
+"#;
+    assert_eq!(run(code), "object|resource\nbool(true)\nbool(true)\nbool(false)\nbool(true)\nbool(false)\nbool(false)\n\
+resource(1) of type (stream)\nbool(true)\nbool(false)\nbool(true)\nbool(false)\n");
+}
+
+#[test]
+fn test_json_encode_flags_and_pretty_print() {
+    let code = r#"<?php
+echo json_encode(["name" => "box", "sizes" => [1, 2.0], "meta" => ["empty" => []]], JSON_PRETTY_PRINT) . "\n";
+echo json_encode(["café", "a/b"], JSON_UNESCAPED_UNICODE | JSON_UNESCAPED_SLASHES) . "|" . json_encode(["café", "😀"]) . "\n";
+echo JSON_UNESCAPED_SLASHES . "," . JSON_PRETTY_PRINT . "," . JSON_UNESCAPED_UNICODE . "|" . json_encode(2.0, JSON_PRESERVE_ZERO_FRACTION) . "\n";
+echo json_encode(1.0) . "|" . json_encode([0.5, -3.0, 1e25]) . "|" . gettype(json_decode(json_encode(1.0))) . "\n";
+"#;
+    assert_eq!(run(code), "{\n    \"name\": \"box\",\n    \"sizes\": [\n        1,\n        2.0\n    ],\n    \"meta\": {\n        \"empty\": []\n    }\n}\n\
+[\"café\",\"a/b\"]|[\"caf\\u00e9\",\"\\ud83d\\ude00\"]\n64,128,256|2.0\n1.0|[0.5,-3.0,1.0e+25]|double\n");
+}
+
+#[test]
+fn test_array_multisort_parallel_arrays() {
+    let code = r#"<?php
+$scores = [30, 10, 20, 10];
+$names = ["carol", "bob", "dave", "alice"];
+array_multisort($scores, $names);
+echo implode(",", $scores) . " " . implode(",", $names) . "\n";
+$ranked = ["x" => 3, "y" => 1, 5 => 2];
+array_multisort($ranked, SORT_DESC);
+echo implode(",", array_keys($ranked)) . "\n";
+$labels = ["10", "9", "2"];
+array_multisort($labels, SORT_STRING);
+echo implode(",", $labels) . "\n";
+try {
+    array_multisort($scores, $ranked);
+} catch (ValueError $e) {
+    echo $e->getMessage() . "\n";
+}
+"#;
+    assert_eq!(run(code), "10,10,20,30 alice,bob,dave,carol\nx,0,y\n10,2,9\nArray sizes are inconsistent\n");
+}
+
+#[test]
+fn test_json_decode_objects_depth_and_errors() {
+    let code = r#"<?php
+$assoc = json_decode('{"b":1,"a":2}', true);
+echo implode(",", array_keys($assoc)) . "=" . $assoc["a"] . "\n";
+$obj = json_decode('{"a":1,"items":[{"n":2}]}');
+echo gettype($obj) . " " . $obj->a . " " . $obj->items[0]->n . "\n";
+var_dump(json_decode('{"a":'));
+var_dump(json_decode('[[1]]', true, 1));
+try {
+    json_decode('{oops}', false, 512, JSON_THROW_ON_ERROR);
+} catch (JsonException $e) {
+    echo $e->getMessage() . " (" . $e->getCode() . ")\n";
+}
+"#;
+    assert_eq!(run(code), "b,a=2\nobject 1 2\nNULL\nNULL\nSyntax error (4)\n");
+}
+
+#[test]
+fn test_integer_division_and_division_by_zero() {
+    let code = r#"<?php
+var_dump(10 / 2, 10 / 3, 10.0 / 2);
+try {
+    echo 7 / 0;
+} catch (DivisionByZeroError $e) {
+    echo "DivisionByZeroError: " . $e->getMessage() . "\n";
+}
+"#;
+    assert_eq!(run(code), "int(5)\nfloat(3.3333333333333335)\nfloat(5)\nDivisionByZeroError: Division by zero\n");
+}
+
+#[test]
+fn test_integer_overflow_becomes_float() {
+    let code = r#"<?php
+var_dump(PHP_INT_MAX * 2, PHP_INT_MAX + 1, PHP_INT_MIN - 1, PHP_INT_MAX - 1 + 1);
+"#;
+    assert_eq!(run(code), "float(1.8446744073709552E+19)\nfloat(9.223372036854776E+18)\nfloat(-9.223372036854776E+18)\nint(9223372036854775807)\n");
+}
+
+#[test]
+fn test_sorts_with_precomputed_keys_match_loose_comparison() {
+    // Numeric strings are parsed once up front; the order must match comparing pairwise
+    let code = r#"<?php
+$numeric = ["10", "9.5", 2, "1e1", " 3", 10.0, "-4", "007"];
+sort($numeric);
+echo implode("|", $numeric) . "\n";
+$mixed = ["10", "9", "abc", 2, "1e1", "Abc", "", "9a"];
+sort($mixed);
+echo implode("|", $mixed) . "\n";
+$ranked = ["b" => "20", "a" => "3", "c" => "100", "d" => "3.0"];
+arsort($ranked);
+echo implode(",", array_keys($ranked)) . "\n";
+$keys = ["10" => 1, "9" => 2, "x" => 3, "1e1" => 4];
+ksort($keys);
+echo implode(",", array_keys($keys)) . "\n";
+$flags = ["10", "9", "1e1", "abc"];
+sort($flags, SORT_NUMERIC);
+echo implode("|", $flags) . "\n";
+sort($flags, SORT_STRING);
+echo implode("|", $flags) . "\n";
+$a = ["3", "1", "2"]; $b = ["c", "a", "b"];
+array_multisort($a, SORT_NUMERIC, SORT_DESC, $b);
+echo implode("", $b) . "\n";
+"#;
+    assert_eq!(run(code), "-4|2| 3|007|9.5|10|1e1|10\n|2|9|10|1e1|9a|Abc|abc\nc,b,a,d\n9,10,1e1,x\nabc|9|10|1e1\n10|1e1|9|abc\ncba\n");
+}
+
+#[test]
+fn test_str_split_and_mb_str_split() {
+    let code = r#"<?php
+echo implode(",", str_split("abcdefg", 3)) . "|" . implode(",", str_split("abc")) . "\n";
+echo implode(",", mb_str_split("añbç", 2)) . "|" . count(mb_str_split("añbç")) . "\n";
+echo count(str_split("")) . count(mb_str_split("")) . "\n";
+try {
+    str_split("abc", 0);
+} catch (ValueError $e) {
+    echo $e->getMessage() . "\n";
+}
+try {
+    mb_str_split("abc", -1);
+} catch (ValueError $e) {
+    echo $e->getMessage() . "\n";
+}
+"#;
+    assert_eq!(run(code), "abc,def,g|a,b,c\nañ,bç|4\n00\nstr_split(): Argument #2 ($length) must be greater than 0\n\
+mb_str_split(): Argument #2 ($length) must be greater than 0\n");
+}
+
+#[test]
+fn test_loose_equality_of_arrays_null_and_objects() {
+    let code = r#"<?php
+var_dump([] == [], [1, 2] == [2, 1], ["a" => 1, "b" => 2] == ["b" => 2, "a" => 1], [1, 2] == [1, 2, 3], ["1", 2] == [1, "2"]);
+var_dump(null == false, null == 0, null == "", null == "0", null == [], null == 1);
+var_dump("abc" == 0, "10" == "1e1", "abc" == "ABC", "1" == [1]);
+class Point { public $x; public function __construct($x) { $this->x = $x; } }
+var_dump(new Point(1) == new Point(1), new Point(1) == new Point(2));
+echo match (1) { "1" => "loose", 1 => "strict", default => "none" } . "\n";
+"#;
+    let expected = [
+        "true", "false", "true", "false", "true",
+        "true", "true", "true", "false", "true", "false",
+        "false", "true", "false", "false",
+        "true", "false",
+    ].map(|b| format!("bool({})\n", b)).concat() + "strict\n";
+    assert_eq!(run(code), expected);
+}
+
+#[test]
+fn test_strict_equality_operators() {
+    let code = r#"<?php
+var_dump("1" == 1, "1" === 1, "1" !== 1, 1 === 1, 0 == "a", 0 === "a");
+var_dump(null === null, 1.0 === 1, [1, 2] === [1, 2], [1, 2] === [2 => 1], ["a" => 1, "b" => 2] === ["b" => 2, "a" => 1]);
+$object = new stdClass();
+$alias = $object;
+var_dump($object === $alias, $object === new stdClass(), 1 + 1 === 2);
+"#;
+    let expected = [
+        "true", "false", "true", "true", "false", "false",
+        "true", "false", "true", "false", "false",
+        "true", "false", "true",
+    ].map(|b| format!("bool({})\n", b)).concat();
+    assert_eq!(run(code), expected);
+}
+
+#[test]
+fn test_readonly_property_initialization_requires_declaring_scope() {
+    let code = r#"<?php
+class Config {
+    public readonly string $name;
+    public function init($name) { $this->name = $name; }
+}
+class AppConfig extends Config {
+    public function rename($name) { $this->name = $name; }
+}
+$outside = new Config();
+try {
+    $outside->name = "global";
+} catch (Error $e) {
+    echo $e->getMessage() . "\n";
+}
+$child = new AppConfig();
+try {
+    $child->rename("child");
+} catch (Error $e) {
+    echo $e->getMessage() . "\n";
+}
+$child->init("inherited");
+echo $child->name . "\n";
+"#;
+    assert_eq!(run(code), "Cannot initialize readonly property Config::$name from global scope\n\
+Cannot initialize readonly property Config::$name from scope AppConfig\ninherited\n");
+}
+
+#[test]
+fn test_yield_from_passes_keyed_yields_through() {
+    let output = run(r#"<?php
+        function settings() {
+            yield "host" => "localhost";
+            yield "port" => 8080;
+            yield 10 => "ten";
+            yield "eleven";
+        }
+        function config() {
+            yield "name" => "app";
+            yield from settings();
+            yield "last";
+        }
+        foreach (config() as $key => $value) { echo $key . "=" . $value . " "; }
+        $merged = iterator_to_array(config());
+        echo $merged["port"] . "," . $merged[0];
+    "#);
+    assert_eq!(output, "name=app host=localhost port=8080 10=ten 11=eleven 0=last 8080,last");
+}
+
 #[test]
-fn basic_test() {
-    // TODO: Add tests for php-runtime
-    assert!(true);
+fn test_byte_escapes_and_escaped_dollars_in_strings() {
+    let output = run(r#"<?php
+        $x = "X";
+        echo "\xE2\x82\xAC" . "," . strlen("\xE2\x82\xAC") . "," . ("\xE2\x82\xAC" === "€" ? "same" : "different") . ";";
+        echo "\$x|\x24x|\\$x|{\$x}|\\\$x|\q$x";
+    "#);
+    assert_eq!(output, r"€,3,same;$x|$x|\X|{$x}|\$x|\qX");
 }
@@ -1,9 +0,0 @@
-//! Integration tests for php-stdlib
-
-use php_stdlib::*;
-
-#[test]
-fn basic_test() {
-    // TODO: Add tests for php-stdlib
-    assert!(true);
-}
@@ -1,6 +1,10 @@
 //! PHP type conversion utilities
 
-use crate::value::PhpValue;
+use crate::value::{PhpArray, PhpArrayKey, PhpObject, PhpValue};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::rc::Rc;
 
 /// Convert between PHP values with type juggling
 pub trait PhpConvert {
@@ -38,63 +42,434 @@ impl PhpConvert for &str {
     }
 }
 
-/// Perform PHP-style arithmetic operations
-pub fn php_add(left: &PhpValue, right: &PhpValue) -> PhpValue {
-    match (left, right) {
+impl PhpConvert for PhpValue {
+    fn to_php_value(self) -> PhpValue {
+        self
+    }
+}
+
+impl PhpConvert for () {
+    fn to_php_value(self) -> PhpValue {
+        PhpValue::Null
+    }
+}
+
+impl<T: PhpConvert> PhpConvert for Option<T> {
+    fn to_php_value(self) -> PhpValue {
+        self.map_or(PhpValue::Null, T::to_php_value)
+    }
+}
+
+impl<T: PhpConvert> PhpConvert for Vec<T> {
+    fn to_php_value(self) -> PhpValue {
+        PhpValue::Array(self.into_iter().map(T::to_php_value).collect())
+    }
+}
+
+impl<T: PhpConvert> PhpConvert for HashMap<String, T> {
+    fn to_php_value(self) -> PhpValue {
+        let mut arr = PhpArray::new();
+        for (key, item) in self {
+            arr.insert_string(key, item.to_php_value());
+        }
+        PhpValue::Array(arr)
+    }
+}
+
+/// Extract a Rust value from a PHP value, juggling scalars the way PHP's
+/// weak parameter types do
+pub trait FromPhpValue: Sized {
+    /// Convert from a PhpValue, or describe why it can't be converted
+    fn from_php_value(value: &PhpValue) -> Result<Self, String>;
+}
+
+impl FromPhpValue for PhpValue {
+    fn from_php_value(value: &PhpValue) -> Result<Self, String> {
+        Ok(value.clone())
+    }
+}
+
+impl FromPhpValue for bool {
+    fn from_php_value(value: &PhpValue) -> Result<Self, String> {
+        match value {
+            PhpValue::Array(_) | PhpValue::Object(_) => Err(format!("must be of type bool, {} given", value.type_name())),
+            _ => Ok(value.to_bool()),
+        }
+    }
+}
+
+impl FromPhpValue for i64 {
+    fn from_php_value(value: &PhpValue) -> Result<Self, String> {
+        match value {
+            PhpValue::Array(_) | PhpValue::Object(_) => Err(format!("must be of type int, {} given", value.type_name())),
+            _ => Ok(value.to_int()),
+        }
+    }
+}
+
+impl FromPhpValue for f64 {
+    fn from_php_value(value: &PhpValue) -> Result<Self, String> {
+        match value {
+            PhpValue::Array(_) | PhpValue::Object(_) => Err(format!("must be of type float, {} given", value.type_name())),
+            _ => Ok(value.to_float()),
+        }
+    }
+}
+
+impl FromPhpValue for String {
+    fn from_php_value(value: &PhpValue) -> Result<Self, String> {
+        match value {
+            PhpValue::Array(_) | PhpValue::Object(_) => Err(format!("must be of type string, {} given", value.type_name())),
+            _ => Ok(value.to_string()),
+        }
+    }
+}
+
+impl<T: FromPhpValue> FromPhpValue for Option<T> {
+    fn from_php_value(value: &PhpValue) -> Result<Self, String> {
+        match value {
+            PhpValue::Null => Ok(None),
+            other => T::from_php_value(other).map(Some),
+        }
+    }
+}
+
+impl<T: FromPhpValue> FromPhpValue for Vec<T> {
+    fn from_php_value(value: &PhpValue) -> Result<Self, String> {
+        match value {
+            PhpValue::Array(arr) => arr.data.values().map(T::from_php_value).collect(),
+            other => Err(format!("must be of type array, {} given", other.type_name())),
+        }
+    }
+}
+
+impl<T: FromPhpValue> FromPhpValue for HashMap<String, T> {
+    fn from_php_value(value: &PhpValue) -> Result<Self, String> {
+        match value {
+            PhpValue::Array(arr) => arr.data.iter()
+                .map(|(key, item)| {
+                    let key = match key {
+                        PhpArrayKey::Int(i) => i.to_string(),
+                        PhpArrayKey::String(s) => s.clone(),
+                    };
+                    Ok((key, T::from_php_value(item)?))
+                })
+                .collect(),
+            other => Err(format!("must be of type array, {} given", other.type_name())),
+        }
+    }
+}
+
+/// Convert the argument at `index` of a native function call, with a PHP-style message on failure
+pub fn php_arg<T: FromPhpValue>(function: &str, args: &[PhpValue], index: usize) -> Result<T, String> {
+    let value = args.get(index)
+        .ok_or_else(|| format!("{}() expects at least {} arguments, {} given", function, index + 1, args.len()))?;
+    T::from_php_value(value).map_err(|reason| format!("{}(): Argument #{} {}", function, index + 1, reason))
+}
+
+/// Convert an arithmetic operand to int or float: null and bools become ints,
+/// numeric strings become the int or float they hold, other strings use their
+/// leading numeric prefix ("5 apples" is 5) and strings without one become 0
+pub fn to_number(value: &PhpValue) -> PhpValue {
+    match value {
+        PhpValue::Int(_) | PhpValue::Float(_) => value.clone(),
+        PhpValue::String(s) if is_numeric_string(s) => numeric_string_value(s),
+        PhpValue::String(s) => numeric_prefix(s).map(numeric_string_value).unwrap_or(PhpValue::Int(0)),
+        other => PhpValue::Int(other.to_int()),
+    }
+}
+
+/// The leading numeric part of a string after optional whitespace: a sign,
+/// digits with an optional fraction, then an optional exponent. None when
+/// the string does not start with a number.
+fn numeric_prefix(s: &str) -> Option<&str> {
+    let s = s.trim_start();
+    let bytes = s.as_bytes();
+    let digits_from = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+    let start = usize::from(matches!(bytes.first(), Some(b'+' | b'-')));
+    let mut end = digits_from(start);
+    let mut has_digits = end > start;
+    if bytes.get(end) == Some(&b'.') {
+        let fraction_end = digits_from(end + 1);
+        has_digits |= fraction_end > end + 1;
+        end = fraction_end;
+    }
+    if !has_digits {
+        return None;
+    }
+    // An exponent only counts when at least one digit follows it
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+        let exponent_end = digits_from(end + 1 + sign);
+        if exponent_end > end + 1 + sign {
+            end = exponent_end;
+        }
+    }
+    Some(&s[..end])
+}
+
+/// Format a float the way echo and string conversion do (PHP's `precision=14`):
+/// `1/3` becomes "0.33333333333333"
+pub fn php_float_to_string(value: f64) -> String {
+    format_float(value, Some(14))
+}
+
+/// Format a float the way var_dump does (PHP's `serialize_precision=-1`): the shortest
+/// digits that read back as the same float, so `1/3` becomes "0.3333333333333333"
+pub fn php_float_repr(value: f64) -> String {
+    format_float(value, None)
+}
+
+/// Format with `precision` significant digits, or the shortest round-trip digits for None.
+/// Like PHP's `%G`, exponents below -4 or beyond the precision (17 when shortest) use E notation.
+fn format_float(value: f64, precision: Option<usize>) -> String {
+    if value.is_nan() {
+        return "NAN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "INF" } else { "-INF" }.to_string();
+    }
+    if value == 0.0 {
+        return if value.is_sign_negative() { "-0" } else { "0" }.to_string();
+    }
+    let scientific = match precision {
+        Some(precision) => format!("{:.*e}", precision - 1, value.abs()),
+        None => format!("{:e}", value.abs()),
+    };
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+    let digits = digits.trim_end_matches('0');
+    let sign = if value < 0.0 { "-" } else { "" };
+    // Position of the decimal point relative to the first significant digit
+    let decimal_point = exponent + 1;
+    if decimal_point < -3 || decimal_point > precision.unwrap_or(17) as i32 {
+        let (first, rest) = digits.split_at(1);
+        let rest = if rest.is_empty() { "0" } else { rest };
+        let exponent_sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}{}.{}E{}{}", sign, first, rest, exponent_sign, exponent.abs())
+    } else if decimal_point <= 0 {
+        format!("{}0.{}{}", sign, "0".repeat(decimal_point.unsigned_abs() as usize), digits)
+    } else if digits.len() <= decimal_point as usize {
+        format!("{}{}{}", sign, digits, "0".repeat(decimal_point as usize - digits.len()))
+    } else {
+        let (integer, fraction) = digits.split_at(decimal_point as usize);
+        format!("{}{}.{}", sign, integer, fraction)
+    }
+}
+
+/// Perform PHP-style negation; -PHP_INT_MIN does not fit an int and becomes a float
+pub fn php_negate(value: &PhpValue) -> PhpValue {
+    match to_number(value) {
+        PhpValue::Int(i) => i.checked_neg().map(PhpValue::Int).unwrap_or(PhpValue::Float(-(i as f64))),
+        number => PhpValue::Float(-number.to_float()),
+    }
+}
+
+/// Whether a string is numeric in PHP's sense: optional surrounding
+/// whitespace around an integer or decimal, possibly with an exponent
+fn is_numeric_string(s: &str) -> bool {
+    let trimmed = s.trim();
+    !trimmed.is_empty()
+        && trimmed.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+        && trimmed.parse::<f64>().is_ok()
+}
+
+/// Perl-style string increment: "a" becomes "b", "Az" becomes "Ba" and
+/// "Zz" becomes "AAa". Only trailing ASCII letters and digits carry.
+fn increment_string(s: &str) -> String {
+    let mut bytes = s.as_bytes().to_vec();
+    for i in (0..bytes.len()).rev() {
+        let (wrapped, first) = match bytes[i] {
+            b'z' => (b'a', b'a'),
+            b'Z' => (b'A', b'A'),
+            b'9' => (b'0', b'1'),
+            b'a'..=b'y' | b'A'..=b'Y' | b'0'..=b'8' => {
+                bytes[i] += 1;
+                return String::from_utf8_lossy(&bytes).into_owned();
+            }
+            _ => return String::from_utf8_lossy(&bytes).into_owned(),
+        };
+        bytes[i] = wrapped;
+        // Carry out of the first character prepends a new one of the same kind
+        if i == 0 {
+            bytes.insert(0, first);
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Value of `$x` after `$x++`: numbers and numeric strings add one, other
+/// strings use Perl-style increment, the empty string becomes "1", null
+/// becomes 1 and bools are left unchanged
+pub fn php_increment(value: &PhpValue) -> PhpValue {
+    match value {
+        PhpValue::Int(i) => i.checked_add(1).map(PhpValue::Int).unwrap_or(PhpValue::Float(*i as f64 + 1.0)),
+        PhpValue::Float(f) => PhpValue::Float(f + 1.0),
+        PhpValue::String(s) if s.is_empty() => PhpValue::String("1".to_string()),
+        PhpValue::String(s) if is_numeric_string(s) => php_increment(&to_number(value)),
+        PhpValue::String(s) => PhpValue::String(increment_string(s)),
+        PhpValue::Null => PhpValue::Int(1),
+        _ => value.clone(),
+    }
+}
+
+/// Value of `$x` after `$x--`: numbers and numeric strings subtract one,
+/// the empty string becomes -1, and null, bools and other strings are
+/// left unchanged
+pub fn php_decrement(value: &PhpValue) -> PhpValue {
+    match value {
+        PhpValue::Int(i) => i.checked_sub(1).map(PhpValue::Int).unwrap_or(PhpValue::Float(*i as f64 - 1.0)),
+        PhpValue::Float(f) => PhpValue::Float(f - 1.0),
+        PhpValue::String(s) if s.is_empty() => PhpValue::Int(-1),
+        PhpValue::String(s) if is_numeric_string(s) => php_decrement(&to_number(value)),
+        _ => value.clone(),
+    }
+}
+
+/// Perform PHP-style addition; int results that overflow become floats
+pub fn php_add(left: &PhpValue, right: &PhpValue) -> Result<PhpValue, String> {
+    Ok(match (left, right) {
         // If both are numbers, do numeric addition
-        (PhpValue::Int(a), PhpValue::Int(b)) => PhpValue::Int(a + b),
+        (PhpValue::Int(a), PhpValue::Int(b)) => a.checked_add(*b).map(PhpValue::Int).unwrap_or(PhpValue::Float(*a as f64 + *b as f64)),
         (PhpValue::Float(a), PhpValue::Float(b)) => PhpValue::Float(a + b),
         (PhpValue::Int(a), PhpValue::Float(b)) => PhpValue::Float(*a as f64 + b),
         (PhpValue::Float(a), PhpValue::Int(b)) => PhpValue::Float(a + *b as f64),
+
+        // Array union: keys already in the left array keep their values
+        (PhpValue::Array(a), PhpValue::Array(b)) => {
+            let mut union = a.clone();
+            for (key, value) in &b.data {
+                if !union.data.contains_key(key) {
+                    union.insert(key.clone(), value.clone());
+                }
+            }
+            PhpValue::Array(union)
+        }
         
-        // Convert to numbers and add
+        // Convert null, bools and numeric strings to int or float first
         _ => {
-            let a = left.to_float();
-            let b = right.to_float();
-            PhpValue::Float(a + b)
+            check_arithmetic_operands(left, right, "+")?;
+            return php_add(&to_number(left), &to_number(right));
         }
-    }
+    })
 }
 
-/// Perform PHP-style subtraction
-pub fn php_subtract(left: &PhpValue, right: &PhpValue) -> PhpValue {
-    match (left, right) {
-        (PhpValue::Int(a), PhpValue::Int(b)) => PhpValue::Int(a - b),
+/// Perform PHP-style subtraction; int results that overflow become floats
+pub fn php_subtract(left: &PhpValue, right: &PhpValue) -> Result<PhpValue, String> {
+    Ok(match (left, right) {
+        (PhpValue::Int(a), PhpValue::Int(b)) => a.checked_sub(*b).map(PhpValue::Int).unwrap_or(PhpValue::Float(*a as f64 - *b as f64)),
         (PhpValue::Float(a), PhpValue::Float(b)) => PhpValue::Float(a - b),
         (PhpValue::Int(a), PhpValue::Float(b)) => PhpValue::Float(*a as f64 - b),
         (PhpValue::Float(a), PhpValue::Int(b)) => PhpValue::Float(a - *b as f64),
+        // Convert null, bools and numeric strings to int or float first
         _ => {
-            let a = left.to_float();
-            let b = right.to_float();
-            PhpValue::Float(a - b)
+            check_arithmetic_operands(left, right, "-")?;
+            return php_subtract(&to_number(left), &to_number(right));
         }
-    }
+    })
 }
 
-/// Perform PHP-style multiplication
-pub fn php_multiply(left: &PhpValue, right: &PhpValue) -> PhpValue {
-    match (left, right) {
-        (PhpValue::Int(a), PhpValue::Int(b)) => PhpValue::Int(a * b),
+/// Perform PHP-style multiplication; int results that overflow become floats
+pub fn php_multiply(left: &PhpValue, right: &PhpValue) -> Result<PhpValue, String> {
+    Ok(match (left, right) {
+        (PhpValue::Int(a), PhpValue::Int(b)) => a.checked_mul(*b).map(PhpValue::Int).unwrap_or(PhpValue::Float(*a as f64 * *b as f64)),
         (PhpValue::Float(a), PhpValue::Float(b)) => PhpValue::Float(a * b),
         (PhpValue::Int(a), PhpValue::Float(b)) => PhpValue::Float(*a as f64 * b),
         (PhpValue::Float(a), PhpValue::Int(b)) => PhpValue::Float(a * *b as f64),
+        // Convert null, bools and numeric strings to int or float first
         _ => {
-            let a = left.to_float();
-            let b = right.to_float();
-            PhpValue::Float(a * b)
+            check_arithmetic_operands(left, right, "*")?;
+            return php_multiply(&to_number(left), &to_number(right));
+        }
+    })
+}
+
+/// Perform PHP-style exponentiation: integer results stay int unless they overflow
+pub fn php_power(left: &PhpValue, right: &PhpValue) -> Result<PhpValue, String> {
+    Ok(match (left, right) {
+        (PhpValue::Int(a), PhpValue::Int(b)) if *b >= 0 => u32::try_from(*b).ok()
+            .and_then(|exponent| a.checked_pow(exponent))
+            .map(PhpValue::Int)
+            .unwrap_or_else(|| PhpValue::Float((*a as f64).powf(*b as f64))),
+        (PhpValue::Int(_) | PhpValue::Float(_), PhpValue::Int(_) | PhpValue::Float(_)) => {
+            PhpValue::Float(left.to_float().powf(right.to_float()))
         }
+        // Convert null, bools and numeric strings to int or float first
+        _ => {
+            check_arithmetic_operands(left, right, "**")?;
+            return php_power(&to_number(left), &to_number(right));
+        }
+    })
+}
+
+/// Reject arrays and objects as arithmetic operands, as PHP 8 does with a TypeError
+fn check_arithmetic_operands(left: &PhpValue, right: &PhpValue, op: &str) -> Result<(), String> {
+    let unsupported = |value: &PhpValue| matches!(value, PhpValue::Array(_) | PhpValue::Object(_));
+    if unsupported(left) || unsupported(right) {
+        return Err(format!("Unsupported operand types: {} {} {}", operand_type_name(left), op, operand_type_name(right)));
     }
+    Ok(())
 }
 
-/// Perform PHP-style division
+/// Type of an operand as PHP 8 error messages name it: `int`, `array`, a class name, ...
+fn operand_type_name(value: &PhpValue) -> String {
+    match value {
+        PhpValue::Null => "null".to_string(),
+        PhpValue::Bool(_) => "bool".to_string(),
+        PhpValue::Int(_) => "int".to_string(),
+        PhpValue::Float(_) => "float".to_string(),
+        PhpValue::String(_) => "string".to_string(),
+        PhpValue::Array(_) => "array".to_string(),
+        PhpValue::Object(object) => object.borrow().class_name.clone(),
+        PhpValue::Resource(_) => "resource".to_string(),
+    }
+}
+
+/// Perform PHP-style division: two ints that divide evenly give an int, anything else a float
 pub fn php_divide(left: &PhpValue, right: &PhpValue) -> Result<PhpValue, String> {
-    let b = right.to_float();
-    if b == 0.0 {
+    let (left, right) = (to_number(left), to_number(right));
+    if right.to_float() == 0.0 {
         return Err("Division by zero".to_string());
     }
-    
-    let a = left.to_float();
-    Ok(PhpValue::Float(a / b))
+    if let (PhpValue::Int(a), PhpValue::Int(b)) = (&left, &right) {
+        // checked_rem is None for PHP_INT_MIN / -1, whose quotient does not fit an int
+        if a.checked_rem(*b) == Some(0) {
+            return Ok(PhpValue::Int(a / b));
+        }
+    }
+    Ok(PhpValue::Float(left.to_float() / right.to_float()))
+}
+
+/// Perform PHP-style modulo: operands are converted to int and the result takes the sign of the dividend
+pub fn php_modulo(left: &PhpValue, right: &PhpValue) -> Result<PhpValue, String> {
+    let b = right.to_int();
+    if b == 0 {
+        return Err("Modulo by zero".to_string());
+    }
+    // Rust's % already truncates toward zero; wrapping keeps PHP_INT_MIN % -1 at 0
+    Ok(PhpValue::Int(left.to_int().wrapping_rem(b)))
+}
+
+/// Perform PHP-style shifts: counts of 64 or more shift every bit out, negative counts are an error
+pub fn php_shift(left: &PhpValue, right: &PhpValue, shift_left: bool) -> Result<PhpValue, String> {
+    let (value, count) = (left.to_int(), right.to_int());
+    if count < 0 {
+        return Err("Bit shift by negative number".to_string());
+    }
+    let shifted = match (shift_left, count >= 64) {
+        (true, false) => value << count,
+        (true, true) => 0,
+        (false, false) => value >> count,
+        // An arithmetic shift that far leaves only copies of the sign bit
+        (false, true) => if value < 0 { -1 } else { 0 },
+    };
+    Ok(PhpValue::Int(shifted))
 }
 
 /// Perform PHP-style string concatenation
@@ -104,25 +479,154 @@ pub fn php_concatenate(left: &PhpValue, right: &PhpValue) -> PhpValue {
     PhpValue::String(format!("{}{}", left_str, right_str))
 }
 
-/// Perform PHP-style equality comparison
-pub fn php_equals(left: &PhpValue, right: &PhpValue) -> bool {
+/// Error from `php_equals` when an object's properties lead back to a pair of objects already being compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Nesting level too deep - recursive dependency?")]
+pub struct NestingTooDeep;
+
+// Runtime errors are plain messages
+impl From<NestingTooDeep> for String {
+    fn from(error: NestingTooDeep) -> Self {
+        error.to_string()
+    }
+}
+
+/// Perform PHP 8's loose equality comparison (`==`); `php_identical` is the strict (`===`) variant.
+///
+/// Null and bools compare as bools, except that null equals only the empty string among
+/// strings. Numbers and numeric strings compare numerically, other strings byte-wise.
+/// Arrays are equal with the same key => loosely equal value pairs in any order, and
+/// objects when they are the same instance or of one class with equal properties.
+/// Comparing two distinct objects whose properties refer back to them fails like PHP's fatal error.
+pub fn php_equals(left: &PhpValue, right: &PhpValue) -> Result<bool, NestingTooDeep> {
+    loose_equals(left, right, &mut Vec::new())
+}
+
+/// `php_equals` with the object pairs whose properties are being compared further up
+fn loose_equals(left: &PhpValue, right: &PhpValue, comparing: &mut Vec<(*const RefCell<PhpObject>, *const RefCell<PhpObject>)>) -> Result<bool, NestingTooDeep> {
     match (left, right) {
-        // Same types - direct comparison
-        (PhpValue::Null, PhpValue::Null) => true,
-        (PhpValue::Bool(a), PhpValue::Bool(b)) => a == b,
-        (PhpValue::Int(a), PhpValue::Int(b)) => a == b,
-        (PhpValue::Float(a), PhpValue::Float(b)) => a == b,
-        (PhpValue::String(a), PhpValue::String(b)) => a == b,
-        
-        // Numeric comparisons
-        (PhpValue::Int(a), PhpValue::Float(b)) => *a as f64 == *b,
-        (PhpValue::Float(a), PhpValue::Int(b)) => *a == *b as f64,
-        
-        // Type juggling - convert both to comparable type
-        _ => {
-            // PHP's equality is complex, simplified version here
-            left.to_string() == right.to_string()
+        (PhpValue::Array(a), PhpValue::Array(b)) => {
+            if a.len() != b.len() {
+                return Ok(false);
+            }
+            for (key, value) in &a.data {
+                match b.data.get(key) {
+                    Some(other) if loose_equals(value, other, comparing)? => {}
+                    _ => return Ok(false),
+                }
+            }
+            Ok(true)
+        }
+        (PhpValue::Object(a), PhpValue::Object(b)) => {
+            if Rc::ptr_eq(a, b) {
+                return Ok(true);
+            }
+            let pair = (Rc::as_ptr(a), Rc::as_ptr(b));
+            if comparing.contains(&pair) {
+                return Err(NestingTooDeep);
+            }
+            let (a, b) = (a.borrow(), b.borrow());
+            if a.class_name != b.class_name || a.closure != b.closure || a.properties.len() != b.properties.len() {
+                return Ok(false);
+            }
+            comparing.push(pair);
+            let mut equal = Ok(true);
+            for (name, value) in &a.properties {
+                equal = match b.properties.get(name) {
+                    Some(other) => loose_equals(value, other, comparing),
+                    None => Ok(false),
+                };
+                if equal != Ok(true) {
+                    break;
+                }
+            }
+            comparing.pop();
+            equal
+        }
+        (PhpValue::Null | PhpValue::Bool(_), _) | (_, PhpValue::Null | PhpValue::Bool(_)) => Ok(php_compare(left, right) == Ordering::Equal),
+        (PhpValue::Array(_) | PhpValue::Object(_), _) | (_, PhpValue::Array(_) | PhpValue::Object(_)) => Ok(false),
+        // NAN equals nothing, not even itself
+        (PhpValue::Float(f), _) | (_, PhpValue::Float(f)) if f.is_nan() => Ok(false),
+        _ => Ok(php_compare(left, right) == Ordering::Equal),
+    }
+}
+
+/// Perform PHP-style identity comparison (`===`): same type and value, arrays
+/// with identical pairs in the same order, and objects only if the same instance
+pub fn php_identical(left: &PhpValue, right: &PhpValue) -> bool {
+    match (left, right) {
+        (PhpValue::Array(a), PhpValue::Array(b)) => {
+            a.len() == b.len()
+                && a.data.iter().zip(b.data.iter()).all(|((ka, va), (kb, vb))| ka == kb && php_identical(va, vb))
+        }
+        (PhpValue::Object(a), PhpValue::Object(b)) => Rc::ptr_eq(a, b),
+        (PhpValue::Array(_), _) | (PhpValue::Object(_), _) => false,
+        _ => left == right,
+    }
+}
+
+/// Three-way comparison with PHP 8's loose semantics, as sort() uses it:
+/// numbers and numeric strings compare numerically, other strings byte-wise,
+/// null and bools as bools, and arrays by size, then element by element
+pub fn php_compare(left: &PhpValue, right: &PhpValue) -> Ordering {
+    match (left, right) {
+        (PhpValue::String(a), PhpValue::String(b)) if is_numeric_string(a) && is_numeric_string(b) => {
+            compare_numbers(&numeric_string_value(a), &numeric_string_value(b))
         }
+        (PhpValue::String(a), PhpValue::String(b)) => a.cmp(b),
+        (PhpValue::Null, PhpValue::String(s)) => "".cmp(s.as_str()),
+        (PhpValue::String(s), PhpValue::Null) => s.as_str().cmp(""),
+        (PhpValue::Null | PhpValue::Bool(_), _) | (_, PhpValue::Null | PhpValue::Bool(_)) => {
+            left.is_truthy().cmp(&right.is_truthy())
+        }
+        (PhpValue::Array(a), PhpValue::Array(b)) => a.len().cmp(&b.len()).then_with(|| {
+            for (key, value) in &a.data {
+                match b.data.get(key) {
+                    Some(other) => match php_compare(value, other) {
+                        Ordering::Equal => continue,
+                        ordering => return ordering,
+                    },
+                    // Arrays with different keys are uncomparable; PHP reports the left as greater
+                    None => return Ordering::Greater,
+                }
+            }
+            Ordering::Equal
+        }),
+        (PhpValue::Array(_), _) => Ordering::Greater,
+        (_, PhpValue::Array(_)) => Ordering::Less,
+        // A number and a non-numeric string compare as strings
+        (PhpValue::Int(_) | PhpValue::Float(_), PhpValue::String(s)) if !is_numeric_string(s) => left.to_string().as_str().cmp(s),
+        (PhpValue::String(s), PhpValue::Int(_) | PhpValue::Float(_)) if !is_numeric_string(s) => s.as_str().cmp(&right.to_string()),
+        (PhpValue::String(s), _) => compare_numbers(&numeric_string_value(s), &to_number(right)),
+        (_, PhpValue::String(s)) => compare_numbers(&to_number(left), &numeric_string_value(s)),
+        _ => compare_numbers(&to_number(left), &to_number(right)),
+    }
+}
+
+/// The int or float a number or numeric string holds, or None for anything else.
+/// Between two such values `php_compare` orders exactly by these numbers.
+pub fn php_numeric_value(value: &PhpValue) -> Option<PhpValue> {
+    match value {
+        PhpValue::Int(_) | PhpValue::Float(_) => Some(value.clone()),
+        PhpValue::String(s) if is_numeric_string(s) => Some(numeric_string_value(s)),
+        _ => None,
+    }
+}
+
+/// The int or float a numeric string holds, ignoring surrounding whitespace
+fn numeric_string_value(s: &str) -> PhpValue {
+    let trimmed = s.trim();
+    match trimmed.parse::<i64>() {
+        Ok(int) => PhpValue::Int(int),
+        Err(_) => PhpValue::Float(trimmed.parse().unwrap_or(0.0)),
+    }
+}
+
+/// Order two values already converted by `to_number`
+fn compare_numbers(left: &PhpValue, right: &PhpValue) -> Ordering {
+    match (left, right) {
+        (PhpValue::Int(a), PhpValue::Int(b)) => a.cmp(b),
+        _ => left.to_float().partial_cmp(&right.to_float()).unwrap_or(Ordering::Equal),
     }
 }
 
@@ -148,13 +652,13 @@ pub fn php_less_than(left: &PhpValue, right: &PhpValue) -> bool {
 }
 
 /// Perform PHP-style less than or equal comparison
-pub fn php_less_than_or_equal(left: &PhpValue, right: &PhpValue) -> bool {
-    php_less_than(left, right) || php_equals(left, right)
+pub fn php_less_than_or_equal(left: &PhpValue, right: &PhpValue) -> Result<bool, NestingTooDeep> {
+    Ok(php_less_than(left, right) || php_equals(left, right)?)
 }
 
 /// Perform PHP-style greater than comparison
-pub fn php_greater_than(left: &PhpValue, right: &PhpValue) -> bool {
-    !php_less_than_or_equal(left, right)
+pub fn php_greater_than(left: &PhpValue, right: &PhpValue) -> Result<bool, NestingTooDeep> {
+    Ok(!php_less_than_or_equal(left, right)?)
 }
 
 /// Perform PHP-style greater than or equal comparison
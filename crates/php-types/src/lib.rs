@@ -12,6 +12,9 @@ pub mod object;
 pub mod conversion;
 
 pub use value::*;
+// Re-exported at the crate root as before; the same types also arrive through `value::*`
+#[allow(unused_imports)]
 pub use array::*;
+#[allow(unused_imports)]
 pub use object::*;
 pub use conversion::*;
@@ -1,8 +1,10 @@
 //! PHP value types and representations
 
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use std::cell::RefCell;
+use indexmap::IndexMap;
 use std::fmt;
+use std::rc::Rc;
 
 /// Core PHP value type that can represent any PHP value
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -19,21 +21,36 @@ pub enum PhpValue {
     String(String),
     /// PHP array value (ordered map)
     Array(PhpArray),
-    /// PHP object value
-    Object(PhpObject),
+    /// PHP object value (shared handle: copies refer to the same instance)
+    Object(PhpObjectRef),
     /// PHP resource (placeholder for now)
     Resource(u64),
 }
 
 /// PHP array type (ordered associative array)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhpArray {
     /// Internal storage as ordered map
-    pub data: HashMap<PhpArrayKey, PhpValue>,
+    pub data: IndexMap<PhpArrayKey, PhpValue>,
     /// Next integer key for auto-indexing
     pub next_index: i64,
+    /// Internal pointer used by current()/next()/reset() etc., as a position in iteration order
+    #[serde(default)]
+    pub pointer: usize,
+}
+
+// The internal pointer is not part of an array's value
+impl PartialEq for PhpArray {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data && self.next_index == other.next_index
+    }
 }
 
+/// Error from `PhpArray::push` when the next auto-index is already occupied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Cannot add element to the array as the next element is already occupied")]
+pub struct ArrayFullError;
+
 /// PHP array key type (string or integer)
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PhpArrayKey {
@@ -43,13 +60,28 @@ pub enum PhpArrayKey {
     String(String),
 }
 
+/// Shared handle to a PHP object, mirroring PHP's by-handle object semantics
+pub type PhpObjectRef = Rc<RefCell<PhpObject>>;
+
 /// PHP object representation
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhpObject {
     /// Class name
     pub class_name: String,
     /// Object properties
-    pub properties: HashMap<String, PhpValue>,
+    pub properties: IndexMap<String, PhpValue>,
+    /// Object handle number (#1, #2, ...) assigned by the engine; not part of equality
+    #[serde(default)]
+    pub id: u64,
+    /// Engine function backing a `Closure` object
+    #[serde(default)]
+    pub closure: Option<String>,
+}
+
+impl PartialEq for PhpObject {
+    fn eq(&self, other: &Self) -> bool {
+        self.class_name == other.class_name && self.properties == other.properties
+    }
 }
 
 impl PhpValue {
@@ -82,6 +114,11 @@ impl PhpValue {
     pub fn array() -> Self {
         PhpValue::Array(PhpArray::new())
     }
+
+    /// Wrap an object into a new shared handle
+    pub fn object(object: PhpObject) -> Self {
+        PhpValue::Object(Rc::new(RefCell::new(object)))
+    }
     
     /// Check if the value is null
     pub fn is_null(&self) -> bool {
@@ -145,12 +182,13 @@ impl PhpValue {
     }
     
     /// Convert to string (PHP semantics)
+    #[allow(clippy::inherent_to_string_shadow_display)]
     pub fn to_string(&self) -> String {
         match self {
             PhpValue::Null => String::new(),
             PhpValue::Bool(b) => if *b { "1".to_string() } else { String::new() },
             PhpValue::Int(i) => i.to_string(),
-            PhpValue::Float(f) => f.to_string(),
+            PhpValue::Float(f) => crate::conversion::php_float_to_string(*f),
             PhpValue::String(s) => s.clone(),
             PhpValue::Array(_) => "Array".to_string(),
             PhpValue::Object(_) => "Object".to_string(),
@@ -177,8 +215,9 @@ impl PhpArray {
     /// Create a new empty array
     pub fn new() -> Self {
         Self {
-            data: HashMap::new(),
+            data: IndexMap::new(),
             next_index: 0,
+            pointer: 0,
         }
     }
     
@@ -192,11 +231,12 @@ impl PhpArray {
         self.data.len()
     }
     
-    /// Insert value with integer key
+    /// Insert value with integer key. Like PHP, the next auto-index stops at
+    /// PHP_INT_MAX once that key is used, so a later push fails.
     pub fn insert_int(&mut self, key: i64, value: PhpValue) {
         self.data.insert(PhpArrayKey::Int(key), value);
         if key >= self.next_index {
-            self.next_index = key + 1;
+            self.next_index = key.saturating_add(1);
         }
     }
     
@@ -205,9 +245,46 @@ impl PhpArray {
         self.data.insert(PhpArrayKey::String(key.into()), value);
     }
     
-    /// Push value to end of array (auto-index)
-    pub fn push(&mut self, value: PhpValue) {
+    /// Insert value under an existing key, keeping auto-indexing past integer keys
+    pub fn insert(&mut self, key: PhpArrayKey, value: PhpValue) {
+        match key {
+            PhpArrayKey::Int(i) => self.insert_int(i, value),
+            key => { self.data.insert(key, value); }
+        }
+    }
+
+    /// Push value to end of array (auto-index). Fails when the next index is
+    /// already taken, which only happens once PHP_INT_MAX is used as a key.
+    pub fn push(&mut self, value: PhpValue) -> Result<(), ArrayFullError> {
+        if self.data.contains_key(&PhpArrayKey::Int(self.next_index)) {
+            return Err(ArrayFullError);
+        }
         self.insert_int(self.next_index, value);
+        Ok(())
+    }
+
+    /// Remove and return the last value in insertion order, resetting the
+    /// internal pointer. Popping the most recent auto-index frees it for reuse.
+    pub fn pop(&mut self) -> Option<PhpValue> {
+        let (key, value) = self.data.pop()?;
+        if key == PhpArrayKey::Int(self.next_index - 1) {
+            self.next_index -= 1;
+        }
+        self.pointer = 0;
+        Some(value)
+    }
+
+    /// Move all elements of `other` into this array with array_merge semantics:
+    /// integer keys are renumbered, string keys overwrite existing entries
+    pub fn merge(&mut self, other: PhpArray) -> Result<(), ArrayFullError> {
+        self.data.reserve(other.len());
+        for (key, value) in other.data {
+            match key {
+                PhpArrayKey::Int(_) => self.push(value)?,
+                key @ PhpArrayKey::String(_) => { self.data.insert(key, value); }
+            }
+        }
+        Ok(())
     }
     
     /// Get value by integer key
@@ -219,6 +296,43 @@ impl PhpArray {
     pub fn get_string(&self, key: &str) -> Option<&PhpValue> {
         self.data.get(&PhpArrayKey::String(key.to_string()))
     }
+
+    /// Get a mutable reference to the value under a key
+    pub fn get_mut(&mut self, key: &PhpArrayKey) -> Option<&mut PhpValue> {
+        self.data.get_mut(key)
+    }
+
+    /// Entry under the internal pointer, or None once it has moved past either end
+    pub fn current(&self) -> Option<(&PhpArrayKey, &PhpValue)> {
+        self.data.get_index(self.pointer)
+    }
+
+    /// Advance the internal pointer by one element
+    pub fn advance(&mut self) -> Option<(&PhpArrayKey, &PhpValue)> {
+        self.pointer = (self.pointer + 1).min(self.len());
+        self.current()
+    }
+
+    /// Move the internal pointer back one element; stepping back from the first element invalidates it
+    pub fn retreat(&mut self) -> Option<(&PhpArrayKey, &PhpValue)> {
+        self.pointer = match self.pointer {
+            p if p == 0 || p >= self.len() => self.len(),
+            p => p - 1,
+        };
+        self.current()
+    }
+
+    /// Move the internal pointer to the first element
+    pub fn rewind(&mut self) -> Option<(&PhpArrayKey, &PhpValue)> {
+        self.pointer = 0;
+        self.current()
+    }
+
+    /// Move the internal pointer to the last element
+    pub fn seek_end(&mut self) -> Option<(&PhpArrayKey, &PhpValue)> {
+        self.pointer = self.len().saturating_sub(1);
+        self.current()
+    }
 }
 
 impl Default for PhpArray {
@@ -227,14 +341,39 @@ impl Default for PhpArray {
     }
 }
 
+/// Collect values into a list keyed 0, 1, 2, ...
+impl FromIterator<PhpValue> for PhpArray {
+    fn from_iter<I: IntoIterator<Item = PhpValue>>(iter: I) -> Self {
+        let mut arr = PhpArray::new();
+        for (index, value) in (0..).zip(iter) {
+            arr.insert_int(index, value);
+        }
+        arr
+    }
+}
+
+// Runtime errors are plain messages
+impl From<ArrayFullError> for String {
+    fn from(error: ArrayFullError) -> Self {
+        error.to_string()
+    }
+}
+
 impl PhpObject {
     /// Create a new object
     pub fn new<S: Into<String>>(class_name: S) -> Self {
         Self {
             class_name: class_name.into(),
-            properties: HashMap::new(),
+            properties: IndexMap::new(),
+            id: 0,
+            closure: None,
         }
     }
+
+    /// Create a `Closure` object invoking the engine function `function_id`
+    pub fn closure<S: Into<String>>(function_id: S) -> Self {
+        Self { closure: Some(function_id.into()), ..Self::new("Closure") }
+    }
     
     /// Set property value
     pub fn set_property<S: Into<String>>(&mut self, name: S, value: PhpValue) {
@@ -253,6 +392,29 @@ impl fmt::Display for PhpValue {
     }
 }
 
+impl PhpArrayKey {
+    /// Key for an int or string value, or None for other types. Like PHP, decimal
+    /// integer strings such as "12" become integer keys.
+    pub fn from_scalar(value: &PhpValue) -> Option<Self> {
+        match value {
+            PhpValue::Int(i) => Some(PhpArrayKey::Int(*i)),
+            PhpValue::String(s) => Some(match s.parse::<i64>() {
+                Ok(i) if i.to_string() == *s => PhpArrayKey::Int(i),
+                _ => PhpArrayKey::String(s.clone()),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The key as a PHP value
+    pub fn to_value(&self) -> PhpValue {
+        match self {
+            PhpArrayKey::Int(i) => PhpValue::Int(*i),
+            PhpArrayKey::String(s) => PhpValue::String(s.clone()),
+        }
+    }
+}
+
 impl fmt::Display for PhpArrayKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
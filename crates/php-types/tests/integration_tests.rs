@@ -1,9 +1,94 @@
 //! Integration tests for php-types
 
-use php_types::*;
+#[test]
+fn test_rust_value_round_trip() {
+    use php_types::{php_arg, FromPhpValue, PhpConvert, PhpValue};
+
+    let list = vec![1i64, 2, 3].to_php_value();
+    let mut back = Vec::<i64>::from_php_value(&list).expect("list converts back");
+    back.sort();
+    assert_eq!(back, vec![1, 2, 3]);
+    assert_eq!(Option::<String>::from_php_value(&PhpValue::Null), Ok(None));
+    assert_eq!(i64::from_php_value(&PhpValue::String("42".into())), Ok(42));
+    assert!(String::from_php_value(&list).is_err());
+    let args = [PhpValue::Int(7)];
+    assert_eq!(php_arg::<String>("f", &args, 0), Ok("7".to_string()));
+    assert_eq!(php_arg::<i64>("f", &args, 1), Err("f() expects at least 2 arguments, 1 given".to_string()));
+}
+
+#[test]
+fn test_php_modulo_follows_dividend_sign() {
+    use php_types::{php_modulo, PhpValue};
+
+    let modulo = |a: i64, b: i64| php_modulo(&PhpValue::Int(a), &PhpValue::Int(b));
+    assert_eq!(modulo(7, 3), Ok(PhpValue::Int(1)));
+    assert_eq!(modulo(-7, 3), Ok(PhpValue::Int(-1)));
+    assert_eq!(modulo(7, -3), Ok(PhpValue::Int(1)));
+    assert_eq!(modulo(i64::MIN, -1), Ok(PhpValue::Int(0)));
+    assert_eq!(modulo(1, 0), Err("Modulo by zero".to_string()));
+}
+
+#[test]
+fn test_int_overflow_promotes_to_float() {
+    use php_types::{php_add, php_multiply, php_subtract, PhpValue};
+
+    let (max, min) = (PhpValue::Int(i64::MAX), PhpValue::Int(i64::MIN));
+    assert_eq!(php_multiply(&max, &PhpValue::Int(2)), Ok(PhpValue::Float(i64::MAX as f64 * 2.0)));
+    assert_eq!(php_add(&max, &PhpValue::Int(1)), Ok(PhpValue::Float(i64::MAX as f64)));
+    assert_eq!(php_subtract(&min, &PhpValue::Int(1)), Ok(PhpValue::Float(i64::MIN as f64)));
+    assert_eq!(php_add(&PhpValue::Int(i64::MAX - 1), &PhpValue::Int(1)), Ok(max));
+    assert_eq!(php_multiply(&PhpValue::Int(-3), &PhpValue::Int(4)), Ok(PhpValue::Int(-12)));
+}
+
+#[test]
+fn test_php_divide_keeps_exact_int_quotients() {
+    use php_types::{php_divide, PhpValue};
+
+    let divide = |a: i64, b: i64| php_divide(&PhpValue::Int(a), &PhpValue::Int(b));
+    assert_eq!(divide(10, 2), Ok(PhpValue::Int(5)));
+    assert_eq!(divide(-9, 3), Ok(PhpValue::Int(-3)));
+    assert_eq!(divide(10, 4), Ok(PhpValue::Float(2.5)));
+    assert_eq!(divide(i64::MIN, -1), Ok(PhpValue::Float(-(i64::MIN as f64))));
+    assert_eq!(php_divide(&PhpValue::String("12".into()), &PhpValue::Int(4)), Ok(PhpValue::Int(3)));
+    assert_eq!(php_divide(&PhpValue::Float(10.0), &PhpValue::Int(2)), Ok(PhpValue::Float(5.0)));
+    assert_eq!(divide(7, 0), Err("Division by zero".to_string()));
+}
 
 #[test]
-fn basic_test() {
-    // TODO: Add tests for php-types
-    assert!(true);
+fn test_loose_equality_detects_recursive_objects() {
+    use php_types::{php_equals, NestingTooDeep, PhpObject, PhpValue};
+    use std::{cell::RefCell, rc::Rc};
+
+    let cyclic = || {
+        let object = PhpValue::Object(Rc::new(RefCell::new(PhpObject::new("Node"))));
+        if let PhpValue::Object(handle) = &object {
+            handle.borrow_mut().set_property("next", object.clone());
+        }
+        object
+    };
+    let (a, b) = (cyclic(), cyclic());
+    assert_eq!(php_equals(&a, &a), Ok(true));
+    assert_eq!(php_equals(&a, &b), Err(NestingTooDeep));
+    assert_eq!(php_equals(&PhpValue::Int(1), &PhpValue::String("1.0".into())), Ok(true));
+}
+
+#[test]
+fn test_float_formatting_precisions() {
+    use php_types::{php_float_repr, php_float_to_string};
+
+    let third = 1.0 / 3.0;
+    assert_eq!(php_float_to_string(third), "0.33333333333333");
+    assert_eq!(php_float_repr(third), "0.3333333333333333");
+    assert_eq!(php_float_to_string(0.1 + 0.2), "0.3");
+    assert_eq!(php_float_repr(0.1 + 0.2), "0.30000000000000004");
+    assert_eq!(php_float_to_string(-2.5), "-2.5");
+    assert_eq!(php_float_repr(100.0), "100");
+    assert_eq!(php_float_to_string(1e15), "1.0E+15");
+    assert_eq!(php_float_to_string(123456789012345.0), "1.2345678901234E+14");
+    assert_eq!(php_float_repr(1e100), "1.0E+100");
+    assert_eq!(php_float_to_string(0.0001), "0.0001");
+    assert_eq!(php_float_repr(0.00001), "1.0E-5");
+    assert_eq!(php_float_to_string(-0.0), "-0");
+    assert_eq!(php_float_to_string(f64::INFINITY), "INF");
+    assert_eq!(php_float_repr(f64::NAN), "NAN");
 }
@@ -22,7 +22,7 @@ async fn main() -> std::io::Result<()> {
         Err(e) => {
             eprintln!("Error initializing Tera templates: {}", e);
             eprintln!("Current working directory: {:?}", std::env::current_dir());
-            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Template error: {}", e)));
+            return Err(std::io::Error::other(format!("Template error: {}", e)));
         }
     };
 
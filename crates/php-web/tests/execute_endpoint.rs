@@ -12,7 +12,7 @@ async fn test_execute_simple_echo() {
     // Raw string payload form
     let req = test::TestRequest::post()
         .uri("/execute")
-        .set_json(&"<?php echo 2+3; ?>".to_string())
+        .set_json("<?php echo 2+3; ?>".to_string())
         .to_request();
     let resp = test::call_and_read_body(&app, req).await;
     let body = String::from_utf8(resp.to_vec()).unwrap();
@@ -21,7 +21,7 @@ async fn test_execute_simple_echo() {
     // Object form payload
     let req2 = test::TestRequest::post()
         .uri("/api/execute")
-        .set_json(&serde_json::json!({"code": "<?php echo 10/2; ?>"}))
+        .set_json(serde_json::json!({"code": "<?php echo 10/2; ?>"}))
         .to_request();
     let resp2 = test::call_and_read_body(&app, req2).await;
     let body2 = String::from_utf8(resp2.to_vec()).unwrap();
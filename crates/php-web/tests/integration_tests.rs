@@ -1,9 +0,0 @@
-//! Integration tests for php-web
-
-use php_web::*;
-
-#[test]
-fn basic_test() {
-    // TODO: Add tests for php-web
-    assert!(true);
-}